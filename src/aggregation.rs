@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Serialize, Deserialize};
+
+use crate::WorkingTime;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregate {
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BreakdownEntry {
+    // Human readable period: 2023-10-02, 2023-W40 or 2023-10
+    pub period: String,
+    // First day of the period
+    pub date: String,
+    pub hours: f64,
+}
+
+fn period_of(date: NaiveDate, aggregate: Aggregate) -> (NaiveDate, String) {
+    match aggregate {
+        Aggregate::Day => (date, date.to_string()),
+        Aggregate::Week => {
+            let week = date.iso_week();
+            let monday = NaiveDate::from_isoywd_opt(week.year(), week.week(), Weekday::Mon).unwrap();
+            (monday, format!("{}-W{:02}", week.year(), week.week()))
+        }
+        Aggregate::Month => {
+            let first = NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap();
+            (first, format!("{}-{:02}", date.year(), date.month()))
+        }
+    }
+}
+
+// Working hours grouped per local calendar day, intervals crossing midnight are split
+pub fn hours_per_day(working_time: &WorkingTime) -> BTreeMap<NaiveDate, f64> {
+    let mut days: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+    for (start, end) in &working_time.intervals {
+        let timezone = start.timezone();
+        let mut cursor = *start;
+        while cursor < *end {
+            let next_day = cursor.date_naive() + chrono::Duration::days(1);
+            let midnight = crate::resolve_local(&timezone, next_day.and_hms_opt(0, 0, 0).unwrap());
+            let piece_end = midnight.min(*end);
+            let hours = piece_end.signed_duration_since(cursor).num_seconds() as f64 / 3600.0;
+            *days.entry(cursor.date_naive()).or_insert(0.0) += hours;
+            cursor = piece_end;
+        }
+    }
+    days
+}
+
+pub fn breakdown(working_time: &WorkingTime, aggregate: Aggregate) -> Vec<BreakdownEntry> {
+    let mut periods: BTreeMap<NaiveDate, (String, f64)> = BTreeMap::new();
+    for (date, hours) in hours_per_day(working_time) {
+        let (period_start, label) = period_of(date, aggregate);
+        periods.entry(period_start).or_insert((label, 0.0)).1 += hours;
+    }
+    periods.into_iter()
+        .map(|(date, (period, hours))| BreakdownEntry {
+            period,
+            date: date.to_string(),
+            hours,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use chrono_tz::Tz;
+
+    #[test]
+    fn test_monthly_breakdown_splits_at_month_boundary() {
+        let tz: Tz = "UTC".parse().unwrap();
+        // Overnight shift from October 31st to November 1st
+        let working_time = WorkingTime {
+            start_date: tz.with_ymd_and_hms(2023, 10, 31, 0, 0, 0).unwrap(),
+            end_date: tz.with_ymd_and_hms(2023, 11, 2, 0, 0, 0).unwrap(),
+            intervals: vec![(
+                tz.with_ymd_and_hms(2023, 10, 31, 22, 0, 0).unwrap(),
+                tz.with_ymd_and_hms(2023, 11, 1, 6, 0, 0).unwrap(),
+            )],
+            holidays_applied: vec![],
+            bulk_seconds: 0,
+            warnings: vec![],
+        };
+
+        let entries = breakdown(&working_time, Aggregate::Month);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].period, "2023-10");
+        assert_eq!(entries[0].hours, 2.0);
+        assert_eq!(entries[1].period, "2023-11");
+        assert_eq!(entries[1].hours, 6.0);
+
+        let entries = breakdown(&working_time, Aggregate::Week);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].period, "2023-W44");
+        assert_eq!(entries[0].date, "2023-10-30");
+    }
+}
@@ -0,0 +1,116 @@
+use actix_web::{web, HttpResponse, get, post, delete};
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+
+use crate::calendar_resolver;
+use crate::db::{BlackoutPeriod, Database};
+use crate::{freeze, AppState};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BlackoutMode {
+    // Leave inside the period is refused unless overridden
+    #[default]
+    Reject,
+    // Leave is accepted and reported with the period
+    Flag,
+}
+
+impl BlackoutMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BlackoutMode::Reject => "reject",
+            BlackoutMode::Flag => "flag",
+        }
+    }
+}
+
+// Period during which employees of a calendar should not take leave, e.g. the December retail peak
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlackoutPeriodRequest {
+    pub start: String,
+    pub end: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub mode: BlackoutMode,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, actix_web::error::Error> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid date format: {}", e)))
+}
+
+// Blackout periods of a calendar and of the calendars it inherits from, covering the date
+pub fn covering(db: &Database, calendar_id: &str, date: NaiveDate) -> Result<Vec<BlackoutPeriod>, String> {
+    let chain = match calendar_resolver::resolve(db, calendar_id)? {
+        Some(calendar) => calendar.chain,
+        None => vec![calendar_id.to_string()],
+    };
+    let date = date.to_string();
+    let mut periods = vec![];
+    for id in chain {
+        let stored = db.get_blackout_periods(&id).map_err(|e| e.to_string())?;
+        periods.extend(stored.into_iter().filter(|period| period.start <= date && date <= period.end));
+    }
+    Ok(periods)
+}
+
+#[get("/blackouts/{calendar}")]
+pub async fn list_blackout_periods(
+    data: web::Data<AppState>,
+    calendar: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let periods = data.db.blocking(move |db| db.get_blackout_periods(&calendar)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(periods))
+}
+
+#[post("/blackouts/{calendar}")]
+pub async fn add_blackout_periods(
+    data: web::Data<AppState>,
+    calendar: web::Path<String>,
+    req: web::Json<Vec<BlackoutPeriodRequest>>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    // Validate everything before storing anything
+    let mut periods = vec![];
+    for period in req.iter() {
+        let start = parse_date(&period.start)?;
+        let end = parse_date(&period.end)?;
+        if start > end {
+            return Err(actix_web::error::ErrorBadRequest("Blackout period start must not be after its end"));
+        }
+        periods.push(BlackoutPeriod {
+            id: None,
+            calendar_id: calendar.clone(),
+            start: start.to_string(),
+            end: end.to_string(),
+            description: period.description.clone(),
+            mode: period.mode.as_str().to_string(),
+        });
+    }
+
+    freeze::ensure_unfrozen(&data.db).await?;
+    let periods = data.db.blocking(move |db| {
+        for period in periods.iter_mut() {
+            period.id = Some(db.add_blackout_period(period)?);
+        }
+        Ok(periods)
+    }).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(periods))
+}
+
+#[delete("/blackouts/{calendar}/{id}")]
+pub async fn delete_blackout_period(
+    data: web::Data<AppState>,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let (calendar, id) = path.into_inner();
+    freeze::ensure_unfrozen(&data.db).await?;
+    let deleted = data.db.blocking(move |db| db.delete_blackout_period(&calendar, id)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if deleted == 0 {
+        return Ok(HttpResponse::NotFound().json(format!("Blackout period {} not found", id)));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::{Serialize, Deserialize};
+use log::warn;
+
+// Closed: calls go through. Open: calls fail at once until the cool-down ends.
+// Half-open: the cool-down ended, the next call decides whether the breaker closes or opens again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    pub threshold: u32,
+    pub cooldown: Duration,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker { threshold: threshold.max(1), cooldown, consecutive_failures: 0, open_until: None }
+    }
+
+    // HOLIDAYS_API_BREAKER_THRESHOLD consecutive failures open the breaker for HOLIDAYS_API_BREAKER_COOLDOWN_SECONDS
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: u64| env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        CircuitBreaker::new(
+            var("HOLIDAYS_API_BREAKER_THRESHOLD", 5) as u32,
+            Duration::from_secs(var("HOLIDAYS_API_BREAKER_COOLDOWN_SECONDS", 30)),
+        )
+    }
+
+    pub fn state(&self, now: Instant) -> State {
+        match self.open_until {
+            Some(until) if now < until => State::Open,
+            Some(_) => State::HalfOpen,
+            None => State::Closed,
+        }
+    }
+
+    pub fn allows(&self, now: Instant) -> bool {
+        self.state(now) != State::Open
+    }
+
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+    }
+
+    // Returns true when this failure opened the breaker
+    pub fn record_failure(&mut self, now: Instant) -> bool {
+        self.consecutive_failures += 1;
+        let trial_failed = self.state(now) == State::HalfOpen;
+        if trial_failed || self.consecutive_failures >= self.threshold {
+            self.open_until = Some(now + self.cooldown);
+            return true;
+        }
+        false
+    }
+}
+
+lazy_static! {
+    // One breaker per upstream provider, shared by every request of the instance
+    static ref BREAKERS: Mutex<BTreeMap<String, CircuitBreaker>> = Mutex::new(BTreeMap::new());
+}
+
+// Whether the provider may be called now, refused while its breaker is open
+pub fn allows(provider: &str) -> bool {
+    let breakers = BREAKERS.lock().unwrap();
+    breakers.get(provider).is_none_or(|breaker| breaker.allows(Instant::now()))
+}
+
+pub fn record_success(provider: &str) {
+    let mut breakers = BREAKERS.lock().unwrap();
+    breakers.entry(provider.to_string()).or_insert_with(CircuitBreaker::from_env).record_success();
+}
+
+pub fn record_failure(provider: &str) {
+    let mut breakers = BREAKERS.lock().unwrap();
+    let breaker = breakers.entry(provider.to_string()).or_insert_with(CircuitBreaker::from_env);
+    if breaker.record_failure(Instant::now()) {
+        warn!(
+            "Circuit breaker opened for {} after {} consecutive failures, falling back for {} seconds",
+            provider, breaker.consecutive_failures, breaker.cooldown.as_secs()
+        );
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BreakerStatus {
+    pub provider: String,
+    // closed, open or half_open
+    pub state: String,
+    pub consecutive_failures: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_until: Option<String>,
+}
+
+// State of the providers called so far, reported by /health
+pub fn statuses() -> Vec<BreakerStatus> {
+    let now = Instant::now();
+    let breakers = BREAKERS.lock().unwrap();
+    breakers.iter()
+        .map(|(provider, breaker)| {
+            let state = breaker.state(now);
+            BreakerStatus {
+                provider: provider.clone(),
+                state: match state {
+                    State::Closed => "closed",
+                    State::Open => "open",
+                    State::HalfOpen => "half_open",
+                }.to_string(),
+                consecutive_failures: breaker.consecutive_failures,
+                open_until: breaker.open_until.filter(|_| state == State::Open).map(|until| {
+                    let remaining = chrono::Duration::from_std(until - now).unwrap_or_else(|_| chrono::Duration::zero());
+                    (Utc::now() + remaining).to_rfc3339()
+                }),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_and_recovers() {
+        let mut breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+        let now = Instant::now();
+
+        assert!(!breaker.record_failure(now));
+        assert!(!breaker.record_failure(now));
+        assert!(breaker.record_failure(now));
+        assert_eq!(breaker.state(now), State::Open);
+        assert!(!breaker.allows(now + Duration::from_secs(29)));
+
+        // The trial call after the cool-down fails: open again at once
+        let later = now + Duration::from_secs(30);
+        assert_eq!(breaker.state(later), State::HalfOpen);
+        assert!(breaker.record_failure(later));
+        assert!(!breaker.allows(later + Duration::from_secs(1)));
+
+        // A successful trial closes it
+        let later = later + Duration::from_secs(30);
+        assert!(breaker.allows(later));
+        breaker.record_success();
+        assert_eq!(breaker.state(later), State::Closed);
+        assert!(!breaker.record_failure(later));
+    }
+}
@@ -0,0 +1,423 @@
+use std::collections::{HashMap, HashSet};
+use actix_web::{web, HttpRequest, HttpResponse, post};
+use chrono::{NaiveDate, NaiveTime, Weekday};
+use serde::{Serialize, Deserialize};
+use log::info;
+
+use crate::country::CountryCode;
+use crate::db::{self, Calendar, Database};
+use crate::{calendar_resolver, country_groups, events, freeze, AppState};
+
+// Declarative description of every calendar the service should know about.
+// Calendars present in the database but missing from the file are removed on apply.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct CalendarConfig {
+    #[serde(default)]
+    pub calendars: Vec<Calendar>,
+}
+
+// Summary of the changes made while reconciling the database with a configuration
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ApplyReport {
+    // Changes reported without being made
+    #[serde(default)]
+    pub dry_run: bool,
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub deleted: Vec<String>,
+    pub unchanged: Vec<String>,
+}
+
+// Parse and validate a YAML calendar configuration
+pub fn parse(yaml: &str) -> Result<CalendarConfig, String> {
+    let mut config: CalendarConfig = serde_yaml::from_str(yaml)
+        .map_err(|e| format!("Invalid calendar configuration: {}", e))?;
+    validate(&config)?;
+
+    config.calendars.iter_mut().for_each(normalize);
+    Ok(config)
+}
+
+// Normalize codes the same way the calculation does
+fn normalize(calendar: &mut Calendar) {
+    if let Ok(country) = CountryCode::parse(&calendar.country) {
+        calendar.country = country.as_str().to_string();
+    }
+    calendar.weekend = calendar.weekend.as_ref()
+        .map(|days| days.iter().map(|d| d.to_lowercase()).collect());
+    // Only overlays inherit, root calendars get the defaults
+    if calendar.parent.is_none() {
+        calendar.start_of_day.get_or_insert_with(crate::default_start_of_day);
+        calendar.end_of_day.get_or_insert_with(crate::default_end_of_day);
+        calendar.weekend.get_or_insert_with(db::default_weekend);
+    }
+}
+
+pub fn load_from_file(path: &str) -> Result<CalendarConfig, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read calendar configuration {}: {}", path, e))?;
+    parse(&content)
+}
+
+// Checks of a single calendar, its parent is checked against the rest of the configuration
+fn validate_calendar(calendar: &Calendar) -> Result<(), String> {
+    if calendar.id.is_empty() {
+        return Err("Calendar id must not be empty".to_string());
+    }
+    if calendar.country.is_empty() && calendar.parent.is_none() {
+        return Err(format!("Calendar {} must define a country", calendar.id));
+    }
+    if !calendar.country.is_empty() {
+        CountryCode::parse(&calendar.country).map_err(|e| format!("{} in calendar {}", e, calendar.id))?;
+    }
+    for time in [&calendar.start_of_day, &calendar.end_of_day].into_iter().flatten() {
+        NaiveTime::parse_from_str(time, "%H:%M:%S")
+            .map_err(|e| format!("Invalid time '{}' in calendar {}: {}", time, calendar.id, e))?;
+    }
+    for day in calendar.weekend.iter().flatten() {
+        day.parse::<Weekday>()
+            .map_err(|_| format!("Invalid weekend day '{}' in calendar {}", day, calendar.id))?;
+    }
+    for closure in &calendar.closures {
+        match (&closure.date, &closure.recurring) {
+            (Some(date), None) => {
+                NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .map_err(|e| format!("Invalid closure date '{}' in calendar {}: {}", date, calendar.id, e))?;
+            }
+            (None, Some(recurring)) => {
+                // Check against a leap year so that 02-29 is accepted
+                NaiveDate::parse_from_str(&format!("2000-{}", recurring), "%Y-%m-%d")
+                    .map_err(|e| format!("Invalid recurring closure '{}' in calendar {}: {}", recurring, calendar.id, e))?;
+            }
+            _ => {
+                return Err(format!(
+                    "Closures in calendar {} must define exactly one of date or recurring",
+                    calendar.id
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate(config: &CalendarConfig) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for calendar in &config.calendars {
+        validate_calendar(calendar)?;
+        if !seen.insert(calendar.id.as_str()) {
+            return Err(format!("Duplicate calendar id: {}", calendar.id));
+        }
+    }
+
+    // Every parent must be part of the configuration, without cycles
+    let parents: HashMap<&str, Option<&str>> = config.calendars.iter()
+        .map(|c| (c.id.as_str(), c.parent.as_deref()))
+        .collect();
+    for calendar in &config.calendars {
+        let mut chain = vec![calendar.id.as_str()];
+        let mut current = calendar.parent.as_deref();
+        while let Some(parent) = current {
+            if chain.contains(&parent) {
+                return Err(format!("Calendar inheritance cycle: {} -> {}", chain.join(" -> "), parent));
+            }
+            current = *parents.get(parent)
+                .ok_or_else(|| format!("Calendar {} inherits from unknown calendar {}", chain[chain.len() - 1], parent))?;
+            chain.push(parent);
+        }
+    }
+    Ok(())
+}
+
+// Countries longer than a code name a country group, which must exist
+fn check_groups<'a>(db: &Database, calendars: impl IntoIterator<Item = &'a Calendar>) -> Result<(), String> {
+    for calendar in calendars {
+        let group = CountryCode::parse(&calendar.country).ok().filter(CountryCode::is_group);
+        if let Some(group) = group.filter(|group| country_groups::resolve(db, group.as_str()).is_none()) {
+            return Err(format!("Unknown country group {} in calendar {}", group, calendar.id));
+        }
+    }
+    Ok(())
+}
+
+// ?dryRun=true reports the changes of an apply or import without writing them
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct DryRunParams {
+    #[serde(rename = "dryRun", default)]
+    pub dry_run: bool,
+}
+
+// Changes an apply of the configuration would make, without making them
+pub fn plan(db: &Database, config: &CalendarConfig) -> Result<ApplyReport, String> {
+    check_groups(db, &config.calendars)?;
+    let existing = db.get_all_calendars().map_err(|e| e.to_string())?;
+    let mut report = ApplyReport { dry_run: true, ..Default::default() };
+
+    for calendar in &config.calendars {
+        match existing.iter().find(|c| c.id == calendar.id) {
+            Some(current) if current == calendar => report.unchanged.push(calendar.id.clone()),
+            Some(_) => report.updated.push(calendar.id.clone()),
+            None => report.created.push(calendar.id.clone()),
+        }
+    }
+
+    for calendar in existing {
+        if !config.calendars.iter().any(|c| c.id == calendar.id) {
+            report.deleted.push(calendar.id);
+        }
+    }
+    Ok(report)
+}
+
+// Reconcile the database so that it contains exactly the calendars of the configuration,
+// every change is recorded in the calendar history under the given author
+pub fn apply(db: &Database, config: &CalendarConfig, author: &str) -> Result<ApplyReport, String> {
+    let mut report = plan(db, config)?;
+    report.dry_run = false;
+    let upserted: Vec<&Calendar> = config.calendars.iter()
+        .filter(|c| report.created.contains(&c.id) || report.updated.contains(&c.id))
+        .collect();
+    db.replace_calendars(&upserted, &report.deleted, author).map_err(|e| e.to_string())?;
+
+    for (action, ids) in [("created", &report.created), ("updated", &report.updated), ("deleted", &report.deleted)] {
+        for id in ids {
+            events::publish(events::Event::CalendarChanged {
+                calendar_id: id.clone(),
+                author: author.to_string(),
+                action: action.to_string(),
+            });
+        }
+    }
+    events::publish(events::Event::JobCompleted {
+        job: "calendar-config-apply".to_string(),
+        details: serde_json::to_value(&report).unwrap_or_default(),
+    });
+
+    info!(
+        "Applied calendar configuration: {} created, {} updated, {} deleted, {} unchanged",
+        report.created.len(), report.updated.len(), report.deleted.len(), report.unchanged.len()
+    );
+    Ok(report)
+}
+
+// Author recorded in the calendar history, as forwarded by the authenticating proxy
+pub fn author(http_req: &HttpRequest) -> String {
+    http_req.headers().get("X-User")
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .unwrap_or("anonymous")
+        .to_string()
+}
+
+#[post("/admin/apply")]
+pub async fn apply_config(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    params: web::Query<DryRunParams>,
+    body: String,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let config = parse(&body).map_err(actix_web::error::ErrorBadRequest)?;
+    let calendars = config.calendars.clone();
+    data.db.blocking(move |db| Ok(check_groups(db, &calendars))).await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .map_err(actix_web::error::ErrorBadRequest)?;
+    if params.dry_run {
+        let report = data.db.blocking(move |db| Ok(plan(db, &config))).await
+            .map_err(actix_web::error::ErrorInternalServerError)?
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+        return Ok(HttpResponse::Ok().json(report));
+    }
+    freeze::ensure_unfrozen(&data.db).await?;
+    let author = author(&http_req);
+    let report = data.db.blocking(move |db| Ok(apply(db, &config, &author))).await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+// Creates a single calendar, e.g. a company calendar on top of a country with its plant shutdown days.
+// Calendars missing from CALENDAR_CONFIG are still removed by the next apply of the file.
+#[post("/calendars")]
+pub async fn create_calendar(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    req: web::Json<Calendar>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let mut calendar = req.into_inner();
+    validate_calendar(&calendar).map_err(actix_web::error::ErrorBadRequest)?;
+    normalize(&mut calendar);
+    let author = author(&http_req);
+    freeze::ensure_unfrozen(&data.db).await?;
+    let checked = calendar.clone();
+    let (exists, known_parent, groups) = data.db.blocking(move |db| Ok((
+        db.get_calendar(&checked.id)?.is_some(),
+        match &checked.parent {
+            Some(parent) => db.get_calendar(parent)?.is_some(),
+            None => true,
+        },
+        check_groups(db, [&checked]),
+    ))).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    groups.map_err(actix_web::error::ErrorBadRequest)?;
+    if exists {
+        return Err(actix_web::error::ErrorConflict(format!("Calendar {} already exists", calendar.id)));
+    }
+    if let Some(parent) = calendar.parent.as_ref().filter(|_| !known_parent) {
+        return Err(actix_web::error::ErrorBadRequest(format!("Calendar {} inherits from unknown calendar {}", calendar.id, parent)));
+    }
+    let calendar_id = calendar.id.clone();
+    let stored_author = author.clone();
+    let resolved = data.db.blocking(move |db| {
+        db.upsert_calendar(&calendar, &stored_author)?;
+        Ok(calendar_resolver::resolve(db, &calendar.id))
+    }).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    events::publish(events::Event::CalendarChanged {
+        calendar_id,
+        author,
+        action: "created".to_string(),
+    });
+    let resolved = resolved.map_err(actix_web::error::ErrorUnprocessableEntity)?;
+    Ok(HttpResponse::Created().json(resolved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+calendars:
+  - id: acme-paris
+    country: FR
+    startOfDay: "08:30:00"
+    endOfDay: "17:30:00"
+    closures:
+      - date: "2025-08-14"
+        description: Plant shutdown
+      - recurring: "12-24"
+        description: Christmas Eve
+  - id: acme-dubai
+    country: ae
+    weekend: [fri, sat]
+"#;
+
+    #[test]
+    fn test_apply_reconciles_database() {
+        let db = Database::new(":memory:").unwrap();
+
+        let config = parse(CONFIG).unwrap();
+        let report = apply(&db, &config, "test").unwrap();
+        assert_eq!(report.created, vec!["acme-paris", "acme-dubai"]);
+
+        let paris = db.get_calendar("acme-paris").unwrap().unwrap();
+        assert_eq!(paris.country, "fr");
+        assert_eq!(paris.closures.len(), 2);
+        assert_eq!(paris.weekend, Some(vec!["sat".to_string(), "sun".to_string()]));
+
+        // A plan of the change reports it without writing it
+        let edited = parse(&CONFIG.replace("08:30:00", "08:00:00")).unwrap();
+        let report = plan(&db, &edited).unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.updated, vec!["acme-paris"]);
+        assert_eq!(db.get_calendar("acme-paris").unwrap().unwrap().start_of_day.as_deref(), Some("08:30:00"));
+
+        // Re-applying the same file is a no-op
+        let report = apply(&db, &config, "test").unwrap();
+        assert_eq!(report.unchanged.len(), 2);
+
+        // Removing a calendar from the file removes it from the database
+        let config = parse("calendars:\n  - id: acme-dubai\n    country: ae\n    weekend: [fri, sat]\n").unwrap();
+        let report = apply(&db, &config, "test").unwrap();
+        assert_eq!(report.deleted, vec!["acme-paris"]);
+        assert!(db.get_calendar("acme-paris").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_inheritance_cycle() {
+        let result = parse("calendars:\n  - id: a\n    parent: b\n  - id: b\n    parent: a\n");
+        assert!(result.unwrap_err().contains("cycle"));
+        let result = parse("calendars:\n  - id: team\n    parent: missing\n");
+        assert!(result.unwrap_err().contains("unknown calendar"));
+    }
+
+    #[actix_rt::test]
+    async fn test_created_calendar_is_used_by_calculations() {
+        use actix_web::{test, App};
+        use crate::holiday_provider::MockProvider;
+        use crate::{compute_work_hours, EndOrDuration, WorkHoursRequest};
+
+        let data = web::Data::new(AppState { db: Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data.clone()).service(create_calendar)).await;
+        let calendar = serde_json::json!({
+            "id": "acme-paris",
+            "country": "FR",
+            "closures": [
+                { "date": "2025-08-14", "description": "Plant shutdown" },
+                { "recurring": "12-24", "description": "Christmas Eve" }
+            ]
+        });
+        let req = test::TestRequest::post().uri("/calendars").set_json(&calendar).to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), 201);
+        let resolved: calendar_resolver::ResolvedCalendar = test::read_body_json(response).await;
+        assert_eq!(resolved.country, "fr");
+
+        let req = test::TestRequest::post().uri("/calendars").set_json(&calendar).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 409);
+
+        // Thursday 14 August is a shutdown day, Christmas Eve recurs every year
+        for (start, end, hours) in [("2025-08-11", "2025-08-15", 32.0), ("2026-12-21", "2026-12-25", 32.0)] {
+            let request = WorkHoursRequest {
+                start_date: start.to_string(),
+                end_or_duration: EndOrDuration::EndDate { end_date: end.to_string() },
+                calendar_id: Some("acme-paris".to_string()),
+                timezone: "UTC".to_string(),
+                ..Default::default()
+            };
+            let response = compute_work_hours(&data, &request).await.unwrap();
+            assert_eq!(response.work_hours, hours);
+            assert_eq!(response.holidays_applied[0].source, "calendar");
+        }
+
+        let request = WorkHoursRequest { calendar_id: Some("unknown".to_string()), ..Default::default() };
+        assert!(compute_work_hours(&data, &request).await.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_calendar_weekend_is_used_by_calculations() {
+        use crate::holiday_provider::MockProvider;
+        use crate::{compute_work_hours, EndOrDuration, WorkHoursRequest};
+
+        let data = web::Data::new(AppState { db: Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let config = parse("calendars:\n  - id: acme-dubai\n    country: ae\n    weekend: [fri, sat]\n").unwrap();
+        data.db.upsert_calendar(&config.calendars[0], "test").unwrap();
+
+        // Thursday 7 to Sunday 10 August: only Thursday is worked, by day and in closed form
+        for (end_date, include_intervals, hours) in [("2025-08-10T00:00:00Z", false, 8.0), ("2025-08-10T00:00:00Z", true, 8.0), ("2025-08-24", false, 96.0)] {
+            let request = WorkHoursRequest {
+                start_date: "2025-08-07T00:00:00Z".to_string(),
+                end_or_duration: EndOrDuration::EndDate { end_date: end_date.to_string() },
+                calendar_id: Some("acme-dubai".to_string()),
+                timezone: "UTC".to_string(),
+                include_intervals,
+                ..Default::default()
+            };
+            assert_eq!(compute_work_hours(&data, &request).await.unwrap().work_hours, hours, "{}", end_date);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_closure() {
+        let result = parse("calendars:\n  - id: broken\n    country: fr\n    closures:\n      - description: nothing\n");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_country() {
+        let result = parse("calendars:\n  - id: broken\n    country: F1\n");
+        assert!(result.unwrap_err().contains("Invalid country code: F1"));
+
+        // Longer names are country groups, which must exist
+        let db = Database::new(":memory:").unwrap();
+        let config = parse("calendars:\n  - id: broken\n    country: France\n").unwrap();
+        assert_eq!(apply(&db, &config, "test").unwrap_err(), "Unknown country group france in calendar broken");
+        assert!(db.get_calendar("broken").unwrap().is_none());
+    }
+}
@@ -0,0 +1,212 @@
+use std::ops::RangeInclusive;
+use actix_web::{web, HttpResponse, get};
+use serde::{Serialize, Deserialize};
+
+use crate::db::{self, Calendar, Closure, Database};
+use crate::{recurring, AppState, WorkHoursRequest};
+
+// Country base -> company -> team -> personal overlay leaves plenty of room
+const MAX_DEPTH: usize = 16;
+
+// Calendar with every inherited setting filled in
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResolvedCalendar {
+    pub id: String,
+    // Calendars the settings were taken from, root first
+    pub chain: Vec<String>,
+    pub country: String,
+    pub subdivision: Option<String>,
+    #[serde(rename = "startOfDay")]
+    pub start_of_day: String,
+    #[serde(rename = "endOfDay")]
+    pub end_of_day: String,
+    pub weekend: Vec<String>,
+    pub closures: Vec<Closure>,
+}
+
+// Merge a chain of calendars ordered from the root to the most specific overlay.
+// Settings of a more specific calendar win; closures accumulate and a closure for
+// the same day replaces the inherited one.
+pub fn merge(chain: &[Calendar]) -> ResolvedCalendar {
+    let mut resolved = ResolvedCalendar {
+        id: chain.last().map(|c| c.id.clone()).unwrap_or_default(),
+        chain: vec![],
+        country: String::new(),
+        subdivision: None,
+        start_of_day: crate::default_start_of_day(),
+        end_of_day: crate::default_end_of_day(),
+        weekend: db::default_weekend(),
+        closures: vec![],
+    };
+    for calendar in chain {
+        resolved.chain.push(calendar.id.clone());
+        if !calendar.country.is_empty() {
+            // A different country does not keep the subdivision of its parent
+            resolved.country = calendar.country.clone();
+            resolved.subdivision = calendar.subdivision.clone();
+        } else if calendar.subdivision.is_some() {
+            resolved.subdivision = calendar.subdivision.clone();
+        }
+        if let Some(start_of_day) = &calendar.start_of_day {
+            resolved.start_of_day = start_of_day.clone();
+        }
+        if let Some(end_of_day) = &calendar.end_of_day {
+            resolved.end_of_day = end_of_day.clone();
+        }
+        if let Some(weekend) = &calendar.weekend {
+            resolved.weekend = weekend.clone();
+        }
+        for closure in &calendar.closures {
+            match resolved.closures.iter_mut()
+                .find(|c| c.date == closure.date && c.recurring == closure.recurring)
+            {
+                Some(inherited) => *inherited = closure.clone(),
+                None => resolved.closures.push(closure.clone()),
+            }
+        }
+    }
+    resolved
+}
+
+// Load a calendar and its ancestors, None when the calendar itself does not exist
+pub fn resolve(db: &Database, id: &str) -> Result<Option<ResolvedCalendar>, String> {
+    let mut chain: Vec<Calendar> = vec![];
+    let mut current = id.to_string();
+    loop {
+        let calendar = match db.get_calendar(&current).map_err(|e| e.to_string())? {
+            Some(calendar) => calendar,
+            None if chain.is_empty() => return Ok(None),
+            None => return Err(format!("Calendar {} inherits from unknown calendar {}", chain[chain.len() - 1].id, current)),
+        };
+        if chain.iter().any(|c| c.id == calendar.id) || chain.len() >= MAX_DEPTH {
+            return Err(format!("Calendar inheritance of {} is cyclic or deeper than {} levels", id, MAX_DEPTH));
+        }
+        let parent = calendar.parent.clone();
+        chain.push(calendar);
+        match parent {
+            Some(parent) => current = parent,
+            None => break,
+        }
+    }
+    chain.reverse();
+    Ok(Some(merge(&chain)))
+}
+
+// Calendar named by the calendarId of a calculation
+pub async fn load(data: &web::Data<AppState>, id: &str) -> Result<ResolvedCalendar, actix_web::error::Error> {
+    let calendar_id = id.to_string();
+    data.db.blocking(move |db| Ok(resolve(db, &calendar_id))).await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .map_err(actix_web::error::ErrorUnprocessableEntity)?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Unknown calendar: {}", id)))
+}
+
+// The calendar replaces the country, subdivision, hours and weekend of the request, None without a calendarId.
+// Its closures are added to the holidays by the calculation.
+pub async fn apply(data: &web::Data<AppState>, req: &WorkHoursRequest) -> Result<Option<WorkHoursRequest>, actix_web::error::Error> {
+    let calendar = match &req.calendar_id {
+        Some(id) => load(data, id).await?,
+        None => return Ok(None),
+    };
+    let mut applied = req.clone();
+    applied.country = calendar.country;
+    applied.subdivision = calendar.subdivision;
+    applied.start_of_day = calendar.start_of_day;
+    applied.end_of_day = calendar.end_of_day;
+    // Checked when the calendar was stored
+    applied.weekend = Some(calendar.weekend.iter().filter_map(|day| day.parse().ok()).collect());
+    Ok(Some(applied))
+}
+
+// Closure days of the calendar in the given years, as company holidays
+pub fn closure_holidays(calendar: &ResolvedCalendar, years: RangeInclusive<i32>) -> Vec<db::Holiday> {
+    let mut holidays = vec![];
+    let mut yearly = vec![];
+    for closure in &calendar.closures {
+        match (&closure.date, &closure.recurring) {
+            (Some(date), _) => holidays.push(db::Holiday {
+                id: None,
+                date: date.clone(),
+                description: closure.description.clone(),
+                country: calendar.country.clone(),
+                portion: db::HolidayPortion::Full,
+                kind: db::HolidayKind::Company,
+                subdivision: None,
+            }),
+            (None, Some(rule)) => yearly.push(db::RecurringHoliday {
+                id: None,
+                country: calendar.country.clone(),
+                rule: rule.clone(),
+                description: closure.description.clone(),
+                kind: db::HolidayKind::Company,
+            }),
+            (None, None) => {}
+        }
+    }
+    holidays.extend(recurring::expand(&yearly, years));
+    holidays
+}
+
+#[get("/calendars/{id}/resolved")]
+pub async fn get_resolved_calendar(
+    data: web::Data<AppState>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let calendar_id = id.to_string();
+    let calendar = data.db.blocking(move |db| Ok(resolve(db, &calendar_id))).await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .map_err(actix_web::error::ErrorUnprocessableEntity)?;
+    match calendar {
+        Some(calendar) => Ok(HttpResponse::Ok().json(calendar)),
+        None => Ok(HttpResponse::NotFound().json(format!("Calendar {} not found", id))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar_config;
+
+    const CONFIG: &str = r#"
+calendars:
+  - id: fr
+    country: FR
+    closures:
+      - recurring: "12-24"
+        description: Christmas Eve
+  - id: acme-fr
+    parent: fr
+    endOfDay: "18:00:00"
+    closures:
+      - date: "2025-08-14"
+        description: Plant shutdown
+  - id: acme-fr-support
+    parent: acme-fr
+    weekend: [sun]
+    closures:
+      - recurring: "12-24"
+        description: Christmas Eve afternoon only
+"#;
+
+    #[test]
+    fn test_overlays_override_their_parents() {
+        let db = Database::new(":memory:").unwrap();
+        calendar_config::apply(&db, &calendar_config::parse(CONFIG).unwrap(), "test").unwrap();
+
+        let resolved = resolve(&db, "acme-fr-support").unwrap().unwrap();
+        assert_eq!(resolved.chain, vec!["fr", "acme-fr", "acme-fr-support"]);
+        assert_eq!(resolved.country, "fr");
+        assert_eq!(resolved.start_of_day, "09:00:00");
+        assert_eq!(resolved.end_of_day, "18:00:00");
+        assert_eq!(resolved.weekend, vec!["sun"]);
+        assert_eq!(resolved.closures.len(), 2);
+        assert_eq!(resolved.closures[0].description, "Christmas Eve afternoon only");
+
+        // The company calendar is not affected by the team overlay
+        let resolved = resolve(&db, "acme-fr").unwrap().unwrap();
+        assert_eq!(resolved.weekend, vec!["sat", "sun"]);
+        assert_eq!(resolved.closures[0].description, "Christmas Eve");
+
+        assert!(resolve(&db, "unknown").unwrap().is_none());
+    }
+}
@@ -0,0 +1,101 @@
+use actix_web::{web, HttpRequest, HttpResponse, get, post};
+
+use crate::calendar_config::author;
+use crate::{events, freeze, AppState};
+
+#[get("/calendars/{id}/versions")]
+pub async fn list_versions(
+    data: web::Data<AppState>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let calendar_id = id.to_string();
+    let versions = data.db.blocking(move |db| db.get_calendar_versions(&calendar_id)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if versions.is_empty() {
+        return Ok(HttpResponse::NotFound().json(format!("Calendar {} has no history", id)));
+    }
+    Ok(HttpResponse::Ok().json(versions))
+}
+
+// Restore a calendar as it was after the given version, the rollback itself becomes a new version
+#[post("/calendars/{id}/rollback/{version}")]
+pub async fn rollback(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    path: web::Path<(String, i64)>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let (id, version) = path.into_inner();
+    let author = format!("{} (rollback to version {})", author(&http_req), version);
+    freeze::ensure_unfrozen(&data.db).await?;
+    let (calendar_id, stored_author) = (id.clone(), author.clone());
+    let versions = data.db.blocking(move |db| {
+        let target = match db.get_calendar_version(&calendar_id, version)? {
+            Some(target) => target,
+            None => return Ok(None),
+        };
+        match &target.snapshot {
+            Some(calendar) => db.upsert_calendar(calendar, &stored_author),
+            None => db.delete_calendar(&calendar_id, &stored_author),
+        }?;
+        db.get_calendar_versions(&calendar_id).map(Some)
+    }).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    let versions = match versions {
+        Some(versions) => versions,
+        None => return Ok(HttpResponse::NotFound().json(format!("Calendar {} has no version {}", id, version))),
+    };
+    events::publish(events::Event::CalendarChanged {
+        calendar_id: id,
+        author,
+        action: "rollback".to_string(),
+    });
+    Ok(HttpResponse::Ok().json(versions.last()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::calendar_config;
+    use crate::db::{self, CalendarVersion};
+
+    #[actix_rt::test]
+    async fn test_rollback_restores_previous_version() {
+        let database = db::Database::new(":memory:").unwrap();
+        let config = calendar_config::parse("calendars:\n  - id: acme\n    country: FR\n").unwrap();
+        calendar_config::apply(&database, &config, "alice").unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(
+            App::new().app_data(data.clone()).service(calendar_config::apply_config).service(list_versions).service(rollback)
+        ).await;
+
+        // A bad bulk edit moves the calendar to another country and shortens the day
+        let req = test::TestRequest::post()
+            .uri("/admin/apply")
+            .insert_header(("X-User", "bob"))
+            .set_payload("calendars:\n  - id: acme\n    country: DE\n    endOfDay: \"12:00:00\"\n")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+        let req = test::TestRequest::get().uri("/calendars/acme/versions").to_request();
+        let versions: Vec<CalendarVersion> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].author, "alice");
+        assert_eq!(versions[0].changes, vec!["created"]);
+        assert_eq!(versions[1].author, "bob");
+        assert_eq!(versions[1].changes, vec!["country", "endOfDay"]);
+
+        let req = test::TestRequest::post().uri("/calendars/acme/rollback/1").insert_header(("X-User", "carol")).to_request();
+        let version: CalendarVersion = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(version.version, 3);
+        assert_eq!(version.author, "carol (rollback to version 1)");
+        assert_eq!(version.changes, vec!["country", "endOfDay"]);
+
+        let calendar = data.db.get_calendar("acme").unwrap().unwrap();
+        assert_eq!(calendar.country, "fr");
+        assert_eq!(calendar.end_of_day, Some("17:00:00".to_string()));
+
+        let req = test::TestRequest::post().uri("/calendars/acme/rollback/9").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+
+// Reduced capacity over the first weeks from a date, e.g. a new hire at 50% for 4 weeks then 75%
+// for 2 weeks. Days after the last step, and before the start, are at full capacity.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Ramp {
+    // First day of the ramp (YYYY-MM-DD)
+    pub start: String,
+    pub steps: Vec<RampStep>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RampStep {
+    #[serde(default)]
+    pub weeks: u32,
+    #[serde(default)]
+    pub days: u32,
+    // Share of the day's working time available, from 0 to 1
+    pub capacity: f64,
+}
+
+// Capacity of each step, by the day after its last one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Multipliers {
+    start: NaiveDate,
+    steps: Vec<(NaiveDate, f64)>,
+}
+
+impl Multipliers {
+    pub fn on(&self, date: NaiveDate) -> f64 {
+        if date < self.start {
+            return 1.0;
+        }
+        self.steps.iter()
+            .find(|(until, _)| date < *until)
+            .map_or(1.0, |(_, capacity)| *capacity)
+    }
+}
+
+impl Ramp {
+    pub fn multipliers(&self) -> Result<Multipliers, String> {
+        let start = NaiveDate::parse_from_str(&self.start, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid ramp start date: {}", self.start))?;
+        let mut until = start;
+        let mut steps = vec![];
+        for step in &self.steps {
+            if !(0.0..=1.0).contains(&step.capacity) {
+                return Err(format!("Ramp capacity must be between 0 and 1: {}", step.capacity));
+            }
+            let days = step.weeks as i64 * 7 + step.days as i64;
+            if days == 0 {
+                return Err("Ramp steps must last at least one day".to_string());
+            }
+            until += chrono::Duration::days(days);
+            steps.push((until, step.capacity));
+        }
+        Ok(Multipliers { start, steps })
+    }
+}
+
+// Keep the first share of a day's working intervals: 50% of 09:00-12:00 and 13:00-18:00 is 09:00-12:00 and 13:00-14:00
+pub fn scale(intervals: Vec<(DateTime<Tz>, DateTime<Tz>)>, capacity: f64) -> Vec<(DateTime<Tz>, DateTime<Tz>)> {
+    let total: i64 = intervals.iter().map(|(start, end)| (*end - *start).num_seconds()).sum();
+    crate::split_working_time(intervals, (total as f64 * capacity).round() as i64).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_multipliers() {
+        let ramp = Ramp {
+            start: "2025-01-06".to_string(),
+            steps: vec![
+                RampStep { weeks: 4, days: 0, capacity: 0.5 },
+                RampStep { weeks: 0, days: 10, capacity: 0.75 },
+            ],
+        };
+        let multipliers = ramp.multipliers().unwrap();
+        assert_eq!(multipliers.on(date("2025-01-03")), 1.0);
+        assert_eq!(multipliers.on(date("2025-01-06")), 0.5);
+        assert_eq!(multipliers.on(date("2025-02-02")), 0.5);
+        assert_eq!(multipliers.on(date("2025-02-03")), 0.75);
+        assert_eq!(multipliers.on(date("2025-02-13")), 1.0);
+
+        let invalid = Ramp { start: "2025-01-06".to_string(), steps: vec![RampStep { weeks: 1, days: 0, capacity: 1.5 }] };
+        assert!(invalid.multipliers().is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use actix_web::{web, HttpResponse, post};
+use chrono::{DateTime, Datelike, NaiveDate};
+use chrono_tz::Tz;
+use futures_util::stream;
+use serde::{Serialize, Deserialize};
+
+use crate::{compute_work_hours, AppState, DurationType, EndOrDuration, TimezoneMode, WorkHoursRequest};
+
+// Longest range of POST /chunked, which still computes every year it is given
+const DEFAULT_MAX_CHUNKED_YEARS: u32 = 200;
+
+pub fn max_chunked_years() -> u32 {
+    std::env::var("MAX_CHUNKED_YEARS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_CHUNKED_YEARS)
+}
+
+// Work hours of one chunk of the range, with the running total so far
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ChunkResult {
+    pub start_date: String,
+    pub end_date: String,
+    pub work_hours: f64,
+    pub cumulative_hours: f64,
+}
+
+// Split a range at each local new year, every chunk stays well within the range limit
+fn chunks(start_date: DateTime<Tz>, end_date: DateTime<Tz>) -> Vec<(DateTime<Tz>, DateTime<Tz>)> {
+    let timezone = start_date.timezone();
+    let mut chunks = vec![];
+    let mut cursor = start_date;
+    while cursor < end_date {
+        let next_year = NaiveDate::from_ymd_opt(cursor.year() + 1, 1, 1).unwrap();
+        let next = crate::resolve_local(&timezone, next_year.and_hms_opt(0, 0, 0).unwrap()).min(end_date);
+        chunks.push((cursor, next));
+        cursor = next;
+    }
+    chunks
+}
+
+struct ChunkState {
+    data: web::Data<AppState>,
+    request: WorkHoursRequest,
+    chunks: std::vec::IntoIter<(DateTime<Tz>, DateTime<Tz>)>,
+    cumulative_hours: f64,
+    failed: bool,
+}
+
+// Compute a range of any length year by year, streamed as newline-delimited JSON so that
+// a huge range neither hits the range limit nor holds a worker until the very end
+#[post("/chunked")]
+pub async fn calculate_chunked(
+    data: web::Data<AppState>,
+    req: web::Json<WorkHoursRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let req = req.into_inner();
+    if req.duration_type == DurationType::Working {
+        return Err(actix_web::error::ErrorBadRequest("Working durations cannot be computed in chunks"));
+    }
+    let timezone: Tz = req.timezone.parse()
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone: {}", e)))?;
+    let intervals = crate::resolve_intervals(&req)?;
+    let start_date = crate::parse_request_date(&req.start_date, "start", timezone, req.timezone_mode, |date| crate::start_of_schedule(date, &intervals))?;
+    let end_date = match &req.end_or_duration {
+        EndOrDuration::EndDate { end_date } => {
+            crate::parse_request_date(end_date, "end", timezone, req.timezone_mode, |date| crate::end_of_schedule(date, &intervals))?
+        }
+        EndOrDuration::Duration { duration_seconds } => start_date + chrono::Duration::seconds(*duration_seconds),
+    };
+    if start_date >= end_date {
+        return Err(actix_web::error::ErrorBadRequest("Start date must be strictly before end date"));
+    }
+    let years = max_chunked_years();
+    let limit = start_date.date_naive().checked_add_months(chrono::Months::new(years * 12));
+    if limit.is_some_and(|limit| end_date.date_naive() > limit) {
+        return Err(actix_web::error::ErrorUnprocessableEntity(format!(
+            "Date range {} - {} exceeds the maximum of {} years of chunked calculations",
+            start_date.to_rfc3339(), end_date.to_rfc3339(), years
+        )));
+    }
+
+    let state = ChunkState {
+        data,
+        request: WorkHoursRequest {
+            timezone_mode: TimezoneMode::Convert,
+            aggregate: None,
+            overtime: None,
+            include_intervals: false,
+            ..req
+        },
+        chunks: chunks(start_date, end_date).into_iter(),
+        cumulative_hours: 0.0,
+        failed: false,
+    };
+    let lines = stream::unfold(state, |mut state| async move {
+        if state.failed {
+            return None;
+        }
+        let (start, end) = state.chunks.next()?;
+        let mut request = state.request.clone();
+        request.start_date = start.to_rfc3339();
+        request.end_or_duration = EndOrDuration::EndDate { end_date: end.to_rfc3339() };
+        let line = match compute_work_hours(&state.data, &request).await {
+            Ok(response) => {
+                state.cumulative_hours += response.work_hours;
+                serde_json::to_string(&ChunkResult {
+                    start_date: response.start_date,
+                    end_date: response.end_date,
+                    work_hours: response.work_hours,
+                    cumulative_hours: state.cumulative_hours,
+                })
+            }
+            Err(e) => {
+                state.failed = true;
+                serde_json::to_string(&serde_json::json!({ "error": e.to_string() }))
+            }
+        };
+        let line = web::Bytes::from(line.unwrap_or_default() + "\n");
+        Some((Ok::<_, std::convert::Infallible>(line), state))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::db;
+
+    #[actix_rt::test]
+    async fn test_long_ranges_are_rejected_or_streamed_in_chunks() {
+        let data = web::Data::new(AppState { db: db::Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(
+            App::new().app_data(data.clone()).service(crate::get_work_hours).service(calculate_chunked)
+        ).await;
+        let body = |end_date: &str| serde_json::json!({
+            "startDate": "2023-10-02", "endDate": end_date, "country": "us", "timezone": "UTC"
+        });
+
+        let req = test::TestRequest::post().uri("/").set_json(body("9999-12-31")).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 422);
+
+        let req = test::TestRequest::post().uri("/chunked").set_json(body("2025-01-31")).to_request();
+        let response = test::call_and_read_body(&app, req).await;
+        let chunks: Vec<ChunkResult> = std::str::from_utf8(&response).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[1].start_date, "2024-01-01T00:00:00+00:00");
+        assert_eq!(chunks[2].end_date, "2025-01-31T17:00:00+00:00");
+
+        let req = test::TestRequest::post().uri("/").set_json(body("2025-01-31")).to_request();
+        let direct: crate::WorkHoursResponse = test::call_and_read_body_json(&app, req).await;
+        assert!((chunks[2].cumulative_hours - direct.work_hours).abs() < 1e-9);
+
+        let req = test::TestRequest::post().uri("/chunked").set_json(body("9999-12-31")).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 422);
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashSet;
+use actix_web::{web, HttpResponse, get};
+use serde_json::Value;
+
+use crate::openapi;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    TypeScript,
+    Python,
+}
+
+impl Language {
+    pub fn parse(value: &str) -> Option<Language> {
+        match value.to_lowercase().as_str() {
+            "typescript" | "ts" => Some(Language::TypeScript),
+            "python" | "py" => Some(Language::Python),
+            _ => None,
+        }
+    }
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Language::TypeScript => "workhours-client.ts",
+            Language::Python => "workhours_client.py",
+        }
+    }
+}
+
+// Operation of the OpenAPI document a client method is generated for
+struct Operation {
+    name: Vec<String>,
+    method: String,
+    path: String,
+    summary: String,
+    // Name and schema
+    path_params: Vec<(String, Value)>,
+    query_params: Vec<String>,
+    body: Option<Value>,
+}
+
+// Lowercase words of a path segment or parameter name, e.g. startDate and start-date are start, date
+fn words(value: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for c in value.chars() {
+        let boundary = !c.is_ascii_alphanumeric() || (c.is_ascii_uppercase() && previous_lower);
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if c.is_ascii_alphanumeric() {
+            word.push(c.to_ascii_lowercase());
+        }
+        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+// Method names come from the HTTP method and the path, e.g. get /holidays/{country} is get_holidays_by_country
+fn operations(spec: &Value) -> Vec<Operation> {
+    let mut operations = vec![];
+    let mut names = HashSet::new();
+    let paths = spec["paths"].as_object().cloned().unwrap_or_default();
+    for (path, methods) in paths {
+        for (method, operation) in methods.as_object().cloned().unwrap_or_default() {
+            let mut name = vec![method.clone()];
+            for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+                match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                    Some(param) => {
+                        name.push("by".to_string());
+                        name.extend(words(param));
+                    }
+                    None => name.extend(words(segment)),
+                }
+            }
+            if name.len() == 1 {
+                name.push("root".to_string());
+            }
+            let mut unique = name.clone();
+            let mut n = 2;
+            while !names.insert(unique.join("_")) {
+                unique = name.iter().cloned().chain(std::iter::once(n.to_string())).collect();
+                n += 1;
+            }
+            let params = operation["parameters"].as_array().cloned().unwrap_or_default();
+            let named = |location: &str| -> Vec<(String, Value)> {
+                params.iter()
+                    .filter(|param| param["in"] == location)
+                    .filter_map(|param| param["name"].as_str().map(|name| (name.to_string(), param["schema"].clone())))
+                    .collect()
+            };
+            operations.push(Operation {
+                name: unique,
+                method: method.to_uppercase(),
+                path: path.clone(),
+                summary: operation["summary"].as_str().unwrap_or_default().to_string(),
+                path_params: named("path"),
+                query_params: named("query").into_iter().map(|(name, _)| name).collect(),
+                body: operation["requestBody"]["content"]["application/json"]["schema"].as_object().map(|schema| Value::Object(schema.clone())),
+            });
+        }
+    }
+    operations
+}
+
+fn camel_case(words: &[String]) -> String {
+    words.iter().enumerate()
+        .map(|(i, word)| if i == 0 { word.clone() } else { word[..1].to_uppercase() + &word[1..] })
+        .collect()
+}
+
+fn ref_name(schema: &Value) -> Option<&str> {
+    schema["$ref"].as_str().and_then(|reference| reference.rsplit('/').next())
+}
+
+fn typescript_type(schema: &Value) -> String {
+    if let Some(name) = ref_name(schema) {
+        return name.to_string();
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        return values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(" | ");
+    }
+    match schema["type"].as_str() {
+        Some("string") => "string".to_string(),
+        Some("integer") | Some("number") => "number".to_string(),
+        Some("boolean") => "boolean".to_string(),
+        Some("array") => format!("{}[]", typescript_type(&schema["items"])),
+        Some("object") if schema["properties"].is_object() => {
+            let required = required(schema);
+            let fields: Vec<String> = schema["properties"].as_object().unwrap().iter()
+                .map(|(name, property)| format!("{}{}: {}", quoted_ts(name), if required.contains(name) { "" } else { "?" }, typescript_type(property)))
+                .collect();
+            format!("{{ {} }}", fields.join("; "))
+        }
+        Some("object") => "Record<string, unknown>".to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn quoted_ts(name: &str) -> String {
+    if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') { name.to_string() } else { format!("\"{}\"", name) }
+}
+
+fn python_type(schema: &Value) -> String {
+    if let Some(name) = ref_name(schema) {
+        return format!("\"{}\"", name);
+    }
+    match schema["type"].as_str() {
+        Some("string") => "str".to_string(),
+        Some("integer") => "int".to_string(),
+        Some("number") => "float".to_string(),
+        Some("boolean") => "bool".to_string(),
+        Some("array") => format!("List[{}]", python_type(&schema["items"])),
+        Some("object") => "Dict[str, Any]".to_string(),
+        _ => "Any".to_string(),
+    }
+}
+
+fn required(schema: &Value) -> HashSet<String> {
+    schema["required"].as_array().map(|names| {
+        names.iter().filter_map(|name| name.as_str().map(|name| name.to_string())).collect()
+    }).unwrap_or_default()
+}
+
+fn schemas(spec: &Value) -> Vec<(String, Value)> {
+    spec["components"]["schemas"].as_object().cloned().unwrap_or_default().into_iter().collect()
+}
+
+pub fn typescript(spec: &Value) -> String {
+    let mut out = format!(
+        "// Generated from the Work Hours API OpenAPI document, version {}. Do not edit.\n\n",
+        spec["info"]["version"].as_str().unwrap_or_default()
+    );
+    for (name, schema) in schemas(spec) {
+        out += &format!("export type {} = {};\n\n", name, typescript_type(&schema));
+    }
+    out += "export class WorkHoursClient {\n";
+    out += "  constructor(private baseUrl: string, private fetchImpl: typeof fetch = fetch) {}\n\n";
+    out += "  private async request(method: string, path: string, query?: Record<string, unknown>, body?: unknown): Promise<any> {\n";
+    out += "    const url = new URL(this.baseUrl.replace(/\\/$/, \"\") + path);\n";
+    out += "    for (const [key, value] of Object.entries(query ?? {})) {\n";
+    out += "      if (value !== undefined && value !== null) url.searchParams.set(key, String(value));\n";
+    out += "    }\n";
+    out += "    const response = await this.fetchImpl(url.toString(), {\n";
+    out += "      method,\n";
+    out += "      headers: body === undefined ? undefined : { \"Content-Type\": \"application/json\" },\n";
+    out += "      body: body === undefined ? undefined : JSON.stringify(body),\n";
+    out += "    });\n";
+    out += "    if (!response.ok) throw new Error(`${method} ${path} failed with status ${response.status}: ${await response.text()}`);\n";
+    out += "    const type = response.headers.get(\"Content-Type\") ?? \"\";\n";
+    out += "    return type.includes(\"json\") ? response.json() : response.text();\n";
+    out += "  }\n";
+    for operation in operations(spec) {
+        let mut args: Vec<String> = operation.path_params.iter()
+            .map(|(param, schema)| format!("{}: {}", camel_case(&words(param)), typescript_type(schema)))
+            .collect();
+        if let Some(body) = &operation.body {
+            args.push(format!("body: {}", typescript_type(body)));
+        }
+        if !operation.query_params.is_empty() {
+            let fields: Vec<String> = operation.query_params.iter().map(|param| format!("{}?: string | number | boolean", quoted_ts(param))).collect();
+            args.push(format!("query: {{ {} }} = {{}}", fields.join("; ")));
+        }
+        let mut path = operation.path.clone();
+        for (param, _) in &operation.path_params {
+            path = path.replace(&format!("{{{}}}", param), &format!("${{encodeURIComponent({})}}", camel_case(&words(param))));
+        }
+        out += &format!("\n  /** {} */\n", operation.summary);
+        out += &format!("  {}({}): Promise<any> {{\n", camel_case(&operation.name), args.join(", "));
+        out += &format!(
+            "    return this.request(\"{}\", `{}`, {}, {});\n  }}\n",
+            operation.method,
+            path,
+            if operation.query_params.is_empty() { "undefined" } else { "query" },
+            if operation.body.is_some() { "body" } else { "undefined" },
+        );
+    }
+    out += "}\n";
+    out
+}
+
+pub fn python(spec: &Value) -> String {
+    let mut out = format!(
+        "# Generated from the Work Hours API OpenAPI document, version {}. Do not edit.\n\n",
+        spec["info"]["version"].as_str().unwrap_or_default()
+    );
+    out += "import json\nimport urllib.parse\nimport urllib.request\nfrom typing import Any, Dict, List, Optional, TypedDict\n\n";
+    for (name, schema) in schemas(spec) {
+        let properties = schema["properties"].as_object().cloned().unwrap_or_default();
+        // Request and response fields are not all valid identifiers, hence the functional syntax
+        let fields: Vec<String> = properties.iter()
+            .map(|(field, property)| format!("\"{}\": {}", field, python_type(property)))
+            .collect();
+        out += &format!("{} = TypedDict(\"{}\", {{{}}}, total=False)\n\n", name, name, fields.join(", "));
+    }
+    out += "\nclass WorkHoursClient:\n";
+    out += "    def __init__(self, base_url: str, timeout: float = 30.0):\n";
+    out += "        self.base_url = base_url.rstrip(\"/\")\n";
+    out += "        self.timeout = timeout\n\n";
+    out += "    def _request(self, method: str, path: str, query: Optional[Dict[str, Any]] = None, body: Any = None) -> Any:\n";
+    out += "        url = self.base_url + path\n";
+    out += "        query = {key: value for key, value in (query or {}).items() if value is not None}\n";
+    out += "        if query:\n";
+    out += "            url += \"?\" + urllib.parse.urlencode(query)\n";
+    out += "        data = None if body is None else json.dumps(body).encode()\n";
+    out += "        headers = {} if body is None else {\"Content-Type\": \"application/json\"}\n";
+    out += "        request = urllib.request.Request(url, data=data, headers=headers, method=method)\n";
+    out += "        with urllib.request.urlopen(request, timeout=self.timeout) as response:\n";
+    out += "            content = response.read()\n";
+    out += "            if \"json\" in response.headers.get(\"Content-Type\", \"\"):\n";
+    out += "                return json.loads(content) if content else None\n";
+    out += "            return content.decode()\n";
+    for operation in operations(spec) {
+        let mut args = vec!["self".to_string()];
+        args.extend(operation.path_params.iter().map(|(param, schema)| format!("{}: {}", words(param).join("_"), python_type(schema))));
+        if let Some(body) = &operation.body {
+            args.push(format!("body: {}", python_type(body)));
+        }
+        args.extend(operation.query_params.iter().map(|param| format!("{}: Any = None", words(param).join("_"))));
+        let mut path = operation.path.clone();
+        for (param, _) in &operation.path_params {
+            path = path.replace(&format!("{{{}}}", param), &format!("{{urllib.parse.quote(str({}), safe='')}}", words(param).join("_")));
+        }
+        let query: Vec<String> = operation.query_params.iter()
+            .map(|param| format!("\"{}\": {}", param, words(param).join("_")))
+            .collect();
+        out += &format!("\n    def {}({}) -> Any:\n", operation.name.join("_"), args.join(", "));
+        out += &format!("        \"\"\"{}\"\"\"\n", operation.summary.replace('"', "'"));
+        out += &format!(
+            "        return self._request(\"{}\", f\"{}\", {}, {})\n",
+            operation.method,
+            path,
+            if query.is_empty() { "None".to_string() } else { format!("{{{}}}", query.join(", ")) },
+            if operation.body.is_some() { "body" } else { "None" },
+        );
+    }
+    out
+}
+
+// Client stubs matching the routes of the deployed version, generated from its OpenAPI document
+#[get("/admin/clients/{lang}")]
+pub async fn download_client(lang: web::Path<String>) -> Result<HttpResponse, actix_web::error::Error> {
+    let language = Language::parse(&lang)
+        .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Unsupported client language: {}, expected typescript or python", lang)))?;
+    let spec: Value = serde_json::from_str(&openapi::swagger_spec())
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let (source, content_type) = match language {
+        Language::TypeScript => (typescript(&spec), "application/typescript; charset=utf-8"),
+        Language::Python => (python(&spec), "text/x-python; charset=utf-8"),
+    };
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", language.file_name())))
+        .body(source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_clients_cover_the_documented_routes() {
+        let app = test::init_service(App::new().service(download_client)).await;
+
+        let req = test::TestRequest::get().uri("/admin/clients/typescript").to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers().get("Content-Disposition").unwrap(), "attachment; filename=\"workhours-client.ts\"");
+        let source = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+        assert!(source.contains("export type WorkHoursRequest = {"));
+        assert!(source.contains("deleteBlackoutsByCalendarById(calendar: string, id: number): Promise<any>"));
+        assert!(source.contains("`/blackouts/${encodeURIComponent(calendar)}/${encodeURIComponent(id)}`"));
+
+        let req = test::TestRequest::get().uri("/admin/clients/py").to_request();
+        let source = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+        assert!(source.contains("class WorkHoursClient:"));
+        assert!(source.contains("def post_staffing_check(self, body: Dict[str, Any]) -> Any:"));
+        assert!(source.contains("f\"/blackouts/{urllib.parse.quote(str(calendar), safe='')}/{urllib.parse.quote(str(id), safe='')}\""));
+
+        assert_eq!(words("startDate"), vec!["start", "date"]);
+        assert_eq!(words("follow-the-sun"), vec!["follow", "the", "sun"]);
+
+        let req = test::TestRequest::get().uri("/admin/clients/cobol").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+}
@@ -0,0 +1,275 @@
+// Canonical scenarios a holiday provider has to pass before it is deployed, for third-party
+// backends plugged in through HolidayProvider. Run with the conformance feature:
+//
+//     let report = conformance::run(Box::new(MyProvider::new()), &Suite::default()).await;
+//     assert!(report.passed(), "{:#?}", report.failures());
+//
+// Expected working time is recomputed from the holidays the provider returns, with the
+// default 09:00-17:00 schedule, so any country with data can be checked. Holidays are stored
+// in the built-in SQLite database, only providers are pluggable and checked here.
+use std::collections::{BTreeMap, HashSet};
+use actix_web::web;
+use chrono::{Datelike, NaiveDate, Weekday};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+
+use crate::country::CountryCode;
+use crate::db::{Calendar, Database, HolidayKind, HolidayPortion};
+use crate::holiday_provider::HolidayProvider;
+use crate::{compute_work_hours, AppState, EndOrDuration, WorkHoursRequest};
+
+const WEEKEND: [Weekday; 2] = [Weekday::Sat, Weekday::Sun];
+
+// Calendar of the suite's country with a Friday and Saturday weekend, as in the Gulf states
+const FRI_SAT_CALENDAR: &str = "conformance-fri-sat";
+
+// Country the provider is checked for, with the timezone of its calendar
+#[derive(Debug, Clone)]
+pub struct Suite {
+    pub country: String,
+    pub subdivision: String,
+    pub timezone: String,
+    pub years: Vec<i32>,
+}
+
+impl Default for Suite {
+    fn default() -> Self {
+        Suite {
+            country: "fr".to_string(),
+            subdivision: String::new(),
+            timezone: "Europe/Paris".to_string(),
+            years: vec![2024, 2025],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Outcome {
+    pub scenario: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Report {
+    pub provider: String,
+    pub outcomes: Vec<Outcome>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.passed)
+    }
+
+    pub fn failures(&self) -> Vec<&Outcome> {
+        self.outcomes.iter().filter(|outcome| !outcome.passed).collect()
+    }
+}
+
+fn outcome(scenario: &str, result: Result<String, String>) -> Outcome {
+    let (passed, detail) = match result {
+        Ok(detail) => (true, detail),
+        Err(detail) => (false, detail),
+    };
+    Outcome { scenario: scenario.to_string(), passed, detail }
+}
+
+// Holidays of the year with their portion and kind, out of the year and the next one a provider may return together
+async fn year_holidays(data: &web::Data<AppState>, suite: &Suite, year: i32) -> Result<Vec<(NaiveDate, HolidayPortion, HolidayKind)>, String> {
+    let first = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+    let country = CountryCode::parse(&suite.country)?;
+    let holidays = data.holidays.holidays(&data.db, &country, &suite.subdivision, first).await?;
+    let dated = holidays.iter()
+        .map(|holiday| NaiveDate::parse_from_str(&holiday.date, "%Y-%m-%d")
+            .map(|date| (date, holiday.portion, holiday.kind))
+            .map_err(|_| format!("Holiday date is not YYYY-MM-DD: {}", holiday.date)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some((date, _, _)) = dated.iter().find(|(date, _, _)| !(year..=year + 1).contains(&date.year())) {
+        return Err(format!("Holiday {} returned for {}", date, year));
+    }
+    Ok(dated.into_iter().filter(|(date, _, _)| date.year() == year).collect())
+}
+
+async fn holiday_dates(data: &web::Data<AppState>, suite: &Suite, year: i32) -> Result<Vec<NaiveDate>, String> {
+    Ok(year_holidays(data, suite, year).await?.into_iter().map(|(date, _, _)| date).collect())
+}
+
+// Dates are valid, inside the requested year or the next one, listed once, and the same on a second call
+async fn check_holidays(data: &web::Data<AppState>, suite: &Suite, year: i32) -> Result<String, String> {
+    let dates = holiday_dates(data, suite, year).await?;
+    if dates.is_empty() {
+        return Err(format!("No holidays for {} {}", suite.country, year));
+    }
+    let unique: HashSet<&NaiveDate> = dates.iter().collect();
+    if unique.len() != dates.len() {
+        return Err(format!("{} duplicate holiday dates in {}", dates.len() - unique.len(), year));
+    }
+    let again: HashSet<NaiveDate> = holiday_dates(data, suite, year).await?.into_iter().collect();
+    if again != unique.into_iter().cloned().collect() {
+        return Err(format!("A second lookup of {} returned other holidays", year));
+    }
+    Ok(format!("{} holidays", dates.len()))
+}
+
+// Working time between two local midnights, compared with the days outside the weekend that
+// are not days off. A half-day holiday leaves 4 of the 8 hours.
+async fn check_range(
+    data: &web::Data<AppState>,
+    suite: &Suite,
+    start: NaiveDate,
+    end: NaiveDate,
+    weekend: &[Weekday],
+    calendar_id: Option<&str>,
+) -> Result<String, String> {
+    let timezone: Tz = suite.timezone.parse().map_err(|e| format!("Invalid timezone {}: {}", suite.timezone, e))?;
+    let mut days_off: BTreeMap<NaiveDate, HolidayPortion> = BTreeMap::new();
+    for year in start.year()..=end.year() {
+        for (date, portion, kind) in year_holidays(data, suite, year).await? {
+            if HolidayKind::DAYS_OFF.contains(&kind) {
+                let combined = days_off.get(&date).map_or(portion, |previous| previous.combine(portion));
+                days_off.insert(date, combined);
+            }
+        }
+    }
+    let expected: f64 = start.iter_days().take_while(|date| *date < end)
+        .filter(|date| !weekend.contains(&date.weekday()))
+        .map(|date| match days_off.get(&date) {
+            Some(HolidayPortion::Full) => 0.0,
+            Some(_) => 4.0,
+            None => 8.0,
+        })
+        .sum();
+
+    let midnight = |date: NaiveDate| crate::resolve_local(&timezone, date.and_hms_opt(0, 0, 0).unwrap()).to_rfc3339();
+    let request = WorkHoursRequest {
+        start_date: midnight(start),
+        end_or_duration: EndOrDuration::EndDate { end_date: midnight(end) },
+        start_of_day: crate::default_start_of_day(),
+        end_of_day: crate::default_end_of_day(),
+        country: suite.country.clone(),
+        subdivision: Some(suite.subdivision.clone()).filter(|subdivision| !subdivision.is_empty()),
+        calendar_id: calendar_id.map(str::to_string),
+        timezone: suite.timezone.clone(),
+        ..Default::default()
+    };
+    // Calculated like a request, with the calendar applied
+    let hours = compute_work_hours(data, &request).await.map_err(|e| e.to_string())?.work_hours;
+    if hours != expected {
+        return Err(format!("{} to {}: {} hours, expected {}", start, end, hours, expected));
+    }
+    Ok(format!("{} to {}: {} hours", start, end, hours))
+}
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+// The last Sunday of the month, when European clocks change
+fn last_sunday(year: i32, month: u32) -> NaiveDate {
+    let next = if month == 12 { date(year + 1, 1, 1) } else { date(year, month + 1, 1) };
+    let last = next.pred_opt().unwrap();
+    last - chrono::Duration::days(last.weekday().num_days_from_sunday() as i64)
+}
+
+// Weeks holding the half-day holidays of the year, from Monday to the next Monday
+async fn check_half_days(data: &web::Data<AppState>, suite: &Suite, year: i32) -> Result<String, String> {
+    let half_days: Vec<NaiveDate> = year_holidays(data, suite, year).await?.into_iter()
+        .filter(|(_, portion, kind)| !portion.is_full() && HolidayKind::DAYS_OFF.contains(kind))
+        .map(|(date, _, _)| date)
+        .collect();
+    if half_days.is_empty() {
+        return Ok(format!("No half-day holidays in {}", year));
+    }
+    for half_day in &half_days {
+        let monday = *half_day - chrono::Duration::days(half_day.weekday().num_days_from_monday() as i64);
+        check_range(data, suite, monday, monday + chrono::Duration::days(7), &WEEKEND, None).await?;
+    }
+    Ok(format!("Half-day holidays on {}", half_days.iter().map(NaiveDate::to_string).collect::<Vec<_>>().join(", ")))
+}
+
+pub async fn run(provider: Box<dyn HolidayProvider>, suite: &Suite) -> Report {
+    let name = provider.name().to_string();
+    let database = Database::new(":memory:").expect("in-memory database");
+    database.upsert_calendar(&Calendar {
+        id: FRI_SAT_CALENDAR.to_string(),
+        parent: None,
+        country: suite.country.clone(),
+        subdivision: Some(suite.subdivision.clone()).filter(|subdivision| !subdivision.is_empty()),
+        start_of_day: None,
+        end_of_day: None,
+        weekend: Some(vec!["fri".to_string(), "sat".to_string()]),
+        closures: vec![],
+    }, "conformance").expect("conformance calendar");
+    let data = web::Data::new(AppState { db: database, holidays: provider, http: reqwest::Client::new() });
+
+    let mut outcomes = vec![];
+    for &year in &suite.years {
+        outcomes.push(outcome(&format!("holidays-{}", year), check_holidays(&data, suite, year).await));
+        // Weeks ending with the spring and autumn clock changes: 167 and 169 hours long
+        for (scenario, month) in [("dst-spring-week", 3), ("dst-autumn-week", 10)] {
+            let sunday = last_sunday(year, month);
+            outcomes.push(outcome(
+                &format!("{}-{}", scenario, year),
+                check_range(&data, suite, sunday - chrono::Duration::days(6), sunday + chrono::Duration::days(1), &WEEKEND, None).await,
+            ));
+        }
+        outcomes.push(outcome(
+            &format!("year-boundary-{}", year),
+            check_range(&data, suite, date(year, 12, 22), date(year + 1, 1, 8), &WEEKEND, None).await,
+        ));
+        // May holds several holidays in most countries, some of them on a Friday
+        outcomes.push(outcome(
+            &format!("fri-sat-weekend-{}", year),
+            check_range(&data, suite, date(year, 5, 1), date(year, 6, 1), &[Weekday::Fri, Weekday::Sat], Some(FRI_SAT_CALENDAR)).await,
+        ));
+        outcomes.push(outcome(&format!("half-day-holidays-{}", year), check_half_days(&data, suite, year).await));
+    }
+    Report { provider: name, outcomes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use crate::holiday_provider::EmbeddedProvider;
+    use crate::holidays_api::Holiday;
+
+    // Embedded holidays with Christmas Eve as an afternoon off
+    struct HalfDayProvider;
+
+    #[async_trait]
+    impl HolidayProvider for HalfDayProvider {
+        fn name(&self) -> &'static str {
+            "half-day"
+        }
+
+        async fn holidays(&self, db: &Database, country: &CountryCode, subdivision: &str, date: NaiveDate) -> Result<Vec<Holiday>, String> {
+            let mut holidays = EmbeddedProvider.holidays(db, country, subdivision, date).await?;
+            for year in date.year()..=date.year() + 1 {
+                holidays.push(Holiday { date: format!("{}-12-24", year), description: "Christmas Eve".to_string(), portion: HolidayPortion::Afternoon, kind: HolidayKind::Public });
+            }
+            Ok(holidays)
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_embedded_provider_conforms() {
+        let report = run(Box::new(EmbeddedProvider), &Suite::default()).await;
+        assert!(report.passed(), "{:#?}", report.failures());
+        assert_eq!(report.outcomes.len(), 12);
+    }
+
+    #[actix_rt::test]
+    async fn test_half_day_holidays_are_checked() {
+        let report = run(Box::new(HalfDayProvider), &Suite::default()).await;
+        assert!(report.passed(), "{:#?}", report.failures());
+        let half_days = report.outcomes.iter().find(|outcome| outcome.scenario == "half-day-holidays-2024").unwrap();
+        assert_eq!(half_days.detail, "Half-day holidays on 2024-12-24");
+    }
+
+    #[test]
+    fn test_last_sunday() {
+        assert_eq!(last_sunday(2024, 3), date(2024, 3, 31));
+        assert_eq!(last_sunday(2025, 10), date(2025, 10, 26));
+    }
+}
@@ -0,0 +1,207 @@
+use std::env;
+use std::time::Duration;
+use actix_web::web;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use log::{info, warn};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::{compute_work_hours, AppState, WorkHoursRequest, WorkHoursResponse};
+
+// Pause before reconnecting after the broker went away
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+// Calculation request read from the queue, the id is echoed back to correlate the result
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueuedRequest {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub request: WorkHoursRequest,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueuedResult {
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<WorkHoursResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConsumerConfig {
+    pub backend: String,
+    pub subject: String,
+    pub reply_subject: String,
+    pub group: String,
+}
+
+impl ConsumerConfig {
+    // Consumer mode is enabled by CONSUMER_BACKEND (nats or kafka)
+    pub fn from_env() -> Option<Self> {
+        let backend = env::var("CONSUMER_BACKEND").ok()?.to_lowercase();
+        Some(ConsumerConfig {
+            backend,
+            subject: env::var("CONSUMER_SUBJECT").unwrap_or_else(|_| "workhours.requests".to_string()),
+            reply_subject: env::var("CONSUMER_REPLY_SUBJECT").unwrap_or_else(|_| "workhours.results".to_string()),
+            group: env::var("CONSUMER_GROUP").unwrap_or_else(|_| "workhours".to_string()),
+        })
+    }
+}
+
+// Compute one queued request, failures are reported in the result rather than dropped
+pub async fn handle(data: &web::Data<AppState>, payload: &[u8]) -> QueuedResult {
+    let queued: QueuedRequest = match serde_json::from_slice(payload) {
+        Ok(queued) => queued,
+        Err(e) => return QueuedResult { id: None, result: None, error: Some(format!("Invalid request: {}", e)) },
+    };
+    match compute_work_hours(data, &queued.request).await {
+        Ok(response) => QueuedResult { id: queued.id, result: Some(response), error: None },
+        Err(e) => QueuedResult { id: queued.id, result: None, error: Some(e.to_string()) },
+    }
+}
+
+pub fn spawn(config: ConsumerConfig, data: web::Data<AppState>) {
+    info!("Consuming calculation requests from {} {}", config.backend, config.subject);
+    actix_web::rt::spawn(async move {
+        loop {
+            let result = match config.backend.as_str() {
+                "nats" => consume_nats(&config, &data).await,
+                "kafka" => consume_kafka(&config, &data).await,
+                other => {
+                    warn!("Unsupported consumer backend {}, consumer mode disabled", other);
+                    return;
+                }
+            };
+            if let Err(e) = result {
+                warn!("Request consumer stopped: {}, reconnecting", e);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+// NATS text protocol: queue-group subscription so that replicas share the requests,
+// results go to the message reply subject when set, the configured reply subject otherwise
+async fn consume_nats(config: &ConsumerConfig, data: &web::Data<AppState>) -> Result<(), String> {
+    let url = env::var("NATS_URL").unwrap_or_else(|_| "nats://localhost:4222".to_string());
+    let stream = TcpStream::connect(url.trim_start_matches("nats://")).await.map_err(|e| e.to_string())?;
+    let mut stream = BufReader::new(stream);
+    let mut line = String::new();
+    stream.read_line(&mut line).await.map_err(|e| e.to_string())?;
+    let subscribe = format!(
+        "CONNECT {{\"verbose\":false,\"name\":\"workhours-consumer\"}}\r\nSUB {} {} 1\r\n",
+        config.subject, config.group
+    );
+    stream.get_mut().write_all(subscribe.as_bytes()).await.map_err(|e| e.to_string())?;
+
+    loop {
+        line.clear();
+        if stream.read_line(&mut line).await.map_err(|e| e.to_string())? == 0 {
+            return Err("connection closed".to_string());
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["PING"] => stream.get_mut().write_all(b"PONG\r\n").await.map_err(|e| e.to_string())?,
+            ["MSG", _subject, _sid, rest @ ..] if !rest.is_empty() => {
+                let (reply_to, size) = match rest {
+                    [reply_to, size] => (Some(reply_to.to_string()), size),
+                    [size] => (None, size),
+                    _ => return Err(format!("Malformed NATS message: {}", line.trim())),
+                };
+                let size: usize = size.parse().map_err(|_| format!("Malformed NATS message: {}", line.trim()))?;
+                // Payload followed by CRLF
+                let mut payload = vec![0; size + 2];
+                stream.read_exact(&mut payload).await.map_err(|e| e.to_string())?;
+                payload.truncate(size);
+
+                let result = serde_json::to_string(&handle(data, &payload).await).unwrap_or_default();
+                let subject = reply_to.unwrap_or_else(|| config.reply_subject.clone());
+                let publish = format!("PUB {} {}\r\n{}\r\n", subject, result.len(), result);
+                stream.get_mut().write_all(publish.as_bytes()).await.map_err(|e| e.to_string())?;
+            }
+            ["-ERR", ..] => return Err(line.trim().to_string()),
+            _ => {}
+        }
+    }
+}
+
+// Kafka through its REST proxy: a consumer instance in the consumer group polls the request topic
+async fn consume_kafka(config: &ConsumerConfig, data: &web::Data<AppState>) -> Result<(), String> {
+    let url = env::var("KAFKA_REST_URL").map_err(|_| "KAFKA_REST_URL is not set".to_string())?;
+    let url = url.trim_end_matches('/');
+    let client = reqwest::Client::new();
+    let content_type = "application/vnd.kafka.v2+json";
+
+    let instance: serde_json::Value = client
+        .post(format!("{}/consumers/{}", url, config.group))
+        .header("Content-Type", content_type)
+        .body(json!({ "format": "json", "auto.offset.reset": "earliest" }).to_string())
+        .send().await.map_err(|e| e.to_string())?
+        .json().await.map_err(|e| e.to_string())?;
+    let base_uri = instance["base_uri"].as_str()
+        .ok_or_else(|| format!("Unexpected consumer instance: {}", instance))?
+        .to_string();
+    client
+        .post(format!("{}/subscription", base_uri))
+        .header("Content-Type", content_type)
+        .body(json!({ "topics": [config.subject] }).to_string())
+        .send().await.map_err(|e| e.to_string())?;
+
+    loop {
+        let records: Vec<serde_json::Value> = client
+            .get(format!("{}/records", base_uri))
+            .header("Accept", "application/vnd.kafka.json.v2+json")
+            .send().await.map_err(|e| e.to_string())?
+            .json().await.map_err(|e| e.to_string())?;
+        if records.is_empty() {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            continue;
+        }
+        let mut results = vec![];
+        for record in records {
+            let payload = record["value"].to_string();
+            results.push(json!({ "value": handle(data, payload.as_bytes()).await }));
+        }
+        let response = client
+            .post(format!("{}/topics/{}", url, config.reply_subject))
+            .header("Content-Type", "application/vnd.kafka.json.v2+json")
+            .body(json!({ "records": results }).to_string())
+            .send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Kafka REST proxy returned {}", response.status()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holiday_provider::MockProvider;
+    use crate::db;
+
+    #[actix_rt::test]
+    async fn test_queued_requests_are_answered() {
+        let data = web::Data::new(AppState { db: db::Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+
+        let payload = json!({
+            "id": "job-42",
+            "request": {
+                "startDate": "2023-10-02T09:00:00Z", "endDate": "2023-10-02T17:00:00Z",
+                "country": "us", "timezone": "UTC"
+            }
+        });
+        let result = handle(&data, payload.to_string().as_bytes()).await;
+        assert_eq!(result.id, Some("job-42".to_string()));
+        assert_eq!(result.result.unwrap().work_hours, 8.0);
+
+        // Errors are reported back with the correlation id
+        let payload = json!({ "id": "job-43", "request": { "startDate": "2023-10-02T09:00:00Z", "endDate": "2023-10-01T09:00:00Z", "timezone": "UTC" } });
+        let result = handle(&data, payload.to_string().as_bytes()).await;
+        assert_eq!(result.id, Some("job-43".to_string()));
+        assert!(result.error.unwrap().contains("strictly before"));
+
+        assert!(handle(&data, b"not json").await.error.is_some());
+    }
+}
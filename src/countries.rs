@@ -0,0 +1,222 @@
+use std::time::Duration;
+use actix_web::{web, HttpResponse, get};
+use lazy_static::lazy_static;
+use log::{error, info};
+use moka::future::Cache;
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+
+use crate::country::{self, CountryCode};
+use crate::{holidays_api, AppState};
+
+// Countries and subdivisions known to OpenHolidays, for clients filling their country and subdivision pickers
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Country {
+    // Lowercase, as accepted by the other endpoints
+    pub code: String,
+    pub name: String,
+    #[serde(rename = "officialLanguages")]
+    pub official_languages: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SubdivisionEntry {
+    // Uppercase, as accepted in the subdivision parameters, e.g. DE-BY
+    pub code: String,
+    #[serde(rename = "shortName")]
+    pub short_name: String,
+    pub name: String,
+    // e.g. federal state, canton
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    // Subdivision this one belongs to, the list is flat
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LocalizedText {
+    language: String,
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiCountry {
+    #[serde(rename = "isoCode")]
+    iso_code: String,
+    #[serde(default)]
+    name: Vec<LocalizedText>,
+    #[serde(rename = "officialLanguages", default)]
+    official_languages: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiSubdivision {
+    code: String,
+    #[serde(rename = "shortName", default)]
+    short_name: String,
+    #[serde(default)]
+    name: Vec<LocalizedText>,
+    #[serde(default)]
+    category: Vec<LocalizedText>,
+    #[serde(default)]
+    children: Vec<ApiSubdivision>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LanguageParams {
+    pub language: Option<String>,
+}
+
+// Text in the requested language, or the first one given
+fn localized(texts: &[LocalizedText], language: &str) -> Option<String> {
+    texts.iter()
+        .find(|text| text.language.eq_ignore_ascii_case(language))
+        .or_else(|| texts.first())
+        .map(|text| text.text.clone())
+}
+
+fn to_countries(api_countries: Vec<ApiCountry>, language: &str) -> Vec<Country> {
+    let mut countries: Vec<Country> = api_countries.into_iter()
+        .map(|api_country| Country {
+            code: api_country.iso_code.to_lowercase(),
+            name: localized(&api_country.name, language).unwrap_or_else(|| api_country.iso_code.clone()),
+            official_languages: api_country.official_languages,
+        })
+        .collect();
+    countries.sort_by(|a, b| a.code.cmp(&b.code));
+    countries
+}
+
+// Nested subdivisions (e.g. districts of a state) follow their parent
+fn to_subdivisions(api_subdivisions: Vec<ApiSubdivision>, parent: Option<&str>, language: &str, subdivisions: &mut Vec<SubdivisionEntry>) {
+    for api_subdivision in api_subdivisions {
+        let code = api_subdivision.code.to_uppercase();
+        subdivisions.push(SubdivisionEntry {
+            code: code.clone(),
+            short_name: api_subdivision.short_name,
+            name: localized(&api_subdivision.name, language).unwrap_or_else(|| code.clone()),
+            category: localized(&api_subdivision.category, language),
+            parent: parent.map(str::to_string),
+        });
+        to_subdivisions(api_subdivision.children, Some(&code), language, subdivisions);
+    }
+}
+
+// The lists rarely change: a week by default, COUNTRIES_CACHE_TTL_SECONDS otherwise
+fn cache_duration() -> Duration {
+    let seconds = std::env::var("COUNTRIES_CACHE_TTL_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(7 * 24 * 60 * 60);
+    Duration::from_secs(seconds)
+}
+
+lazy_static! {
+    // Keyed by language, failures are not cached
+    static ref COUNTRIES: Cache<String, Vec<Country>> = Cache::builder()
+        .max_capacity(100)
+        .time_to_live(cache_duration())
+        .build();
+    // Keyed by country and language
+    static ref SUBDIVISIONS: Cache<(CountryCode, String), Vec<SubdivisionEntry>> = Cache::builder()
+        .max_capacity(1_000)
+        .time_to_live(cache_duration())
+        .build();
+}
+
+async fn fetch<T: DeserializeOwned>(client: &reqwest::Client, url: &str) -> Result<T, String> {
+    info!("Fetching {} from OpenHolidays", url);
+    let response = holidays_api::get_with_retry(client, "openholidays", url, "miss").await
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("API request failed with status: {}", response.status()));
+    }
+    response.json().await.map_err(|e| format!("Failed to parse API response: {}", e))
+}
+
+pub async fn countries(client: &reqwest::Client, language: &str) -> Result<Vec<Country>, String> {
+    COUNTRIES.try_get_with(language.to_string(), async {
+        let url = format!("https://openholidaysapi.org/Countries?languageIsoCode={}", language);
+        fetch(client, &url).await.map(|api_countries| to_countries(api_countries, language))
+    }).await.map_err(|e| e.to_string())
+}
+
+pub async fn subdivisions(client: &reqwest::Client, country: &CountryCode, language: &str) -> Result<Vec<SubdivisionEntry>, String> {
+    SUBDIVISIONS.try_get_with((country.clone(), language.to_string()), async {
+        let url = format!("https://openholidaysapi.org/Subdivisions?countryIsoCode={}&languageIsoCode={}", country.api_code(), language);
+        let api_subdivisions: Vec<ApiSubdivision> = fetch(client, &url).await?;
+        let mut subdivisions = vec![];
+        to_subdivisions(api_subdivisions, None, language, &mut subdivisions);
+        Ok::<_, String>(subdivisions)
+    }).await.map_err(|e| e.to_string())
+}
+
+fn request_language(params: &LanguageParams) -> Result<String, actix_web::error::Error> {
+    match &params.language {
+        Some(language) => {
+            holidays_api::validate_language(language).map_err(actix_web::error::ErrorBadRequest)?;
+            Ok(language.to_uppercase())
+        }
+        None => Ok(holidays_api::default_language()),
+    }
+}
+
+#[get("/countries")]
+pub async fn list_countries(
+    data: web::Data<AppState>,
+    params: web::Query<LanguageParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let language = request_language(&params)?;
+    match countries(&data.http, &language).await {
+        Ok(countries) => Ok(HttpResponse::Ok().json(countries)),
+        Err(e) => {
+            error!("Failed to list countries: {}", e);
+            Err(actix_web::error::ErrorBadGateway(e))
+        }
+    }
+}
+
+#[get("/countries/{country}/subdivisions")]
+pub async fn list_subdivisions(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    params: web::Query<LanguageParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let country = country::parse_request(&path)?;
+    let language = request_language(&params)?;
+    let known = countries(&data.http, &language).await.map_err(actix_web::error::ErrorBadGateway)?;
+    if !known.iter().any(|known| known.code == country.as_str()) {
+        return Ok(HttpResponse::NotFound().json(format!("Unknown country: {}", country)));
+    }
+    match subdivisions(&data.http, &country, &language).await {
+        Ok(subdivisions) => Ok(HttpResponse::Ok().json(subdivisions)),
+        Err(e) => {
+            error!("Failed to list subdivisions of {}: {}", country, e);
+            Err(actix_web::error::ErrorBadGateway(e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upstream_lists_are_localized_and_flattened() {
+        let api_countries: Vec<ApiCountry> = serde_json::from_str(r#"[
+            {"isoCode": "DE", "name": [{"language": "EN", "text": "Germany"}, {"language": "DE", "text": "Deutschland"}], "officialLanguages": ["DE"]},
+            {"isoCode": "AT", "name": [{"language": "DE", "text": "Österreich"}], "officialLanguages": ["DE"]}
+        ]"#).unwrap();
+        let countries = to_countries(api_countries, "EN");
+        assert_eq!(countries[0], Country { code: "at".to_string(), name: "Österreich".to_string(), official_languages: vec!["DE".to_string()] });
+        assert_eq!(countries[1].name, "Germany");
+
+        let api_subdivisions: Vec<ApiSubdivision> = serde_json::from_str(r#"[
+            {"code": "DE-BY", "shortName": "BY", "name": [{"language": "EN", "text": "Bavaria"}, {"language": "DE", "text": "Bayern"}],
+             "category": [{"language": "EN", "text": "federal state"}],
+             "children": [{"code": "DE-BY-AU", "shortName": "AU", "name": [{"language": "DE", "text": "Augsburg"}]}]}
+        ]"#).unwrap();
+        let mut subdivisions = vec![];
+        to_subdivisions(api_subdivisions, None, "DE", &mut subdivisions);
+        let listed: Vec<(&str, &str, Option<&str>)> = subdivisions.iter().map(|s| (s.code.as_str(), s.name.as_str(), s.parent.as_deref())).collect();
+        assert_eq!(listed, vec![("DE-BY", "Bayern", None), ("DE-BY-AU", "Augsburg", Some("DE-BY"))]);
+        assert_eq!(subdivisions[0].category.as_deref(), Some("federal state"));
+    }
+}
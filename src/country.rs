@@ -0,0 +1,171 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Serialize, Deserialize};
+
+// Country code as accepted by the API: an ISO 3166-1 alpha-2 code, or the name of a country group.
+// Stored lowercase like the database and calendars, upstream providers get the uppercase form.
+// Requests without a country, which have no holidays, have no code: see parse_optional.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CountryCode(String);
+
+// Longest country group name
+const MAX_GROUP_NAME: usize = 32;
+
+impl CountryCode {
+    // Two letters, or a group name: a letter followed by letters, digits and dashes
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let code = value.trim().to_lowercase();
+        let well_formed = match code.len() {
+            0 | 1 => false,
+            2 => code.chars().all(|c| c.is_ascii_alphabetic()),
+            len => len <= MAX_GROUP_NAME
+                && code.starts_with(|c: char| c.is_ascii_alphabetic())
+                && !code.ends_with('-')
+                && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'),
+        };
+        if !well_formed {
+            return Err(format!("Invalid country code: {}, expected e.g. FR or a country group name", value));
+        }
+        Ok(CountryCode(code))
+    }
+
+    // None for an empty value, which stands for no country and so no holidays
+    pub fn parse_optional(value: &str) -> Result<Option<Self>, String> {
+        if value.trim().is_empty() {
+            return Ok(None);
+        }
+        CountryCode::parse(value).map(Some)
+    }
+
+    // Lowercase, as used by the database, calendars and country groups
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    // Uppercase, as expected by the holiday providers
+    pub fn api_code(&self) -> String {
+        self.0.to_uppercase()
+    }
+
+    // Longer than a country code, so possibly the name of a country group
+    pub fn is_group(&self) -> bool {
+        self.0.len() > 2
+    }
+}
+
+impl FromStr for CountryCode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        CountryCode::parse(value)
+    }
+}
+
+impl TryFrom<String> for CountryCode {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        CountryCode::parse(&value)
+    }
+}
+
+impl From<CountryCode> for String {
+    fn from(code: CountryCode) -> Self {
+        code.0
+    }
+}
+
+impl fmt::Display for CountryCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// ISO 3166-2 subdivision of a country, e.g. DE-BY, or DE-BY-AU below it. Kept with its
+// country, whose code has to prefix it, and uppercase like the providers expect.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Subdivision {
+    country: CountryCode,
+    code: String,
+}
+
+impl Subdivision {
+    pub fn parse(country: &CountryCode, value: &str) -> Result<Self, String> {
+        let code = value.trim().to_uppercase();
+        let invalid = || format!("Invalid subdivision code for country {}: {}, expected e.g. {}-XX", country.api_code(), value, country.api_code());
+        let (prefix, rest) = code.split_once('-').ok_or_else(invalid)?;
+        if prefix != country.api_code() || !rest.split('-').all(|part| (1..=3).contains(&part.len()) && part.chars().all(|c| c.is_ascii_alphanumeric())) {
+            return Err(invalid());
+        }
+        Ok(Subdivision { country: country.clone(), code })
+    }
+
+    // None for an empty value, which stands for the whole country
+    pub fn parse_optional(country: &CountryCode, value: &str) -> Result<Option<Self>, String> {
+        if value.trim().is_empty() {
+            return Ok(None);
+        }
+        Subdivision::parse(country, value).map(Some)
+    }
+
+    pub fn country(&self) -> &CountryCode {
+        &self.country
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.code
+    }
+}
+
+impl fmt::Display for Subdivision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.code)
+    }
+}
+
+// Country code of a request or path, rejected with 400 Bad Request when invalid
+pub fn parse_request(value: &str) -> Result<CountryCode, actix_web::error::Error> {
+    CountryCode::parse(value).map_err(actix_web::error::ErrorBadRequest)
+}
+
+// Same as parse_request, None for requests without a country
+pub fn parse_request_optional(value: &str) -> Result<Option<CountryCode>, actix_web::error::Error> {
+    CountryCode::parse_optional(value).map_err(actix_web::error::ErrorBadRequest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalization() {
+        let code = CountryCode::parse(" DE ").unwrap();
+        assert_eq!(code, CountryCode::parse("de").unwrap());
+        assert_eq!(code.as_str(), "de");
+        assert_eq!(code.api_code(), "DE");
+        assert_eq!(CountryCode::parse("eu-team").unwrap().as_str(), "eu-team");
+        assert!(CountryCode::parse("fr;de").is_err());
+        for invalid in ["", " ", "f", "f1", "a_b-c", "1team", "team-", &"x".repeat(33)] {
+            assert!(CountryCode::parse(invalid).is_err(), "{}", invalid);
+        }
+        assert_eq!(CountryCode::parse_optional(" ").unwrap(), None);
+        assert!(CountryCode::parse_optional("f").is_err());
+        assert_eq!(serde_json::from_str::<CountryCode>("\"FR\"").unwrap().as_str(), "fr");
+        assert!(serde_json::from_str::<CountryCode>("\"f r\"").is_err());
+    }
+
+    #[test]
+    fn test_subdivision() {
+        let de = CountryCode::parse("de").unwrap();
+        let subdivision = Subdivision::parse(&de, "de-by").unwrap();
+        assert_eq!(subdivision.as_str(), "DE-BY");
+        assert_eq!(subdivision.country(), &de);
+        assert_eq!(Subdivision::parse(&de, "DE-BY-AU").unwrap().to_string(), "DE-BY-AU");
+        assert_eq!(Subdivision::parse_optional(&de, " ").unwrap(), None);
+        assert!(Subdivision::parse(&de, "FR-IDF").is_err());
+        assert!(Subdivision::parse(&de, "BY").is_err());
+        assert!(Subdivision::parse(&de, "DE-").is_err());
+        assert!(Subdivision::parse(&de, "DE-B Y").is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::env;
+use actix_web::{web, HttpResponse, get, put, delete};
+use lazy_static::lazy_static;
+use serde::{Serialize, Deserialize};
+
+use crate::country::CountryCode;
+use crate::db::{CountryGroup, Database};
+use crate::{freeze, AppState};
+
+lazy_static! {
+    // Groups from the configuration, e.g. COUNTRY_GROUPS=eu-team=fr,de,es;dach=de,at,ch
+    static ref CONFIGURED: BTreeMap<String, Vec<String>> = parse_groups(&env::var("COUNTRY_GROUPS").unwrap_or_default());
+}
+
+fn parse_countries(value: &str) -> Vec<String> {
+    value.split(',')
+        .map(|country| country.trim().to_lowercase())
+        .filter(|country| !country.is_empty())
+        .collect()
+}
+
+fn parse_groups(value: &str) -> BTreeMap<String, Vec<String>> {
+    value.split(';')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(name, countries)| (name.trim().to_lowercase(), parse_countries(countries)))
+        .filter(|(name, countries)| validate(name, countries).is_ok())
+        .collect()
+}
+
+// Groups stand where a country code is accepted, so their names cannot look like one
+// and they only list country codes, groups of groups are not expanded
+fn validate(name: &str, countries: &[String]) -> Result<(), String> {
+    if !CountryCode::parse(name).is_ok_and(|code| code.is_group()) {
+        return Err(format!("Invalid group name {}: it must be longer than a country code, made of letters, digits and dashes", name));
+    }
+    if countries.is_empty() {
+        return Err(format!("Group {} must list at least one country", name));
+    }
+    if let Some(country) = countries.iter().find(|country| country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic())) {
+        return Err(format!("Invalid country code in group {}: {}", name, country));
+    }
+    Ok(())
+}
+
+// Countries of a group, the stored groups taking precedence over the configured ones.
+// None when the name is not a group, i.e. a plain country code.
+pub fn resolve(db: &Database, name: &str) -> Option<Vec<String>> {
+    let name = name.to_lowercase();
+    if name.len() <= 2 {
+        return None;
+    }
+    match db.get_country_group(&name) {
+        Ok(Some(group)) => Some(group.countries),
+        _ => CONFIGURED.get(&name).cloned(),
+    }
+}
+
+fn all_groups(db: &Database) -> Result<Vec<CountryGroup>, rusqlite::Error> {
+    let mut groups: BTreeMap<String, Vec<String>> = CONFIGURED.clone();
+    for group in db.get_country_groups()? {
+        groups.insert(group.name, group.countries);
+    }
+    Ok(groups.into_iter().map(|(name, countries)| CountryGroup { name, countries }).collect())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupRequest {
+    pub countries: Vec<String>,
+}
+
+#[get("/country-groups")]
+pub async fn list_groups(data: web::Data<AppState>) -> Result<HttpResponse, actix_web::error::Error> {
+    let groups = data.db.blocking(all_groups).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(groups))
+}
+
+#[get("/country-groups/{name}")]
+pub async fn get_group(
+    data: web::Data<AppState>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let group = name.to_string();
+    let countries = data.db.blocking(move |db| Ok(resolve(db, &group))).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    match countries {
+        Some(countries) => Ok(HttpResponse::Ok().json(CountryGroup { name: name.to_lowercase(), countries })),
+        None => Ok(HttpResponse::NotFound().json(format!("Country group {} not found", name))),
+    }
+}
+
+#[put("/country-groups/{name}")]
+pub async fn save_group(
+    data: web::Data<AppState>,
+    name: web::Path<String>,
+    req: web::Json<GroupRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let group = CountryGroup {
+        name: name.trim().to_lowercase(),
+        countries: parse_countries(&req.countries.join(",")),
+    };
+    validate(&group.name, &group.countries).map_err(actix_web::error::ErrorBadRequest)?;
+    freeze::ensure_unfrozen(&data.db).await?;
+    let stored = group.clone();
+    data.db.blocking(move |db| db.save_country_group(&stored)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(group))
+}
+
+// Only stored groups can be deleted, configured ones come back with the configuration
+#[delete("/country-groups/{name}")]
+pub async fn delete_group(
+    data: web::Data<AppState>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    freeze::ensure_unfrozen(&data.db).await?;
+    let group = name.to_lowercase();
+    let deleted = data.db.blocking(move |db| db.delete_country_group(&group)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if deleted == 0 {
+        return Ok(HttpResponse::NotFound().json(format!("Country group {} not found", name)));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_resolve_to_countries() {
+        let groups = parse_groups("EU-team = fr, DE,es; us=us; broken=f1; eu_team=fr");
+        assert_eq!(groups.get("eu-team"), Some(&vec!["fr".to_string(), "de".to_string(), "es".to_string()]));
+        assert_eq!(groups.len(), 1);
+
+        let db = Database::new(":memory:").unwrap();
+        assert_eq!(resolve(&db, "fr"), None);
+        assert_eq!(resolve(&db, "dach"), None);
+        db.save_country_group(&CountryGroup { name: "dach".to_string(), countries: vec!["de".to_string(), "at".to_string(), "ch".to_string()] }).unwrap();
+        assert_eq!(resolve(&db, "DACH"), Some(vec!["de".to_string(), "at".to_string(), "ch".to_string()]));
+    }
+}
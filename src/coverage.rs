@@ -0,0 +1,121 @@
+use actix_web::{web, HttpResponse, get};
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+
+use crate::export::{self, ExportFormat};
+use crate::{country, AppState};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CoverageParams {
+    // Comma separated list of country codes, e.g. fr,de,us
+    pub countries: String,
+    // Single year (2025) or inclusive range (2024-2026)
+    pub years: String,
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CoverageEntry {
+    pub country: String,
+    pub year: i32,
+    pub provider: bool,
+    pub database: bool,
+    // "provider", "database_only" or "missing"
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CoverageResponse {
+    pub entries: Vec<CoverageEntry>,
+    pub missing: usize,
+}
+
+// Guard against accidental reports over centuries
+const MAX_YEARS: i32 = 20;
+
+fn parse_years(value: &str) -> Result<(i32, i32), String> {
+    let (from, to) = match value.split_once('-') {
+        Some((from, to)) => (from, to),
+        None => (value, value),
+    };
+    let from: i32 = from.trim().parse().map_err(|_| format!("Invalid year: {}", from))?;
+    let to: i32 = to.trim().parse().map_err(|_| format!("Invalid year: {}", to))?;
+    for year in [from, to] {
+        if NaiveDate::from_ymd_opt(year, 1, 1).is_none() {
+            return Err(format!("Invalid year: {}", year));
+        }
+    }
+    if from > to {
+        return Err(format!("Invalid year range: {}", value));
+    }
+    if to - from >= MAX_YEARS {
+        return Err(format!("Year range cannot exceed {} years", MAX_YEARS));
+    }
+    Ok((from, to))
+}
+
+#[get("/admin/coverage")]
+pub async fn get_coverage(
+    data: web::Data<AppState>,
+    params: web::Query<CoverageParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let (from, to) = parse_years(&params.years).map_err(actix_web::error::ErrorBadRequest)?;
+    let countries = params.countries
+        .split(',')
+        .filter(|c| !c.trim().is_empty())
+        .map(country::parse_request)
+        .collect::<Result<Vec<_>, _>>()?;
+    if countries.is_empty() {
+        return Err(actix_web::error::ErrorBadRequest("At least one country must be provided"));
+    }
+
+    let mut entries = vec![];
+    for country in &countries {
+        let stored_country = country.clone();
+        let stored = data.db.blocking(move |db| db.get_holidays_by_country(&stored_country, None)).await
+            .unwrap_or_default();
+        for year in from..=to {
+            let prefix = year.to_string();
+            let first_day = NaiveDate::from_ymd_opt(year, 1, 1)
+                .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Invalid year: {}", year)))?;
+            let provider = crate::fetch_provider_holidays(&data, country, "", first_day, None)
+                .await
+                .is_ok_and(|holidays| holidays.iter().any(|h| h.date.starts_with(&prefix)));
+            let database = stored.iter().any(|h| h.date.starts_with(&prefix));
+            let status = if provider {
+                "provider"
+            } else if database {
+                "database_only"
+            } else {
+                "missing"
+            };
+            entries.push(CoverageEntry {
+                country: country.to_string(),
+                year,
+                provider,
+                database,
+                status: status.to_string(),
+            });
+        }
+    }
+
+    let missing = entries.iter().filter(|e| e.status == "missing").count();
+    let response = CoverageResponse { entries, missing };
+    export::respond(params.format, &response, &response.entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_years() {
+        assert_eq!(parse_years("2024-2026"), Ok((2024, 2026)));
+        assert_eq!(parse_years("2025"), Ok((2025, 2025)));
+        assert!(parse_years("2026-2024").is_err());
+        assert!(parse_years("abc").is_err());
+        assert!(parse_years("999999").is_err());
+        assert!(parse_years("2024-999999").is_err());
+    }
+}
@@ -0,0 +1,421 @@
+use actix_web::{web, HttpRequest, HttpResponse, delete, get, post, put};
+use chrono::{DateTime, NaiveDate, SecondsFormat};
+use serde::{Serialize, Deserialize};
+
+use crate::db::{Holiday, HolidayKind, HolidayPortion, HolidayQuery, RecurringHoliday, Upsert};
+use crate::recurring::RecurringRule;
+use crate::country::{CountryCode, Subdivision};
+use crate::calendar_config::author;
+use crate::{country, freeze, AppState};
+
+// Holiday of a single day, or repeated every year with a recurring rule
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewHoliday {
+    // YYYY-MM-DD or RFC3339
+    #[serde(default)]
+    pub date: Option<String>,
+    // MM-DD, or a weekday of a month such as "last monday of may"
+    #[serde(default)]
+    pub recurring: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub kind: HolidayKind,
+    // Half days only apply to dated holidays
+    #[serde(default)]
+    pub portion: HolidayPortion,
+    // Subdivision observing a dated holiday (e.g. DE-BY), the whole country otherwise
+    #[serde(default)]
+    pub subdivision: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListParams {
+    pub subdivision: Option<String>,
+    // Days, inclusive, as YYYY-MM-DD. A year narrows them to its days.
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub year: Option<i32>,
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub offset: u32,
+    #[serde(default)]
+    pub order: Order,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct AddedHolidays {
+    pub holidays: Vec<Holiday>,
+    pub recurring: Vec<RecurringHoliday>,
+    // Ids of the dated holidays that replaced one already added for the same day
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub updated: Vec<i32>,
+}
+
+// Dates are stored in canonical form, chrono also reads unpadded days and months such as 2025-8-1
+fn normalize_date(value: &str) -> Result<String, actix_web::error::Error> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|date| date.to_string())
+        .or_else(|_| DateTime::parse_from_rfc3339(value).map(|instant| instant.to_rfc3339_opts(SecondsFormat::AutoSi, true)))
+        .map_err(|_| actix_web::error::ErrorBadRequest(format!("Invalid holiday date: {}", value)))
+}
+
+// Holiday of a single day, its subdivision checked against the country
+fn dated_holiday(country: &CountryCode, date: &str, holiday: &NewHoliday) -> Result<Holiday, actix_web::error::Error> {
+    let date = normalize_date(date)?;
+    let subdivision = match &holiday.subdivision {
+        Some(subdivision) => Some(Subdivision::parse(country, subdivision).map_err(actix_web::error::ErrorBadRequest)?.to_string()),
+        None => None,
+    };
+    Ok(Holiday {
+        id: None,
+        date,
+        description: holiday.description.clone(),
+        country: country.to_string(),
+        portion: holiday.portion,
+        kind: holiday.kind,
+        subdivision,
+    })
+}
+
+// Adds custom holidays to a country. Recurring ones are stored once and expanded for
+// whichever years a calculation touches.
+#[post("/holidays/{country}")]
+pub async fn add_holidays(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    country: web::Path<String>,
+    req: web::Json<Vec<NewHoliday>>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let country = country::parse_request(&country)?;
+    // Validate everything before storing anything
+    let mut added = AddedHolidays::default();
+    for holiday in req.iter() {
+        match (&holiday.date, &holiday.recurring) {
+            (Some(date), None) => added.holidays.push(dated_holiday(&country, date, holiday)?),
+            (None, Some(_)) if holiday.subdivision.is_some() => {
+                return Err(actix_web::error::ErrorBadRequest("Subdivisions only apply to dated holidays"));
+            }
+            (None, Some(rule)) => {
+                let rule: RecurringRule = rule.parse().map_err(actix_web::error::ErrorBadRequest)?;
+                added.recurring.push(RecurringHoliday {
+                    id: None,
+                    country: country.to_string(),
+                    rule: rule.to_string(),
+                    description: holiday.description.clone(),
+                    kind: holiday.kind,
+                });
+            }
+            _ => return Err(actix_web::error::ErrorBadRequest("Holidays must define exactly one of date or recurring")),
+        }
+    }
+
+    freeze::ensure_unfrozen(&data.db).await?;
+    let author = author(&http_req);
+    let added = data.db.blocking(move |db| {
+        for holiday in added.holidays.iter_mut() {
+            let (id, upsert) = db.add_holiday(holiday, &author)?;
+            holiday.id = Some(id);
+            if upsert == Upsert::Updated {
+                added.updated.push(id);
+            }
+        }
+        for holiday in added.recurring.iter_mut() {
+            holiday.id = Some(db.add_recurring_holiday(holiday)?);
+        }
+        Ok(added)
+    }).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(added))
+}
+
+fn parse_day(value: &Option<String>, name: &str) -> Result<Option<NaiveDate>, actix_web::error::Error> {
+    value.as_deref().map(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d")
+        .map_err(|_| actix_web::error::ErrorBadRequest(format!("Invalid {} date: {}", name, day))))
+        .transpose()
+}
+
+fn holiday_query(params: &ListParams) -> Result<HolidayQuery, actix_web::error::Error> {
+    let (mut from, mut to) = (parse_day(&params.from, "from")?, parse_day(&params.to, "to")?);
+    if let Some(year) = params.year {
+        let first_day = NaiveDate::from_ymd_opt(year, 1, 1)
+            .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Invalid year: {}", year)))?;
+        let last_day = NaiveDate::from_ymd_opt(year, 12, 31).unwrap();
+        from = from.max(Some(first_day));
+        to = Some(to.map_or(last_day, |to| to.min(last_day)));
+    }
+    Ok(HolidayQuery { from, to, limit: params.limit, offset: params.offset, descending: params.order == Order::Desc })
+}
+
+// Holidays stored for a country, added or kept from the providers: the nationwide ones,
+// plus the regional ones of the subdivision when one is given. Sorted by day and paged
+// with limit and offset, X-Total-Count giving the number of holidays on all pages.
+#[get("/holidays/{country}")]
+pub async fn list_holidays(
+    data: web::Data<AppState>,
+    country: web::Path<String>,
+    params: web::Query<ListParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let country = country::parse_request(&country)?;
+    let subdivision = Subdivision::parse_optional(&country, params.subdivision.as_deref().unwrap_or_default())
+        .map_err(actix_web::error::ErrorBadRequest)?;
+    let query = holiday_query(&params)?;
+    let (holidays, total) = data.db.blocking(move |db| db.get_holidays_page(&country, subdivision.as_ref(), &query)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().insert_header(("X-Total-Count", total.to_string())).json(holidays))
+}
+
+// Under /id, clear of the year listing at GET /holidays/{country}/{year}
+#[get("/holidays/{country}/id/{id}")]
+pub async fn get_holiday(
+    data: web::Data<AppState>,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let (country, id) = path.into_inner();
+    let country = country::parse_request(&country)?;
+    let holiday = data.db.blocking(move |db| db.get_holiday(&country, id)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    match holiday {
+        Some(holiday) => Ok(HttpResponse::Ok().json(holiday)),
+        None => Ok(HttpResponse::NotFound().json(format!("Holiday {} not found", id))),
+    }
+}
+
+// Replaces a dated holiday, recurring ones are not stored in the holidays table
+#[put("/holidays/{country}/id/{id}")]
+pub async fn update_holiday(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    path: web::Path<(String, i32)>,
+    req: web::Json<NewHoliday>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let (country, id) = path.into_inner();
+    let country = country::parse_request(&country)?;
+    let holiday = match (&req.date, &req.recurring) {
+        (Some(date), None) => dated_holiday(&country, date, &req)?,
+        _ => return Err(actix_web::error::ErrorBadRequest("Holidays are updated with a date and no recurring rule")),
+    };
+    freeze::ensure_unfrozen(&data.db).await?;
+    let author = author(&http_req);
+    let (updated, mut holiday) = data.db.blocking(move |db| Ok((db.update_holiday(&country, id, &holiday, &author), holiday))).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let updated = match updated {
+        Err(rusqlite::Error::SqliteFailure(error, _)) if error.code == rusqlite::ErrorCode::ConstraintViolation => {
+            return Ok(HttpResponse::Conflict().json(format!("A holiday was already added on {}", holiday.date)));
+        }
+        updated => updated.map_err(actix_web::error::ErrorInternalServerError)?,
+    };
+    if updated == 0 {
+        return Ok(HttpResponse::NotFound().json(format!("Holiday {} not found", id)));
+    }
+    holiday.id = Some(id);
+    Ok(HttpResponse::Ok().json(holiday))
+}
+
+#[delete("/holidays/{country}/id/{id}")]
+pub async fn delete_holiday(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let (country, id) = path.into_inner();
+    let country = country::parse_request(&country)?;
+    freeze::ensure_unfrozen(&data.db).await?;
+    let author = author(&http_req);
+    let found = data.db.blocking(move |db| {
+        if db.get_holiday(&country, id)?.is_none() {
+            return Ok(false);
+        }
+        db.delete_holiday(id, &author)?;
+        Ok(true)
+    }).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    if !found {
+        return Ok(HttpResponse::NotFound().json(format!("Holiday {} not found", id)));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::db::Database;
+    use crate::holiday_provider::MockProvider;
+    use crate::{compute_work_hours, EndOrDuration, WorkHoursRequest};
+
+    #[actix_rt::test]
+    async fn test_recurring_holidays_are_expanded_for_each_year() {
+        let data = web::Data::new(AppState { db: Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data.clone()).service(add_holidays)).await;
+        let req = test::TestRequest::post().uri("/holidays/US")
+            .set_json(serde_json::json!([
+                { "recurring": "Last Monday of May", "description": "Memorial Day" },
+                { "date": "2025-06-02", "description": "Company retreat", "kind": "company" }
+            ]))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), 201);
+        let added: AddedHolidays = test::read_body_json(response).await;
+        assert_eq!(added.recurring[0].rule, "last mon of may");
+        assert_eq!(added.holidays[0].kind, HolidayKind::Company);
+
+        // Both years have their Memorial Day without a row per year
+        for (start, end, hours) in [("2025-05-26", "2025-06-06", 64.0), ("2026-05-25", "2026-05-29", 32.0)] {
+            let request = WorkHoursRequest {
+                start_date: start.to_string(),
+                end_or_duration: EndOrDuration::EndDate { end_date: end.to_string() },
+                start_of_day: crate::default_start_of_day(),
+                end_of_day: crate::default_end_of_day(),
+                country: "us".to_string(),
+                timezone: "UTC".to_string(),
+                ..Default::default()
+            };
+            assert_eq!(compute_work_hours(&data, &request).await.unwrap().work_hours, hours);
+        }
+
+        let req = test::TestRequest::post().uri("/holidays/us")
+            .set_json(serde_json::json!([{ "date": "2025-07-04", "recurring": "07-04" }]))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+
+    #[actix_rt::test]
+    async fn test_recurring_holidays_are_expanded_over_multi_year_ranges() {
+        let data = web::Data::new(AppState { db: Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data.clone()).service(add_holidays)).await;
+        let request = WorkHoursRequest {
+            start_date: "2025-01-01".to_string(),
+            end_or_duration: EndOrDuration::EndDate { end_date: "2028-12-31".to_string() },
+            start_of_day: crate::default_start_of_day(),
+            end_of_day: crate::default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            ..Default::default()
+        };
+        let before = compute_work_hours(&data, &request).await.unwrap().work_hours;
+        let req = test::TestRequest::post().uri("/holidays/us")
+            .set_json(serde_json::json!([{ "recurring": "12-27", "description": "Year-end closure" }]))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+
+        // December 27 falls on a weekday in 2027 and 2028 only, past the first two years of the range
+        let after = compute_work_hours(&data, &request).await.unwrap().work_hours;
+        assert_eq!(before - after, 16.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_regional_holidays_are_listed_for_their_subdivision() {
+        let data = web::Data::new(AppState { db: Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data.clone()).service(add_holidays).service(list_holidays)).await;
+        let req = test::TestRequest::post().uri("/holidays/de")
+            .set_json(serde_json::json!([
+                { "date": "2025-10-03", "description": "German Unity Day" },
+                { "date": "2025-08-15", "description": "Assumption Day", "subdivision": "de-by" }
+            ]))
+            .to_request();
+        let added: AddedHolidays = test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(added.holidays[1].subdivision.as_deref(), Some("DE-BY"));
+
+        for (uri, count) in [("/holidays/de", 1), ("/holidays/de?subdivision=DE-BY", 2), ("/holidays/de?subdivision=DE-BW", 1)] {
+            let holidays: Vec<Holiday> = test::read_body_json(test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await).await;
+            assert_eq!(holidays.len(), count, "{}", uri);
+        }
+
+        for body in [
+            serde_json::json!([{ "date": "2025-08-15", "subdivision": "FR-IDF" }]),
+            serde_json::json!([{ "recurring": "08-15", "subdivision": "DE-BY" }]),
+        ] {
+            let req = test::TestRequest::post().uri("/holidays/de").set_json(body).to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), 400);
+        }
+        let req = test::TestRequest::get().uri("/holidays/de?subdivision=BY").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+
+    #[actix_rt::test]
+    async fn test_holidays_are_listed_by_day_and_paged() {
+        let data = web::Data::new(AppState { db: Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data.clone()).service(add_holidays).service(list_holidays)).await;
+        let req = test::TestRequest::post().uri("/holidays/fr")
+            .set_json(serde_json::json!([
+                { "date": "2025-12-25", "description": "Christmas" },
+                { "date": "2024-07-14T00:00:00Z", "description": "Bastille Day" },
+                { "date": "2025-05-01", "description": "Labour Day" },
+                { "date": "2025-07-14", "description": "Bastille Day" },
+                { "date": "2026-01-01", "description": "New Year's Day" }
+            ]))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+
+        let list = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+        for (uri, total, dates) in [
+            ("/holidays/fr", "5", vec!["2024-07-14T00:00:00Z", "2025-05-01", "2025-07-14", "2025-12-25", "2026-01-01"]),
+            ("/holidays/fr?year=2025&limit=2&offset=1", "3", vec!["2025-07-14", "2025-12-25"]),
+            ("/holidays/fr?from=2024-07-14&to=2025-07-14&order=desc", "3", vec!["2025-07-14", "2025-05-01", "2024-07-14T00:00:00Z"]),
+            ("/holidays/fr?year=2025&from=2025-06-01&to=2026-06-01", "2", vec!["2025-07-14", "2025-12-25"]),
+        ] {
+            let resp = test::call_service(&app, list(uri)).await;
+            assert_eq!(resp.headers().get("X-Total-Count").unwrap(), total, "{}", uri);
+            let holidays: Vec<Holiday> = test::read_body_json(resp).await;
+            assert_eq!(holidays.iter().map(|h| h.date.as_str()).collect::<Vec<_>>(), dates, "{}", uri);
+        }
+        for uri in ["/holidays/fr?from=14/07/2025", "/holidays/fr?order=newest", "/holidays/fr?limit=-1"] {
+            assert_eq!(test::call_service(&app, list(uri)).await.status(), 400, "{}", uri);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_holidays_are_read_updated_and_deleted_by_id() {
+        let data = web::Data::new(AppState { db: Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data.clone()).service(crate::holiday_list::list_year_holidays)
+            .service(add_holidays).service(get_holiday).service(update_holiday).service(delete_holiday)).await;
+        let req = test::TestRequest::post().uri("/holidays/fr")
+            .set_json(serde_json::json!([{ "date": "2025-07-14", "description": "Bastille" }]))
+            .to_request();
+        let added: AddedHolidays = test::read_body_json(test::call_service(&app, req).await).await;
+        let uri = format!("/holidays/fr/id/{}", added.holidays[0].id.unwrap());
+
+        let req = test::TestRequest::put().uri(&uri)
+            .set_json(serde_json::json!({ "date": "2025-7-14", "description": "Bastille Day", "portion": "afternoon" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+        let holiday: Holiday = test::read_body_json(test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await).await;
+        assert_eq!((holiday.date.as_str(), holiday.description.as_str(), holiday.portion), ("2025-07-14", "Bastille Day", HolidayPortion::Afternoon));
+
+        let req = test::TestRequest::put().uri(&uri).set_json(serde_json::json!({ "recurring": "07-14" })).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+        // Holidays of another country are not found through this one
+        let req = test::TestRequest::get().uri(&uri.replace("/fr/", "/de/")).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+        // Four-digit ids are holidays too, the year listing stays apart
+        let req = test::TestRequest::get().uri("/holidays/fr/id/2025").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+        let req = test::TestRequest::get().uri("/holidays/fr/2025").to_request();
+        let year: serde_json::Value = test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(year["year"], 2025);
+
+        // Adding the day again replaces the holiday, moving it onto another added one conflicts
+        let req = test::TestRequest::post().uri("/holidays/fr")
+            .set_json(serde_json::json!([{ "date": "2025-07-14", "description": "Fête nationale" }, { "date": "2025-11-11", "description": "Armistice" }]))
+            .to_request();
+        let readded: AddedHolidays = test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(readded.updated, vec![added.holidays[0].id.unwrap()]);
+        let req = test::TestRequest::put().uri(&uri).set_json(serde_json::json!({ "date": "2025-11-11" })).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 409);
+
+        assert_eq!(test::call_service(&app, test::TestRequest::delete().uri(&uri).to_request()).await.status(), 204);
+        for req in [
+            test::TestRequest::get().uri(&uri).to_request(),
+            test::TestRequest::delete().uri(&uri).to_request(),
+            test::TestRequest::put().uri(&uri).set_json(serde_json::json!({ "date": "2025-07-14" })).to_request(),
+        ] {
+            assert_eq!(test::call_service(&app, req).await.status(), 404);
+        }
+    }
+}
@@ -1,7 +1,16 @@
-use rusqlite::{Connection, Result, params};
-use std::sync::Mutex;
+use rusqlite::{Connection, OptionalExtension, Result, Row, TransactionBehavior, params};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use r2d2::{ManageConnection, Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use serde::{Serialize, Deserialize};
 
+use crate::country::{CountryCode, Subdivision};
+use crate::migrations;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Holiday {
     pub id: Option<i32>,
@@ -9,82 +18,1225 @@ pub struct Holiday {
     #[serde(default)]
     pub description: String,
     pub country: String,
+    #[serde(default, skip_serializing_if = "HolidayPortion::is_full")]
+    pub portion: HolidayPortion,
+    #[serde(default)]
+    pub kind: HolidayKind,
+    // ISO 3166-2 code (e.g. DE-BY) of a holiday observed in one subdivision only, None nationwide
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subdivision: Option<String>,
+}
+
+// Days, inclusive, and page of get_holidays_page
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HolidayQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub limit: Option<u32>,
+    pub offset: u32,
+    pub descending: bool,
+}
+
+// What add_holiday did: holidays added through the API are unique per country, subdivision and day
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Upsert {
+    Inserted,
+    Updated,
+}
+
+// Part of the working day a holiday covers: half-day holidays remove the first or the
+// second half of the working time of the day
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HolidayPortion {
+    #[default]
+    Full,
+    Morning,
+    Afternoon,
+}
+
+// Category of a holiday, requests choose which ones are days off (see HolidayKind::DAYS_OFF)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HolidayKind {
+    #[default]
+    Public,
+    // Banks are closed, most other businesses are not
+    Bank,
+    // Public holiday of some subdivisions only
+    Regional,
+    // Marked in calendars without being a day off, e.g. Mother's Day
+    Observance,
+    Company,
+}
+
+impl HolidayKind {
+    // Kinds removing working time when a request does not list its own
+    pub const DAYS_OFF: [HolidayKind; 3] = [HolidayKind::Public, HolidayKind::Regional, HolidayKind::Company];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            HolidayKind::Public => "public",
+            HolidayKind::Bank => "bank",
+            HolidayKind::Regional => "regional",
+            HolidayKind::Observance => "observance",
+            HolidayKind::Company => "company",
+        }
+    }
+
+    fn from_column(value: &str) -> HolidayKind {
+        match value {
+            "bank" => HolidayKind::Bank,
+            "regional" => HolidayKind::Regional,
+            "observance" => HolidayKind::Observance,
+            "company" => HolidayKind::Company,
+            _ => HolidayKind::Public,
+        }
+    }
+}
+
+impl HolidayPortion {
+    pub fn is_full(&self) -> bool {
+        *self == HolidayPortion::Full
+    }
+
+    // Two holidays on the same day: a morning and an afternoon make a full day
+    pub fn combine(self, other: HolidayPortion) -> HolidayPortion {
+        if self == other { self } else { HolidayPortion::Full }
+    }
+
+    // Stored as NULL for full days
+    fn to_column(self) -> Option<&'static str> {
+        match self {
+            HolidayPortion::Full => None,
+            HolidayPortion::Morning => Some("morning"),
+            HolidayPortion::Afternoon => Some("afternoon"),
+        }
+    }
+
+    fn from_column(value: Option<String>) -> HolidayPortion {
+        match value.as_deref() {
+            Some("morning") => HolidayPortion::Morning,
+            Some("afternoon") => HolidayPortion::Afternoon,
+            _ => HolidayPortion::Full,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Closure {
+    // One-off closure day (YYYY-MM-DD)
+    #[serde(default)]
+    pub date: Option<String>,
+    // Yearly closure day (MM-DD)
+    #[serde(default)]
+    pub recurring: Option<String>,
+    #[serde(default)]
+    pub description: String,
+}
+
+// Settings left unset (None or an empty country) are inherited from the parent calendar,
+// root calendars fall back to the defaults
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Calendar {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+    #[serde(rename = "startOfDay", default)]
+    pub start_of_day: Option<String>,
+    #[serde(rename = "endOfDay", default)]
+    pub end_of_day: Option<String>,
+    #[serde(default)]
+    pub weekend: Option<Vec<String>>,
+    #[serde(default)]
+    pub closures: Vec<Closure>,
+}
+
+pub fn default_weekend() -> Vec<String> {
+    vec!["sat".to_string(), "sun".to_string()]
+}
+
+// Countries are stored lowercase, as looked up with a CountryCode
+fn stored_country(country: &str) -> String {
+    CountryCode::parse(country).map_or_else(|_| country.to_string(), String::from)
+}
+
+// Subdivisions are compared uppercase, like Subdivision::parse returns them
+fn stored_subdivision(subdivision: &Option<String>) -> Option<String> {
+    subdivision.as_ref().map(|subdivision| subdivision.trim().to_uppercase())
+}
+
+// Stored in the NOT NULL weekend column of calendars inheriting their weekend
+const INHERITED_WEEKEND: &str = "inherit";
+
+// Custom holiday repeated every year, expanded for the years a calculation needs (see recurring.rs)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecurringHoliday {
+    pub id: Option<i32>,
+    #[serde(default)]
+    pub country: String,
+    // MM-DD, or a weekday of a month such as "last mon of may"
+    pub rule: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub kind: HolidayKind,
+}
+
+// Period during which a calendar is on call 24/7 (RFC3339 instants)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OnCallPeriod {
+    pub id: Option<i32>,
+    #[serde(default)]
+    pub calendar_id: String,
+    pub start: String,
+    pub end: String,
+}
+
+// Dates (inclusive) during which leave on a calendar is rejected, or only flagged
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BlackoutPeriod {
+    pub id: Option<i32>,
+    #[serde(default)]
+    pub calendar_id: String,
+    pub start: String,
+    pub end: String,
+    #[serde(default)]
+    pub description: String,
+    // reject or flag
+    pub mode: String,
+}
+
+// Time off in lieu: positive hours are accrued, negative hours are taken as leave
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ToilEntry {
+    pub id: Option<i32>,
+    #[serde(default)]
+    pub employee: String,
+    pub date: String,
+    pub hours: f64,
+    #[serde(default)]
+    pub description: String,
+}
+
+// Schedule of a calendar or employee valid between two dates (inclusive, open-ended without validTo)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Schedule {
+    pub id: Option<i32>,
+    #[serde(default)]
+    pub owner: String,
+    #[serde(rename = "validFrom")]
+    pub valid_from: String,
+    #[serde(rename = "validTo", default)]
+    pub valid_to: Option<String>,
+    #[serde(rename = "startOfDay", default = "crate::default_start_of_day")]
+    pub start_of_day: String,
+    #[serde(rename = "endOfDay", default = "crate::default_end_of_day")]
+    pub end_of_day: String,
+    #[serde(rename = "workingIntervals", default)]
+    pub working_intervals: Vec<crate::WorkingInterval>,
+}
+
+// Calculation request saved under a name, its strings may hold {{variable}} placeholders
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SavedQuery {
+    pub name: String,
+    pub request: serde_json::Value,
+}
+
+// Named set of countries usable in place of a country, e.g. eu-team = fr,de,es
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CountryGroup {
+    pub name: String,
+    pub countries: Vec<String>,
+}
+
+// Payroll freeze: holiday and calendar data stay as they are until the given instant (RFC3339)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Freeze {
+    pub until: String,
+    pub author: String,
+    pub created_at: String,
+}
+
+// One edit of a calendar: who made it, when, which settings changed and the resulting calendar
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CalendarVersion {
+    pub calendar_id: String,
+    pub version: i64,
+    pub author: String,
+    pub created_at: String,
+    pub changes: Vec<String>,
+    // None when the edit deleted the calendar
+    pub snapshot: Option<Calendar>,
+}
+
+// Change of a holiday through the API, with the holiday before and after it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub holiday_id: i32,
+    pub country: String,
+    pub action: AuditAction,
+    pub author: String,
+    pub changed_at: String,
+    pub before: Option<Holiday>,
+    pub after: Option<Holiday>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl AuditAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Created => "created",
+            AuditAction::Updated => "updated",
+            AuditAction::Deleted => "deleted",
+        }
+    }
+
+    fn from_column(value: &str) -> Self {
+        match value {
+            "created" => AuditAction::Created,
+            "deleted" => AuditAction::Deleted,
+            _ => AuditAction::Updated,
+        }
+    }
 }
 
+// Handle on a shared connection pool: clones are cheap and use the same connections, so the
+// clone of an in-memory database sees its data
+#[derive(Clone)]
 pub struct Database {
-    conn: Mutex<Connection>,
-    path: String,
+    pool: Arc<Pool<SqliteConnectionManager>>,
 }
 
 impl Database {
+    // Connections are pooled, DATABASE_POOL_SIZE of them (default 8). An in-memory database lives
+    // in its single connection.
     pub fn new(path: &str) -> Result<Self> {
-        let conn = Connection::open(path)?;
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS holidays (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                date TEXT NOT NULL,
-                description TEXT,
-                country TEXT NOT NULL
-            )",
-            [],
-        )?;
-        Ok(Database {
-            conn: Mutex::new(conn),
-            path: path.to_string(),
-        })
+        let (manager, size) = if path == ":memory:" {
+            (SqliteConnectionManager::memory(), 1)
+        } else {
+            (SqliteConnectionManager::file(path), pool_size())
+        };
+        let manager = manager.with_init(|conn| {
+            // Writers wait for each other instead of failing with SQLITE_BUSY
+            conn.busy_timeout(Duration::from_secs(5))?;
+            conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+        });
+        // An unusable path fails here at once, the pool would retry it until its timeout
+        drop(manager.connect()?);
+        let pool = Pool::builder()
+            .max_size(size)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .build(manager)
+            .map_err(pool_error)?;
+        let mut conn = pool.get().map_err(pool_error)?;
+        migrations::migrate(&mut conn)?;
+        // Not a migration: it waits for duplicates stored before it to be merged
+        create_unique_holiday_index(&conn)?;
+        drop(conn);
+        Ok(Database { pool: Arc::new(pool) })
+    }
+
+    // Transactions writing through it are IMMEDIATE: a deferred one reading first could not take
+    // the write lock once another connection wrote, busy timeout or not
+    fn conn(&self) -> Result<PooledConnection<SqliteConnectionManager>> {
+        self.pool.get().map_err(pool_error)
+    }
+
+    // Runs database work on the blocking thread pool, so that the async workers are not held
+    // while SQLite reads or waits for a pooled connection
+    pub async fn blocking<T, F>(&self, work: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T> + Send + 'static,
+    {
+        let db = self.clone();
+        actix_web::web::block(move || work(&db)).await.map_err(pool_error)?
+    }
+
+    // Replaces the holiday added through the API for the same country, subdivision and day, if any.
+    // Looked up rather than left to the unique index, which databases holding duplicates do not have yet.
+    pub fn add_holiday(&self, holiday: &Holiday, author: &str) -> Result<(i32, Upsert)> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let (country, subdivision) = (stored_country(&holiday.country), stored_subdivision(&holiday.subdivision));
+        let existing: Option<i32> = tx.query_row(
+            &format!("SELECT id FROM holidays WHERE {} ORDER BY id LIMIT 1", SAME_MANUAL_DAY),
+            params![country, subdivision, holiday.date],
+            |row| row.get(0),
+        ).optional()?;
+        let upsert = match existing {
+            Some(id) => {
+                let before = holiday_in(&tx, id)?;
+                tx.execute(
+                    "UPDATE holidays SET date = ?, description = ?, portion = ?, kind = ?, updated_at = ? WHERE id = ?",
+                    params![holiday.date, holiday.description, holiday.portion.to_column(), holiday.kind.as_str(), chrono::Utc::now().to_rfc3339(), id],
+                )?;
+                record_holiday_change(&tx, id, AuditAction::Updated, author, before)?;
+                (id, Upsert::Updated)
+            }
+            None => (insert_holiday(&tx, holiday, author)?, Upsert::Inserted),
+        };
+        tx.commit()?;
+        Ok(upsert)
+    }
+
+    pub fn schema_version(&self) -> Result<i64> {
+        let conn = self.conn()?;
+        migrations::current_version(&conn)
+    }
+
+    // Creates the unique index once the duplicates that prevented it are merged
+    pub fn ensure_unique_holidays(&self) -> Result<bool> {
+        let conn = self.conn()?;
+        create_unique_holiday_index(&conn)
+    }
+
+    // Replace the holidays previously fetched from a provider for the same country and years,
+    // so that the database fallback holds the latest answer of each provider
+    pub fn store_fetched_holidays(&self, country: &CountryCode, source: &str, holidays: &[Holiday]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let year = |holiday: &Holiday| holiday.date.get(..4).and_then(|year| year.parse::<i32>().ok());
+        let mut years: Vec<i32> = holidays.iter().filter_map(year).collect();
+        years.sort();
+        years.dedup();
+        let fetched_at = chrono::Utc::now().to_rfc3339();
+        for year in years {
+            tx.execute(
+                "DELETE FROM holidays WHERE country = ? AND source = ? AND year = ?",
+                params![country.as_str(), source, year],
+            )?;
+        }
+        for holiday in holidays {
+            tx.execute(
+                "INSERT INTO holidays (date, description, country, source, year, portion, kind, fetched_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                params![holiday.date, holiday.description, country.as_str(), source, year(holiday), holiday.portion.to_column(), holiday.kind.as_str(), fetched_at],
+            )?;
+        }
+        tx.commit()
+    }
+
+    // Oldest fetch among the provider holidays stored for the years, None when none say when they were fetched
+    pub fn get_fetched_at(&self, country: &CountryCode, years: RangeInclusive<i32>) -> Result<Option<String>> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "SELECT MIN(fetched_at) FROM holidays WHERE country = ? AND source IS NOT NULL AND year BETWEEN ? AND ?",
+            params![country.as_str(), years.start(), years.end()],
+            |row| row.get(0),
+        )
+    }
+
+    // Nationwide holidays, plus the ones of the subdivision and of the subdivisions above it
+    // (DE-BY for DE-BY-AU). Without a subdivision, regional holidays are left out.
+    pub fn get_holidays_by_country(&self, country: &CountryCode, subdivision: Option<&Subdivision>) -> Result<Vec<Holiday>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM holidays WHERE {}", HOLIDAY_COLUMNS, IN_SUBDIVISION))?;
+        let holidays = stmt.query_map(params![country.as_str(), subdivision.map(Subdivision::as_str)], row_to_holiday)?.collect::<Result<Vec<_>>>()?;
+        Ok(holidays)
+    }
+
+    // A page of the holidays of get_holidays_by_country sorted by day, with the number of holidays on all pages
+    pub fn get_holidays_page(&self, country: &CountryCode, subdivision: Option<&Subdivision>, query: &HolidayQuery) -> Result<(Vec<Holiday>, i64)> {
+        let conn = self.conn()?;
+        let filter = format!(
+            "{} AND (?3 IS NULL OR substr(date, 1, 10) >= ?3) AND (?4 IS NULL OR substr(date, 1, 10) <= ?4)",
+            IN_SUBDIVISION,
+        );
+        let (from, to) = (query.from.map(|day| day.to_string()), query.to.map(|day| day.to_string()));
+        let total = conn.query_row(
+            &format!("SELECT COUNT(*) FROM holidays WHERE {}", filter),
+            params![country.as_str(), subdivision.map(Subdivision::as_str), from, to],
+            |row| row.get(0),
+        )?;
+        let direction = if query.descending { "DESC" } else { "ASC" };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM holidays WHERE {} ORDER BY substr(date, 1, 10) {}, id {} LIMIT ?5 OFFSET ?6",
+            HOLIDAY_COLUMNS, filter, direction, direction,
+        ))?;
+        // A negative limit is no limit
+        let limit = query.limit.map_or(-1, i64::from);
+        let holidays = stmt.query_map(
+            params![country.as_str(), subdivision.map(Subdivision::as_str), from, to, limit, query.offset],
+            row_to_holiday,
+        )?.collect::<Result<Vec<_>>>()?;
+        Ok((holidays, total))
+    }
+
+    // Holidays added through the API, without the ones fetched from providers
+    pub fn get_manual_holidays(&self, country: &CountryCode) -> Result<Vec<Holiday>> {
+        Ok(self.get_all_manual_holidays()?.into_iter().filter(|holiday| holiday.country == country.as_str()).collect())
+    }
+
+    pub fn get_all_manual_holidays(&self) -> Result<Vec<Holiday>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM holidays WHERE source IS NULL AND deleted_at IS NULL ORDER BY id", HOLIDAY_COLUMNS))?;
+        let holidays = stmt.query_map([], row_to_holiday)?.collect::<Result<Vec<_>>>()?;
+        Ok(holidays)
+    }
+
+    // Applies an import in one transaction: new holidays, new descriptions by id, deleted ids
+    pub fn import_holidays(&self, created: &[Holiday], updated: &[(i32, String)], deleted: &[i32], author: &str) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        for holiday in created {
+            insert_holiday(&tx, holiday, author)?;
+        }
+        for (id, description) in updated {
+            let before = holiday_in(&tx, *id)?;
+            tx.execute(
+                "UPDATE holidays SET description = ?, updated_at = ? WHERE id = ? AND deleted_at IS NULL",
+                params![description, chrono::Utc::now().to_rfc3339(), id],
+            )?;
+            record_holiday_change(&tx, *id, AuditAction::Updated, author, before)?;
+        }
+        for id in deleted {
+            soft_delete_holiday(&tx, *id, author)?;
+        }
+        tx.commit()
+    }
+
+    pub fn get_all_holidays(&self) -> Result<Vec<Holiday>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM holidays WHERE deleted_at IS NULL", HOLIDAY_COLUMNS))?;
+        let holidays = stmt.query_map([], row_to_holiday)?.collect::<Result<Vec<_>>>()?;
+        Ok(holidays)
+    }
+
+    pub fn get_holiday(&self, country: &CountryCode, id: i32) -> Result<Option<Holiday>> {
+        let conn = self.conn()?;
+        conn.query_row(
+            &format!("SELECT {} FROM holidays WHERE country = ? AND id = ? AND deleted_at IS NULL", HOLIDAY_COLUMNS),
+            params![country.as_str(), id],
+            row_to_holiday,
+        ).optional()
+    }
+
+    // Replaces the date, description, portion, kind and subdivision of a holiday of the country
+    pub fn update_holiday(&self, country: &CountryCode, id: i32, holiday: &Holiday, author: &str) -> Result<usize> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let before = holiday_in(&tx, id)?;
+        let updated = tx.execute(
+            "UPDATE holidays SET date = ?, description = ?, portion = ?, kind = ?, subdivision = ?, updated_at = ?
+             WHERE country = ? AND id = ? AND deleted_at IS NULL",
+            params![holiday.date, holiday.description, holiday.portion.to_column(), holiday.kind.as_str(), stored_subdivision(&holiday.subdivision), chrono::Utc::now().to_rfc3339(), country.as_str(), id],
+        )?;
+        if updated > 0 {
+            record_holiday_change(&tx, id, AuditAction::Updated, author, before)?;
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    // Marks the holiday deleted, the audit log keeps showing it until then
+    pub fn delete_holiday(&self, id: i32, author: &str) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        soft_delete_holiday(&tx, id, author)?;
+        tx.commit()
+    }
+
+    // Changes of the holidays of a country added through the API, oldest first
+    pub fn get_audit_log(&self, country: &CountryCode, holiday_id: Option<i32>) -> Result<Vec<AuditEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT a.id, a.holiday_id, a.country, a.action, a.author, a.changed_at, a.before, a.after
+             FROM audit_log a JOIN holidays h ON h.id = a.holiday_id
+             WHERE a.country = ?1 AND h.source IS NULL AND (?2 IS NULL OR a.holiday_id = ?2)
+             ORDER BY a.id"
+        )?;
+        let entries = stmt.query_map(params![country.as_str(), holiday_id], row_to_audit_entry)?.collect::<Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    // Holidays of a country added through the API as they were at the instant: after the last change
+    // made by then, before the first one made since, or as they are when never changed since
+    pub fn get_manual_holidays_at(&self, country: &CountryCode, at: DateTime<Utc>) -> Result<Vec<Holiday>> {
+        let mut states: BTreeMap<i32, Option<Holiday>> = self.get_manual_holidays(country)?.into_iter()
+            .map(|holiday| (holiday.id.unwrap_or_default(), Some(holiday)))
+            .collect();
+        let mut changed_by_then = BTreeMap::new();
+        for entry in self.get_audit_log(country, None)?.into_iter().rev() {
+            let changed_at = DateTime::parse_from_rfc3339(&entry.changed_at).map_or(at, |changed_at| changed_at.with_timezone(&Utc));
+            if changed_at > at {
+                states.insert(entry.holiday_id, entry.before);
+            } else {
+                changed_by_then.entry(entry.holiday_id).or_insert(entry.after);
+            }
+        }
+        states.extend(changed_by_then);
+        Ok(states.into_values().flatten().collect())
+    }
+
+    // Create or replace a calendar together with its closure days
+    pub fn upsert_calendar(&self, calendar: &Calendar, author: &str) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        upsert_calendar_in(&tx, calendar, author)?;
+        tx.commit()
+    }
+
+    // Upserts and deletes calendars all together or not at all, as an apply of the configuration does
+    pub fn replace_calendars(&self, upserted: &[&Calendar], deleted: &[String], author: &str) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        for calendar in upserted {
+            upsert_calendar_in(&tx, calendar, author)?;
+        }
+        for id in deleted {
+            delete_calendar_in(&tx, id, author)?;
+        }
+        tx.commit()
     }
 
-    pub fn add_holiday(&self, holiday: &Holiday) -> Result<i32> {
-        let conn = self.conn.lock().unwrap();
+    pub fn get_calendar(&self, id: &str) -> Result<Option<Calendar>> {
+        let conn = self.conn()?;
+        calendar_in(&conn, id)
+    }
+
+    pub fn get_all_calendars(&self) -> Result<Vec<Calendar>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, country, subdivision, start_of_day, end_of_day, weekend, parent FROM calendars ORDER BY id"
+        )?;
+        let mut calendars = stmt.query_map([], row_to_calendar)?.collect::<Result<Vec<_>>>()?;
+        for calendar in calendars.iter_mut() {
+            calendar.closures = closures_for(&conn, &calendar.id)?;
+        }
+        Ok(calendars)
+    }
+
+    pub fn delete_calendar(&self, id: &str, author: &str) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        delete_calendar_in(&tx, id, author)?;
+        tx.commit()
+    }
+
+    pub fn get_calendar_versions(&self, id: &str) -> Result<Vec<CalendarVersion>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT calendar_id, version, author, created_at, changes, snapshot
+             FROM calendar_versions WHERE calendar_id = ? ORDER BY version"
+        )?;
+        let versions = stmt.query_map(params![id], row_to_version)?.collect::<Result<Vec<_>>>()?;
+        Ok(versions)
+    }
+
+    pub fn get_calendar_version(&self, id: &str, version: i64) -> Result<Option<CalendarVersion>> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "SELECT calendar_id, version, author, created_at, changes, snapshot
+             FROM calendar_versions WHERE calendar_id = ? AND version = ?",
+            params![id, version],
+            row_to_version,
+        ).optional()
+    }
+
+    // Acquire or renew a named lease, returns true when the holder owns the lease afterwards
+    pub fn try_acquire_lease(&self, name: &str, holder: &str, ttl: Duration) -> Result<bool> {
+        let conn = self.conn()?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let changed = conn.execute(
+            "INSERT INTO leases (name, holder, expires_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(name) DO UPDATE SET holder = excluded.holder, expires_at = excluded.expires_at
+             WHERE leases.holder = excluded.holder OR leases.expires_at <= ?4",
+            params![name, holder, now + ttl.as_secs() as i64, now],
+        )?;
+        Ok(changed > 0)
+    }
+
+    pub fn add_recurring_holiday(&self, holiday: &RecurringHoliday) -> Result<i32> {
+        let conn = self.conn()?;
         conn.execute(
-            "INSERT INTO holidays (date, description, country) VALUES (?, ?, ?)",
-            params![holiday.date, holiday.description, holiday.country],
+            "INSERT INTO recurring_holidays (country, rule, description, kind) VALUES (?, ?, ?, ?)",
+            params![holiday.country, holiday.rule, holiday.description, holiday.kind.as_str()],
         )?;
         Ok(conn.last_insert_rowid() as i32)
     }
 
-    pub fn get_holidays_by_country(&self, country: &str) -> Result<Vec<Holiday>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT * FROM holidays WHERE country = ?")?;
-        let holidays = stmt.query_map(params![country], |row| {
-            Ok(Holiday {
+    pub fn get_recurring_holidays(&self, country: &CountryCode) -> Result<Vec<RecurringHoliday>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, country, rule, description, kind FROM recurring_holidays WHERE country = ? ORDER BY id"
+        )?;
+        let holidays = stmt.query_map(params![country.as_str()], |row| {
+            Ok(RecurringHoliday {
                 id: Some(row.get(0)?),
-                date: row.get(1)?,
-                description: row.get(2)?,
-                country: row.get(3)?,
+                country: row.get(1)?,
+                rule: row.get(2)?,
+                description: row.get(3)?,
+                kind: HolidayKind::from_column(&row.get::<_, String>(4)?),
             })
         })?.collect::<Result<Vec<_>>>()?;
         Ok(holidays)
     }
 
-    pub fn get_all_holidays(&self) -> Result<Vec<Holiday>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT * FROM holidays")?;
-        let holidays = stmt.query_map([], |row| {
-            Ok(Holiday {
+    pub fn add_on_call_period(&self, period: &OnCallPeriod) -> Result<i32> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO on_call_periods (calendar_id, start, end) VALUES (?, ?, ?)",
+            params![period.calendar_id, period.start, period.end],
+        )?;
+        Ok(conn.last_insert_rowid() as i32)
+    }
+
+    pub fn get_on_call_periods(&self, calendar_id: &str) -> Result<Vec<OnCallPeriod>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, calendar_id, start, end FROM on_call_periods WHERE calendar_id = ? ORDER BY start"
+        )?;
+        let periods = stmt.query_map(params![calendar_id], |row| {
+            Ok(OnCallPeriod {
                 id: Some(row.get(0)?),
-                date: row.get(1)?,
-                description: row.get(2)?,
-                country: row.get(3)?,
+                calendar_id: row.get(1)?,
+                start: row.get(2)?,
+                end: row.get(3)?,
             })
         })?.collect::<Result<Vec<_>>>()?;
-        Ok(holidays)
+        Ok(periods)
+    }
+
+    pub fn delete_on_call_period(&self, calendar_id: &str, id: i32) -> Result<usize> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM on_call_periods WHERE calendar_id = ? AND id = ?", params![calendar_id, id])
+    }
+
+    pub fn add_blackout_period(&self, period: &BlackoutPeriod) -> Result<i32> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO blackout_periods (calendar_id, start, end, description, mode) VALUES (?, ?, ?, ?, ?)",
+            params![period.calendar_id, period.start, period.end, period.description, period.mode],
+        )?;
+        Ok(conn.last_insert_rowid() as i32)
+    }
+
+    pub fn get_blackout_periods(&self, calendar_id: &str) -> Result<Vec<BlackoutPeriod>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, calendar_id, start, end, description, mode FROM blackout_periods WHERE calendar_id = ? ORDER BY start"
+        )?;
+        let periods = stmt.query_map(params![calendar_id], |row| {
+            Ok(BlackoutPeriod {
+                id: Some(row.get(0)?),
+                calendar_id: row.get(1)?,
+                start: row.get(2)?,
+                end: row.get(3)?,
+                description: row.get(4)?,
+                mode: row.get(5)?,
+            })
+        })?.collect::<Result<Vec<_>>>()?;
+        Ok(periods)
+    }
+
+    pub fn delete_blackout_period(&self, calendar_id: &str, id: i32) -> Result<usize> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM blackout_periods WHERE calendar_id = ? AND id = ?", params![calendar_id, id])
+    }
+
+    pub fn add_schedule(&self, schedule: &Schedule) -> Result<i32> {
+        let conn = self.conn()?;
+        let working_intervals = serde_json::to_string(&schedule.working_intervals)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        conn.execute(
+            "INSERT INTO schedules (owner, valid_from, valid_to, start_of_day, end_of_day, working_intervals)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                schedule.owner,
+                schedule.valid_from,
+                schedule.valid_to,
+                schedule.start_of_day,
+                schedule.end_of_day,
+                working_intervals
+            ],
+        )?;
+        Ok(conn.last_insert_rowid() as i32)
+    }
+
+    pub fn get_schedules(&self, owner: &str) -> Result<Vec<Schedule>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, owner, valid_from, valid_to, start_of_day, end_of_day, working_intervals
+             FROM schedules WHERE owner = ? ORDER BY valid_from, id"
+        )?;
+        let schedules = stmt.query_map(params![owner], |row| {
+            let working_intervals: String = row.get(6)?;
+            Ok(Schedule {
+                id: Some(row.get(0)?),
+                owner: row.get(1)?,
+                valid_from: row.get(2)?,
+                valid_to: row.get(3)?,
+                start_of_day: row.get(4)?,
+                end_of_day: row.get(5)?,
+                working_intervals: serde_json::from_str(&working_intervals).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e))
+                })?,
+            })
+        })?.collect::<Result<Vec<_>>>()?;
+        Ok(schedules)
+    }
+
+    pub fn delete_schedule(&self, owner: &str, id: i32) -> Result<usize> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM schedules WHERE owner = ? AND id = ?", params![owner, id])
+    }
+
+    pub fn set_schedule_script(&self, owner: &str, script: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO schedule_scripts (owner, script) VALUES (?, ?)
+             ON CONFLICT(owner) DO UPDATE SET script = excluded.script",
+            params![owner, script],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_schedule_script(&self, owner: &str) -> Result<Option<String>> {
+        let conn = self.conn()?;
+        conn.query_row("SELECT script FROM schedule_scripts WHERE owner = ?", params![owner], |row| row.get(0))
+            .optional()
+    }
+
+    pub fn delete_schedule_script(&self, owner: &str) -> Result<usize> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM schedule_scripts WHERE owner = ?", params![owner])
+    }
+
+    pub fn save_query(&self, query: &SavedQuery) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO saved_queries (name, request) VALUES (?, ?)
+             ON CONFLICT(name) DO UPDATE SET request = excluded.request",
+            params![query.name, query.request.to_string()],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_saved_queries(&self) -> Result<Vec<SavedQuery>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT name, request FROM saved_queries ORDER BY name")?;
+        let queries = stmt.query_map([], row_to_saved_query)?.collect::<Result<Vec<_>>>()?;
+        Ok(queries)
+    }
+
+    pub fn get_saved_query(&self, name: &str) -> Result<Option<SavedQuery>> {
+        let conn = self.conn()?;
+        conn.query_row("SELECT name, request FROM saved_queries WHERE name = ?", params![name], row_to_saved_query)
+            .optional()
+    }
+
+    pub fn delete_saved_query(&self, name: &str) -> Result<usize> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM saved_queries WHERE name = ?", params![name])
+    }
+
+    pub fn save_country_group(&self, group: &CountryGroup) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO country_groups (name, countries) VALUES (?, ?)
+             ON CONFLICT(name) DO UPDATE SET countries = excluded.countries",
+            params![group.name, group.countries.join(",")],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_country_groups(&self) -> Result<Vec<CountryGroup>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT name, countries FROM country_groups ORDER BY name")?;
+        let groups = stmt.query_map([], row_to_country_group)?.collect::<Result<Vec<_>>>()?;
+        Ok(groups)
+    }
+
+    pub fn get_country_group(&self, name: &str) -> Result<Option<CountryGroup>> {
+        let conn = self.conn()?;
+        conn.query_row("SELECT name, countries FROM country_groups WHERE name = ?", params![name], row_to_country_group)
+            .optional()
+    }
+
+    pub fn delete_country_group(&self, name: &str) -> Result<usize> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM country_groups WHERE name = ?", params![name])
+    }
+
+    pub fn set_freeze(&self, freeze: &Freeze) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO freezes (id, until, author, created_at) VALUES (1, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET until = excluded.until, author = excluded.author, created_at = excluded.created_at",
+            params![freeze.until, freeze.author, freeze.created_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_freeze(&self) -> Result<Option<Freeze>> {
+        let conn = self.conn()?;
+        conn.query_row("SELECT until, author, created_at FROM freezes WHERE id = 1", [], |row| {
+            Ok(Freeze { until: row.get(0)?, author: row.get(1)?, created_at: row.get(2)? })
+        }).optional()
+    }
+
+    pub fn delete_freeze(&self) -> Result<usize> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM freezes", [])
+    }
+
+    pub fn add_toil_entry(&self, entry: &ToilEntry) -> Result<i32> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO toil_entries (employee, date, hours, description) VALUES (?, ?, ?, ?)",
+            params![entry.employee, entry.date, entry.hours, entry.description],
+        )?;
+        Ok(conn.last_insert_rowid() as i32)
+    }
+
+    // Replaces the accruals of the employee between two days, inclusive, so that posting a timesheet
+    // again does not accrue its hours twice. Leave taken in the meantime is kept.
+    pub fn replace_toil_accruals(&self, employee: &str, from: &str, to: &str, entries: &[ToilEntry]) -> Result<Vec<i32>> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        tx.execute(
+            "DELETE FROM toil_entries WHERE employee = ? AND hours > 0 AND date BETWEEN ? AND ?",
+            params![employee, from, to],
+        )?;
+        let mut ids = vec![];
+        for entry in entries {
+            tx.execute(
+                "INSERT INTO toil_entries (employee, date, hours, description) VALUES (?, ?, ?, ?)",
+                params![entry.employee, entry.date, entry.hours, entry.description],
+            )?;
+            ids.push(tx.last_insert_rowid() as i32);
+        }
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    pub fn get_toil_entries(&self, employee: &str) -> Result<Vec<ToilEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, employee, date, hours, description FROM toil_entries WHERE employee = ? ORDER BY date, id"
+        )?;
+        let entries = stmt.query_map(params![employee], |row| {
+            Ok(ToilEntry {
+                id: Some(row.get(0)?),
+                employee: row.get(1)?,
+                date: row.get(2)?,
+                hours: row.get(3)?,
+                description: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+            })
+        })?.collect::<Result<Vec<_>>>()?;
+        Ok(entries)
     }
 
-    pub fn delete_holiday(&self, id: i32) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM holidays WHERE id = ?", params![id])?;
+    pub fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM leases WHERE name = ? AND holder = ?", params![name, holder])?;
         Ok(())
     }
 }
 
-impl Clone for Database {
-    fn clone(&self) -> Self {
-        // Create a new database with the same path
-        Database::new(&self.path).expect("Failed to clone database")
+fn row_to_country_group(row: &Row) -> Result<CountryGroup> {
+    let countries: String = row.get(1)?;
+    Ok(CountryGroup {
+        name: row.get(0)?,
+        countries: countries.split(',').filter(|c| !c.is_empty()).map(str::to_string).collect(),
+    })
+}
+
+fn row_to_saved_query(row: &Row) -> Result<SavedQuery> {
+    let request: String = row.get(1)?;
+    Ok(SavedQuery {
+        name: row.get(0)?,
+        request: serde_json::from_str(&request).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+    })
+}
+
+fn row_to_calendar(row: &Row) -> Result<Calendar> {
+    let weekend: String = row.get(5)?;
+    let time = |index: usize| -> Result<Option<String>> {
+        let value: String = row.get(index)?;
+        Ok(if value.is_empty() { None } else { Some(value) })
+    };
+    Ok(Calendar {
+        id: row.get(0)?,
+        parent: row.get(6)?,
+        country: row.get(1)?,
+        subdivision: row.get(2)?,
+        start_of_day: time(3)?,
+        end_of_day: time(4)?,
+        weekend: if weekend == INHERITED_WEEKEND {
+            None
+        } else {
+            Some(weekend.split(',').filter(|d| !d.is_empty()).map(str::to_string).collect())
+        },
+        closures: vec![],
+    })
+}
+
+// Holidays of the country ?1 observed in the subdivision ?2: nationwide ones, and the regional ones
+// of the subdivision or of one above it (DE-BY ones in DE-BY-AU)
+const IN_SUBDIVISION: &str = "country = ?1 AND deleted_at IS NULL AND (subdivision IS NULL OR ?2 = subdivision OR ?2 LIKE subdivision || '-%')";
+
+// Holidays added through the API are unique per country, subdivision and day, whether their
+// date is a day or an RFC3339 timestamp. Provider copies are not, two providers may agree.
+const UNIQUE_HOLIDAY_KEY: &str = "country, COALESCE(subdivision, ''), substr(date, 1, 10)";
+
+// Holiday added through the API on the same day as ?3, for the country ?1 and subdivision ?2
+const SAME_MANUAL_DAY: &str = "source IS NULL AND deleted_at IS NULL AND country = ?1 AND COALESCE(subdivision, '') = COALESCE(?2, '') AND substr(date, 1, 10) = substr(?3, 1, 10)";
+
+// Skipped with a warning while duplicates from before the index are stored, until they are
+// merged through POST /admin/duplicates/merge
+fn create_unique_holiday_index(conn: &Connection) -> Result<bool> {
+    let duplicated_days: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM (SELECT 1 FROM holidays WHERE source IS NULL AND deleted_at IS NULL GROUP BY {} HAVING COUNT(*) > 1)", UNIQUE_HOLIDAY_KEY),
+        [],
+        |row| row.get(0),
+    )?;
+    if duplicated_days > 0 {
+        log::warn!("{} days have duplicate holidays, merge them through POST /admin/duplicates/merge to enforce unique holidays", duplicated_days);
+        return Ok(false);
+    }
+    conn.execute(
+        &format!("CREATE UNIQUE INDEX IF NOT EXISTS holidays_unique_live_day ON holidays ({}) WHERE source IS NULL AND deleted_at IS NULL", UNIQUE_HOLIDAY_KEY),
+        [],
+    )?;
+    Ok(true)
+}
+
+const HOLIDAY_COLUMNS: &str = "id, date, description, country, portion, kind, subdivision";
+
+fn row_to_holiday(row: &Row) -> Result<Holiday> {
+    Ok(Holiday {
+        id: Some(row.get(0)?),
+        date: row.get(1)?,
+        description: row.get(2)?,
+        country: row.get(3)?,
+        portion: HolidayPortion::from_column(row.get(4)?),
+        kind: HolidayKind::from_column(&row.get::<_, String>(5)?),
+        subdivision: row.get(6)?,
+    })
+}
+
+fn pool_size() -> u32 {
+    std::env::var("DATABASE_POOL_SIZE").ok().and_then(|size| size.parse().ok()).filter(|size| *size > 0).unwrap_or(8)
+}
+
+// Pool timeouts and lost blocking tasks surface as busy database errors
+fn pool_error(error: impl std::fmt::Display) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY), Some(error.to_string()))
+}
+
+fn holiday_in(conn: &Connection, id: i32) -> Result<Option<Holiday>> {
+    conn.query_row(
+        &format!("SELECT {} FROM holidays WHERE id = ? AND deleted_at IS NULL", HOLIDAY_COLUMNS),
+        params![id],
+        row_to_holiday,
+    ).optional()
+}
+
+fn insert_holiday(conn: &Connection, holiday: &Holiday, author: &str) -> Result<i32> {
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO holidays (date, description, country, portion, kind, subdivision, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![holiday.date, holiday.description, stored_country(&holiday.country), holiday.portion.to_column(), holiday.kind.as_str(), stored_subdivision(&holiday.subdivision), now, now],
+    )?;
+    let id = conn.last_insert_rowid() as i32;
+    record_holiday_change(conn, id, AuditAction::Created, author, None)?;
+    Ok(id)
+}
+
+fn soft_delete_holiday(conn: &Connection, id: i32, author: &str) -> Result<()> {
+    let before = holiday_in(conn, id)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    if conn.execute("UPDATE holidays SET deleted_at = ?, updated_at = ? WHERE id = ? AND deleted_at IS NULL", params![now, now, id])? > 0 {
+        record_holiday_change(conn, id, AuditAction::Deleted, author, before)?;
+    }
+    Ok(())
+}
+
+// Records the change of a holiday, reading it back as it now is unless it was deleted
+fn record_holiday_change(conn: &Connection, id: i32, action: AuditAction, author: &str, before: Option<Holiday>) -> Result<()> {
+    let after = holiday_in(conn, id)?;
+    let country = match after.as_ref().or(before.as_ref()) {
+        Some(holiday) => holiday.country.clone(),
+        None => return Ok(()),
+    };
+    conn.execute(
+        "INSERT INTO audit_log (holiday_id, country, action, author, changed_at, before, after) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![id, country, action.as_str(), author, chrono::Utc::now().to_rfc3339(), before.as_ref().map(to_json).transpose()?, after.as_ref().map(to_json).transpose()?],
+    )?;
+    Ok(())
+}
+
+fn row_to_audit_entry(row: &Row) -> Result<AuditEntry> {
+    let holiday = |index: usize| -> Result<Option<Holiday>> {
+        row.get::<_, Option<String>>(index)?
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+    };
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        holiday_id: row.get(1)?,
+        country: row.get(2)?,
+        action: AuditAction::from_column(&row.get::<_, String>(3)?),
+        author: row.get(4)?,
+        changed_at: row.get(5)?,
+        before: holiday(6)?,
+        after: holiday(7)?,
+    })
+}
+
+fn upsert_calendar_in(conn: &Connection, calendar: &Calendar, author: &str) -> Result<()> {
+    let previous = calendar_in(conn, &calendar.id)?;
+    conn.execute(
+        "INSERT INTO calendars (id, country, subdivision, start_of_day, end_of_day, weekend, parent)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET
+            country = excluded.country,
+            subdivision = excluded.subdivision,
+            start_of_day = excluded.start_of_day,
+            end_of_day = excluded.end_of_day,
+            weekend = excluded.weekend,
+            parent = excluded.parent",
+        params![
+            calendar.id,
+            calendar.country,
+            calendar.subdivision,
+            calendar.start_of_day.clone().unwrap_or_default(),
+            calendar.end_of_day.clone().unwrap_or_default(),
+            calendar.weekend.as_ref().map_or(INHERITED_WEEKEND.to_string(), |days| days.join(",")),
+            calendar.parent
+        ],
+    )?;
+    conn.execute("DELETE FROM calendar_closures WHERE calendar_id = ?", params![calendar.id])?;
+    for closure in &calendar.closures {
+        conn.execute(
+            "INSERT INTO calendar_closures (calendar_id, date, recurring, description) VALUES (?, ?, ?, ?)",
+            params![calendar.id, closure.date, closure.recurring, closure.description],
+        )?;
+    }
+    record_version(conn, &calendar.id, author, previous.as_ref(), Some(calendar))?;
+    Ok(())
+}
+
+fn delete_calendar_in(conn: &Connection, id: &str, author: &str) -> Result<()> {
+    let previous = calendar_in(conn, id)?;
+    conn.execute("DELETE FROM calendar_closures WHERE calendar_id = ?", params![id])?;
+    conn.execute("DELETE FROM calendars WHERE id = ?", params![id])?;
+    if previous.is_some() {
+        record_version(conn, id, author, previous.as_ref(), None)?;
+    }
+    Ok(())
+}
+
+fn calendar_in(conn: &Connection, id: &str) -> Result<Option<Calendar>> {
+    let calendar = conn.query_row(
+        "SELECT id, country, subdivision, start_of_day, end_of_day, weekend, parent FROM calendars WHERE id = ?",
+        params![id],
+        row_to_calendar,
+    ).optional()?;
+    match calendar {
+        Some(mut calendar) => {
+            calendar.closures = closures_for(conn, &calendar.id)?;
+            Ok(Some(calendar))
+        }
+        None => Ok(None),
     }
 }
 
+// Names of the settings that differ between two states of a calendar
+fn changed_fields(previous: Option<&Calendar>, current: Option<&Calendar>) -> Vec<String> {
+    let (previous, current) = match (previous, current) {
+        (None, _) => return vec!["created".to_string()],
+        (_, None) => return vec!["deleted".to_string()],
+        (Some(previous), Some(current)) => (previous, current),
+    };
+    let fields = [
+        ("parent", previous.parent != current.parent),
+        ("country", previous.country != current.country),
+        ("subdivision", previous.subdivision != current.subdivision),
+        ("startOfDay", previous.start_of_day != current.start_of_day),
+        ("endOfDay", previous.end_of_day != current.end_of_day),
+        ("weekend", previous.weekend != current.weekend),
+        ("closures", previous.closures != current.closures),
+    ];
+    fields.iter().filter(|(_, changed)| *changed).map(|(name, _)| name.to_string()).collect()
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn record_version(
+    conn: &Connection,
+    calendar_id: &str,
+    author: &str,
+    previous: Option<&Calendar>,
+    current: Option<&Calendar>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO calendar_versions (calendar_id, version, author, created_at, changes, snapshot)
+         SELECT ?1, COALESCE(MAX(version), 0) + 1, ?2, ?3, ?4, ?5 FROM calendar_versions WHERE calendar_id = ?1",
+        params![
+            calendar_id,
+            author,
+            chrono::Utc::now().to_rfc3339(),
+            to_json(&changed_fields(previous, current))?,
+            current.map(to_json).transpose()?
+        ],
+    )?;
+    Ok(())
+}
+
+fn row_to_version(row: &Row) -> Result<CalendarVersion> {
+    let json_error = |index: usize| move |e: serde_json::Error| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    };
+    let changes: String = row.get(4)?;
+    let snapshot: Option<String> = row.get(5)?;
+    Ok(CalendarVersion {
+        calendar_id: row.get(0)?,
+        version: row.get(1)?,
+        author: row.get(2)?,
+        created_at: row.get(3)?,
+        changes: serde_json::from_str(&changes).map_err(json_error(4))?,
+        snapshot: snapshot.map(|s| serde_json::from_str(&s)).transpose().map_err(json_error(5))?,
+    })
+}
+
+fn closures_for(conn: &Connection, calendar_id: &str) -> Result<Vec<Closure>> {
+    let mut stmt = conn.prepare(
+        "SELECT date, recurring, description FROM calendar_closures WHERE calendar_id = ? ORDER BY id"
+    )?;
+    let closures = stmt.query_map(params![calendar_id], |row| {
+        Ok(Closure {
+            date: row.get(0)?,
+            recurring: row.get(1)?,
+            description: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+        })
+    })?.collect::<Result<Vec<_>>>()?;
+    Ok(closures)
+}
+
+
 
 
 #[cfg(test)]
@@ -102,15 +1254,135 @@ mod tests {
             date: "2025-07-04".to_string(),
             description: "Independence Day".to_string(),
             country: "US".to_string(),
+            portion: HolidayPortion::Full,
+            kind: HolidayKind::Public,
+            subdivision: None,
         };
 
-        let id = db.add_holiday(&holiday).unwrap();
-        let holidays = db.get_holidays_by_country("US").unwrap();
+        let (id, upsert) = db.add_holiday(&holiday, "test").unwrap();
+        assert_eq!(upsert, Upsert::Inserted);
+        let us = CountryCode::parse("us").unwrap();
+        let holidays = db.get_holidays_by_country(&us, None).unwrap();
         assert_eq!(holidays.len(), 1);
         assert_eq!(holidays[0].id, Some(id));
 
-        db.delete_holiday(id).unwrap();
-        let holidays = db.get_holidays_by_country("US").unwrap();
+        db.delete_holiday(id, "test").unwrap();
+        let holidays = db.get_holidays_by_country(&us, None).unwrap();
         assert_eq!(holidays.len(), 0);
     }
+
+    #[actix_rt::test]
+    async fn test_blocking_work_shares_the_pool() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("pool.db").to_str().unwrap()).unwrap();
+        let writes = (1..=12).map(|month| db.blocking(move |db| db.add_holiday(&Holiday {
+            id: None,
+            date: format!("2025-{:02}-01", month),
+            description: String::new(),
+            country: "fr".to_string(),
+            portion: HolidayPortion::Full,
+            kind: HolidayKind::Company,
+            subdivision: None,
+        }, "test")));
+        for write in futures_util::future::join_all(writes).await {
+            assert_eq!(write.unwrap().1, Upsert::Inserted);
+        }
+        assert_eq!(db.get_all_holidays().unwrap().len(), 12);
+    }
+
+    #[test]
+    fn test_regional_holidays_are_filtered_by_subdivision() {
+        let db = Database::new(":memory:").unwrap();
+        let de = CountryCode::parse("de").unwrap();
+        for (date, subdivision) in [("2025-10-03", None), ("2025-08-15", Some("de-by")), ("2025-11-01", Some("DE-BW")), ("2025-08-08", Some("DE-BY-AU"))] {
+            db.add_holiday(&Holiday {
+                id: None,
+                date: date.to_string(),
+                description: String::new(),
+                country: "de".to_string(),
+                portion: HolidayPortion::Full,
+                kind: HolidayKind::Public,
+                subdivision: subdivision.map(str::to_string),
+            }, "test").unwrap();
+        }
+        let dates = |subdivision: Option<&str>| -> Vec<String> {
+            let subdivision = subdivision.map(|code| Subdivision::parse(&de, code).unwrap());
+            let mut dates: Vec<String> = db.get_holidays_by_country(&de, subdivision.as_ref()).unwrap().into_iter().map(|h| h.date).collect();
+            dates.sort();
+            dates
+        };
+        assert_eq!(dates(None), vec!["2025-10-03"]);
+        assert_eq!(dates(Some("DE-BY")), vec!["2025-08-15", "2025-10-03"]);
+        assert_eq!(dates(Some("DE-BY-AU")), vec!["2025-08-08", "2025-08-15", "2025-10-03"]);
+        assert_eq!(db.get_all_holidays().unwrap().iter().filter_map(|h| h.subdivision.clone()).min().unwrap(), "DE-BW");
+    }
+
+    #[test]
+    fn test_holidays_added_twice_on_a_day_are_replaced() {
+        let db = Database::new(":memory:").unwrap();
+        let holiday = |date: &str, description: &str, subdivision: Option<&str>| Holiday {
+            id: None,
+            date: date.to_string(),
+            description: description.to_string(),
+            country: "fr".to_string(),
+            portion: HolidayPortion::Full,
+            kind: HolidayKind::Public,
+            subdivision: subdivision.map(str::to_string),
+        };
+        let (id, upsert) = db.add_holiday(&holiday("2025-07-14", "Bastille Day", None), "test").unwrap();
+        assert_eq!(upsert, Upsert::Inserted);
+        assert_eq!(db.add_holiday(&holiday("2025-07-14T00:00:00Z", "Fête nationale", None), "test").unwrap(), (id, Upsert::Updated));
+        // Another subdivision is another holiday
+        assert_eq!(db.add_holiday(&holiday("2025-07-14", "Bastille Day", Some("FR-IDF")), "test").unwrap().1, Upsert::Inserted);
+
+        let holidays = db.get_all_manual_holidays().unwrap();
+        assert_eq!(holidays.len(), 2);
+        assert_eq!((holidays[0].date.as_str(), holidays[0].description.as_str()), ("2025-07-14T00:00:00Z", "Fête nationale"));
+
+        // The index refuses duplicates written around add_holiday
+        let conn = db.conn().unwrap();
+        assert!(conn.execute("INSERT INTO holidays (date, description, country) VALUES ('2025-07-14', 'Bastille Day', 'fr')", []).is_err());
+    }
+
+    #[test]
+    fn test_fetched_holidays_replace_previous_fetches() {
+        let db = Database::new(":memory:").unwrap();
+        let holiday = |date: &str, description: &str| Holiday {
+            id: None,
+            date: date.to_string(),
+            description: description.to_string(),
+            country: "fr".to_string(),
+            portion: HolidayPortion::Full,
+            kind: HolidayKind::Public,
+            subdivision: None,
+        };
+        db.add_holiday(&holiday("2025-06-02", "Company day"), "test").unwrap();
+
+        let fr = CountryCode::parse("fr").unwrap();
+        db.store_fetched_holidays(&fr, "openholidays", &[holiday("2025-05-01", "Labour Day"), holiday("2026-05-01", "Labour Day")]).unwrap();
+        db.store_fetched_holidays(&fr, "openholidays", &[holiday("2025-05-01", "Labour Day"), holiday("2025-05-08", "Victory Day")]).unwrap();
+
+        // 2025 is replaced, 2026 and the holiday added by hand are kept
+        let mut dates: Vec<String> = db.get_holidays_by_country(&fr, None).unwrap().into_iter().map(|h| h.date).collect();
+        dates.sort();
+        assert_eq!(dates, vec!["2025-05-01", "2025-05-08", "2025-06-02", "2026-05-01"]);
+
+        // Only fetched holidays have a fetch time
+        assert!(db.get_fetched_at(&fr, 2025..=2026).unwrap().is_some());
+        assert_eq!(db.get_fetched_at(&fr, 2027..=2028).unwrap(), None);
+    }
+
+    #[test]
+    fn test_lease_operations() {
+        let db = Database::new(":memory:").unwrap();
+        let ttl = Duration::from_secs(30);
+
+        assert!(db.try_acquire_lease("jobs", "pod-a", ttl).unwrap());
+        // Renewing by the holder succeeds, another holder is rejected
+        assert!(db.try_acquire_lease("jobs", "pod-a", ttl).unwrap());
+        assert!(!db.try_acquire_lease("jobs", "pod-b", ttl).unwrap());
+
+        db.release_lease("jobs", "pod-a").unwrap();
+        assert!(db.try_acquire_lease("jobs", "pod-b", ttl).unwrap());
+    }
 }
@@ -0,0 +1,287 @@
+use actix_web::{web, HttpResponse, post};
+use chrono::{DateTime, NaiveTime};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+
+use crate::{compute_working_time, AppState, EndOrDuration, TimezoneMode, WorkHoursRequest, WorkingInterval};
+
+// Working time is searched in windows of this many days
+const WINDOW_DAYS: i64 = 31;
+// Give up after roughly ten years without enough working time
+const MAX_WINDOWS: usize = 120;
+
+// Calendar and schedule shared by the deadline and promise calculations
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduleParams {
+    #[serde(rename = "startDate", deserialize_with = "crate::deserialize_date_input")]
+    pub start_date: String,
+    #[serde(rename = "startOfDay", default = "crate::default_start_of_day")]
+    pub start_of_day: String,
+    #[serde(rename = "endOfDay", default = "crate::default_end_of_day")]
+    pub end_of_day: String,
+    #[serde(rename = "workingIntervals", default)]
+    pub working_intervals: Vec<WorkingInterval>,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+    #[serde(default)]
+    pub timezone: String,
+    #[serde(rename = "timezoneMode", default)]
+    pub timezone_mode: TimezoneMode,
+    #[serde(rename = "excludeDates", default)]
+    pub exclude_dates: Vec<String>,
+    #[serde(rename = "includeDates", default)]
+    pub include_dates: Vec<String>,
+    // Requests received after this local time are processed from the next day
+    #[serde(default)]
+    pub cutoff: Option<String>,
+}
+
+impl ScheduleParams {
+    fn template(&self) -> WorkHoursRequest {
+        WorkHoursRequest {
+            start_date: self.start_date.clone(),
+            start_of_day: self.start_of_day.clone(),
+            end_of_day: self.end_of_day.clone(),
+            working_intervals: self.working_intervals.clone(),
+            country: self.country.clone(),
+            subdivision: self.subdivision.clone(),
+            timezone: self.timezone.clone(),
+            timezone_mode: self.timezone_mode,
+            exclude_dates: self.exclude_dates.clone(),
+            include_dates: self.include_dates.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadlineRequest {
+    #[serde(flatten)]
+    pub schedule: ScheduleParams,
+    #[serde(rename = "workingSeconds")]
+    pub working_seconds: i64,
+    // Contractual lead time: the deadline is never earlier than start + N business hours
+    #[serde(rename = "minimumLeadBusinessHours", default)]
+    pub minimum_lead_business_hours: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadlineResponse {
+    pub deadline: String,
+    pub effective_start: String,
+    pub rolled: bool,
+    #[serde(default)]
+    pub minimum_lead_applied: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PromiseRequest {
+    #[serde(flatten)]
+    pub schedule: ScheduleParams,
+    #[serde(rename = "businessDays")]
+    pub business_days: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PromiseResponse {
+    pub promise_date: String,
+    pub processing_date: String,
+    pub rolled: bool,
+}
+
+// Parse the start date in the request timezone, rolling to the next day after the cut-off
+fn effective_start(schedule: &ScheduleParams) -> Result<(DateTime<Tz>, bool), actix_web::error::Error> {
+    let timezone: Tz = schedule.timezone.parse()
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone: {}", e)))?;
+    let intervals = crate::resolve_intervals(&schedule.template())?;
+    let start = crate::parse_request_date(
+        &schedule.start_date, "start", timezone, schedule.timezone_mode,
+        |date| crate::start_of_schedule(date, &intervals),
+    )?;
+
+    let cutoff = match &schedule.cutoff {
+        Some(cutoff) => NaiveTime::parse_from_str(cutoff, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(cutoff, "%H:%M"))
+            .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid cutoff format: {}", e)))?,
+        None => return Ok((start, false)),
+    };
+    if start.time() <= cutoff {
+        return Ok((start, false));
+    }
+
+    let next_day = start.date_naive() + chrono::Duration::days(1);
+    let rolled = crate::resolve_local(&timezone, next_day.and_hms_opt(0, 0, 0).unwrap());
+    Ok((rolled, true))
+}
+
+async fn window(
+    data: &web::Data<AppState>,
+    template: &WorkHoursRequest,
+    from: DateTime<Tz>,
+    to: DateTime<Tz>,
+) -> Result<Vec<(DateTime<Tz>, DateTime<Tz>)>, actix_web::error::Error> {
+    let mut request = template.clone();
+    request.start_date = from.to_rfc3339();
+    request.end_or_duration = EndOrDuration::EndDate { end_date: to.to_rfc3339() };
+    Ok(compute_working_time(data, &request).await?.intervals)
+}
+
+// Consume the given amount of working time from start and return the instant it runs out
+pub async fn add_working_seconds(
+    data: &web::Data<AppState>,
+    template: &WorkHoursRequest,
+    start: DateTime<Tz>,
+    seconds: i64,
+) -> Result<DateTime<Tz>, actix_web::error::Error> {
+    if seconds <= 0 {
+        return Err(actix_web::error::ErrorBadRequest("Working duration must be strictly positive"));
+    }
+
+    let mut cursor = start;
+    let mut remaining = seconds;
+    for _ in 0..MAX_WINDOWS {
+        let window_end = cursor + chrono::Duration::days(WINDOW_DAYS);
+        for (interval_start, interval_end) in window(data, template, cursor, window_end).await? {
+            let available = interval_end.signed_duration_since(interval_start).num_seconds();
+            if available >= remaining {
+                return Ok(interval_start + chrono::Duration::seconds(remaining));
+            }
+            remaining -= available;
+        }
+        cursor = window_end;
+    }
+    Err(actix_web::error::ErrorUnprocessableEntity("Not enough working time found in the search window"))
+}
+
+#[post("/deadline")]
+pub async fn calculate_deadline(
+    data: web::Data<AppState>,
+    req: web::Json<DeadlineRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let (start, rolled) = effective_start(&req.schedule)?;
+    let template = req.schedule.template();
+    let mut deadline = add_working_seconds(&data, &template, start, req.working_seconds).await?;
+
+    let mut minimum_lead_applied = false;
+    if let Some(lead_hours) = req.minimum_lead_business_hours {
+        if lead_hours < 0.0 {
+            return Err(actix_web::error::ErrorBadRequest("minimumLeadBusinessHours must not be negative"));
+        }
+        let lead_seconds = (lead_hours * 3600.0).round() as i64;
+        if lead_seconds > 0 {
+            let earliest = add_working_seconds(&data, &template, start, lead_seconds).await?;
+            if earliest > deadline {
+                deadline = earliest;
+                minimum_lead_applied = true;
+            }
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(DeadlineResponse {
+        deadline: deadline.to_rfc3339(),
+        effective_start: start.to_rfc3339(),
+        rolled,
+        minimum_lead_applied,
+    }))
+}
+
+#[post("/promise")]
+pub async fn calculate_promise(
+    data: web::Data<AppState>,
+    req: web::Json<PromiseRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let (start, rolled) = effective_start(&req.schedule)?;
+    let template = req.schedule.template();
+
+    // Working days are the days with at least one working interval, starting at local midnight
+    let mut cursor = crate::resolve_local(&start.timezone(), start.date_naive().and_hms_opt(0, 0, 0).unwrap());
+    let mut working_days = vec![];
+    for _ in 0..MAX_WINDOWS {
+        let window_end = cursor + chrono::Duration::days(WINDOW_DAYS);
+        for (interval_start, _) in window(&data, &template, cursor, window_end).await? {
+            let date = interval_start.date_naive();
+            if working_days.last() != Some(&date) {
+                working_days.push(date);
+            }
+        }
+        // The processing day plus the requested number of business days
+        if working_days.len() > req.business_days as usize {
+            break;
+        }
+        cursor = window_end;
+    }
+
+    // Processing happens on the start day when it is a working day, otherwise on the next one
+    let processing_date = working_days.first()
+        .ok_or_else(|| actix_web::error::ErrorUnprocessableEntity("No working day found in the search window"))?;
+    let promise_date = working_days.get(req.business_days as usize)
+        .ok_or_else(|| actix_web::error::ErrorUnprocessableEntity("Not enough working days found in the search window"))?;
+
+    Ok(HttpResponse::Ok().json(PromiseResponse {
+        promise_date: promise_date.to_string(),
+        processing_date: processing_date.to_string(),
+        rolled,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::db;
+
+    async fn post<T: serde::de::DeserializeOwned>(uri: &str, body: serde_json::Value) -> T {
+        let data = web::Data::new(AppState { db: db::Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(
+            App::new().app_data(data).service(calculate_deadline).service(calculate_promise)
+        ).await;
+        let req = test::TestRequest::post().uri(uri).set_json(body).to_request();
+        test::call_and_read_body_json(&app, req).await
+    }
+
+    #[actix_rt::test]
+    async fn test_deadline_rolls_after_cutoff() {
+        // Friday 16:00 with 4 working hours: 1 hour on Friday and 3 hours on Monday
+        let response: DeadlineResponse = post("/deadline", serde_json::json!({
+            "startDate": "2023-10-06T16:00:00Z", "workingSeconds": 14400, "timezone": "UTC"
+        })).await;
+        assert_eq!(response.deadline, "2023-10-09T12:00:00+00:00");
+        assert!(!response.rolled);
+
+        // After a 15:00 cutoff the work only starts on Monday morning
+        let response: DeadlineResponse = post("/deadline", serde_json::json!({
+            "startDate": "2023-10-06T16:00:00Z", "workingSeconds": 14400, "timezone": "UTC", "cutoff": "15:00"
+        })).await;
+        assert_eq!(response.deadline, "2023-10-09T13:00:00+00:00");
+        assert!(response.rolled);
+    }
+
+    #[actix_rt::test]
+    async fn test_deadline_minimum_lead_time() {
+        // One working hour requested, but the contract requires at least 16 business hours
+        let response: DeadlineResponse = post("/deadline", serde_json::json!({
+            "startDate": "2023-10-02T09:00:00Z", "workingSeconds": 3600, "timezone": "UTC",
+            "minimumLeadBusinessHours": 16
+        })).await;
+        assert_eq!(response.deadline, "2023-10-03T17:00:00+00:00");
+        assert!(response.minimum_lead_applied);
+    }
+
+    #[actix_rt::test]
+    async fn test_promise_with_cutoff() {
+        let response: PromiseResponse = post("/promise", serde_json::json!({
+            "startDate": "2023-10-05T10:00:00Z", "businessDays": 1, "timezone": "UTC", "cutoff": "15:00"
+        })).await;
+        assert_eq!(response.processing_date, "2023-10-05");
+        assert_eq!(response.promise_date, "2023-10-06");
+
+        let response: PromiseResponse = post("/promise", serde_json::json!({
+            "startDate": "2023-10-05T16:00:00Z", "businessDays": 1, "timezone": "UTC", "cutoff": "15:00"
+        })).await;
+        assert_eq!(response.processing_date, "2023-10-06");
+        assert_eq!(response.promise_date, "2023-10-09");
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use actix_web::{web, HttpRequest, HttpResponse, get, post};
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+
+use crate::db::Database;
+use crate::holiday_import::stored_date;
+use crate::calendar_config::author;
+use crate::{freeze, AppState};
+
+// Holidays added through the API several times for the same country, subdivision and day, by
+// repeated POSTs or imports before add_holiday replaced them
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Duplicate {
+    pub country: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subdivision: Option<String>,
+    pub date: String,
+    pub ids: Vec<i32>,
+    // Distinct descriptions, in the order they were added
+    pub descriptions: Vec<String>,
+}
+
+// Description to keep for a duplicated day, the distinct descriptions joined otherwise
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MergeChoice {
+    pub country: String,
+    pub date: String,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MergeReport {
+    pub merged: Vec<Duplicate>,
+    // Rows removed, one per merged day is kept
+    pub deleted: usize,
+}
+
+pub fn find(db: &Database) -> rusqlite::Result<Vec<Duplicate>> {
+    let mut days: BTreeMap<(String, Option<String>, NaiveDate), Duplicate> = BTreeMap::new();
+    for holiday in db.get_all_manual_holidays()? {
+        let date = match stored_date(&holiday) {
+            Some(date) => date,
+            None => continue,
+        };
+        let subdivision = holiday.subdivision.as_ref().map(|subdivision| subdivision.to_uppercase());
+        let day = days.entry((holiday.country.clone(), subdivision.clone(), date)).or_insert_with(|| Duplicate {
+            country: holiday.country.clone(),
+            subdivision,
+            date: date.to_string(),
+            ids: vec![],
+            descriptions: vec![],
+        });
+        day.ids.push(holiday.id.unwrap_or_default());
+        if !day.descriptions.contains(&holiday.description) {
+            day.descriptions.push(holiday.description);
+        }
+    }
+    Ok(days.into_values().filter(|day| day.ids.len() > 1).collect())
+}
+
+#[get("/admin/duplicates")]
+pub async fn list_duplicates(data: web::Data<AppState>) -> Result<HttpResponse, actix_web::error::Error> {
+    let duplicates = data.db.blocking(find).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(duplicates))
+}
+
+// Keeps the first holiday of each duplicated day, with the chosen description or the distinct
+// descriptions joined, and deletes the others. Holidays are unique per day from then on.
+#[post("/admin/duplicates/merge")]
+pub async fn merge_duplicates(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    choices: Option<web::Json<Vec<MergeChoice>>>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let choices = choices.map(|choices| choices.into_inner()).unwrap_or_default();
+    let author = author(&http_req);
+    freeze::ensure_unfrozen(&data.db).await?;
+    let report = data.db.blocking(move |db| {
+        let duplicates = find(db)?;
+        let (mut updated, mut deleted) = (vec![], vec![]);
+        for duplicate in &duplicates {
+            let description = choices.iter()
+                .find(|choice| choice.country.eq_ignore_ascii_case(&duplicate.country) && choice.date == duplicate.date)
+                .map_or_else(|| duplicate.descriptions.join(", "), |choice| choice.description.clone());
+            updated.push((duplicate.ids[0], description));
+            deleted.extend_from_slice(&duplicate.ids[1..]);
+        }
+        db.import_holidays(&[], &updated, &deleted, &author)?;
+        db.ensure_unique_holidays()?;
+        Ok(MergeReport { merged: duplicates, deleted: deleted.len() })
+    }).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::db::{Holiday, HolidayKind, HolidayPortion, Upsert};
+    use crate::holiday_provider::MockProvider;
+
+    fn holiday(country: &str, date: &str, description: &str) -> Holiday {
+        Holiday { id: None, date: date.to_string(), description: description.to_string(), country: country.to_string(), portion: HolidayPortion::Full, kind: HolidayKind::Public, subdivision: None }
+    }
+
+    #[actix_rt::test]
+    async fn test_merge_duplicates() {
+        // Stored before holidays were unique per day, add_holiday now replaces them
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("legacy.db");
+        let path = path.to_str().unwrap();
+        drop(Database::new(path).unwrap());
+        let conn = rusqlite::Connection::open(path).unwrap();
+        conn.execute("DROP INDEX holidays_unique_live_day", []).unwrap();
+        for holiday in [
+            holiday("fr", "2025-07-14", "Bastille Day"),
+            holiday("fr", "2025-07-14T00:00:00Z", "Fête nationale"),
+            holiday("fr", "2025-07-14", "Bastille Day"),
+            holiday("de", "2025-10-03", "German Unity Day"),
+            holiday("de", "2025-10-03", "Tag der Deutschen Einheit"),
+            holiday("de", "2025-12-25", "Christmas"),
+        ] {
+            conn.execute("INSERT INTO holidays (date, description, country) VALUES (?, ?, ?)", rusqlite::params![holiday.date, holiday.description, holiday.country]).unwrap();
+        }
+        drop(conn);
+        let database = Database::new(path).unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data.clone()).service(list_duplicates).service(merge_duplicates)).await;
+
+        let req = test::TestRequest::get().uri("/admin/duplicates").to_request();
+        let duplicates: Vec<Duplicate> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(duplicates[1], Duplicate {
+            country: "fr".to_string(),
+            subdivision: None,
+            date: "2025-07-14".to_string(),
+            ids: vec![1, 2, 3],
+            descriptions: vec!["Bastille Day".to_string(), "Fête nationale".to_string()],
+        });
+
+        let req = test::TestRequest::post().uri("/admin/duplicates/merge")
+            .set_json(serde_json::json!([{ "country": "de", "date": "2025-10-03", "description": "German Unity Day" }]))
+            .to_request();
+        let report: MergeReport = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(report.deleted, 3);
+
+        let db = &data.db;
+        assert!(find(db).unwrap().is_empty());
+        let descriptions: Vec<String> = db.get_all_manual_holidays().unwrap().into_iter().map(|h| h.description).collect();
+        assert_eq!(descriptions, vec!["Bastille Day, Fête nationale", "German Unity Day", "Christmas"]);
+        let (_, upsert) = db.add_holiday(&holiday("fr", "2025-07-14", "Bastille Day"), "test").unwrap();
+        assert_eq!(upsert, Upsert::Updated);
+    }
+}
@@ -0,0 +1,88 @@
+// ISO 8601 durations (PT38H30M), hours are not folded into days since a working day is not 24 hours
+pub fn format_iso8601(seconds: i64) -> String {
+    if seconds == 0 {
+        return "PT0S".to_string();
+    }
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.abs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+
+    let mut duration = format!("{}PT", sign);
+    if hours > 0 {
+        duration.push_str(&format!("{}H", hours));
+    }
+    if minutes > 0 {
+        duration.push_str(&format!("{}M", minutes));
+    }
+    if seconds > 0 {
+        duration.push_str(&format!("{}S", seconds));
+    }
+    duration
+}
+
+// Parse PnW, PnD and PTnHnMnS durations into seconds. Years and months have no fixed
+// length and are rejected, a day is 24 hours of wall-clock time.
+pub fn parse_iso8601(value: &str) -> Result<i64, String> {
+    let invalid = || format!("Invalid ISO 8601 duration: {}", value);
+    let rest = value.strip_prefix('P').ok_or_else(invalid)?;
+    if rest.is_empty() || rest == "T" {
+        return Err(invalid());
+    }
+
+    let mut seconds: i64 = 0;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' if !in_time && number.is_empty() => in_time = true,
+            _ => {
+                let amount: i64 = number.parse().map_err(|_| invalid())?;
+                number.clear();
+                let unit = match (c, in_time) {
+                    ('W', false) => 7 * 86400,
+                    ('D', false) => 86400,
+                    ('H', true) => 3600,
+                    ('M', true) => 60,
+                    ('S', true) => 1,
+                    ('Y', false) | ('M', false) => {
+                        return Err(format!("Years and months are not supported in durations: {}", value));
+                    }
+                    _ => return Err(invalid()),
+                };
+                seconds = amount.checked_mul(unit)
+                    .and_then(|s| seconds.checked_add(s))
+                    .ok_or_else(invalid)?;
+            }
+        }
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    Ok(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_iso8601() {
+        assert_eq!(format_iso8601(0), "PT0S");
+        assert_eq!(format_iso8601(138600), "PT38H30M");
+        assert_eq!(format_iso8601(3605), "PT1H5S");
+        assert_eq!(format_iso8601(-90), "-PT1M30S");
+    }
+
+    #[test]
+    fn test_parse_iso8601() {
+        assert_eq!(parse_iso8601("P3D"), Ok(259200));
+        assert_eq!(parse_iso8601("PT40H"), Ok(144000));
+        assert_eq!(parse_iso8601("P1DT1H30M5S"), Ok(91805));
+        assert_eq!(parse_iso8601("P1W"), Ok(604800));
+        assert!(parse_iso8601("P1M").is_err());
+        assert!(parse_iso8601("PT").is_err());
+        assert!(parse_iso8601("3D").is_err());
+        assert!(parse_iso8601("PT5").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{Duration, NaiveDate};
+
+// Easter Sunday of the Gregorian calendar (Meeus/Jones/Butcher algorithm)
+pub fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+// Holidays at a fixed distance from Easter Sunday, named like in the holiday providers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MovableFeast {
+    GoodFriday,
+    EasterMonday,
+    AscensionDay,
+    WhitMonday,
+}
+
+impl MovableFeast {
+    pub const ALL: [MovableFeast; 4] = [MovableFeast::GoodFriday, MovableFeast::EasterMonday, MovableFeast::AscensionDay, MovableFeast::WhitMonday];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            MovableFeast::GoodFriday => "Good Friday",
+            MovableFeast::EasterMonday => "Easter Monday",
+            MovableFeast::AscensionDay => "Ascension Day",
+            MovableFeast::WhitMonday => "Whit Monday",
+        }
+    }
+
+    pub fn from_description(description: &str) -> Option<MovableFeast> {
+        MovableFeast::ALL.into_iter().find(|feast| feast.description().eq_ignore_ascii_case(description.trim()))
+    }
+
+    // Days after Easter Sunday
+    fn offset(&self) -> i64 {
+        match self {
+            MovableFeast::GoodFriday => -2,
+            MovableFeast::EasterMonday => 1,
+            MovableFeast::AscensionDay => 39,
+            MovableFeast::WhitMonday => 50,
+        }
+    }
+
+    pub fn date(&self, year: i32) -> NaiveDate {
+        easter_sunday(year) + Duration::days(self.offset())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_easter_dates() {
+        for (year, month, day) in [(1961, 4, 2), (2000, 4, 23), (2008, 3, 23), (2011, 4, 24), (2024, 3, 31), (2025, 4, 20), (2038, 4, 25)] {
+            assert_eq!(easter_sunday(year), NaiveDate::from_ymd_opt(year, month, day).unwrap());
+        }
+        assert_eq!(MovableFeast::GoodFriday.date(2025), NaiveDate::from_ymd_opt(2025, 4, 18).unwrap());
+        assert_eq!(MovableFeast::AscensionDay.date(2025), NaiveDate::from_ymd_opt(2025, 5, 29).unwrap());
+        assert_eq!(MovableFeast::WhitMonday.date(2024), NaiveDate::from_ymd_opt(2024, 5, 20).unwrap());
+        assert_eq!(MovableFeast::from_description("whit monday"), Some(MovableFeast::WhitMonday));
+        assert_eq!(MovableFeast::from_description("Labour Day"), None);
+    }
+}
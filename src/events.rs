@@ -0,0 +1,270 @@
+use std::env;
+use std::time::Duration;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use log::{info, warn};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+// Events waiting for the NATS connection, newer ones are dropped when it is full
+const NATS_QUEUE_SIZE: usize = 1024;
+
+// Time allowed to connect to NATS or to have an event acknowledged
+const NATS_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Events emitted for downstream analytics and cache invalidation consumers
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    CalculationPerformed {
+        start_date: String,
+        end_date: String,
+        country: String,
+        work_seconds: f64,
+    },
+    // Calendar created, updated or deleted, including its closures (company holidays)
+    CalendarChanged {
+        calendar_id: String,
+        author: String,
+        action: String,
+    },
+    JobCompleted {
+        job: String,
+        details: serde_json::Value,
+    },
+}
+
+impl Event {
+    // Appended to the configured prefix to form the NATS subject or Kafka topic
+    pub fn subject(&self) -> &'static str {
+        match self {
+            Event::CalculationPerformed { .. } => "calculation.performed",
+            Event::CalendarChanged { .. } => "calendar.changed",
+            Event::JobCompleted { .. } => "job.completed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Backend {
+    // host:port of a NATS server
+    Nats(String),
+    // Base URL of a Kafka REST proxy
+    Kafka(String),
+}
+
+pub struct Publisher {
+    backend: Option<Backend>,
+    prefix: String,
+    client: reqwest::Client,
+    // Queue of the task holding the NATS connection, with the subject and payload of each event
+    nats: Option<mpsc::Sender<(String, String)>>,
+}
+
+impl Publisher {
+    pub fn from_env() -> Self {
+        let backend = match env::var("EVENTS_BACKEND").unwrap_or_default().to_lowercase().as_str() {
+            "nats" => {
+                let url = env::var("NATS_URL").unwrap_or_else(|_| "nats://localhost:4222".to_string());
+                Some(Backend::Nats(url.trim_start_matches("nats://").to_string()))
+            }
+            "kafka" => env::var("KAFKA_REST_URL").ok()
+                .map(|url| Backend::Kafka(url.trim_end_matches('/').to_string())),
+            _ => None,
+        };
+        if let Some(backend) = &backend {
+            info!("Publishing events to {:?}", backend);
+        }
+        let nats = match &backend {
+            Some(Backend::Nats(address)) => Some(spawn_nats(address.clone())),
+            _ => None,
+        };
+        Publisher {
+            backend,
+            prefix: env::var("EVENTS_PREFIX").unwrap_or_else(|_| "workhours".to_string()),
+            client: reqwest::Client::new(),
+            nats,
+        }
+    }
+
+    fn subject(&self, event: &Event) -> String {
+        format!("{}.{}", self.prefix, event.subject())
+    }
+
+    // Fire and forget, a broker outage must never fail the request that emitted the event
+    pub fn publish(&'static self, event: Event) {
+        let url = match (&self.backend, &self.nats) {
+            (Some(Backend::Kafka(url)), _) => url.clone(),
+            (Some(Backend::Nats(_)), Some(queue)) => {
+                let subject = self.subject(&event);
+                let payload = json!({ "occurredAt": Utc::now().to_rfc3339(), "event": event });
+                if let Err(e) = queue.try_send((subject.clone(), payload.to_string())) {
+                    warn!("Dropped {} event: {}", subject, e);
+                }
+                return;
+            }
+            _ => return,
+        };
+        let runtime = match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => runtime,
+            Err(_) => return,
+        };
+        runtime.spawn(async move {
+            let subject = self.subject(&event);
+            let payload = json!({ "occurredAt": Utc::now().to_rfc3339(), "event": event });
+            if let Err(e) = self.publish_kafka(&url, &subject, payload).await {
+                warn!("Failed to publish {} event: {}", subject, e);
+            }
+        });
+    }
+
+    async fn publish_kafka(&self, url: &str, topic: &str, payload: serde_json::Value) -> Result<(), String> {
+        let response = self.client
+            .post(format!("{}/topics/{}", url, topic))
+            .header("Content-Type", "application/vnd.kafka.json.v2+json")
+            .body(json!({ "records": [{ "value": payload }] }).to_string())
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Kafka REST proxy returned {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+// NATS text protocol: CONNECT once, then PUB <subject> <bytes> per event
+const NATS_CONNECT: &str = "CONNECT {\"verbose\":false,\"name\":\"workhours\"}\r\n";
+
+// PING/PONG confirms the server processed the PUB
+fn nats_frame(subject: &str, payload: &str) -> String {
+    format!("PUB {} {}\r\n{}\r\nPING\r\n", subject, payload.len(), payload)
+}
+
+async fn connect_nats(address: &str) -> Result<BufReader<TcpStream>, String> {
+    let stream = TcpStream::connect(address).await.map_err(|e| e.to_string())?;
+    let mut stream = BufReader::new(stream);
+    let mut line = String::new();
+    // The server greets with INFO
+    stream.read_line(&mut line).await.map_err(|e| e.to_string())?;
+    stream.get_mut().write_all(NATS_CONNECT.as_bytes()).await.map_err(|e| e.to_string())?;
+    Ok(stream)
+}
+
+async fn publish_nats(stream: &mut BufReader<TcpStream>, subject: &str, payload: &str) -> Result<(), String> {
+    stream.get_mut().write_all(nats_frame(subject, payload).as_bytes()).await.map_err(|e| e.to_string())?;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if stream.read_line(&mut line).await.map_err(|e| e.to_string())? == 0 {
+            return Err("Connection closed by the NATS server".to_string());
+        }
+        match line.trim() {
+            "PONG" => return Ok(()),
+            // Keepalive of the server, sent while the connection was idle
+            "PING" => stream.get_mut().write_all(b"PONG\r\n").await.map_err(|e| e.to_string())?,
+            reply if reply.starts_with("INFO") => {}
+            reply => return Err(format!("Unexpected NATS reply: {}", reply)),
+        }
+    }
+}
+
+// Publishes queued events over a single connection, opened again after a failure. Each event
+// is tried once more on a fresh connection, since an idle one may have been closed meanwhile.
+async fn run_nats(address: String, mut queue: mpsc::Receiver<(String, String)>) {
+    let mut connection: Option<BufReader<TcpStream>> = None;
+    while let Some((subject, payload)) = queue.recv().await {
+        let mut result = Err(String::new());
+        for _ in 0..2 {
+            let mut stream = match connection.take() {
+                Some(stream) => stream,
+                None => match timeout(NATS_TIMEOUT, connect_nats(&address)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => { result = Err(e); continue; }
+                    Err(_) => { result = Err(format!("Timed out connecting to {}", address)); continue; }
+                },
+            };
+            result = match timeout(NATS_TIMEOUT, publish_nats(&mut stream, &subject, &payload)).await {
+                Ok(result) => result,
+                Err(_) => Err("Timed out waiting for the NATS server".to_string()),
+            };
+            if result.is_ok() {
+                connection = Some(stream);
+                break;
+            }
+        }
+        if let Err(e) = result {
+            warn!("Failed to publish {} event: {}", subject, e);
+        }
+    }
+}
+
+// The publisher runs on a thread of its own, so it outlives the worker that sent the first event
+fn spawn_nats(address: String) -> mpsc::Sender<(String, String)> {
+    let (sender, queue) = mpsc::channel(NATS_QUEUE_SIZE);
+    std::thread::spawn(move || {
+        match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime.block_on(run_nats(address, queue)),
+            Err(e) => warn!("Failed to start the NATS publisher: {}", e),
+        }
+    });
+    sender
+}
+
+lazy_static! {
+    static ref PUBLISHER: Publisher = Publisher::from_env();
+}
+
+pub fn publish(event: Event) {
+    PUBLISHER.publish(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialization_and_nats_frame() {
+        let event = Event::CalendarChanged {
+            calendar_id: "acme-paris".to_string(),
+            author: "alice".to_string(),
+            action: "updated".to_string(),
+        };
+        assert_eq!(event.subject(), "calendar.changed");
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({ "type": "calendar_changed", "calendar_id": "acme-paris", "author": "alice", "action": "updated" })
+        );
+
+        let frame = nats_frame("workhours.calendar.changed", "{}");
+        assert_eq!(frame, "PUB workhours.calendar.changed 2\r\n{}\r\nPING\r\n");
+    }
+
+    #[actix_rt::test]
+    async fn test_nats_events_share_one_connection() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let queue = spawn_nats(listener.local_addr().unwrap().to_string());
+        queue.send(("workhours.a".to_string(), "{}".to_string())).await.unwrap();
+        queue.send(("workhours.b".to_string(), "{}".to_string())).await.unwrap();
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        stream.get_mut().write_all(b"INFO {}\r\n").await.unwrap();
+        let mut subjects = vec![];
+        let mut line = String::new();
+        while subjects.len() < 2 {
+            line.clear();
+            stream.read_line(&mut line).await.unwrap();
+            if let Some(publish) = line.strip_prefix("PUB ") {
+                subjects.push(publish.split(' ').next().unwrap().to_string());
+            } else if line.trim() == "PING" {
+                stream.get_mut().write_all(b"PONG\r\n").await.unwrap();
+            }
+        }
+        assert_eq!(subjects, vec!["workhours.a", "workhours.b"]);
+    }
+}
@@ -0,0 +1,105 @@
+use actix_web::{HttpResponse, get};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+
+use crate::presets;
+
+// Ready-to-run request of an endpoint, for the playground and Swagger UI
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Example {
+    pub name: String,
+    pub method: String,
+    // Path with its query string
+    pub path: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+// Country of the examples, EXAMPLES_COUNTRY or France
+pub fn country() -> String {
+    std::env::var("EXAMPLES_COUNTRY").map(|country| country.trim().to_lowercase())
+        .ok()
+        .filter(|country| !country.is_empty())
+        .unwrap_or_else(|| "fr".to_string())
+}
+
+fn example(name: &str, method: &str, path: String, description: &str, body: Option<Value>) -> Example {
+    Example { name: name.to_string(), method: method.to_string(), path, description: description.to_string(), body }
+}
+
+// Dated around today: the next two working weeks from the coming Monday, with the hours
+// and timezone of the country preset when there is one
+pub fn examples(country: &str, today: NaiveDate) -> Vec<Example> {
+    let preset = presets::preset_for(country);
+    let timezone = preset.map_or("UTC", |preset| preset.timezone);
+    let start_of_day = preset.map_or_else(crate::default_start_of_day, |preset| preset.start_of_day.to_string());
+    let end_of_day = preset.map_or_else(crate::default_end_of_day, |preset| preset.end_of_day.to_string());
+    let monday = today + Duration::days((7 - today.weekday().num_days_from_monday() as i64) % 7);
+    let friday = monday + Duration::days(11);
+    vec![
+        example("Work hours", "POST", "/".to_string(), "Working hours over two weeks, holidays removed", Some(json!({
+            "startDate": monday.to_string(),
+            "endDate": friday.to_string(),
+            "startOfDay": start_of_day,
+            "endOfDay": end_of_day,
+            "country": country,
+            "timezone": timezone,
+        }))),
+        example("Is working", "GET", format!("/is-working?at={}T10:00:00Z&country={}&timezone={}&startOfDay={}&endOfDay={}", monday, country, timezone, start_of_day, end_of_day),
+            "Whether an instant falls in the working time, and the next working instant otherwise", None),
+        example("First working day", "GET", format!("/first-working-day?after={}&country={}", today, country),
+            "First working day after today", None),
+        example("Last working day of the month", "GET", format!("/nth-working-day?month={}&n=-1&country={}", today.format("%Y-%m"), country),
+            "Last working day of the current month", None),
+        example("Deadline", "POST", "/deadline".to_string(), "Instant at which three working days run out", Some(json!({
+            "startDate": monday.to_string(),
+            "workingSeconds": 3 * 8 * 3600,
+            "startOfDay": start_of_day,
+            "endOfDay": end_of_day,
+            "country": country,
+            "timezone": timezone,
+        }))),
+        example("Promise date", "POST", "/promise".to_string(), "Date five business days from today", Some(json!({
+            "startDate": today.to_string(),
+            "businessDays": 5,
+            "country": country,
+            "timezone": timezone,
+        }))),
+        example("Holidays of the year", "GET", format!("/holidays/{}/{}", country, today.year()),
+            "Holidays a calculation loads for the current year", None),
+        example("Check a date", "GET", format!("/holidays/{}/check?date={}", country, today),
+            "Whether today is a holiday", None),
+        example("Countries", "GET", "/countries".to_string(), "Countries with holiday data", None),
+        example("Preset", "GET", format!("/presets/{}", country), "Typical business hours of the country", None),
+    ]
+}
+
+#[get("/examples")]
+pub async fn list_examples() -> HttpResponse {
+    HttpResponse::Ok().json(examples(&country(), Utc::now().date_naive()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples_follow_today_and_the_country_preset() {
+        // A Wednesday
+        let today = NaiveDate::from_ymd_opt(2025, 6, 11).unwrap();
+        let examples = examples("de", today);
+        let work_hours = examples[0].body.as_ref().unwrap();
+        assert_eq!(work_hours["startDate"], "2025-06-16");
+        assert_eq!(work_hours["endDate"], "2025-06-27");
+        assert_eq!(work_hours["timezone"], "Europe/Berlin");
+        assert_eq!(work_hours["endOfDay"], "16:00:00");
+        assert!(examples.iter().any(|example| example.path == "/holidays/de/2025"));
+
+        // A Monday starts the same day, countries without a preset use UTC
+        let monday = NaiveDate::from_ymd_opt(2025, 6, 16).unwrap();
+        let work_hours = super::examples("zz", monday)[0].body.clone().unwrap();
+        assert_eq!((work_hours["startDate"].as_str(), work_hours["timezone"].as_str()), (Some("2025-06-16"), Some("UTC")));
+    }
+}
@@ -0,0 +1,90 @@
+use actix_web::HttpResponse;
+use serde::{Serialize, Deserialize};
+
+// Output format of report endpoints, parquet needs the `parquet` feature
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Parquet,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ExportParams {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+pub const PARQUET_CONTENT_TYPE: &str = "application/vnd.apache.parquet";
+
+// Encode rows as a single Parquet file, the columns are inferred from the serialized rows
+#[cfg(feature = "parquet")]
+pub fn to_parquet<T: Serialize>(rows: &[T]) -> Result<Vec<u8>, String> {
+    use std::sync::Arc;
+    use arrow::json::reader::{infer_json_schema_from_iterator, ReaderBuilder};
+    use parquet::arrow::ArrowWriter;
+
+    let values = rows.iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let schema = Arc::new(infer_json_schema_from_iterator(values.iter().map(Ok)).map_err(|e| e.to_string())?);
+
+    let mut decoder = ReaderBuilder::new(schema.clone()).build_decoder().map_err(|e| e.to_string())?;
+    decoder.serialize(&values).map_err(|e| e.to_string())?;
+
+    let mut buffer = vec![];
+    let mut writer = ArrowWriter::try_new(&mut buffer, schema, None).map_err(|e| e.to_string())?;
+    if let Some(batch) = decoder.flush().map_err(|e| e.to_string())? {
+        writer.write(&batch).map_err(|e| e.to_string())?;
+    }
+    writer.close().map_err(|e| e.to_string())?;
+    Ok(buffer)
+}
+
+#[cfg(not(feature = "parquet"))]
+pub fn to_parquet<T: Serialize>(_rows: &[T]) -> Result<Vec<u8>, String> {
+    Err("Parquet export is not enabled in this build".to_string())
+}
+
+// Respond with the JSON body, or with the rows as a Parquet file
+pub fn respond<B: Serialize, T: Serialize>(
+    format: ExportFormat,
+    body: &B,
+    rows: &[T],
+) -> Result<HttpResponse, actix_web::error::Error> {
+    match format {
+        ExportFormat::Json => Ok(HttpResponse::Ok().json(body)),
+        ExportFormat::Parquet => {
+            if !cfg!(feature = "parquet") {
+                return Err(actix_web::error::ErrorBadRequest("Parquet export is not enabled in this build"));
+            }
+            let file = to_parquet(rows).map_err(actix_web::error::ErrorInternalServerError)?;
+            Ok(HttpResponse::Ok().content_type(PARQUET_CONTENT_TYPE).body(file))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "parquet"))]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Row {
+        date: String,
+        hours: f64,
+        weekend: bool,
+    }
+
+    #[test]
+    fn test_rows_are_written_as_parquet() {
+        let rows = vec![
+            Row { date: "2023-10-07".to_string(), hours: 6.0, weekend: true },
+            Row { date: "2023-10-09".to_string(), hours: 8.0, weekend: false },
+        ];
+        let file = to_parquet(&rows).unwrap();
+        assert_eq!(&file[..4], b"PAR1");
+        assert_eq!(&file[file.len() - 4..], b"PAR1");
+    }
+}
@@ -0,0 +1,95 @@
+use actix_web::HttpResponse;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+// Sparse fieldset, e.g. fields=workHours,breakdown.date,breakdown.hours
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct FieldsParams {
+    #[serde(default)]
+    pub fields: Option<String>,
+}
+
+// Field names match whatever their casing: workHours, work_hours and WORKHOURS are the same field
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| *c != '_').flat_map(char::to_lowercase).collect()
+}
+
+fn select_paths(value: Value, paths: &[Vec<String>]) -> Value {
+    match value {
+        // A path through an array applies to each of its items
+        Value::Array(items) => Value::Array(items.into_iter().map(|item| select_paths(item, paths)).collect()),
+        Value::Object(fields) => Value::Object(fields.into_iter()
+            .filter_map(|(key, field)| {
+                let name = normalize(&key);
+                let matching: Vec<&Vec<String>> = paths.iter().filter(|path| path[0] == name).collect();
+                if matching.is_empty() {
+                    None
+                } else if matching.iter().any(|path| path.len() == 1) {
+                    Some((key, field))
+                } else {
+                    let rest: Vec<Vec<String>> = matching.iter().map(|path| path[1..].to_vec()).collect();
+                    Some((key, select_paths(field, &rest)))
+                }
+            })
+            .collect()),
+        other => other,
+    }
+}
+
+// Keep only the listed fields (dot-separated for nested ones) of a JSON document
+pub fn select(value: Value, fields: &str) -> Value {
+    let paths: Vec<Vec<String>> = fields.split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(|field| field.split('.').map(normalize).collect())
+        .collect();
+    if paths.is_empty() {
+        return value;
+    }
+    select_paths(value, &paths)
+}
+
+// JSON response pruned to the requested fields, the whole document without a fieldset
+pub fn respond<T: Serialize>(body: &T, fields: Option<&str>) -> Result<HttpResponse, actix_web::error::Error> {
+    match fields {
+        Some(fields) => {
+            let value = serde_json::to_value(body).map_err(actix_web::error::ErrorInternalServerError)?;
+            Ok(HttpResponse::Ok().json(select(value, fields)))
+        }
+        None => Ok(HttpResponse::Ok().json(body)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fields_are_selected_at_any_depth() {
+        let response = json!({
+            "work_hours": 16.0,
+            "work_minutes": 960.0,
+            "start_date": "2023-10-02T09:00:00+00:00",
+            "breakdown": [
+                { "period": "2023-10-02", "date": "2023-10-02", "hours": 8.0, "holidays": [] },
+                { "period": "2023-10-03", "date": "2023-10-03", "hours": 8.0, "holidays": [] }
+            ],
+            "holidaysApplied": [{ "date": "2023-10-04", "description": "Holiday", "source": "provider" }]
+        });
+
+        assert_eq!(
+            select(response.clone(), "workHours, breakdown.date,breakdown.hours,holidays_applied"),
+            json!({
+                "work_hours": 16.0,
+                "breakdown": [
+                    { "date": "2023-10-02", "hours": 8.0 },
+                    { "date": "2023-10-03", "hours": 8.0 }
+                ],
+                "holidaysApplied": [{ "date": "2023-10-04", "description": "Holiday", "source": "provider" }]
+            })
+        );
+        assert_eq!(select(response.clone(), "unknown"), json!({}));
+        assert_eq!(select(response.clone(), ""), response);
+    }
+}
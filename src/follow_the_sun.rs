@@ -0,0 +1,128 @@
+use actix_web::{web, HttpResponse, post};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+
+use crate::{compute_working_time, merge_intervals, AppState, EndOrDuration, WorkHoursRequest, WorkingInterval};
+
+// A regional team with its own calendar and schedule
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Region {
+    pub name: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+    pub timezone: String,
+    #[serde(rename = "startOfDay", default = "crate::default_start_of_day")]
+    pub start_of_day: String,
+    #[serde(rename = "endOfDay", default = "crate::default_end_of_day")]
+    pub end_of_day: String,
+    #[serde(rename = "workingIntervals", default)]
+    pub working_intervals: Vec<WorkingInterval>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FollowTheSunRequest {
+    #[serde(rename = "startDate")]
+    pub start_date: String,
+    #[serde(rename = "endDate")]
+    pub end_date: String,
+    pub regions: Vec<Region>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Window {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FollowTheSunResponse {
+    pub covered_hours: f64,
+    pub uncovered_hours: f64,
+    pub windows: Vec<Window>,
+    pub gaps: Vec<Window>,
+}
+
+fn hours(start: DateTime<Utc>, end: DateTime<Utc>) -> f64 {
+    end.signed_duration_since(start).num_seconds() as f64 / 3600.0
+}
+
+#[post("/follow-the-sun")]
+pub async fn calculate_follow_the_sun(
+    data: web::Data<AppState>,
+    req: web::Json<FollowTheSunRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let start = DateTime::parse_from_rfc3339(&req.start_date)
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid start date format: {}", e)))?
+        .with_timezone(&Utc);
+    let end = DateTime::parse_from_rfc3339(&req.end_date)
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid end date format: {}", e)))?
+        .with_timezone(&Utc);
+    if req.regions.is_empty() {
+        return Err(actix_web::error::ErrorBadRequest("At least one region must be provided"));
+    }
+
+    let mut intervals = vec![];
+    for region in &req.regions {
+        let timezone: Tz = region.timezone.parse()
+            .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone for {}: {}", region.name, e)))?;
+
+        // Express the shared range in the local time of the region
+        let request = WorkHoursRequest {
+            start_date: start.with_timezone(&timezone).to_rfc3339(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: end.with_timezone(&timezone).to_rfc3339(),
+            },
+            start_of_day: region.start_of_day.clone(),
+            end_of_day: region.end_of_day.clone(),
+            country: region.country.clone(),
+            subdivision: region.subdivision.clone(),
+            timezone: region.timezone.clone(),
+            working_intervals: region.working_intervals.clone(),
+            ..Default::default()
+        };
+        let working_time = compute_working_time(&data, &request).await
+            .map_err(|e| actix_web::error::ErrorBadRequest(format!("Region {}: {}", region.name, e)))?;
+        intervals.extend(working_time.intervals.iter()
+            .map(|(s, e)| (s.with_timezone(&Utc), e.with_timezone(&Utc))));
+    }
+
+    let windows = merge_intervals(intervals);
+    let mut gaps = vec![];
+    let mut cursor = start;
+    for (window_start, window_end) in &windows {
+        if *window_start > cursor {
+            gaps.push((cursor, *window_start));
+        }
+        cursor = cursor.max(*window_end);
+    }
+    if cursor < end {
+        gaps.push((cursor, end));
+    }
+
+    let to_windows = |intervals: &[(DateTime<Utc>, DateTime<Utc>)]| -> Vec<Window> {
+        intervals.iter()
+            .map(|(s, e)| Window { start: s.to_rfc3339(), end: e.to_rfc3339() })
+            .collect()
+    };
+    Ok(HttpResponse::Ok().json(FollowTheSunResponse {
+        covered_hours: windows.iter().map(|(s, e)| hours(*s, *e)).sum(),
+        uncovered_hours: gaps.iter().map(|(s, e)| hours(*s, *e)).sum(),
+        windows: to_windows(&windows),
+        gaps: to_windows(&gaps),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_overlapping_windows() {
+        let at = |h: u32| DateTime::parse_from_rfc3339(&format!("2023-10-02T{:02}:00:00Z", h)).unwrap().with_timezone(&Utc);
+        let merged = merge_intervals(vec![(at(14), at(18)), (at(7), at(15)), (at(20), at(22))]);
+        assert_eq!(merged, vec![(at(7), at(18)), (at(20), at(22))]);
+    }
+}
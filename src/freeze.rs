@@ -0,0 +1,152 @@
+use actix_web::{web, HttpRequest, HttpResponse, get, post, delete};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::calendar_config::author;
+use crate::db::{Database, Freeze};
+use crate::{events, AppState};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FreezeParams {
+    // RFC3339 instant, or a date to freeze through the end of that day (UTC)
+    pub until: String,
+}
+
+fn parse_until(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(instant) = DateTime::parse_from_rfc3339(value) {
+        return Ok(instant.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid until: {}, expected an RFC3339 date-time or YYYY-MM-DD", value))?;
+    Ok(date.succ_opt().unwrap_or(date).and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+// The freeze in force at the given instant, expired freezes are ignored
+pub fn active_at(db: &Database, now: DateTime<Utc>) -> Option<Freeze> {
+    db.get_freeze().ok().flatten().filter(|frozen| {
+        DateTime::parse_from_rfc3339(&frozen.until).is_ok_and(|until| until > now)
+    })
+}
+
+pub fn active(db: &Database) -> Option<Freeze> {
+    active_at(db, Utc::now())
+}
+
+// Rejects changes of holidays, calendars and the data calculations read with them while a freeze is in force
+pub async fn ensure_unfrozen(db: &Database) -> Result<(), actix_web::error::Error> {
+    let frozen = db.blocking(|db| Ok(active(db))).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    match frozen {
+        Some(frozen) => Err(actix_web::error::ErrorConflict(format!(
+            "Holiday and calendar data are frozen until {} by {}", frozen.until, frozen.author
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[post("/admin/freeze")]
+pub async fn freeze(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    params: web::Query<FreezeParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let until = parse_until(&params.until).map_err(actix_web::error::ErrorBadRequest)?;
+    let now = Utc::now();
+    if until <= now {
+        return Err(actix_web::error::ErrorBadRequest(format!("until must be in the future: {}", until.to_rfc3339())));
+    }
+    let frozen = Freeze {
+        until: until.to_rfc3339(),
+        author: author(&http_req),
+        created_at: now.to_rfc3339(),
+    };
+    let stored = frozen.clone();
+    data.db.blocking(move |db| db.set_freeze(&stored)).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    events::publish(events::Event::JobCompleted {
+        job: "holiday-freeze".to_string(),
+        details: serde_json::to_value(&frozen).unwrap_or_default(),
+    });
+    Ok(HttpResponse::Ok().json(frozen))
+}
+
+#[get("/admin/freeze")]
+pub async fn get_freeze(data: web::Data<AppState>) -> Result<HttpResponse, actix_web::error::Error> {
+    let frozen = data.db.blocking(|db| Ok(active(db))).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    match frozen {
+        Some(frozen) => Ok(HttpResponse::Ok().json(frozen)),
+        None => Ok(HttpResponse::NotFound().json("No freeze in force")),
+    }
+}
+
+// Lift the freeze before its end, e.g. once payroll has been run
+#[delete("/admin/freeze")]
+pub async fn unfreeze(data: web::Data<AppState>) -> Result<HttpResponse, actix_web::error::Error> {
+    data.db.blocking(|db| db.delete_freeze()).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::{blackouts, calendar_config, country_groups, oncall, schedules};
+
+    #[actix_rt::test]
+    async fn test_freeze_rejects_calendar_changes() {
+        let data = web::Data::new(AppState { db: Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(
+            App::new()
+                .app_data(data.clone())
+                .service(freeze)
+                .service(get_freeze)
+                .service(unfreeze)
+                .service(calendar_config::apply_config)
+                .service(country_groups::save_group)
+                .service(country_groups::delete_group)
+                .service(blackouts::delete_blackout_period)
+                .service(oncall::delete_on_call_period)
+                .service(schedules::delete_schedule)
+        ).await;
+        let config = "calendars:\n  - id: acme-paris\n    country: fr\n";
+
+        let req = test::TestRequest::post().uri("/admin/freeze?until=2000-01-31").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::post().uri("/admin/freeze?until=2999-01-31").insert_header(("X-User", "payroll")).to_request();
+        let frozen: Freeze = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(frozen.until, "2999-02-01T00:00:00+00:00");
+        assert_eq!(frozen.author, "payroll");
+
+        let req = test::TestRequest::post().uri("/admin/apply").set_payload(config).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 409);
+
+        let req = test::TestRequest::put().uri("/country-groups/dach").set_json(serde_json::json!({ "countries": ["de", "at", "ch"] })).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 409);
+        for uri in ["/country-groups/dach", "/blackouts/acme-paris/1", "/on-call/acme-paris/1", "/schedules/alice/1"] {
+            let req = test::TestRequest::delete().uri(uri).to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), 409, "{}", uri);
+        }
+
+        let req = test::TestRequest::delete().uri("/admin/freeze").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 204);
+        let req = test::TestRequest::get().uri("/admin/freeze").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+        let req = test::TestRequest::post().uri("/admin/apply").set_payload(config).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+    }
+
+    #[actix_rt::test]
+    async fn test_expired_freeze_is_ignored() {
+        let db = Database::new(":memory:").unwrap();
+        db.set_freeze(&Freeze {
+            until: "2025-02-01T00:00:00+00:00".to_string(),
+            author: "payroll".to_string(),
+            created_at: "2025-01-25T00:00:00+00:00".to_string(),
+        }).unwrap();
+        let before = DateTime::parse_from_rfc3339("2025-01-31T23:00:00Z").unwrap().with_timezone(&Utc);
+        let after = DateTime::parse_from_rfc3339("2025-02-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        assert!(active_at(&db, before).is_some());
+        assert!(active_at(&db, after).is_none());
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::trace::UpstreamCall;
+use crate::warnings::{Warning, WarningCode};
+
+// Age of the holiday data behind a result, reported when the holidays came from the cache
+// or the database and are older than DATA_FRESHNESS_MAX_AGE_SECONDS
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DataFreshness {
+    // cache or database
+    pub source: String,
+    // When the provider returned the holidays (RFC3339), None when the stored ones do not say
+    #[serde(rename = "fetchedAt")]
+    pub fetched_at: Option<String>,
+}
+
+// Unset by default, responses then never carry the watermark
+pub fn max_age() -> Option<Duration> {
+    std::env::var("DATA_FRESHNESS_MAX_AGE_SECONDS").ok().and_then(|v| v.parse().ok()).map(Duration::from_secs)
+}
+
+// DATA_FRESHNESS_WARNING=true also adds the holiday_data_stale warning next to the watermark
+pub fn warning_enabled() -> bool {
+    std::env::var("DATA_FRESHNESS_WARNING").is_ok_and(|value| value == "true")
+}
+
+// Oldest holidays a calculation used from the cache or the database, if older than max_age.
+// Holidays fetched during the calculation are fresh, unknown fetch times count as too old.
+pub fn watermark(calls: &[UpstreamCall], max_age: Duration, now: DateTime<Utc>) -> Option<DataFreshness> {
+    let (source, fetched_at) = calls.iter()
+        .filter_map(|call| match (call.cache.as_deref(), call.provider.as_str()) {
+            (Some("hit" | "stale"), _) if call.error.is_none() => Some(("cache", call.fetched_at.as_deref())),
+            (None, "database") => Some(("database", call.fetched_at.as_deref())),
+            _ => None,
+        })
+        .map(|(source, fetched_at)| (source, fetched_at.and_then(|f| DateTime::parse_from_rfc3339(f).ok()).map(|f| f.with_timezone(&Utc))))
+        .min_by_key(|(_, fetched_at)| *fetched_at)?;
+    let max_age = chrono::Duration::from_std(max_age).unwrap_or(chrono::Duration::MAX);
+    if fetched_at.is_some_and(|fetched_at| now - fetched_at <= max_age) {
+        return None;
+    }
+    Some(DataFreshness {
+        source: source.to_string(),
+        fetched_at: fetched_at.map(|fetched_at| fetched_at.to_rfc3339()),
+    })
+}
+
+pub fn warning(freshness: &DataFreshness) -> Warning {
+    Warning::new(
+        WarningCode::HolidayDataStale,
+        match &freshness.fetched_at {
+            Some(fetched_at) => format!("The holidays used come from the {} and were fetched at {}", freshness.source, fetched_at),
+            None => format!("The holidays used come from the {} and their fetch time is unknown", freshness.source),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watermark_reports_the_oldest_holidays() {
+        let now = DateTime::parse_from_rfc3339("2025-06-10T12:00:00Z").unwrap().with_timezone(&Utc);
+        let call = |provider: &str, cache: Option<&str>, fetched_at: Option<&str>| UpstreamCall {
+            provider: provider.to_string(),
+            cache: cache.map(str::to_string),
+            fetched_at: fetched_at.map(str::to_string),
+            ..Default::default()
+        };
+        let day = Duration::from_secs(24 * 60 * 60);
+
+        // Fresh cache hits and provider calls are not reported
+        let calls = [call("openholidays", Some("hit"), Some("2025-06-10T08:00:00+00:00")), call("nager", Some("miss"), None)];
+        assert_eq!(watermark(&calls, day, now), None);
+
+        let calls = [
+            call("openholidays", Some("stale"), Some("2025-06-08T08:00:00+00:00")),
+            call("openholidays", Some("hit"), Some("2025-06-09T08:00:00+00:00")),
+        ];
+        assert_eq!(watermark(&calls, day, now), Some(DataFreshness { source: "cache".to_string(), fetched_at: Some("2025-06-08T08:00:00+00:00".to_string()) }));
+
+        // Stored holidays without a fetch time are always reported
+        let calls = [call("openholidays", Some("hit"), Some("2025-06-10T08:00:00+00:00")), call("database", None, None)];
+        let freshness = watermark(&calls, day, now).unwrap();
+        assert_eq!(freshness, DataFreshness { source: "database".to_string(), fetched_at: None });
+        assert_eq!(warning(&freshness).code, "W006");
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{NaiveTime, Timelike};
+use log::warn;
+
+// Boundaries the times of a schedule must fall on, set per deployment with
+// SCHEDULE_GRANULARITY_MINUTES (e.g. 5, 15 or 30) so that spreadsheet exports such as
+// 08:59:59 do not leak one-second gaps into the results
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Granularity {
+    pub minutes: u32,
+    pub mode: GranularityMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GranularityMode {
+    // 400 Bad Request for a time off the boundaries
+    Reject,
+    // Round to the nearest boundary, halfway times go to the later one
+    Snap,
+}
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+// None unless SCHEDULE_GRANULARITY_MINUTES divides a day. SCHEDULE_GRANULARITY_MODE is
+// reject (default) or snap.
+pub fn from_env() -> Option<Granularity> {
+    let minutes: u32 = std::env::var("SCHEDULE_GRANULARITY_MINUTES").ok()?.parse().ok()?;
+    if minutes == 0 || !MINUTES_PER_DAY.is_multiple_of(minutes) {
+        warn!("Ignoring SCHEDULE_GRANULARITY_MINUTES={}, it must divide a day", minutes);
+        return None;
+    }
+    let mode = match std::env::var("SCHEDULE_GRANULARITY_MODE").as_deref() {
+        Ok("snap") => GranularityMode::Snap,
+        _ => GranularityMode::Reject,
+    };
+    Some(Granularity { minutes, mode })
+}
+
+impl Granularity {
+    // Snapping the end of the day up to 24:00 gives 00:00, i.e. until midnight
+    pub fn apply(&self, time: NaiveTime, label: &str) -> Result<NaiveTime, String> {
+        let step = self.minutes * 60;
+        let seconds = time.num_seconds_from_midnight();
+        let offset = seconds % step;
+        if offset == 0 && time.nanosecond() == 0 {
+            return Ok(time);
+        }
+        match self.mode {
+            GranularityMode::Reject => Err(format!(
+                "Invalid {} time {}: schedules must align to {}-minute boundaries",
+                label, time, self.minutes,
+            )),
+            GranularityMode::Snap => {
+                let snapped = if offset * 2 >= step { seconds - offset + step } else { seconds - offset };
+                Ok(NaiveTime::from_num_seconds_from_midnight_opt(snapped % (MINUTES_PER_DAY * 60), 0).unwrap())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_times_are_rejected_or_snapped() {
+        let time = |value: &str| NaiveTime::parse_from_str(value, "%H:%M:%S").unwrap();
+        let reject = Granularity { minutes: 5, mode: GranularityMode::Reject };
+        assert_eq!(reject.apply(time("08:55:00"), "start"), Ok(time("08:55:00")));
+        assert!(reject.apply(time("08:59:59"), "start").unwrap_err().contains("5-minute"));
+
+        let snap = Granularity { minutes: 15, mode: GranularityMode::Snap };
+        assert_eq!(snap.apply(time("08:59:59"), "start"), Ok(time("09:00:00")));
+        assert_eq!(snap.apply(time("12:07:29"), "end"), Ok(time("12:00:00")));
+        assert_eq!(snap.apply(time("12:07:30"), "end"), Ok(time("12:15:00")));
+        assert_eq!(snap.apply(time("23:59:59"), "end"), Ok(time("00:00:00")));
+    }
+}
@@ -0,0 +1,113 @@
+use actix_web::{web, HttpResponse, get};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::{country, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct AuditParams {
+    #[serde(rename = "holidayId")]
+    pub holiday_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotParams {
+    // RFC3339 instant, or YYYY-MM-DD for the end of that day in UTC
+    pub at: String,
+}
+
+fn parse_instant(value: &str) -> Result<DateTime<Utc>, actix_web::error::Error> {
+    if let Ok(day) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok((day + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap().and_utc() - Duration::nanoseconds(1));
+    }
+    DateTime::parse_from_rfc3339(value).map(|at| at.with_timezone(&Utc))
+        .map_err(|_| actix_web::error::ErrorBadRequest(format!("Invalid instant: {}", value)))
+}
+
+// Who created, updated or deleted which holiday of the country when, with the holiday before
+// and after each change. Holidays fetched from providers are not audited.
+#[get("/holidays/{country}/audit")]
+pub async fn list_audit(
+    data: web::Data<AppState>,
+    country: web::Path<String>,
+    params: web::Query<AuditParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let country = country::parse_request(&country)?;
+    let holiday_id = params.holiday_id;
+    let entries = data.db.blocking(move |db| db.get_audit_log(&country, holiday_id)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+// Holidays added through the API to the country as they were at an instant, for payroll audits
+#[get("/holidays/{country}/snapshot")]
+pub async fn get_snapshot(
+    data: web::Data<AppState>,
+    country: web::Path<String>,
+    params: web::Query<SnapshotParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let country = country::parse_request(&country)?;
+    let at = parse_instant(&params.at)?;
+    let mut holidays = data.db.blocking(move |db| db.get_manual_holidays_at(&country, at)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    holidays.sort_by(|a, b| a.date.get(..10).cmp(&b.date.get(..10)).then(a.id.cmp(&b.id)));
+    Ok(HttpResponse::Ok().json(holidays))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::country::CountryCode;
+    use crate::db::{AuditAction, AuditEntry, Database, Holiday, HolidayKind, HolidayPortion};
+    use crate::holiday_provider::MockProvider;
+
+    fn holiday(date: &str, description: &str) -> Holiday {
+        Holiday { id: None, date: date.to_string(), description: description.to_string(), country: "fr".to_string(), portion: HolidayPortion::Full, kind: HolidayKind::Public, subdivision: None }
+    }
+
+    // Instant between two changes
+    fn checkpoint() -> String {
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let at = Utc::now().to_rfc3339();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        at
+    }
+
+    #[actix_rt::test]
+    async fn test_changes_are_audited_and_replayed() {
+        let fr = CountryCode::parse("fr").unwrap();
+        let database = Database::new(":memory:").unwrap();
+        let (bastille, _) = database.add_holiday(&holiday("2025-07-14", "Bastille"), "alice").unwrap();
+        let (armistice, _) = database.add_holiday(&holiday("2025-11-11", "Armistice"), "alice").unwrap();
+        let before_changes = checkpoint();
+        database.add_holiday(&holiday("2025-07-14", "Bastille Day"), "bob").unwrap();
+        database.delete_holiday(armistice, "bob").unwrap();
+        let after_changes = checkpoint();
+        database.add_holiday(&holiday("2025-11-11", "Armistice Day"), "carol").unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data.clone()).service(list_audit).service(get_snapshot)).await;
+
+        let req = test::TestRequest::get().uri(&format!("/holidays/fr/audit?holidayId={}", bastille)).to_request();
+        let entries: Vec<AuditEntry> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(entries.iter().map(|entry| (entry.action, entry.author.as_str())).collect::<Vec<_>>(), vec![(AuditAction::Created, "alice"), (AuditAction::Updated, "bob")]);
+        assert_eq!(entries[1].before.as_ref().unwrap().description, "Bastille");
+
+        for (at, descriptions) in [
+            (before_changes, vec!["Bastille", "Armistice"]),
+            (after_changes, vec!["Bastille Day"]),
+            ("2020-01-01".to_string(), vec![]),
+            (Utc::now().date_naive().to_string(), vec!["Bastille Day", "Armistice Day"]),
+        ] {
+            let req = test::TestRequest::get().uri(&format!("/holidays/fr/snapshot?at={}", at.replace('+', "%2B"))).to_request();
+            let holidays: Vec<Holiday> = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(holidays.iter().map(|h| h.description.as_str()).collect::<Vec<_>>(), descriptions, "{}", at);
+        }
+
+        // The deleted holiday stays in the table, out of every read
+        let db = &data.db;
+        assert!(db.get_holiday(&fr, armistice).unwrap().is_none());
+        assert_eq!(db.get_manual_holidays(&fr).unwrap().len(), 2);
+        assert_eq!(db.get_audit_log(&fr, Some(armistice)).unwrap().last().unwrap().action, AuditAction::Deleted);
+    }
+}
@@ -0,0 +1,464 @@
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse, get, delete};
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use moka::Expiry;
+use moka::future::Cache;
+use moka::notification::RemovalCause;
+use serde::{Serialize, Deserialize};
+
+use crate::country::{CountryCode, Subdivision};
+use crate::holidays_api::Holiday;
+use crate::trace;
+
+// Expired holidays are served stale while refreshed for up to a week
+const STALE_FOR: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+// Format of the keys listed by /admin/cache, sent in its X-Cache-Key-Format header
+pub const KEY_FORMAT: &str = "provider:country[:subdivision]:year:language";
+
+// Everything the provider is asked for. Holiday kinds are not part of it: the cached lists
+// keep every kind, and calculations filter them by their holidayKinds afterwards.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct CacheKey {
+    // openholidays or nager
+    pub provider: &'static str,
+    pub country: CountryCode,
+    // None for national holidays
+    pub subdivision: Option<Subdivision>,
+    pub year: i32,
+    // Language of the holiday names
+    pub language: String,
+}
+
+impl CacheKey {
+    pub fn new(provider: &'static str, country: &CountryCode, subdivision: Option<&Subdivision>, year: i32, language: &str) -> Self {
+        CacheKey {
+            provider,
+            country: country.clone(),
+            subdivision: subdivision.cloned(),
+            year,
+            language: language.to_uppercase(),
+        }
+    }
+}
+
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.subdivision {
+            None => write!(f, "{}:{}:{}:{}", self.provider, self.country.api_code(), self.year, self.language),
+            Some(subdivision) => write!(f, "{}:{}:{}:{}:{}", self.provider, self.country.api_code(), subdivision, self.year, self.language),
+        }
+    }
+}
+
+// Parses the key as displayed by /admin/cache, e.g. openholidays:DE:DE-BY:2025:EN
+impl FromStr for CacheKey {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = value.split(':').collect();
+        let provider = match parts[0] {
+            "openholidays" => "openholidays",
+            "nager" => "nager",
+            other => return Err(format!("Unknown provider in cache key: {}", other)),
+        };
+        let (country, subdivision, year, language) = match parts.len() {
+            4 => (parts[1], "", parts[2], parts[3]),
+            5 => (parts[1], parts[2], parts[3], parts[4]),
+            _ => return Err(format!("Invalid cache key: {}, expected {}", value, KEY_FORMAT)),
+        };
+        let year = year.parse().map_err(|_| format!("Invalid year in cache key: {}", year))?;
+        let country: CountryCode = country.parse()?;
+        let subdivision = Subdivision::parse_optional(&country, subdivision)?;
+        Ok(CacheKey::new(provider, &country, subdivision.as_ref(), year, language))
+    }
+}
+
+// Failures and empty lists are cached too, for a short time, so that repeated
+// lookups of an unsupported country do not all reach the API
+#[derive(Clone)]
+struct CacheEntry {
+    result: Result<Vec<Holiday>, String>,
+    // When the provider answered, reported as the age of the data
+    fetched_at: SystemTime,
+    // End of freshness, the entry may be served stale after it
+    expiration: SystemTime,
+    // Time after which the cache drops the entry
+    evict_after: Duration,
+    // A background refresh of the expired holidays is running
+    refreshing: Arc<AtomicBool>,
+}
+
+pub enum CacheLookup {
+    // Holidays, or the failure of the previous lookup
+    Hit(Result<Vec<Holiday>, String>),
+    // Expired holidays, served while they are refreshed in the background.
+    // refresh is set for the lookup that has to start the refresh.
+    Stale { holidays: Vec<Holiday>, refresh: bool },
+    // miss or expired
+    Miss(&'static str),
+}
+
+struct PerEntryExpiry;
+
+impl Expiry<CacheKey, CacheEntry> for PerEntryExpiry {
+    fn expire_after_create(&self, _key: &CacheKey, entry: &CacheEntry, _created_at: Instant) -> Option<Duration> {
+        Some(entry.evict_after)
+    }
+
+    fn expire_after_update(&self, _key: &CacheKey, entry: &CacheEntry, _updated_at: Instant, _remaining: Option<Duration>) -> Option<Duration> {
+        Some(entry.evict_after)
+    }
+}
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static STALE_HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+static EVICTED_EXPIRED: AtomicU64 = AtomicU64::new(0);
+static EVICTED_SIZE: AtomicU64 = AtomicU64::new(0);
+
+lazy_static! {
+    // Bounded by HOLIDAY_CACHE_MAX_ENTRIES, the least used entries are evicted first
+    static ref CACHE: Cache<CacheKey, CacheEntry> = Cache::builder()
+        .max_capacity(std::env::var("HOLIDAY_CACHE_MAX_ENTRIES").ok().and_then(|v| v.parse().ok()).unwrap_or(10_000))
+        .expire_after(PerEntryExpiry)
+        .eviction_listener(|key, _entry, cause| match cause {
+            RemovalCause::Expired => { EVICTED_EXPIRED.fetch_add(1, Ordering::Relaxed); }
+            RemovalCause::Size => {
+                EVICTED_SIZE.fetch_add(1, Ordering::Relaxed);
+                info!("CACHE EVICT: {} dropped, the cache is full", key);
+            }
+            _ => {}
+        })
+        .build();
+}
+
+fn record(key: &CacheKey, decision: &str, entry: &CacheEntry) {
+    trace::record(trace::UpstreamCall {
+        provider: key.provider.to_string(),
+        cache: Some(decision.to_string()),
+        error: entry.result.as_ref().err().cloned(),
+        fetched_at: Some(DateTime::<Utc>::from(entry.fetched_at).to_rfc3339()),
+        ..Default::default()
+    });
+}
+
+// Holidays cached for the key, if they have not expired yet, otherwise miss or expired.
+// Expired holidays are still served (stale-while-revalidate), expired failures are not.
+pub async fn lookup(key: &CacheKey) -> CacheLookup {
+    let entry = match CACHE.get(key).await {
+        Some(entry) => entry,
+        None => {
+            info!("CACHE MISS: No cached holidays found for key: {}", key);
+            MISSES.fetch_add(1, Ordering::Relaxed);
+            return CacheLookup::Miss("miss");
+        }
+    };
+    let now = SystemTime::now();
+    if entry.expiration > now {
+        let remaining_secs = entry.expiration.duration_since(now).unwrap_or(Duration::from_secs(0)).as_secs();
+        info!("CACHE HIT: Using cached holidays for key: {}. Cache expires in {} seconds", key, remaining_secs);
+        HITS.fetch_add(1, Ordering::Relaxed);
+        record(key, "hit", &entry);
+        return CacheLookup::Hit(entry.result);
+    }
+    match &entry.result {
+        Ok(holidays) if !holidays.is_empty() => {
+            let refresh = !entry.refreshing.swap(true, Ordering::SeqCst);
+            info!("CACHE STALE: Serving expired holidays for key: {}{}", key, if refresh { ", refreshing them" } else { "" });
+            STALE_HITS.fetch_add(1, Ordering::Relaxed);
+            record(key, "stale", &entry);
+            CacheLookup::Stale { holidays: holidays.clone(), refresh }
+        }
+        _ => {
+            info!("CACHE EXPIRED: Holidays cache for key: {} has expired", key);
+            MISSES.fetch_add(1, Ordering::Relaxed);
+            CacheLookup::Miss("expired")
+        }
+    }
+}
+
+// Holidays stay fresh HOLIDAY_CACHE_TTL_SECONDS (a day by default), 0 disables the cache
+fn cache_duration() -> Duration {
+    let seconds = std::env::var("HOLIDAY_CACHE_TTL_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(24 * 60 * 60);
+    Duration::from_secs(seconds)
+}
+
+// Failures and empty lists are kept HOLIDAY_NEGATIVE_CACHE_TTL_SECONDS, 0 to not cache them
+fn negative_cache_duration() -> Duration {
+    let seconds = std::env::var("HOLIDAY_NEGATIVE_CACHE_TTL_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(60);
+    Duration::from_secs(seconds)
+}
+
+pub async fn store(key: CacheKey, result: &Result<Vec<Holiday>, String>) {
+    let fresh_for = cache_duration();
+    if fresh_for.is_zero() {
+        return;
+    }
+    let (cache_duration, evict_after) = match result {
+        Ok(holidays) if !holidays.is_empty() => (fresh_for, fresh_for + STALE_FOR),
+        _ => (negative_cache_duration(), negative_cache_duration()),
+    };
+    if cache_duration.is_zero() {
+        return;
+    }
+    match result {
+        Ok(holidays) => info!("CACHE UPDATE: Cached {} holidays for key: {}. Cache will expire in {} seconds",
+                              holidays.len(), key, cache_duration.as_secs()),
+        Err(e) => info!("CACHE UPDATE: Cached failure for key: {} ({}). Cache will expire in {} seconds",
+                        key, e, cache_duration.as_secs()),
+    }
+    let now = SystemTime::now();
+    CACHE.insert(key, CacheEntry {
+        result: result.clone(),
+        fetched_at: now,
+        expiration: now + cache_duration,
+        evict_after,
+        refreshing: Arc::new(AtomicBool::new(false)),
+    }).await;
+}
+
+// Refresh expired holidays without holding up the request that found them
+pub fn refresh_in_background<F>(key: CacheKey, fetch: F)
+where
+    F: Future<Output = Result<Vec<Holiday>, String>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let result = fetch.await;
+        refreshed(key, result).await;
+    });
+}
+
+// A failed refresh keeps the stale holidays, the next lookup tries again
+async fn refreshed(key: CacheKey, result: Result<Vec<Holiday>, String>) {
+    match result {
+        Ok(holidays) if !holidays.is_empty() => store(key, &Ok(holidays)).await,
+        result => {
+            warn!("Failed to refresh holidays for key: {}: {}", key, result.err().unwrap_or_else(|| "no holidays".to_string()));
+            if let Some(entry) = CACHE.get(&key).await {
+                entry.refreshing.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+// Counters since startup, reported by /health
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CacheStats {
+    pub entries: u64,
+    pub hits: u64,
+    pub stale_hits: u64,
+    pub misses: u64,
+    pub evicted_expired: u64,
+    pub evicted_size: u64,
+}
+
+pub async fn stats() -> CacheStats {
+    CACHE.run_pending_tasks().await;
+    CacheStats {
+        entries: CACHE.entry_count(),
+        hits: HITS.load(Ordering::Relaxed),
+        stale_hits: STALE_HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+        evicted_expired: EVICTED_EXPIRED.load(Ordering::Relaxed),
+        evicted_size: EVICTED_SIZE.load(Ordering::Relaxed),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CachedHolidays {
+    pub key: String,
+    pub provider: String,
+    pub country: String,
+    pub subdivision: Option<String>,
+    pub year: i32,
+    pub language: String,
+    // Holidays cached, 0 for a cached failure
+    pub count: usize,
+    pub fetched_at: String,
+    pub expires_at: String,
+    // Expired holidays still served until they are refreshed
+    pub stale: bool,
+    pub error: Option<String>,
+}
+
+// Entries of this instance's cache, each instance caches on its own
+#[get("/admin/cache")]
+pub async fn list_cache() -> Result<HttpResponse, actix_web::error::Error> {
+    let now = SystemTime::now();
+    let mut entries: Vec<CachedHolidays> = CACHE.iter()
+        .map(|(key, entry)| CachedHolidays {
+            key: key.to_string(),
+            provider: key.provider.to_string(),
+            country: key.country.api_code(),
+            subdivision: key.subdivision.as_ref().map(Subdivision::to_string),
+            year: key.year,
+            language: key.language.clone(),
+            count: entry.result.as_ref().map_or(0, |holidays| holidays.len()),
+            fetched_at: DateTime::<Utc>::from(entry.fetched_at).to_rfc3339(),
+            expires_at: DateTime::<Utc>::from(entry.expiration).to_rfc3339(),
+            stale: entry.expiration <= now,
+            error: entry.result.err(),
+        })
+        .collect();
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(HttpResponse::Ok().insert_header(("X-Cache-Key-Format", KEY_FORMAT)).json(entries))
+}
+
+// Drops a cached lookup, e.g. when a holiday is announced during the year,
+// so that the next calculation fetches it again
+#[delete("/admin/cache/{key}")]
+pub async fn invalidate(key: web::Path<String>) -> Result<HttpResponse, actix_web::error::Error> {
+    let key: CacheKey = key.parse().map_err(actix_web::error::ErrorBadRequest)?;
+    match CACHE.remove(&key).await {
+        Some(_) => {
+            info!("CACHE INVALIDATE: Dropped holidays for key: {}", key);
+            Ok(HttpResponse::NoContent().finish())
+        }
+        None => Ok(HttpResponse::NotFound().json(format!("No cached holidays for key {}", key))),
+    }
+}
+
+#[delete("/admin/cache")]
+pub async fn purge() -> Result<HttpResponse, actix_web::error::Error> {
+    CACHE.invalidate_all();
+    CACHE.run_pending_tasks().await;
+    info!("CACHE PURGE: Dropped all cached holidays");
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Cache outcome of a request: miss when a lookup reached the provider, stale when expired
+// holidays were served, hit otherwise. None when no cached provider was consulted.
+pub fn outcome(calls: &[trace::UpstreamCall]) -> Option<&'static str> {
+    let decisions: Vec<&str> = calls.iter().filter_map(|call| call.cache.as_deref()).collect();
+    if decisions.iter().any(|decision| *decision == "miss" || *decision == "expired") {
+        Some("miss")
+    } else if decisions.contains(&"stale") {
+        Some("stale")
+    } else if decisions.contains(&"hit") {
+        Some("hit")
+    } else {
+        None
+    }
+}
+
+// Adds X-Holiday-Cache: hit|miss|stale to the responses that looked holidays up,
+// enabled with HOLIDAY_CACHE_HEADER=true
+pub async fn report_cache(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::error::Error> {
+    let (response, calls) = trace::collect(next.call(req)).await;
+    let mut response = response?.map_into_boxed_body();
+    if let Some(outcome) = outcome(&calls) {
+        response.headers_mut().insert(
+            actix_web::http::header::HeaderName::from_static("x-holiday-cache"),
+            actix_web::http::header::HeaderValue::from_static(outcome),
+        );
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::db::{HolidayKind, HolidayPortion};
+
+    fn code(country: &str) -> CountryCode {
+        CountryCode::parse(country).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_failures_and_empty_results_are_cached() {
+        let key = |country: &str| CacheKey::new("openholidays", &code(country), None, 2025, "EN");
+        store(key("zz"), &Err("API request failed with status: 404 Not Found".to_string())).await;
+        match lookup(&key("zz")).await {
+            CacheLookup::Hit(Err(e)) => assert!(e.contains("404")),
+            _ => panic!("failure not cached"),
+        }
+        store(key("zy"), &Ok(vec![])).await;
+        assert!(matches!(lookup(&key("zy")).await, CacheLookup::Hit(Ok(holidays)) if holidays.is_empty()));
+        assert!(matches!(lookup(&key("zx")).await, CacheLookup::Miss("miss")));
+        // Keys of another provider or language are distinct
+        assert!(matches!(lookup(&CacheKey::new("nager", &code("zz"), None, 2025, "EN")).await, CacheLookup::Miss("miss")));
+        assert!(matches!(lookup(&CacheKey::new("openholidays", &code("zz"), None, 2025, "FR")).await, CacheLookup::Miss("miss")));
+    }
+
+    #[actix_rt::test]
+    async fn test_expired_holidays_are_served_while_refreshed() {
+        let key = CacheKey::new("openholidays", &code("zw"), None, 2025, "EN");
+        let holidays = vec![Holiday { date: "2025-12-25".to_string(), description: "Christmas Day".to_string(), portion: HolidayPortion::Full, kind: HolidayKind::Public }];
+        CACHE.insert(key.clone(), CacheEntry {
+            result: Ok(holidays.clone()),
+            fetched_at: SystemTime::now() - Duration::from_secs(86401),
+            expiration: SystemTime::now() - Duration::from_secs(1),
+            evict_after: STALE_FOR,
+            refreshing: Arc::new(AtomicBool::new(false)),
+        }).await;
+
+        // Only the first lookup starts a refresh
+        assert!(matches!(lookup(&key).await, CacheLookup::Stale { refresh: true, .. }));
+        assert!(matches!(lookup(&key).await, CacheLookup::Stale { refresh: false, .. }));
+
+        // A failed refresh keeps the stale holidays for the next attempt
+        refreshed(key.clone(), Err("API request failed with status: 502 Bad Gateway".to_string())).await;
+        assert!(matches!(lookup(&key).await, CacheLookup::Stale { refresh: true, .. }));
+
+        refreshed(key.clone(), Ok(holidays)).await;
+        assert!(matches!(lookup(&key).await, CacheLookup::Hit(Ok(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_outcome_of_a_request() {
+        let call = |cache: Option<&str>| trace::UpstreamCall { provider: "openholidays".to_string(), cache: cache.map(str::to_string), ..Default::default() };
+        assert_eq!(outcome(&[call(Some("hit")), call(Some("stale"))]), Some("stale"));
+        assert_eq!(outcome(&[call(Some("hit")), call(Some("miss")), call(None)]), Some("miss"));
+        assert_eq!(outcome(&[call(Some("hit"))]), Some("hit"));
+        assert_eq!(outcome(&[call(None)]), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_cache_key_round_trip() {
+        let key = CacheKey::new("openholidays", &code("de"), Some(&Subdivision::parse(&code("de"), "de-by").unwrap()), 2025, "de");
+        assert_eq!(key.to_string(), "openholidays:DE:DE-BY:2025:DE");
+        assert_eq!("openholidays:DE:DE-BY:2025:DE".parse::<CacheKey>(), Ok(key));
+        assert_eq!("nager:us:2024:en".parse::<CacheKey>(), Ok(CacheKey::new("nager", &code("US"), None, 2024, "EN")));
+        assert!("redis:FR:2025:EN".parse::<CacheKey>().is_err());
+        assert!("nager:FR:2025".parse::<CacheKey>().is_err());
+        // The subdivision has to belong to the country
+        assert!("openholidays:FR:DE-BY:2025:DE".parse::<CacheKey>().is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_inspect_and_invalidate_cache_entries() {
+        let app = test::init_service(App::new().service(list_cache).service(invalidate).service(purge)).await;
+        let holidays = vec![Holiday { date: "2025-05-08".to_string(), description: "Victory Day".to_string(), portion: HolidayPortion::Full, kind: HolidayKind::Public }];
+        store(CacheKey::new("nager", &code("zv"), None, 2025, "EN"), &Ok(holidays)).await;
+
+        let req = test::TestRequest::get().uri("/admin/cache").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("X-Cache-Key-Format").unwrap(), KEY_FORMAT);
+        let entries: Vec<CachedHolidays> = test::read_body_json(resp).await;
+        let entry = entries.iter().find(|entry| entry.key == "nager:ZV:2025:EN").expect("entry listed");
+        assert_eq!((entry.count, entry.stale, entry.subdivision.clone()), (1, false, None));
+
+        let req = test::TestRequest::delete().uri("/admin/cache/nager:ZV:2025:EN").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 204);
+        let req = test::TestRequest::delete().uri("/admin/cache/nager:ZV:2025:EN").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+        let req = test::TestRequest::delete().uri("/admin/cache/nager:ZV:EN").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+        assert!(matches!(lookup(&CacheKey::new("nager", &code("zv"), None, 2025, "EN")).await, CacheLookup::Miss("miss")));
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::{btree_map, BTreeMap};
+use actix_web::{web, HttpResponse, get, post};
+use chrono::{Datelike, NaiveDate};
+use serde::{Serialize, Deserialize};
+
+use crate::db::{self, HolidayKind};
+use crate::country::CountryCode;
+use crate::{country, recurring, AppState};
+
+// A whole dataset can be annotated in one call, holidays are loaded once per year
+const MAX_DATES: usize = 10_000;
+
+#[derive(Debug, Deserialize)]
+pub struct CheckParams {
+    // YYYY-MM-DD
+    pub date: String,
+    pub subdivision: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct HolidayCheck {
+    pub date: String,
+    pub holiday: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<HolidayKind>,
+    // api, database or custom
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+// Whether a single date is a holiday, with the holidays a calculation would use
+#[get("/holidays/{country}/check")]
+pub async fn check(
+    data: web::Data<AppState>,
+    country: web::Path<String>,
+    params: web::Query<CheckParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let date = NaiveDate::parse_from_str(&params.date, "%Y-%m-%d")
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid date {}: {}", params.date, e)))?;
+    let country = country::parse_request(&country)?;
+    let subdivision = params.subdivision.clone().unwrap_or_default();
+
+    let (holidays, source) = crate::load_holidays_with_source(&data, &country, &subdivision, date, None).await;
+    let holiday = crate::holiday_on(date, &holidays);
+    let custom = match holiday {
+        Some(_) => custom_holidays(&data, &country, date.year()).await,
+        None => vec![],
+    };
+    let source = holiday.map(|holiday| {
+        if is_custom(&custom, date, holiday) {
+            "custom"
+        } else if source == "provider" {
+            "api"
+        } else {
+            "database"
+        }
+    });
+    Ok(HttpResponse::Ok().json(HolidayCheck {
+        date: date.to_string(),
+        holiday: holiday.is_some(),
+        name: holiday.map(|holiday| holiday.description.clone()),
+        kind: holiday.map(|holiday| holiday.kind),
+        source: source.map(str::to_string),
+    }))
+}
+
+// Holidays added through POST /holidays/{country}, dated or recurring, with the recurring ones in the year
+pub(crate) async fn custom_holidays(data: &web::Data<AppState>, country: &CountryCode, year: i32) -> Vec<db::Holiday> {
+    let country = country.clone();
+    data.db.blocking(move |db| {
+        let mut custom = db.get_manual_holidays(&country).unwrap_or_default();
+        custom.extend(recurring::expand(&db.get_recurring_holidays(&country).unwrap_or_default(), year..=year));
+        Ok(custom)
+    }).await.unwrap_or_default()
+}
+
+pub(crate) fn is_custom(custom: &[db::Holiday], date: NaiveDate, holiday: &db::Holiday) -> bool {
+    let day = date.to_string();
+    custom.iter().any(|custom| custom.date.get(..10) == Some(day.as_str()) && custom.description == holiday.description)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckBatchRequest {
+    // YYYY-MM-DD
+    pub dates: Vec<String>,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+    // Holiday kinds that count, public, regional and company ones otherwise
+    #[serde(rename = "holidayKinds", default)]
+    pub holiday_kinds: Option<Vec<HolidayKind>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DateCheck {
+    pub date: String,
+    pub holiday: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<HolidayKind>,
+}
+
+// Whether each date is a holiday of the country, in the order of the request
+#[post("/holidays/{country}/check-batch")]
+pub async fn check_batch(
+    data: web::Data<AppState>,
+    country: web::Path<String>,
+    req: web::Json<CheckBatchRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    if req.dates.len() > MAX_DATES {
+        return Err(actix_web::error::ErrorBadRequest(format!("At most {} dates can be checked at once", MAX_DATES)));
+    }
+    let dates = req.dates.iter()
+        .map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid date {}: {}", date, e))))
+        .collect::<Result<Vec<_>, _>>()?;
+    let country = country::parse_request(&country)?;
+    let subdivision = req.subdivision.clone().unwrap_or_default();
+    let kinds = req.holiday_kinds.as_deref().unwrap_or(&HolidayKind::DAYS_OFF);
+
+    let mut holidays_by_year: BTreeMap<i32, Vec<db::Holiday>> = BTreeMap::new();
+    let mut checks = vec![];
+    for date in dates {
+        if let btree_map::Entry::Vacant(entry) = holidays_by_year.entry(date.year()) {
+            let first_day = NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap();
+            let mut holidays = crate::load_holidays(&data, Some(&country), &subdivision, first_day).await;
+            holidays.retain(|holiday| kinds.contains(&holiday.kind));
+            entry.insert(holidays);
+        }
+        let holiday = crate::holiday_on(date, &holidays_by_year[&date.year()]);
+        checks.push(DateCheck {
+            date: date.to_string(),
+            holiday: holiday.is_some(),
+            name: holiday.map(|holiday| holiday.description.clone()),
+            kind: holiday.map(|holiday| holiday.kind),
+        });
+    }
+    Ok(HttpResponse::Ok().json(checks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::db::{Database, Holiday, HolidayPortion};
+    use crate::holiday_provider::MockProvider;
+
+    #[actix_rt::test]
+    async fn test_check_batch() {
+        let db = Database::new(":memory:").unwrap();
+        for (date, description, kind) in [
+            ("2025-12-25", "Christmas Day", HolidayKind::Public),
+            ("2026-01-01", "New Year's Day", HolidayKind::Public),
+            ("2025-05-11", "Mother's Day", HolidayKind::Observance),
+        ] {
+            db.add_holiday(&Holiday { id: None, date: date.to_string(), description: description.to_string(), country: "fr".to_string(), portion: HolidayPortion::Full, kind, subdivision: None }, "test").unwrap();
+        }
+        let data = web::Data::new(AppState { db, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data).service(check_batch)).await;
+
+        let req = test::TestRequest::post().uri("/holidays/FR/check-batch")
+            .set_json(serde_json::json!({ "dates": ["2025-12-25", "2025-12-26", "2026-01-01", "2025-05-11"] }))
+            .to_request();
+        let checks: Vec<DateCheck> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(checks.iter().map(|date_check| date_check.holiday).collect::<Vec<_>>(), vec![true, false, true, false]);
+        assert_eq!(checks[0].name.as_deref(), Some("Christmas Day"));
+        assert_eq!(checks[1].name, None);
+
+        let req = test::TestRequest::post().uri("/holidays/fr/check-batch")
+            .set_json(serde_json::json!({ "dates": ["2025-02-30"] }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+
+    #[actix_rt::test]
+    async fn test_check() {
+        let db = Database::new(":memory:").unwrap();
+        db.add_holiday(&Holiday { id: None, date: "2025-12-25".to_string(), description: "Christmas Day".to_string(), country: "de".to_string(), portion: HolidayPortion::Full, kind: HolidayKind::Public, subdivision: None }, "test").unwrap();
+        db.store_fetched_holidays(&CountryCode::parse("de").unwrap(), "openholidays", &[
+            Holiday { id: None, date: "2025-10-03".to_string(), description: "German Unity Day".to_string(), country: "de".to_string(), portion: HolidayPortion::Full, kind: HolidayKind::Public, subdivision: None },
+        ]).unwrap();
+        let data = web::Data::new(AppState { db, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data).service(check)).await;
+
+        let req = test::TestRequest::get().uri("/holidays/DE/check?date=2025-12-25&subdivision=DE-BY").to_request();
+        let holiday_check: HolidayCheck = test::call_and_read_body_json(&app, req).await;
+        assert!(holiday_check.holiday);
+        assert_eq!(holiday_check.name.as_deref(), Some("Christmas Day"));
+        assert_eq!(holiday_check.source.as_deref(), Some("custom"));
+
+        // The mock provider fails, so fetched holidays come from the database
+        let req = test::TestRequest::get().uri("/holidays/de/check?date=2025-10-03").to_request();
+        let holiday_check: HolidayCheck = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(holiday_check.source.as_deref(), Some("database"));
+
+        let req = test::TestRequest::get().uri("/holidays/de/check?date=2025-12-26").to_request();
+        let holiday_check: HolidayCheck = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(holiday_check, HolidayCheck { date: "2025-12-26".to_string(), holiday: false, name: None, kind: None, source: None });
+
+        let req = test::TestRequest::get().uri("/holidays/de/check?date=tomorrow").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use actix_web::{web, HttpRequest, HttpResponse, post};
+use chrono::{DateTime, Datelike, NaiveDate};
+use serde::{Serialize, Deserialize};
+
+use crate::calendar_config::{author, DryRunParams};
+use crate::country::{self, CountryCode};
+use crate::db::{Holiday, HolidayKind, HolidayPortion};
+use crate::{freeze, AppState};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportedHoliday {
+    // YYYY-MM-DD or RFC3339, only the date is kept
+    pub date: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HolidayChange {
+    pub date: String,
+    pub description: String,
+    // Description replaced by an update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+}
+
+// Date the import cannot reconcile on its own, nothing is written while there are conflicts
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Conflict {
+    pub date: String,
+    pub reason: String,
+    pub descriptions: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: Vec<HolidayChange>,
+    pub updated: Vec<HolidayChange>,
+    pub deleted: Vec<HolidayChange>,
+    pub unchanged: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| DateTime::parse_from_rfc3339(value).map(|date| date.date_naive()))
+        .map_err(|_| format!("Invalid holiday date: {}", value))
+}
+
+// Stored dates may be RFC3339 timestamps, they are compared by day
+pub fn stored_date(holiday: &Holiday) -> Option<NaiveDate> {
+    parse_date(&holiday.date).ok()
+}
+
+// Writes of an import, with the report describing them
+pub struct Plan {
+    pub report: ImportReport,
+    created: Vec<Holiday>,
+    updated: Vec<(i32, String)>,
+    deleted: Vec<i32>,
+}
+
+// Changes making the country's holidays added through the API match the imported list for the years
+// it covers: other years and the holidays fetched from providers are left alone
+pub fn plan(manual: Vec<Holiday>, country: &CountryCode, imported: &[ImportedHoliday]) -> Result<Plan, actix_web::error::Error> {
+    let mut wanted: BTreeMap<NaiveDate, Vec<String>> = BTreeMap::new();
+    for holiday in imported {
+        let date = parse_date(&holiday.date).map_err(actix_web::error::ErrorBadRequest)?;
+        let descriptions = wanted.entry(date).or_default();
+        if !descriptions.contains(&holiday.description) {
+            descriptions.push(holiday.description.clone());
+        }
+    }
+    let years: Vec<i32> = wanted.keys().map(|date| date.year()).collect();
+
+    let mut stored: BTreeMap<NaiveDate, Vec<Holiday>> = BTreeMap::new();
+    for holiday in manual {
+        if let Some(date) = stored_date(&holiday).filter(|date| years.contains(&date.year())) {
+            stored.entry(date).or_default().push(holiday);
+        }
+    }
+
+    let mut report = ImportReport::default();
+    let (mut created, mut updated, mut deleted) = (vec![], vec![], vec![]);
+    for (date, descriptions) in &wanted {
+        if descriptions.len() > 1 {
+            report.conflicts.push(Conflict { date: date.to_string(), reason: "imported more than once with different descriptions".to_string(), descriptions: descriptions.clone() });
+            continue;
+        }
+        let description = &descriptions[0];
+        match stored.get(date).map(|holidays| holidays.as_slice()) {
+            None | Some([]) => {
+                report.created.push(HolidayChange { date: date.to_string(), description: description.clone(), previous: None });
+                created.push(Holiday { id: None, date: date.to_string(), description: description.clone(), country: country.to_string(), portion: HolidayPortion::Full, kind: HolidayKind::Public, subdivision: None });
+            }
+            Some([current]) if &current.description == description => report.unchanged += 1,
+            Some([current]) => {
+                report.updated.push(HolidayChange { date: date.to_string(), description: description.clone(), previous: Some(current.description.clone()) });
+                updated.push((current.id.unwrap_or_default(), description.clone()));
+            }
+            Some(duplicates) => report.conflicts.push(Conflict {
+                date: date.to_string(),
+                reason: "already stored more than once".to_string(),
+                descriptions: duplicates.iter().map(|holiday| holiday.description.clone()).collect(),
+            }),
+        }
+    }
+    for (date, holidays) in &stored {
+        if wanted.contains_key(date) {
+            continue;
+        }
+        for holiday in holidays {
+            report.deleted.push(HolidayChange { date: date.to_string(), description: holiday.description.clone(), previous: None });
+            deleted.push(holiday.id.unwrap_or_default());
+        }
+    }
+    Ok(Plan { report, created, updated, deleted })
+}
+
+// Replaces the holidays of a country for the years of the list. With ?dryRun=true the changes
+// are only reported, so that a large import can be reviewed first.
+#[post("/holidays/{country}/import")]
+pub async fn import_holidays(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    country: web::Path<String>,
+    params: web::Query<DryRunParams>,
+    req: web::Json<Vec<ImportedHoliday>>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let country = country::parse_request(&country)?;
+    run(&data, &country, params.dry_run, &req, &author(&http_req)).await
+}
+
+// Plans the import and applies it unless it is a dry run or has conflicts (409)
+pub(crate) async fn run(data: &web::Data<AppState>, country: &CountryCode, dry_run: bool, imported: &[ImportedHoliday], author: &str) -> Result<HttpResponse, actix_web::error::Error> {
+    let stored_country = country.clone();
+    let manual = data.db.blocking(move |db| db.get_manual_holidays(&stored_country)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let Plan { mut report, created, updated, deleted } = plan(manual, country, imported)?;
+    report.dry_run = dry_run;
+    if dry_run {
+        return Ok(HttpResponse::Ok().json(report));
+    }
+    if !report.conflicts.is_empty() {
+        return Ok(HttpResponse::Conflict().json(report));
+    }
+    freeze::ensure_unfrozen(&data.db).await?;
+    let author = author.to_string();
+    data.db.blocking(move |db| db.import_holidays(&created, &updated, &deleted, &author)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::db::Database;
+    use crate::holiday_provider::MockProvider;
+
+    fn holiday(date: &str, description: &str) -> Holiday {
+        Holiday { id: None, date: date.to_string(), description: description.to_string(), country: "fr".to_string(), portion: HolidayPortion::Full, kind: HolidayKind::Public, subdivision: None }
+    }
+
+    #[actix_rt::test]
+    async fn test_dry_run_reports_changes_without_writing() {
+        let database = Database::new(":memory:").unwrap();
+        database.add_holiday(&holiday("2025-05-01", "Labour Day"), "test").unwrap();
+        database.add_holiday(&holiday("2025-06-09T00:00:00Z", "Whit Monday"), "test").unwrap();
+        database.add_holiday(&holiday("2025-11-11", "Armistice"), "test").unwrap();
+        database.add_holiday(&holiday("2024-11-11", "Armistice"), "test").unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data.clone()).service(import_holidays)).await;
+        let body = serde_json::json!([
+            { "date": "2025-05-01", "description": "Labour Day" },
+            { "date": "2025-06-09", "description": "Whit Monday (solidarity day)" },
+            { "date": "2025-12-25T00:00:00Z", "description": "Christmas" }
+        ]);
+
+        let req = test::TestRequest::post().uri("/holidays/FR/import?dryRun=true").set_json(&body).to_request();
+        let report: ImportReport = test::call_and_read_body_json(&app, req).await;
+        assert!(report.dry_run);
+        assert_eq!(report.created, vec![HolidayChange { date: "2025-12-25".to_string(), description: "Christmas".to_string(), previous: None }]);
+        assert_eq!(report.updated[0].previous, Some("Whit Monday".to_string()));
+        // 2024 is not covered by the import
+        assert_eq!(report.deleted, vec![HolidayChange { date: "2025-11-11".to_string(), description: "Armistice".to_string(), previous: None }]);
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(data.db.get_manual_holidays(&"fr".parse().unwrap()).unwrap().len(), 4);
+
+        let req = test::TestRequest::post().uri("/holidays/fr/import").set_json(&body).to_request();
+        let report: ImportReport = test::call_and_read_body_json(&app, req).await;
+        assert!(!report.dry_run);
+        let mut dates: Vec<String> = data.db.get_manual_holidays(&"fr".parse().unwrap()).unwrap().into_iter().map(|h| h.date).collect();
+        dates.sort();
+        assert_eq!(dates, vec!["2024-11-11", "2025-05-01", "2025-06-09T00:00:00Z", "2025-12-25"]);
+    }
+
+    #[actix_rt::test]
+    async fn test_conflicts_block_the_import() {
+        let data = web::Data::new(AppState { db: Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data.clone()).service(import_holidays)).await;
+        let req = test::TestRequest::post().uri("/holidays/fr/import")
+            .set_json(serde_json::json!([
+                { "date": "2025-07-14", "description": "Bastille Day" },
+                { "date": "2025-07-14", "description": "Fête nationale" }
+            ]))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), 409);
+        let report: ImportReport = test::read_body_json(response).await;
+        assert_eq!(report.conflicts[0].descriptions, vec!["Bastille Day", "Fête nationale"]);
+        assert!(data.db.get_manual_holidays(&"fr".parse().unwrap()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+use actix_web::{web, HttpResponse, get};
+use chrono::{Datelike, NaiveDate};
+use serde::{Serialize, Deserialize};
+
+use crate::db::{HolidayKind, HolidayPortion};
+use crate::{calendar_resolver, country, holiday_check, holiday_provider, holidays_api, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct YearHolidaysParams {
+    pub subdivision: Option<String>,
+    // Adds the closures of the calendar, whose subdivision is used unless one is given
+    #[serde(rename = "calendarId")]
+    pub calendar_id: Option<String>,
+    #[serde(rename = "holidaySource")]
+    pub holiday_source: Option<String>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ListedHoliday {
+    pub date: String,
+    pub description: String,
+    pub kind: HolidayKind,
+    #[serde(default, skip_serializing_if = "HolidayPortion::is_full")]
+    pub portion: HolidayPortion,
+    // provider, database, frozen, custom or calendar
+    pub source: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YearHolidays {
+    pub country: String,
+    pub year: i32,
+    pub holidays: Vec<ListedHoliday>,
+}
+
+// Holidays of a year as a calculation loads them: the provider's (or the stored fallback),
+// the custom ones and the closures of a calendar. Every kind is listed, calculations only
+// count the ones of their holidayKinds.
+#[get("/holidays/{country}/{year:\\d{4}}")]
+pub async fn list_year_holidays(
+    data: web::Data<AppState>,
+    path: web::Path<(String, i32)>,
+    params: web::Query<YearHolidaysParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let (country, year) = path.into_inner();
+    let country = country::parse_request(&country)?;
+    let first_day = NaiveDate::from_ymd_opt(year, 1, 1)
+        .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Invalid year: {}", year)))?;
+    if let Some(source) = &params.holiday_source {
+        if holiday_provider::by_name(source, &data.http).is_none() {
+            return Err(actix_web::error::ErrorBadRequest(format!("Unknown holiday source: {}", source)));
+        }
+    }
+    if let Some(language) = &params.language {
+        holidays_api::validate_language(language).map_err(actix_web::error::ErrorBadRequest)?;
+    }
+    let calendar = match &params.calendar_id {
+        Some(calendar_id) => {
+            let calendar = calendar_resolver::load(&data, calendar_id).await?;
+            if calendar.country != country.as_str() {
+                return Err(actix_web::error::ErrorBadRequest(format!("Calendar {} is for country {}", calendar.id, calendar.country)));
+            }
+            Some(calendar)
+        }
+        None => None,
+    };
+    let subdivision = params.subdivision.clone()
+        .or_else(|| calendar.as_ref().and_then(|calendar| calendar.subdivision.clone()))
+        .unwrap_or_default();
+
+    let (holidays, source) = holidays_api::with_language(
+        params.language.as_deref(),
+        crate::load_holidays_with_source(&data, &country, &subdivision, first_day, params.holiday_source.as_deref()),
+    ).await;
+    let prefix = format!("{}-", year);
+    let custom = holiday_check::custom_holidays(&data, &country, year).await;
+    // Rows whose date cannot be read are left out
+    let mut listed: Vec<ListedHoliday> = holidays.iter()
+        .filter_map(|holiday| crate::holiday_date(holiday).map(|date| (date, holiday)))
+        .filter(|(date, _)| date.year() == year)
+        .map(|(date, holiday)| ListedHoliday {
+            date: date.to_string(),
+            description: holiday.description.clone(),
+            kind: holiday.kind,
+            portion: holiday.portion,
+            source: if holiday_check::is_custom(&custom, date, holiday) { "custom" } else { source }.to_string(),
+        })
+        .collect();
+    if let Some(calendar) = &calendar {
+        listed.extend(calendar_resolver::closure_holidays(calendar, year..=year).into_iter()
+            .filter(|holiday| holiday.date.starts_with(&prefix))
+            .map(|holiday| ListedHoliday {
+                date: holiday.date,
+                description: holiday.description,
+                kind: holiday.kind,
+                portion: holiday.portion,
+                source: "calendar".to_string(),
+            }));
+    }
+    listed.sort_by(|a, b| a.date.cmp(&b.date));
+    Ok(HttpResponse::Ok().json(YearHolidays { country: country.to_string(), year, holidays: listed }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::country::CountryCode;
+    use crate::db::{Database, Holiday, RecurringHoliday};
+    use crate::holiday_provider::MockProvider;
+
+    #[actix_rt::test]
+    async fn test_list_year_holidays() {
+        let db = Database::new(":memory:").unwrap();
+        let fetched = |date: &str, description: &str| Holiday { id: None, date: date.to_string(), description: description.to_string(), country: "fr".to_string(), portion: HolidayPortion::Full, kind: HolidayKind::Public, subdivision: None };
+        db.store_fetched_holidays(&CountryCode::parse("fr").unwrap(), "openholidays", &[
+            fetched("2025-07-14", "Bastille Day"),
+            fetched("2025-01-01", "New Year's Day"),
+            fetched("2026-01-01", "New Year's Day"),
+            fetched("2025-8-1", "Unpadded"),
+        ]).unwrap();
+        db.add_recurring_holiday(&RecurringHoliday { id: None, country: "fr".to_string(), rule: "12-24".to_string(), description: "Christmas Eve".to_string(), kind: HolidayKind::Company }).unwrap();
+        let data = web::Data::new(AppState { db, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data).service(list_year_holidays)).await;
+
+        let req = test::TestRequest::get().uri("/holidays/FR/2025").to_request();
+        let list: YearHolidays = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(list.country, "fr");
+        let days: Vec<(&str, &str)> = list.holidays.iter().map(|holiday| (holiday.date.as_str(), holiday.source.as_str())).collect();
+        assert_eq!(days, vec![("2025-01-01", "database"), ("2025-07-14", "database"), ("2025-12-24", "custom")]);
+
+        let req = test::TestRequest::get().uri("/holidays/fr/2025?calendarId=missing").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+        let req = test::TestRequest::get().uri("/holidays/fr/2025?holidaySource=calendarific").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+}
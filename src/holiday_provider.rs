@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::env;
+use async_trait::async_trait;
+use chrono::{Datelike, NaiveDate};
+use lazy_static::lazy_static;
+use log::{info, warn};
+
+use crate::country::{CountryCode, Subdivision};
+use crate::db::Database;
+use crate::easter::MovableFeast;
+use crate::freeze;
+use crate::holidays_api::{self, Holiday, Origin};
+
+// Source of public holidays used by the calculations, stored in AppState.
+// When a provider fails, the calculations fall back to the holidays stored in the database.
+#[async_trait]
+pub trait HolidayProvider: Send + Sync {
+    // Reported in the logs and the admin endpoints
+    fn name(&self) -> &'static str;
+
+    // Holidays of the year of the date, which may be followed by those of the next year:
+    // the remote providers and the embedded dataset return both years at once
+    async fn holidays(
+        &self,
+        db: &Database,
+        country: &CountryCode,
+        subdivision: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<Holiday>, String>;
+}
+
+// Keep a copy of fresh remote holidays in the database, for the fallback used when the provider is down.
+// Regional lists mix nationwide and regional holidays without telling them apart, so only national lists are stored.
+async fn persist(db: &Database, source: &'static str, country: &CountryCode, subdivision: Option<&Subdivision>, holidays: &[Holiday]) {
+    if subdivision.is_some() || holidays.is_empty() {
+        return;
+    }
+    let holidays = holidays_api::convert_to_db_holiday(holidays.to_vec(), country);
+    let stored_country = country.clone();
+    let stored = db.blocking(move |db| {
+        // The stored holidays are what calculations use during a payroll freeze
+        if freeze::active(db).is_some() {
+            return Ok(());
+        }
+        db.store_fetched_holidays(&stored_country, source, &holidays)
+    }).await;
+    if let Err(e) = stored {
+        warn!("Failed to store {} holidays for {}: {}", source, country, e);
+    }
+}
+
+// openholidaysapi.org, cached per country and year
+pub struct OpenHolidaysProvider {
+    pub http: reqwest::Client,
+}
+
+#[async_trait]
+impl HolidayProvider for OpenHolidaysProvider {
+    fn name(&self) -> &'static str {
+        "openholidays"
+    }
+
+    async fn holidays(&self, db: &Database, country: &CountryCode, subdivision: &str, date: NaiveDate) -> Result<Vec<Holiday>, String> {
+        let subdivision = Subdivision::parse_optional(country, subdivision)?;
+        let (holidays, origin) = holidays_api::get_holidays_for_country(&self.http, country, subdivision.as_ref(), date).await?;
+        if origin == Origin::Api {
+            persist(db, self.name(), country, subdivision.as_ref(), &holidays).await;
+        }
+        Ok(holidays)
+    }
+}
+
+// date.nager.at, wider country coverage than OpenHolidays outside Europe
+pub struct NagerDateProvider {
+    pub http: reqwest::Client,
+}
+
+#[async_trait]
+impl HolidayProvider for NagerDateProvider {
+    fn name(&self) -> &'static str {
+        "nager"
+    }
+
+    async fn holidays(&self, db: &Database, country: &CountryCode, subdivision: &str, date: NaiveDate) -> Result<Vec<Holiday>, String> {
+        let subdivision = Subdivision::parse_optional(country, subdivision)?;
+        let (holidays, origin) = holidays_api::get_nager_holidays_for_country(&self.http, country, subdivision.as_ref(), date).await?;
+        if origin == Origin::Api {
+            persist(db, self.name(), country, subdivision.as_ref(), &holidays).await;
+        }
+        Ok(holidays)
+    }
+}
+
+// Holidays maintained in the local database only, for air-gapped deployments
+pub struct DatabaseProvider;
+
+#[async_trait]
+impl HolidayProvider for DatabaseProvider {
+    fn name(&self) -> &'static str {
+        "database"
+    }
+
+    async fn holidays(&self, db: &Database, country: &CountryCode, subdivision: &str, _date: NaiveDate) -> Result<Vec<Holiday>, String> {
+        let subdivision = Subdivision::parse_optional(country, subdivision)?;
+        let country = country.clone();
+        let holidays = db.blocking(move |db| db.get_holidays_by_country(&country, subdivision.as_ref())).await.map_err(|e| e.to_string())?;
+        Ok(holidays.into_iter()
+            .map(|holiday| Holiday { date: holiday.date, description: holiday.description, portion: holiday.portion, kind: holiday.kind })
+            .collect())
+    }
+}
+
+// Holidays of major countries compiled into the binary, or read from HOLIDAY_DATASET
+// (same format: country code to list of date/description), so that the service keeps
+// working offline. National holidays only, subdivisions are ignored.
+const EMBEDDED_DATASET: &str = include_str!("../data/holidays.json");
+
+lazy_static! {
+    static ref DATASET: HashMap<String, Vec<Holiday>> = load_dataset();
+}
+
+fn load_dataset() -> HashMap<String, Vec<Holiday>> {
+    if let Ok(path) = env::var("HOLIDAY_DATASET") {
+        match std::fs::read_to_string(&path).map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string())) {
+            Ok(dataset) => {
+                info!("Holiday dataset: {}", path);
+                return dataset;
+            }
+            Err(e) => warn!("Failed to load holiday dataset {}, using the embedded one: {}", path, e),
+        }
+    }
+    serde_json::from_str(EMBEDDED_DATASET).expect("Invalid embedded holiday dataset")
+}
+
+fn holiday_year(holiday: &Holiday) -> Option<i32> {
+    holiday.date.get(..4).and_then(|year| year.parse().ok())
+}
+
+// Holidays of a year the dataset does not list: the ones always on the same day, and the
+// feasts computed from Easter. Holidays moving otherwise (e.g. Thanksgiving) and substitute
+// days are left out.
+fn derive_year(dataset: &[Holiday], year: i32) -> Vec<Holiday> {
+    let years: BTreeSet<i32> = dataset.iter().filter_map(holiday_year).collect();
+    let mut days: BTreeMap<&str, (BTreeSet<i32>, BTreeSet<&str>)> = BTreeMap::new();
+    for holiday in dataset {
+        if let (Some(year), Some(day)) = (holiday_year(holiday), holiday.date.get(5..)) {
+            let (holiday_years, holiday_days) = days.entry(holiday.description.as_str()).or_default();
+            holiday_years.insert(year);
+            holiday_days.insert(day);
+        }
+    }
+    let mut holidays: Vec<Holiday> = days.into_iter()
+        .filter_map(|(description, (holiday_years, holiday_days))| {
+            // Substitute days only occur in some years
+            let date = match MovableFeast::from_description(description) {
+                Some(feast) => feast.date(year),
+                None if holiday_days.len() == 1 && holiday_years == years => {
+                    NaiveDate::parse_from_str(&format!("{}-{}", year, holiday_days.first()?), "%Y-%m-%d").ok()?
+                }
+                None => return None,
+            };
+            let sample = dataset.iter().find(|holiday| holiday.description == description)?;
+            Some(Holiday { date: date.format("%Y-%m-%d").to_string(), ..sample.clone() })
+        })
+        .collect();
+    holidays.sort_by(|a, b| a.date.cmp(&b.date));
+    holidays
+}
+
+pub struct EmbeddedProvider;
+
+#[async_trait]
+impl HolidayProvider for EmbeddedProvider {
+    fn name(&self) -> &'static str {
+        "embedded"
+    }
+
+    // Same two-year window as the remote providers
+    async fn holidays(&self, _db: &Database, country: &CountryCode, _subdivision: &str, date: NaiveDate) -> Result<Vec<Holiday>, String> {
+        let dataset = DATASET.get(country.as_str())
+            .ok_or_else(|| format!("No embedded holidays for country: {}", country))?;
+        let mut holidays = vec![];
+        for year in date.year()..=date.year() + 1 {
+            let listed: Vec<Holiday> = dataset.iter()
+                .filter(|holiday| holiday_year(holiday) == Some(year))
+                .cloned()
+                .collect();
+            if listed.is_empty() {
+                holidays.extend(derive_year(dataset, year));
+            } else {
+                holidays.extend(listed);
+            }
+        }
+        if holidays.is_empty() {
+            return Err(format!("No embedded holidays for country {} in {}", country, date.year()));
+        }
+        Ok(holidays)
+    }
+}
+
+// Providers tried in order until one returns holidays, e.g. embedded,database,openholidays
+pub struct ChainProvider {
+    pub providers: Vec<Box<dyn HolidayProvider>>,
+}
+
+#[async_trait]
+impl HolidayProvider for ChainProvider {
+    fn name(&self) -> &'static str {
+        "chain"
+    }
+
+    async fn holidays(&self, db: &Database, country: &CountryCode, subdivision: &str, date: NaiveDate) -> Result<Vec<Holiday>, String> {
+        let mut errors = vec![];
+        for provider in &self.providers {
+            match provider.holidays(db, country, subdivision, date).await {
+                Ok(holidays) if !holidays.is_empty() => return Ok(holidays),
+                Ok(_) => errors.push(format!("{}: no holidays", provider.name())),
+                Err(e) => errors.push(format!("{}: {}", provider.name(), e)),
+            }
+        }
+        Err(errors.join(", "))
+    }
+}
+
+// Holidays registered with holidays_api::mock, for tests
+pub struct MockProvider;
+
+#[async_trait]
+impl HolidayProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn holidays(&self, _db: &Database, country: &CountryCode, subdivision: &str, _date: NaiveDate) -> Result<Vec<Holiday>, String> {
+        let subdivision = Subdivision::parse_optional(country, subdivision)?;
+        holidays_api::mock::get_holidays_for_country(country, subdivision.as_ref()).await
+    }
+}
+
+// Built-in provider by name, as accepted by HOLIDAY_PROVIDER and the holidaySource request field.
+// A comma-separated list builds a chain tried in that order. Remote providers use the shared HTTP client.
+pub fn by_name(name: &str, http: &reqwest::Client) -> Option<Box<dyn HolidayProvider>> {
+    if name.contains(',') {
+        let providers = name.split(',').map(|name| by_name(name.trim(), http)).collect::<Option<Vec<_>>>()?;
+        return Some(Box::new(ChainProvider { providers }));
+    }
+    match name.to_lowercase().as_str() {
+        "openholidays" => Some(Box::new(OpenHolidaysProvider { http: http.clone() })),
+        "nager" => Some(Box::new(NagerDateProvider { http: http.clone() })),
+        "database" => Some(Box::new(DatabaseProvider)),
+        "embedded" => Some(Box::new(EmbeddedProvider)),
+        _ => None,
+    }
+}
+
+// HOLIDAY_PROVIDER selects the provider: openholidays (default), nager, database, embedded or a list of them
+pub fn from_env(http: &reqwest::Client) -> Box<dyn HolidayProvider> {
+    let name = env::var("HOLIDAY_PROVIDER").unwrap_or_default();
+    let provider = by_name(&name, http).unwrap_or_else(|| Box::new(OpenHolidaysProvider { http: http.clone() }));
+    info!("Holiday provider: {}", if name.contains(',') { name.as_str() } else { provider.name() });
+    provider
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn code(country: &str) -> CountryCode {
+        CountryCode::parse(country).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_database_provider_reads_stored_holidays() {
+        let database = Database::new(":memory:").unwrap();
+        database.add_holiday(&db::Holiday {
+            id: None,
+            date: "2023-12-25".to_string(),
+            description: "Christmas Day".to_string(),
+            country: "gb".to_string(),
+            portion: db::HolidayPortion::Full,
+            kind: db::HolidayKind::Public,
+            subdivision: None,
+        }, "test").unwrap();
+        let date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+
+        let holidays = DatabaseProvider.holidays(&database, &code("gb"), "", date).await.unwrap();
+        assert_eq!(holidays.len(), 1);
+        assert_eq!(holidays[0].description, "Christmas Day");
+
+        // Nothing registered with the mock
+        assert!(MockProvider.holidays(&database, &code("gb"), "", date).await.is_err());
+
+        assert_eq!(by_name("Nager", &reqwest::Client::new()).unwrap().name(), "nager");
+        assert!(by_name("calendarific", &reqwest::Client::new()).is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_chain_falls_through_to_the_embedded_dataset() {
+        let database = Database::new(":memory:").unwrap();
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+
+        // The database is empty, so the embedded dataset answers
+        let chain = by_name("database, embedded", &reqwest::Client::new()).unwrap();
+        let holidays = chain.holidays(&database, &code("FR"), "", date).await.unwrap();
+        assert!(holidays.iter().any(|holiday| holiday.date == "2025-07-14"));
+        assert!(holidays.iter().all(|holiday| holiday.date.starts_with("2025") || holiday.date.starts_with("2026")));
+
+        // Unknown countries fail in every provider
+        assert!(by_name("embedded,database", &reqwest::Client::new()).unwrap().holidays(&database, &code("zz"), "", date).await.is_err());
+        assert!(by_name("embedded,unknown", &reqwest::Client::new()).is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_embedded_years_outside_the_dataset_are_derived() {
+        let database = Database::new(":memory:").unwrap();
+        let date = NaiveDate::from_ymd_opt(2035, 1, 1).unwrap();
+
+        let holidays = EmbeddedProvider.holidays(&database, &code("de"), "", date).await.unwrap();
+        let on = |date: &str| holidays.iter().find(|holiday| holiday.date == date).map(|holiday| holiday.description.as_str());
+        assert_eq!(on("2035-03-23"), Some("Good Friday"));
+        assert_eq!(on("2035-03-26"), Some("Easter Monday"));
+        assert_eq!(on("2035-05-03"), Some("Ascension Day"));
+        assert_eq!(on("2035-05-14"), Some("Whit Monday"));
+        assert_eq!(on("2035-10-03"), Some("German Unity Day"));
+        assert_eq!(on("2036-04-14"), Some("Easter Monday"));
+
+        // Dated holidays which move every year are not guessed
+        let holidays = EmbeddedProvider.holidays(&database, &code("us"), "", date).await.unwrap();
+        assert!(holidays.iter().any(|holiday| holiday.date == "2035-07-04"));
+        assert!(holidays.iter().all(|holiday| holiday.description != "Thanksgiving Day"));
+        assert!(holidays.iter().all(|holiday| !holiday.description.contains("(observed)")));
+    }
+}
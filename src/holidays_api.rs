@@ -1,10 +1,14 @@
 use std::collections::HashMap;
-use std::sync::Mutex;
-use std::time::{Duration, SystemTime};
+use std::future::Future;
+use std::time::{Duration, Instant, SystemTime};
 use chrono::{NaiveDate, Datelike};
-use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use log::{error, info};
+use log::{error, info, warn};
+
+use crate::country::{CountryCode, Subdivision};
+use crate::db::{HolidayKind, HolidayPortion};
+use crate::holiday_cache::{self, CacheKey, CacheLookup};
+use crate::{breaker, trace};
 
 // For testing
 use std::cell::RefCell;
@@ -15,6 +19,10 @@ pub struct Holiday {
     pub date: String,
     #[serde(default)]
     pub description: String,
+    #[serde(default, skip_serializing_if = "HolidayPortion::is_full")]
+    pub portion: HolidayPortion,
+    #[serde(default)]
+    pub kind: HolidayKind,
 }
 
 // Struct to represent the API response
@@ -22,7 +30,15 @@ pub struct Holiday {
 struct OpenHolidayApiResponse {
     #[serde(rename = "startDate")]
     start_date: String,
-    name: Vec<LocalizedName>
+    name: Vec<LocalizedName>,
+    // FullDay or HalfDay
+    #[serde(rename = "temporalScope", default)]
+    temporal_scope: Option<String>,
+    // Public, Bank, Optional...
+    #[serde(rename = "type", default)]
+    holiday_type: Option<String>,
+    #[serde(default = "default_global")]
+    nationwide: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -31,43 +47,67 @@ struct LocalizedName {
     text: String,
 }
 
-// Cache entry with expiration time
-struct CacheEntry {
-    holidays: Vec<Holiday>,
-    expiration: SystemTime,
+// Where a list of holidays came from: the API answers are worth persisting, cached ones already were
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Origin {
+    Cache,
+    Api,
+}
+
+tokio::task_local! {
+    static LANGUAGE: String;
 }
 
-// Global cache for holidays
-lazy_static! {
-    static ref HOLIDAY_CACHE: Mutex<HashMap<String, CacheEntry>> = Mutex::new(HashMap::new());
+// Language of holiday names when the request does not choose one, HOLIDAY_LANGUAGE or EN
+pub fn default_language() -> String {
+    std::env::var("HOLIDAY_LANGUAGE").ok()
+        .filter(|language| !language.is_empty())
+        .map_or_else(|| "EN".to_string(), |language| language.to_uppercase())
+}
+
+// Language of the calculation being run, the default one outside with_language
+pub fn language() -> String {
+    LANGUAGE.try_with(|language| language.clone()).unwrap_or_else(|_| default_language())
+}
+
+// Run a future with holiday names fetched in the given ISO 639-1 language
+pub async fn with_language<F: Future>(language: Option<&str>, future: F) -> F::Output {
+    let language = language.map_or_else(default_language, |language| language.to_uppercase());
+    LANGUAGE.scope(language, future).await
+}
+
+pub fn validate_language(language: &str) -> Result<(), String> {
+    if language.len() == 2 && language.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(())
+    } else {
+        Err(format!("Invalid language: {}, expected a two-letter ISO 639-1 code", language))
+    }
 }
 
 // Function to get holidays for a country from the API
-pub async fn get_holidays_for_country(country: &str, subdivision: &str, current_date: NaiveDate) -> Result<Vec<Holiday>, String> {
+pub async fn get_holidays_for_country(client: &reqwest::Client, country: &CountryCode, subdivision: Option<&Subdivision>, current_date: NaiveDate) -> Result<(Vec<Holiday>, Origin), String> {
+    let language = language();
     // Check if we have a valid cached entry
-    let code = if subdivision != "" {
-        &subdivision.to_uppercase()
-    } else {
-        &country.to_uppercase()
-    };
-    let cache_key = code.to_string() + current_date.year().to_string().as_str();
-    {
-        let cache = HOLIDAY_CACHE.lock().unwrap();
-
-        if let Some(entry) = cache.get(&cache_key) {
-            let now = SystemTime::now();
-            if entry.expiration > now {
-                let remaining_secs = entry.expiration.duration_since(now).unwrap_or(Duration::from_secs(0)).as_secs();
-                info!("CACHE HIT: Using cached holidays for key: {}. Cache expires in {} seconds", cache_key, remaining_secs);
-                return Ok(entry.holidays.clone());
-            } else {
-                info!("CACHE EXPIRED: Holidays cache for country: {} has expired", code);
+    let cache_key = CacheKey::new("openholidays", country, subdivision, current_date.year(), &language);
+    let cache = match holiday_cache::lookup(&cache_key).await {
+        CacheLookup::Hit(result) => return result.map(|holidays| (holidays, Origin::Cache)),
+        CacheLookup::Stale { holidays, refresh } => {
+            if refresh {
+                let (client, country, subdivision) = (client.clone(), country.clone(), subdivision.cloned());
+                holiday_cache::refresh_in_background(cache_key, async move {
+                    fetch_openholidays(&client, &country, subdivision.as_ref(), &language, current_date, "stale").await
+                });
             }
-        } else {
-            info!("CACHE MISS: No cached holidays found for country: {}", code);
+            return Ok((holidays, Origin::Cache));
         }
-    }
+        CacheLookup::Miss(cache) => cache,
+    };
+    let result = fetch_openholidays(client, country, subdivision, &language, current_date, cache).await;
+    holiday_cache::store(cache_key, &result).await;
+    result.map(|holidays| (holidays, Origin::Api))
+}
 
+async fn fetch_openholidays(client: &reqwest::Client, country: &CountryCode, subdivision: Option<&Subdivision>, language: &str, current_date: NaiveDate, cache: &str) -> Result<Vec<Holiday>, String> {
     // If not in cache or expired, fetch from API
     info!("Fetching holidays from API for country: {}", country);
 
@@ -76,25 +116,27 @@ pub async fn get_holidays_for_country(country: &str, subdivision: &str, current_
 
     // Construct the API URL
 
-    let url = if subdivision != "" {
+    let url = if let Some(subdivision) = subdivision {
         format!(
-            "https://openholidaysapi.org/PublicHolidays?countryIsoCode={}&subdivisionCode={}&languageIsoCode=EN&validFrom={}-01-01&validTo={}-12-31",
-            country.to_uppercase(),
-            subdivision.to_uppercase(),
+            "https://openholidaysapi.org/PublicHolidays?countryIsoCode={}&subdivisionCode={}&languageIsoCode={}&validFrom={}-01-01&validTo={}-12-31",
+            country.api_code(),
+            subdivision,
+            language,
             current_year,
             current_year + 1
         )
     } else {
         format!(
-            "https://openholidaysapi.org/PublicHolidays?countryIsoCode={}&languageIsoCode=EN&validFrom={}-01-01&validTo={}-12-31",
-            country.to_uppercase(),
+            "https://openholidaysapi.org/PublicHolidays?countryIsoCode={}&languageIsoCode={}&validFrom={}-01-01&validTo={}-12-31",
+            country.api_code(),
+            language,
             current_year,
             current_year + 1
         )
     };
 
     // Make the API request
-    let response = match reqwest::get(&url).await {
+    let response = match get_with_retry(client, "openholidays", &url, cache).await {
         Ok(resp) => resp,
         Err(e) => {
             error!("Failed to fetch holidays from API: {}", e);
@@ -124,7 +166,7 @@ pub async fn get_holidays_for_country(country: &str, subdivision: &str, current_
         .map(|api_holiday| {
             let description = api_holiday.name
                 .iter()
-                .find(|name| name.language == "EN")
+                .find(|name| name.language.eq_ignore_ascii_case(language))
                 .map_or_else(
                     || api_holiday.name.first().map_or("".to_string(), |name| name.text.clone()),
                     |name| name.text.clone()
@@ -133,33 +175,257 @@ pub async fn get_holidays_for_country(country: &str, subdivision: &str, current_
             Holiday {
                 date: api_holiday.start_date,
                 description,
+                portion: openholidays_portion(api_holiday.temporal_scope.as_deref()),
+                kind: holiday_kind(api_holiday.holiday_type.as_slice(), api_holiday.nationwide),
             }
         })
         .collect();
 
-    // Cache the result with 24-hour expiration
-    {
-        let cache_duration = Duration::from_secs(24 * 60 * 60);
-        let expiration_time = SystemTime::now() + cache_duration;
+    Ok(holidays)
+}
+
+// OpenHolidays does not say which half of the day is off: half-day holidays such as
+// Christmas Eve or New Year's Eve are afternoons off
+fn openholidays_portion(temporal_scope: Option<&str>) -> HolidayPortion {
+    match temporal_scope {
+        Some("HalfDay") => HolidayPortion::Afternoon,
+        _ => HolidayPortion::Full,
+    }
+}
+
+// Kind of a holiday from the types reported by OpenHolidays or Nager.Date: public holidays
+// observed in some subdivisions only are regional, optional ones are observances
+fn holiday_kind(types: &[String], nationwide: bool) -> HolidayKind {
+    if types.is_empty() || types.iter().any(|t| t.eq_ignore_ascii_case("Public")) {
+        if nationwide { HolidayKind::Public } else { HolidayKind::Regional }
+    } else if types.iter().any(|t| t.eq_ignore_ascii_case("Bank")) {
+        HolidayKind::Bank
+    } else {
+        HolidayKind::Observance
+    }
+}
+
+// HTTP client shared by every holiday API call, so that connections and TLS sessions are reused.
+// The timeout bounds a single attempt, HOLIDAYS_API_DEADLINE_MS bounds the call with its retries.
+pub fn client_from_env() -> reqwest::Client {
+    let var = |name: &str, default: u64| std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+    reqwest::Client::builder()
+        .connect_timeout(Duration::from_millis(var("UPSTREAM_CONNECT_TIMEOUT_MS", 2000)))
+        .timeout(Duration::from_millis(var("UPSTREAM_TIMEOUT_MS", 5000)))
+        .pool_max_idle_per_host(var("UPSTREAM_POOL_MAX_IDLE_PER_HOST", 16) as usize)
+        .pool_idle_timeout(Duration::from_secs(var("UPSTREAM_POOL_IDLE_TIMEOUT_SECONDS", 90)))
+        .build()
+        .expect("Invalid HTTP client configuration")
+}
+
+// GET recorded in the trace of explained requests, with its status and latency
+async fn traced_get(client: &reqwest::Client, provider: &str, url: &str, cache: &str) -> Result<reqwest::Response, reqwest::Error> {
+    let started = Instant::now();
+    let response = client.get(url).send().await;
+    trace::record(trace::UpstreamCall {
+        provider: provider.to_string(),
+        url: Some(url.to_string()),
+        status: response.as_ref().ok().map(|response| response.status().as_u16()),
+        latency_ms: Some(started.elapsed().as_millis() as u64),
+        cache: Some(cache.to_string()),
+        error: response.as_ref().err().map(|e| e.to_string()),
+        fetched_at: None,
+    });
+    response
+}
+
+// Retries of the holiday API calls, configured from the environment
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    // Attempts after the first one
+    pub retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    // Overall budget of a call, retries included
+    pub deadline: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: u64| std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        RetryPolicy {
+            retries: var("HOLIDAYS_API_RETRIES", 3) as u32,
+            base_delay: Duration::from_millis(var("HOLIDAYS_API_BACKOFF_MS", 200)),
+            max_delay: Duration::from_millis(var("HOLIDAYS_API_MAX_BACKOFF_MS", 5000)),
+            deadline: Duration::from_millis(var("HOLIDAYS_API_DEADLINE_MS", 10000)),
+        }
+    }
+
+    // Exponential backoff with full jitter: a random delay up to base * 2^attempt, capped
+    pub fn delay(&self, attempt: u32, jitter: f64) -> Duration {
+        let ceiling = self.base_delay.saturating_mul(2u32.saturating_pow(attempt)).min(self.max_delay);
+        ceiling.mul_f64(jitter.clamp(0.0, 1.0))
+    }
+}
+
+// Transient answers worth another attempt
+fn is_retryable(status: reqwest::StatusCode) -> bool {
+    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS || status == reqwest::StatusCode::REQUEST_TIMEOUT
+}
+
+// Pseudo-random factor in [0, 1) for the jitter, precise enough to spread retries apart
+fn jitter() -> f64 {
+    let nanos = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+    (nanos % 1_000_000) as f64 / 1_000_000.0
+}
+
+// GET through the provider circuit breaker: while it is open the call fails at once,
+// so that the caller falls back without waiting on an upstream known to be down
+pub(crate) async fn get_with_retry(client: &reqwest::Client, provider: &str, url: &str, cache: &str) -> Result<reqwest::Response, String> {
+    if !breaker::allows(provider) {
+        let error = format!("Circuit breaker open for {}", provider);
+        trace::record(trace::UpstreamCall {
+            provider: provider.to_string(),
+            url: Some(url.to_string()),
+            cache: Some(cache.to_string()),
+            error: Some(error.clone()),
+            ..Default::default()
+        });
+        return Err(error);
+    }
+    let result = get_retrying(client, provider, url, cache).await;
+    match &result {
+        Ok(response) if !is_retryable(response.status()) => breaker::record_success(provider),
+        _ => breaker::record_failure(provider),
+    }
+    result
+}
+
+// GET retried on connection errors and transient statuses, within the policy deadline.
+// The last response is returned as is, so that the caller reports its status.
+async fn get_retrying(client: &reqwest::Client, provider: &str, url: &str, cache: &str) -> Result<reqwest::Response, String> {
+    let policy = RetryPolicy::from_env();
+    let started = Instant::now();
+    let mut attempt = 0;
+    loop {
+        let remaining = policy.deadline.saturating_sub(started.elapsed());
+        let result = match tokio::time::timeout(remaining, traced_get(client, provider, url, cache)).await {
+            Ok(result) => result.map_err(|e| e.to_string()),
+            Err(_) => return Err(format!("no answer within {} ms", policy.deadline.as_millis())),
+        };
+        let retryable = match &result {
+            Ok(response) => is_retryable(response.status()),
+            Err(_) => true,
+        };
+        if !retryable || attempt >= policy.retries {
+            return result;
+        }
+        let delay = policy.delay(attempt, jitter());
+        if started.elapsed() + delay >= policy.deadline {
+            return result;
+        }
+        match &result {
+            Ok(response) => warn!("{} answered {}, retrying in {} ms", provider, response.status(), delay.as_millis()),
+            Err(e) => warn!("{} call failed: {}, retrying in {} ms", provider, e, delay.as_millis()),
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+// Struct to represent a Nager.Date holiday
+#[derive(Debug, Deserialize)]
+struct NagerHoliday {
+    date: String,
+    name: String,
+    // Name in the country's language
+    #[serde(rename = "localName", default)]
+    local_name: Option<String>,
+    // Nationwide holiday, otherwise only observed in the listed subdivisions (e.g. DE-BY)
+    #[serde(default = "default_global")]
+    global: bool,
+    #[serde(default)]
+    counties: Option<Vec<String>>,
+    // Public, Bank, School, Authorities, Optional or Observance
+    #[serde(default)]
+    types: Vec<String>,
+}
 
-        let mut cache = HOLIDAY_CACHE.lock().unwrap();
+fn default_global() -> bool {
+    true
+}
 
-        cache.insert(
-            cache_key.clone(),
-            CacheEntry {
-                holidays: holidays.clone(),
-                expiration: expiration_time,
+// Nager.Date holidays of a subdivision: the nationwide ones plus the regional ones it observes.
+// Names are in English, or in the country's language for any other language.
+fn nager_to_holidays(api_holidays: Vec<NagerHoliday>, subdivision: Option<&Subdivision>, language: &str) -> Vec<Holiday> {
+    api_holidays
+        .into_iter()
+        .filter(|api_holiday| {
+            api_holiday.global || subdivision.is_some_and(|subdivision| api_holiday.counties.as_ref()
+                .is_some_and(|counties| counties.iter().any(|county| county.eq_ignore_ascii_case(subdivision.as_str()))))
+        })
+        .map(|api_holiday| Holiday {
+            date: api_holiday.date,
+            description: match api_holiday.local_name {
+                Some(local_name) if !language.eq_ignore_ascii_case("EN") => local_name,
+                _ => api_holiday.name,
             },
-        );
-        info!("CACHE UPDATE: Cached {} holidays for key: {}. Cache will expire in {} seconds",
-              holidays.len(), cache_key, cache_duration.as_secs());
+            portion: HolidayPortion::Full,
+            kind: holiday_kind(&api_holiday.types, api_holiday.global),
+        })
+        .collect()
+}
+
+// Function to get holidays for a country from date.nager.at, which covers countries outside Europe
+pub async fn get_nager_holidays_for_country(client: &reqwest::Client, country: &CountryCode, subdivision: Option<&Subdivision>, current_date: NaiveDate) -> Result<(Vec<Holiday>, Origin), String> {
+    let language = language();
+    let cache_key = CacheKey::new("nager", country, subdivision, current_date.year(), &language);
+    let cache = match holiday_cache::lookup(&cache_key).await {
+        CacheLookup::Hit(result) => return result.map(|holidays| (holidays, Origin::Cache)),
+        CacheLookup::Stale { holidays, refresh } => {
+            if refresh {
+                let (client, country, subdivision) = (client.clone(), country.clone(), subdivision.cloned());
+                holiday_cache::refresh_in_background(cache_key, async move {
+                    fetch_nager(&client, &country, subdivision.as_ref(), &language, current_date, "stale").await
+                });
+            }
+            return Ok((holidays, Origin::Cache));
+        }
+        CacheLookup::Miss(cache) => cache,
+    };
+    let result = fetch_nager(client, country, subdivision, &language, current_date, cache).await;
+    holiday_cache::store(cache_key, &result).await;
+    result.map(|holidays| (holidays, Origin::Api))
+}
+
+async fn fetch_nager(client: &reqwest::Client, country: &CountryCode, subdivision: Option<&Subdivision>, language: &str, current_date: NaiveDate, cache: &str) -> Result<Vec<Holiday>, String> {
+    info!("Fetching holidays from Nager.Date for country: {}", country);
+
+    // Same two-year window as the OpenHolidays client
+    let mut api_holidays: Vec<NagerHoliday> = vec![];
+    for year in [current_date.year(), current_date.year() + 1] {
+        let url = format!("https://date.nager.at/api/v3/PublicHolidays/{}/{}", year, country.api_code());
+        let response = match get_with_retry(client, "nager", &url, cache).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                error!("Failed to fetch holidays from Nager.Date: {}", e);
+                return Err(format!("Failed to fetch holidays: {}", e));
+            }
+        };
+        if !response.status().is_success() {
+            let status = response.status();
+            error!("Nager.Date request failed with status: {}", status);
+            return Err(format!("API request failed with status: {}", status));
+        }
+        match response.json::<Vec<NagerHoliday>>().await {
+            Ok(holidays) => api_holidays.extend(holidays),
+            Err(e) => {
+                error!("Failed to parse Nager.Date response: {}", e);
+                return Err(format!("Failed to parse API response: {}", e));
+            }
+        }
     }
 
-    Ok(holidays)
+    Ok(nager_to_holidays(api_holidays, subdivision, language))
 }
 
 // Function to convert our Holiday format to the format expected by the work hours calculation
-pub fn convert_to_db_holiday(holidays: Vec<Holiday>, country: &str) -> Vec<crate::db::Holiday> {
+pub fn convert_to_db_holiday(holidays: Vec<Holiday>, country: &CountryCode) -> Vec<crate::db::Holiday> {
     holidays
         .into_iter()
         .map(|holiday| crate::db::Holiday {
@@ -167,10 +433,98 @@ pub fn convert_to_db_holiday(holidays: Vec<Holiday>, country: &str) -> Vec<crate
             date: holiday.date,
             description: holiday.description,
             country: country.to_string(),
+            portion: holiday.portion,
+            kind: holiday.kind,
+            subdivision: None,
         })
         .collect()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_backoff_is_jittered_and_capped() {
+        let policy = RetryPolicy {
+            retries: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_millis(1000),
+            deadline: Duration::from_secs(10),
+        };
+        assert_eq!(policy.delay(0, 1.0), Duration::from_millis(200));
+        assert_eq!(policy.delay(2, 1.0), Duration::from_millis(800));
+        assert_eq!(policy.delay(5, 1.0), Duration::from_millis(1000));
+        assert_eq!(policy.delay(2, 0.5), Duration::from_millis(400));
+        assert_eq!(policy.delay(30, 0.0), Duration::ZERO);
+
+        assert!(is_retryable(reqwest::StatusCode::BAD_GATEWAY));
+        assert!(is_retryable(reqwest::StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable(reqwest::StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn test_nager_regional_holidays_are_filtered_by_subdivision() {
+        let api_holidays: Vec<NagerHoliday> = serde_json::from_str(r#"[
+            {"date": "2024-01-01", "localName": "Neujahr", "name": "New Year's Day", "countryCode": "DE", "global": true, "counties": null},
+            {"date": "2024-01-06", "localName": "Heilige Drei Könige", "name": "Epiphany", "countryCode": "DE", "global": false, "counties": ["DE-BW", "DE-BY", "DE-ST"]}
+        ]"#).unwrap();
+
+        let holidays = nager_to_holidays(api_holidays, Some(&Subdivision::parse(&CountryCode::parse("de").unwrap(), "de-by").unwrap()), "EN");
+        assert_eq!(holidays.len(), 2);
+        assert_eq!(holidays[1].description, "Epiphany");
+
+        let api_holidays: Vec<NagerHoliday> = serde_json::from_str(r#"[
+            {"date": "2024-01-06", "name": "Epiphany", "global": false, "counties": ["DE-BY"]}
+        ]"#).unwrap();
+        assert!(nager_to_holidays(api_holidays, None, "EN").is_empty());
+
+        let api_holidays: Vec<NagerHoliday> = serde_json::from_str(r#"[
+            {"date": "2024-01-06", "localName": "Heilige Drei Könige", "name": "Epiphany", "global": true},
+            {"date": "2024-01-07", "name": "Without local name", "global": true}
+        ]"#).unwrap();
+        let holidays = nager_to_holidays(api_holidays, None, "de");
+        assert_eq!(holidays[0].description, "Heilige Drei Könige");
+        assert_eq!(holidays[1].description, "Without local name");
+    }
+
+    #[test]
+    fn test_openholidays_half_days() {
+        let api_holidays: Vec<OpenHolidayApiResponse> = serde_json::from_str(r#"[
+            {"startDate": "2024-12-24", "name": [{"language": "EN", "text": "Christmas Eve"}], "temporalScope": "HalfDay"},
+            {"startDate": "2024-12-25", "name": [{"language": "EN", "text": "Christmas Day"}], "temporalScope": "FullDay"}
+        ]"#).unwrap();
+        let portions: Vec<HolidayPortion> = api_holidays.iter().map(|h| openholidays_portion(h.temporal_scope.as_deref())).collect();
+        assert_eq!(portions, vec![HolidayPortion::Afternoon, HolidayPortion::Full]);
+    }
+
+    #[test]
+    fn test_holiday_kinds() {
+        let api_holidays: Vec<OpenHolidayApiResponse> = serde_json::from_str(r#"[
+            {"startDate": "2024-01-06", "name": [], "type": "Public", "nationwide": false},
+            {"startDate": "2024-12-24", "name": [], "type": "Bank", "nationwide": true}
+        ]"#).unwrap();
+        let kinds: Vec<HolidayKind> = api_holidays.iter().map(|h| holiday_kind(h.holiday_type.as_slice(), h.nationwide)).collect();
+        assert_eq!(kinds, vec![HolidayKind::Regional, HolidayKind::Bank]);
+
+        let api_holidays: Vec<NagerHoliday> = serde_json::from_str(r#"[
+            {"date": "2024-01-01", "name": "New Year's Day", "global": true, "types": ["Public"]},
+            {"date": "2024-05-12", "name": "Mother's Day", "global": true, "types": ["Observance"]},
+            {"date": "2024-08-05", "name": "Civic Holiday", "global": true, "types": ["Bank", "Optional"]}
+        ]"#).unwrap();
+        let kinds: Vec<HolidayKind> = nager_to_holidays(api_holidays, None, "EN").into_iter().map(|h| h.kind).collect();
+        assert_eq!(kinds, vec![HolidayKind::Public, HolidayKind::Observance, HolidayKind::Bank]);
+    }
+
+    #[actix_rt::test]
+    async fn test_language_is_scoped_to_the_calculation() {
+        assert_eq!(with_language(Some("fr"), async { language() }).await, "FR");
+        assert_eq!(with_language(None, async { language() }).await, default_language());
+        assert!(validate_language("de").is_ok());
+        assert!(validate_language("deu").is_err());
+    }
+}
+
 // Mock implementation for testing
 pub mod mock {
     use super::*;
@@ -179,9 +533,10 @@ pub mod mock {
         static MOCK_HOLIDAYS: RefCell<HashMap<String, Vec<Holiday>>> = RefCell::new(HashMap::new());
     }
 
-    pub fn set_mock_holidays(country: &str, holidays: Vec<Holiday>) {
+    // Keyed like the lookups, by the uppercase country or subdivision code
+    pub fn set_mock_holidays(code: &str, holidays: Vec<Holiday>) {
         MOCK_HOLIDAYS.with(|mock_holidays| {
-            mock_holidays.borrow_mut().insert(country.to_string(), holidays);
+            mock_holidays.borrow_mut().insert(code.to_uppercase(), holidays);
         });
     }
 
@@ -191,15 +546,14 @@ pub mod mock {
         });
     }
 
-    pub async fn get_holidays_for_country(country: &str, subdivision: &str) -> Result<Vec<Holiday>, String> {
-        let code = if subdivision != "" {
-            &subdivision.to_uppercase()
-        } else {
-            &country.to_uppercase()
+    pub async fn get_holidays_for_country(country: &CountryCode, subdivision: Option<&Subdivision>) -> Result<Vec<Holiday>, String> {
+        let code = match subdivision {
+            Some(subdivision) => subdivision.to_string(),
+            None => country.api_code(),
         };
         MOCK_HOLIDAYS.with(|mock_holidays| {
             let holidays = mock_holidays.borrow();
-            match holidays.get(code) {
+            match holidays.get(&code) {
                 Some(holidays) => {
                     info!("MOCK: Using mock holidays for country: {}, found {} holidays", country, holidays.len());
                     Ok(holidays.clone())
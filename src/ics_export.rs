@@ -0,0 +1,161 @@
+use actix_web::{web, HttpResponse, get};
+use chrono::{Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::Deserialize;
+
+use crate::db::{Holiday, HolidayKind, HolidayPortion};
+use crate::{country, holidays_api, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct CalendarExportParams {
+    pub country: String,
+    pub year: i32,
+    pub subdivision: Option<String>,
+    pub language: Option<String>,
+    // Adds a weekly all-day event on Saturdays and Sundays, the days calculations never count
+    #[serde(default)]
+    pub weekends: bool,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+// Content lines are folded at 75 octets, continuation lines start with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn all_day_event(uid: &str, stamp: &str, day: NaiveDate, summary: &str, extra: &[String]) -> Vec<String> {
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", stamp),
+        format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")),
+        format!("DTEND;VALUE=DATE:{}", (day + Duration::days(1)).format("%Y%m%d")),
+        format!("SUMMARY:{}", escape(summary)),
+    ];
+    lines.extend_from_slice(extra);
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+fn kind_name(kind: HolidayKind) -> String {
+    serde_json::to_value(kind).ok().and_then(|value| value.as_str().map(str::to_uppercase)).unwrap_or_default()
+}
+
+// Feed of the holidays of a year, one all-day event each. Half-day holidays say which half
+// in their summary. Holidays stay transparent so that subscribers are not shown as busy.
+pub fn to_ics(country: &str, year: i32, holidays: &[Holiday], weekends: bool, stamp: &str) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//workhours//holidays//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape(&format!("Holidays {} {}", country.to_uppercase(), year))),
+    ];
+    for (index, holiday) in holidays.iter().enumerate() {
+        let day = match holiday.date.get(..10).and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()) {
+            Some(day) if day.year() == year => day,
+            _ => continue,
+        };
+        let summary = match holiday.portion {
+            HolidayPortion::Full => holiday.description.clone(),
+            HolidayPortion::Morning => format!("{} (morning)", holiday.description),
+            HolidayPortion::Afternoon => format!("{} (afternoon)", holiday.description),
+        };
+        let uid = format!("{}-{}-{}@workhours", country, day.format("%Y%m%d"), index);
+        lines.extend(all_day_event(&uid, stamp, day, &summary, &[
+            format!("CATEGORIES:{}", kind_name(holiday.kind)),
+            "TRANSP:TRANSPARENT".to_string(),
+        ]));
+    }
+    if weekends {
+        let first_day = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+        for (weekday, by_day) in [(Weekday::Sat, "SA"), (Weekday::Sun, "SU")] {
+            let day = first_day.iter_days().find(|day| day.weekday() == weekday).unwrap();
+            let uid = format!("{}-{}-weekend-{}@workhours", country, year, by_day.to_lowercase());
+            lines.extend(all_day_event(&uid, stamp, day, "Weekend", &[
+                format!("RRULE:FREQ=WEEKLY;BYDAY={};UNTIL={}1231", by_day, year),
+                "CATEGORIES:WEEKEND".to_string(),
+                "TRANSP:TRANSPARENT".to_string(),
+            ]));
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+// Holidays of a year as an iCalendar feed to subscribe to from Outlook or Google Calendar,
+// loaded like GET /holidays/{country}/{year}
+#[get("/calendar.ics")]
+pub async fn export_calendar(
+    data: web::Data<AppState>,
+    params: web::Query<CalendarExportParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let country = country::parse_request(&params.country)?;
+    let first_day = NaiveDate::from_ymd_opt(params.year, 1, 1)
+        .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Invalid year: {}", params.year)))?;
+    if let Some(language) = &params.language {
+        holidays_api::validate_language(language).map_err(actix_web::error::ErrorBadRequest)?;
+    }
+    let subdivision = params.subdivision.clone().unwrap_or_default();
+    let (holidays, _) = holidays_api::with_language(
+        params.language.as_deref(),
+        crate::load_holidays_with_source(&data, &country, &subdivision, first_day, None),
+    ).await;
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .body(to_ics(country.as_str(), params.year, &holidays, params.weekends, &stamp)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ics_import;
+
+    #[test]
+    fn test_holidays_are_exported_as_all_day_events() {
+        let holiday = |date: &str, description: &str, portion: HolidayPortion| Holiday {
+            id: None,
+            date: date.to_string(),
+            description: description.to_string(),
+            country: "fr".to_string(),
+            portion,
+            kind: HolidayKind::Public,
+            subdivision: None,
+        };
+        let holidays = [
+            holiday("2025-07-14", "Fête nationale, Bastille Day", HolidayPortion::Full),
+            holiday("2025-12-24T00:00:00Z", "Christmas Eve", HolidayPortion::Afternoon),
+            // The providers also return the next year
+            holiday("2026-01-01", "New Year's Day", HolidayPortion::Full),
+        ];
+        let ics = to_ics("fr", 2025, &holidays, true, "20250101T000000Z");
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("SUMMARY:Fête nationale\\, Bastille Day\r\n"));
+        assert!(ics.contains("RRULE:FREQ=WEEKLY;BYDAY=SA;UNTIL=20251231\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= 75));
+
+        // The import reads the feed back, weekend markers aside
+        let days: Vec<(String, String)> = ics_import::parse_events(&ics).unwrap().into_iter()
+            .filter(|holiday| holiday.description != "Weekend")
+            .map(|holiday| (holiday.date, holiday.description))
+            .collect();
+        assert_eq!(days, vec![
+            ("2025-07-14".to_string(), "Fête nationale, Bastille Day".to_string()),
+            ("2025-12-24".to_string(), "Christmas Eve (afternoon)".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,465 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+use actix_web::{web, HttpRequest, HttpResponse, post};
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use log::info;
+use serde::{Serialize, Deserialize};
+
+use crate::db::Closure;
+use crate::holiday_import::{self, ImportedHoliday};
+use crate::{calendar_config, country, events, freeze, AppState};
+
+// Longest event expanded into days, longer ones are refused
+const MAX_EVENT_DAYS: i64 = 366;
+
+// Occurrences a recurrence rule is expanded into, longer ones are refused
+const MAX_OCCURRENCES: usize = 1000;
+
+// Largest feed read from a url, bigger ones are refused
+const MAX_FEED_BYTES: usize = 5 * 1024 * 1024;
+
+// Redirects followed when fetching a feed, each to an allowed host
+const MAX_REDIRECTS: usize = 5;
+
+#[derive(Debug, Deserialize)]
+pub struct IcsImportParams {
+    // Feed fetched by the server instead of an uploaded file, webcal:// is read over https
+    pub url: Option<String>,
+    #[serde(rename = "dryRun", default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ClosureImportReport {
+    pub dry_run: bool,
+    pub created: Vec<Closure>,
+    pub deleted: Vec<Closure>,
+    pub unchanged: usize,
+}
+
+// Content lines with the folded continuation lines (starting with a space or a tab) joined back
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in content.lines() {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    let mut text = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push(' '),
+            Some(escaped) => text.push(escaped),
+            None => {}
+        }
+    }
+    text
+}
+
+// Day of a DTSTART or DTEND value: YYYYMMDD, or YYYYMMDDTHHMMSS with an optional Z
+fn parse_day(value: &str) -> Result<NaiveDate, String> {
+    value.get(..8).and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(|| format!("Invalid iCalendar date: {}", value))
+}
+
+// Last day of an event ending at DTEND, which is excluded for a date or a midnight
+fn last_day(end: &str) -> Result<NaiveDate, String> {
+    let day = parse_day(end)?;
+    let exclusive = matches!(end.get(8..).map(|time| time.trim_end_matches('Z')), None | Some("") | Some("T000000"));
+    Ok(if exclusive { day.pred_opt().unwrap_or(day) } else { day })
+}
+
+#[derive(Default)]
+struct Event {
+    start: Option<String>,
+    end: Option<String>,
+    summary: String,
+    cancelled: bool,
+    rule: Option<String>,
+    excluded: Vec<NaiveDate>,
+    dates: bool,
+}
+
+fn weekday(value: &str) -> Option<Weekday> {
+    match value.trim().to_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+// First days of the occurrences of an RRULE starting on the given day: DAILY, WEEKLY (with
+// an optional BYDAY list of plain weekdays) and YEARLY rules, ending with UNTIL or COUNT
+fn occurrences(first: NaiveDate, rule: &str) -> Result<Vec<NaiveDate>, String> {
+    let unsupported = || format!("Unsupported recurrence rule: {}", rule);
+    let (mut frequency, mut interval, mut until, mut count, mut days) = (None, 1, None, None, vec![]);
+    for part in rule.split(';') {
+        let (name, value) = part.split_once('=').ok_or_else(unsupported)?;
+        match name.trim().to_uppercase().as_str() {
+            "FREQ" => frequency = Some(value.trim().to_uppercase()),
+            "INTERVAL" => interval = value.trim().parse::<u32>().ok().filter(|interval| *interval > 0).ok_or_else(unsupported)?,
+            "UNTIL" => until = Some(parse_day(value.trim())?),
+            "COUNT" => count = Some(value.trim().parse::<usize>().map_err(|_| unsupported())?),
+            "BYDAY" => days = value.split(',').map(weekday).collect::<Option<Vec<_>>>().ok_or_else(unsupported)?,
+            "WKST" => {}
+            _ => return Err(unsupported()),
+        }
+    }
+    if until.is_none() && count.is_none() {
+        return Err(format!("Recurrence rule without UNTIL or COUNT: {}", rule));
+    }
+    let until = until.unwrap_or(NaiveDate::MAX);
+    let count = count.unwrap_or(usize::MAX);
+    let interval = u64::from(interval);
+    // Candidate days of the n-th period of the rule, in order
+    let period: Box<dyn Fn(u32) -> Vec<NaiveDate>> = match frequency.as_deref() {
+        Some("DAILY") if days.is_empty() => Box::new(move |n| first.checked_add_days(Days::new(u64::from(n) * interval)).into_iter().collect()),
+        Some("WEEKLY") => {
+            if days.is_empty() {
+                days.push(first.weekday());
+            }
+            days.sort_by_key(|day| day.num_days_from_monday());
+            let monday = first - Days::new(first.weekday().num_days_from_monday() as u64);
+            Box::new(move |n| match monday.checked_add_days(Days::new(7 * u64::from(n) * interval)) {
+                Some(week) => days.iter()
+                    .map(|day| week + Days::new(day.num_days_from_monday() as u64))
+                    .filter(|day| *day >= first)
+                    .collect(),
+                None => vec![],
+            })
+        }
+        Some("YEARLY") if days.is_empty() => Box::new(move |n| i32::try_from(u64::from(n) * interval).ok()
+            .and_then(|years| first.year().checked_add(years))
+            .and_then(|year| first.with_year(year))
+            .into_iter()
+            .collect()),
+        _ => return Err(unsupported()),
+    };
+    let mut starts = vec![];
+    // A few periods may have no day at all, e.g. years without the 29th of February
+    for n in 0..(MAX_OCCURRENCES as u32) * 4 {
+        for day in period(n) {
+            if day > until || starts.len() == count {
+                return Ok(starts);
+            }
+            if starts.len() == MAX_OCCURRENCES {
+                return Err(format!("Recurrence rule with more than {} occurrences: {}", MAX_OCCURRENCES, rule));
+            }
+            starts.push(day);
+        }
+    }
+    Ok(starts)
+}
+
+// Days off of the VEVENTs of a calendar, one per day of multi-day events. All-day events
+// end the day before DTEND, timed ones on the day of DTEND unless it is midnight. Cancelled
+// events are skipped, recurring ones are expanded without their EXDATEs, and events with
+// RDATEs or rules that cannot be expanded are refused rather than imported as their first day only.
+pub fn parse_events(content: &str) -> Result<Vec<ImportedHoliday>, String> {
+    let mut holidays = vec![];
+    let mut event: Option<Event> = None;
+    for line in unfold(content) {
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name, value.trim()),
+            None => continue,
+        };
+        let name = name.split(';').next().unwrap_or_default().to_uppercase();
+        match (name.as_str(), &mut event) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => event = Some(Event::default()),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                let event = event.take().unwrap();
+                let summary = event.summary;
+                if event.cancelled {
+                    continue;
+                }
+                if event.dates {
+                    return Err(format!("Recurrence dates are not supported: {}", summary));
+                }
+                let start = event.start.ok_or_else(|| format!("Event without DTSTART: {}", summary))?;
+                let first = parse_day(&start)?;
+                let last = match event.end {
+                    Some(end) => last_day(&end)?.max(first),
+                    None => first,
+                };
+                if (last - first).num_days() >= MAX_EVENT_DAYS {
+                    return Err(format!("Event longer than {} days: {}", MAX_EVENT_DAYS, summary));
+                }
+                let starts = match &event.rule {
+                    Some(rule) => occurrences(first, rule).map_err(|e| format!("{} in {}", e, summary))?,
+                    None => vec![first],
+                };
+                for start in starts.into_iter().filter(|start| !event.excluded.contains(start)) {
+                    for day in start.iter_days().take_while(|day| *day <= start + (last - first)) {
+                        holidays.push(ImportedHoliday { date: day.to_string(), description: summary.clone() });
+                    }
+                }
+            }
+            ("DTSTART", Some(event)) => event.start = Some(value.to_string()),
+            ("DTEND", Some(event)) => event.end = Some(value.to_string()),
+            ("SUMMARY", Some(event)) => event.summary = unescape(value),
+            ("STATUS", Some(event)) => event.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            ("RRULE", Some(event)) => event.rule = Some(value.to_string()),
+            ("EXDATE", Some(event)) => {
+                for excluded in value.split(',') {
+                    event.excluded.push(parse_day(excluded.trim())?);
+                }
+            }
+            ("RDATE", Some(event)) => event.dates = true,
+            _ => {}
+        }
+    }
+    Ok(holidays)
+}
+
+// Hosts feeds may be fetched from, none when unset
+fn allowed_hosts() -> Vec<String> {
+    std::env::var("ICS_IMPORT_HOSTS").unwrap_or_default()
+        .split(',')
+        .map(|host| host.trim().to_lowercase())
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+fn is_allowed(url: &reqwest::Url, hosts: &[String]) -> bool {
+    matches!(url.scheme(), "http" | "https") && url.host_str().is_some_and(|host| hosts.iter().any(|allowed| allowed == host))
+}
+
+// The url of a feed, webcal:// read over https, if it may be fetched
+fn feed_url(url: &str, hosts: &[String]) -> Result<reqwest::Url, String> {
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    };
+    let parsed = reqwest::Url::parse(&url).map_err(|_| format!("Invalid iCalendar url: {}", url))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Unsupported iCalendar url: {}", url));
+    }
+    if !is_allowed(&parsed, hosts) {
+        return Err(format!("Fetching iCalendar feeds from {} is not allowed, see ICS_IMPORT_HOSTS", parsed.host_str().unwrap_or_default()));
+    }
+    Ok(parsed)
+}
+
+// The uploaded file, or the feed of ?url= when its host is listed in ICS_IMPORT_HOSTS
+async fn load(params: &IcsImportParams, body: &web::Bytes) -> Result<String, actix_web::error::Error> {
+    let url = match &params.url {
+        Some(url) => url,
+        None if body.is_empty() => return Err(actix_web::error::ErrorBadRequest("Upload an iCalendar file or give its url")),
+        None => return String::from_utf8(body.to_vec()).map_err(|_| actix_web::error::ErrorBadRequest("The iCalendar file is not UTF-8")),
+    };
+    let hosts = allowed_hosts();
+    let url = feed_url(url, &hosts).map_err(actix_web::error::ErrorBadRequest)?;
+    // Redirects must stay on the allowed hosts too
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if is_allowed(attempt.url(), &hosts) {
+                attempt.follow()
+            } else {
+                attempt.stop()
+            }
+        }))
+        .connect_timeout(Duration::from_secs(5))
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    info!("Fetching iCalendar feed {}", url);
+    let mut response = client.get(url.clone()).send().await
+        .map_err(|e| actix_web::error::ErrorBadGateway(format!("Failed to fetch {}: {}", url, e)))?;
+    if !response.status().is_success() {
+        return Err(actix_web::error::ErrorBadGateway(format!("Fetching {} failed with status: {}", url, response.status())));
+    }
+    let too_large = || actix_web::error::ErrorBadGateway(format!("The feed {} is larger than {} bytes", url, MAX_FEED_BYTES));
+    if response.content_length().is_some_and(|length| length > MAX_FEED_BYTES as u64) {
+        return Err(too_large());
+    }
+    let mut content = vec![];
+    while let Some(chunk) = response.chunk().await
+        .map_err(|e| actix_web::error::ErrorBadGateway(format!("Failed to read {}: {}", url, e)))? {
+        if content.len() + chunk.len() > MAX_FEED_BYTES {
+            return Err(too_large());
+        }
+        content.extend_from_slice(&chunk);
+    }
+    String::from_utf8(content).map_err(|_| actix_web::error::ErrorBadGateway(format!("The feed {} is not UTF-8", url)))
+}
+
+// Imports the events of an iCalendar file or feed as the holidays of a country, like
+// POST /holidays/{country}/import: the holidays of the years it covers are replaced
+#[post("/holidays/{country}/import/ics")]
+pub async fn import_country_ics(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    country: web::Path<String>,
+    params: web::Query<IcsImportParams>,
+    body: web::Bytes,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let country = country::parse_request(&country)?;
+    let content = load(&params, &body).await?;
+    let imported = parse_events(&content).map_err(actix_web::error::ErrorBadRequest)?;
+    holiday_import::run(&data, &country, params.dry_run, &imported, &calendar_config::author(&http_req)).await
+}
+
+// Imports the events as closure days of a calendar: its dated closures of the years the
+// events cover are replaced, yearly closures and other years are kept
+#[post("/calendars/{id}/import/ics")]
+pub async fn import_calendar_ics(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    id: web::Path<String>,
+    params: web::Query<IcsImportParams>,
+    body: web::Bytes,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let content = load(&params, &body).await?;
+    let mut imported = parse_events(&content).map_err(actix_web::error::ErrorBadRequest)?;
+    imported.sort_by(|a, b| a.date.cmp(&b.date));
+    imported.dedup_by(|a, b| a.date == b.date);
+    let years: BTreeSet<i32> = imported.iter()
+        .filter_map(|holiday| NaiveDate::parse_from_str(&holiday.date, "%Y-%m-%d").ok())
+        .map(|date| date.year())
+        .collect();
+
+    let author = calendar_config::author(&http_req);
+    let calendar_id = id.to_string();
+    let calendar = data.db.blocking(move |db| db.get_calendar(&calendar_id)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let mut calendar = match calendar {
+        Some(calendar) => calendar,
+        None => return Ok(HttpResponse::NotFound().json(format!("Calendar {} not found", id))),
+    };
+    let covered = |closure: &Closure| closure.date.as_ref()
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+        .is_some_and(|date| years.contains(&date.year()));
+    let wanted: Vec<Closure> = imported.into_iter()
+        .map(|holiday| Closure { date: Some(holiday.date), recurring: None, description: holiday.description })
+        .collect();
+    let mut report = ClosureImportReport { dry_run: params.dry_run, ..Default::default() };
+    let (replaced, mut closures): (Vec<Closure>, Vec<Closure>) = calendar.closures.into_iter().partition(|closure| covered(closure));
+    report.deleted = replaced.iter().filter(|closure| !wanted.contains(closure)).cloned().collect();
+    report.created = wanted.iter().filter(|closure| !replaced.contains(closure)).cloned().collect();
+    report.unchanged = wanted.len() - report.created.len();
+    closures.extend(wanted);
+    calendar.closures = closures;
+    if params.dry_run || (report.created.is_empty() && report.deleted.is_empty()) {
+        return Ok(HttpResponse::Ok().json(report));
+    }
+    freeze::ensure_unfrozen(&data.db).await?;
+    let calendar_id = calendar.id.clone();
+    let stored_author = author.clone();
+    data.db.blocking(move |db| db.upsert_calendar(&calendar, &stored_author)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    events::publish(events::Event::CalendarChanged {
+        calendar_id,
+        author,
+        action: "updated".to_string(),
+    });
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::db::{Calendar, Database};
+    use crate::holiday_provider::MockProvider;
+
+    const FEED: &str = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+BEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20251224\r\nDTEND;VALUE=DATE:20251227\r\nSUMMARY:Winter closure\\, all sites\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nDTSTART:20250714T080000Z\r\nDTEND:20250714T170000Z\r\nSUMMARY:Bastille\r\n  Day\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20250815\r\nSTATUS:CANCELLED\r\nSUMMARY:Cancelled\r\nEND:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[actix_rt::test]
+    async fn test_events_are_expanded_into_days() {
+        let days: Vec<(String, String)> = parse_events(FEED).unwrap().into_iter().map(|h| (h.date, h.description)).collect();
+        assert_eq!(days, vec![
+            ("2025-12-24".to_string(), "Winter closure, all sites".to_string()),
+            ("2025-12-25".to_string(), "Winter closure, all sites".to_string()),
+            ("2025-12-26".to_string(), "Winter closure, all sites".to_string()),
+            ("2025-07-14".to_string(), "Bastille Day".to_string()),
+        ]);
+        assert!(parse_events("BEGIN:VEVENT\nSUMMARY:No date\nEND:VEVENT\n").is_err());
+        assert!(parse_events("BEGIN:VEVENT\nDTSTART;VALUE=DATE:20250101\nRRULE:FREQ=YEARLY\nSUMMARY:New Year\nEND:VEVENT\n").is_err());
+        assert!(parse_events("BEGIN:VEVENT\nDTSTART;VALUE=DATE:20250101\nRRULE:FREQ=MONTHLY;COUNT=3\nSUMMARY:Payday\nEND:VEVENT\n").is_err());
+        assert!(parse_events("BEGIN:VEVENT\nDTSTART;VALUE=DATE:20250101\nRDATE;VALUE=DATE:20250301\nSUMMARY:Payday\nEND:VEVENT\n").is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_recurring_events_are_expanded() {
+        let feed = "BEGIN:VEVENT\nDTSTART;VALUE=DATE:20250101\nRRULE:FREQ=YEARLY;COUNT=3\nSUMMARY:New Year\nEND:VEVENT\n\
+BEGIN:VEVENT\nDTSTART;VALUE=DATE:20250801\nDTEND;VALUE=DATE:20250802\nRRULE:FREQ=WEEKLY;BYDAY=FR,MO;UNTIL=20250815\n\
+EXDATE;VALUE=DATE:20250808\nSUMMARY:Summer hours\nEND:VEVENT\n";
+        let days: Vec<String> = parse_events(feed).unwrap().into_iter().map(|h| h.date).collect();
+        assert_eq!(days, vec!["2025-01-01", "2026-01-01", "2027-01-01", "2025-08-01", "2025-08-04", "2025-08-11", "2025-08-15"]);
+    }
+
+    #[actix_rt::test]
+    async fn test_feeds_are_only_fetched_from_allowed_hosts() {
+        let hosts = vec!["intranet.example.com".to_string()];
+        assert_eq!(feed_url("webcal://intranet.example.com/closures.ics", &hosts).unwrap().as_str(), "https://intranet.example.com/closures.ics");
+        for url in [
+            "http://169.254.169.254/latest/meta-data",
+            "http://localhost:8080/admin",
+            "file:///etc/passwd",
+            "https://intranet.example.com.evil.test/closures.ics",
+        ] {
+            assert!(feed_url(url, &hosts).is_err(), "{}", url);
+        }
+        assert!(feed_url("https://intranet.example.com/closures.ics", &[]).is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_feed_is_imported_as_holidays_or_closures() {
+        let database = Database::new(":memory:").unwrap();
+        let calendar = Calendar {
+            id: "acme".to_string(),
+            parent: None,
+            country: "fr".to_string(),
+            subdivision: None,
+            start_of_day: None,
+            end_of_day: None,
+            weekend: None,
+            closures: vec![
+                Closure { date: Some("2025-05-02".to_string()), recurring: None, description: "Bridge".to_string() },
+                Closure { date: None, recurring: Some("08-01".to_string()), description: "Summer".to_string() },
+            ],
+        };
+        database.upsert_calendar(&calendar, "test").unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data.clone()).service(import_country_ics).service(import_calendar_ics)).await;
+
+        let req = test::TestRequest::post().uri("/holidays/fr/import/ics").set_payload(FEED).to_request();
+        let report: holiday_import::ImportReport = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(report.created.len(), 4);
+        assert_eq!(data.db.get_manual_holidays(&"fr".parse().unwrap()).unwrap().len(), 4);
+
+        let req = test::TestRequest::post().uri("/calendars/acme/import/ics").set_payload(FEED).to_request();
+        let report: ClosureImportReport = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((report.created.len(), report.deleted.len()), (4, 1));
+        let closures = data.db.get_calendar("acme").unwrap().unwrap().closures;
+        assert_eq!(closures.len(), 5);
+        assert!(closures.iter().any(|closure| closure.recurring.as_deref() == Some("08-01")));
+
+        let req = test::TestRequest::post().uri("/calendars/unknown/import/ics").set_payload(FEED).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+        for uri in ["/holidays/fr/import/ics?url=ftp://example.com/feed.ics", "/holidays/fr/import/ics"] {
+            assert_eq!(test::call_service(&app, test::TestRequest::post().uri(uri).to_request()).await.status(), 400, "{}", uri);
+        }
+    }
+}
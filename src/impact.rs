@@ -0,0 +1,195 @@
+use actix_web::{web, HttpResponse, post};
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use serde::{Serialize, Deserialize};
+
+use crate::calendar_resolver::{self, ResolvedCalendar};
+use crate::country::{self, CountryCode};
+use crate::AppState;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProposedHoliday {
+    pub date: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+// Proposed global change, evaluated without being stored
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImpactRequest {
+    pub holidays: Vec<ProposedHoliday>,
+    // Only calendars of this country are affected, all calendars when omitted
+    #[serde(default)]
+    pub country: Option<String>,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CalendarImpact {
+    pub calendar_id: String,
+    pub country: String,
+    pub lost_hours: f64,
+    // Proposed days that are currently working days of the calendar
+    pub affected_dates: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImpactResponse {
+    pub total_lost_hours: f64,
+    pub calendars: Vec<CalendarImpact>,
+    // Calendars whose inheritance chain could not be resolved
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub errors: Vec<String>,
+}
+
+fn hours_per_day(intervals: &[(NaiveTime, NaiveTime)]) -> f64 {
+    intervals.iter()
+        .map(|(start, end)| {
+            let seconds = end.signed_duration_since(*start).num_seconds();
+            // Overnight interval
+            if seconds <= 0 { seconds + 86400 } else { seconds }
+        })
+        .sum::<i64>() as f64 / 3600.0
+}
+
+fn is_closed(calendar: &ResolvedCalendar, date: NaiveDate) -> bool {
+    let day = date.to_string();
+    let recurring = format!("{:02}-{:02}", date.month(), date.day());
+    calendar.closures.iter().any(|closure| {
+        closure.date.as_deref() == Some(day.as_str()) || closure.recurring.as_deref() == Some(recurring.as_str())
+    })
+}
+
+fn is_weekend(calendar: &ResolvedCalendar, date: NaiveDate) -> bool {
+    calendar.weekend.iter().any(|day| day.parse::<Weekday>().is_ok_and(|day| day == date.weekday()))
+}
+
+fn in_scope(calendar: &ResolvedCalendar, country: &Option<String>, subdivision: &Option<String>) -> bool {
+    let country_matches = country.as_ref().is_none_or(|country| CountryCode::parse(country).is_ok_and(|country| calendar.country == country.as_str()));
+    // A national change affects every subdivision, a regional one only its own
+    let subdivision_matches = subdivision.as_ref().is_none_or(|subdivision| {
+        calendar.subdivision.as_ref().is_some_and(|s| s.eq_ignore_ascii_case(subdivision))
+    });
+    country_matches && subdivision_matches
+}
+
+#[post("/admin/impact")]
+pub async fn evaluate_impact(
+    data: web::Data<AppState>,
+    req: web::Json<ImpactRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let dates = req.holidays.iter()
+        .map(|holiday| NaiveDate::parse_from_str(&holiday.date, "%Y-%m-%d")
+            .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid holiday date format: {}", e))))
+        .collect::<Result<Vec<_>, _>>()?;
+    if dates.is_empty() {
+        return Err(actix_web::error::ErrorBadRequest("At least one holiday must be provided"));
+    }
+
+    let (calendars, errors) = data.db.blocking(|db| {
+        let (mut calendars, mut errors) = (vec![], vec![]);
+        for calendar in db.get_all_calendars()? {
+            match calendar_resolver::resolve(db, &calendar.id) {
+                Ok(Some(resolved)) => calendars.push(resolved),
+                Ok(None) => {}
+                Err(e) => errors.push(e),
+            }
+        }
+        Ok((calendars, errors))
+    }).await.map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let mut impacts = vec![];
+    for calendar in calendars.iter().filter(|c| in_scope(c, &req.country, &req.subdivision)) {
+        let intervals = crate::resolve_schedule(&calendar.start_of_day, &calendar.end_of_day, &[])?;
+        let subdivision = calendar.subdivision.clone().unwrap_or(String::new());
+        let mut affected_dates = vec![];
+        for date in &dates {
+            if is_weekend(calendar, *date) || is_closed(calendar, *date) {
+                continue;
+            }
+            // Days already off for an existing public holiday lose nothing more
+            let holidays = crate::load_holidays(&data, Some(&country::parse_request(&calendar.country)?), &subdivision, *date).await;
+            if crate::is_holiday(*date, &holidays) {
+                continue;
+            }
+            affected_dates.push(date.to_string());
+        }
+        impacts.push(CalendarImpact {
+            calendar_id: calendar.id.clone(),
+            country: calendar.country.clone(),
+            lost_hours: affected_dates.len() as f64 * hours_per_day(&intervals),
+            affected_dates,
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(ImpactResponse {
+        total_lost_hours: impacts.iter().map(|i| i.lost_hours).sum(),
+        calendars: impacts,
+        errors,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::{calendar_config, db};
+
+    const CONFIG: &str = r#"
+calendars:
+  - id: acme-paris
+    country: FR
+    endOfDay: "18:00:00"
+  - id: acme-paris-support
+    parent: acme-paris
+    weekend: [sun, mon]
+  - id: acme-lyon
+    country: FR
+    closures:
+      - date: "2025-06-02"
+        description: Plant shutdown
+  - id: acme-berlin
+    country: DE
+"#;
+
+    #[actix_rt::test]
+    async fn test_new_holiday_impact_per_calendar() {
+        let database = db::Database::new(":memory:").unwrap();
+        calendar_config::apply(&database, &calendar_config::parse(CONFIG).unwrap(), "test").unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data).service(evaluate_impact)).await;
+
+        // A new national holiday on Monday 2 June 2025 in France
+        let req = test::TestRequest::post()
+            .uri("/admin/impact")
+            .set_json(serde_json::json!({
+                "country": "fr",
+                "holidays": [{ "date": "2025-06-02", "description": "New national day" }]
+            }))
+            .to_request();
+        let response: ImpactResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(response.calendars, vec![
+            CalendarImpact {
+                calendar_id: "acme-lyon".to_string(),
+                country: "fr".to_string(),
+                lost_hours: 0.0,
+                affected_dates: vec![],
+            },
+            CalendarImpact {
+                calendar_id: "acme-paris".to_string(),
+                country: "fr".to_string(),
+                lost_hours: 9.0,
+                affected_dates: vec!["2025-06-02".to_string()],
+            },
+            CalendarImpact {
+                calendar_id: "acme-paris-support".to_string(),
+                country: "fr".to_string(),
+                lost_hours: 0.0,
+                affected_dates: vec![],
+            },
+        ]);
+        assert_eq!(response.total_lost_hours, 9.0);
+    }
+}
@@ -0,0 +1,156 @@
+use std::env;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use actix_web::{web, HttpResponse, get};
+use serde::{Serialize, Deserialize};
+use log::{info, warn};
+
+use crate::db::Database;
+use crate::AppState;
+
+// Lease shared by every background job (sync, reports, cleanup)
+const BACKGROUND_JOBS_LEASE: &str = "background-jobs";
+
+// Shortest lease, renewed every third of its TTL: once a second
+const MIN_TTL_SECONDS: u64 = 3;
+
+// Database-lease based leader election so that only one replica runs background jobs
+pub struct LeaderElector {
+    pub instance_id: String,
+    pub lease_name: String,
+    pub ttl: Duration,
+    is_leader: AtomicBool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaderStatus {
+    pub instance_id: String,
+    pub lease_name: String,
+    pub is_leader: bool,
+}
+
+impl LeaderElector {
+    pub fn new(instance_id: &str, lease_name: &str, ttl: Duration) -> Self {
+        LeaderElector {
+            instance_id: instance_id.to_string(),
+            lease_name: lease_name.to_string(),
+            ttl,
+            is_leader: AtomicBool::new(false),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        // POD_NAME is usually injected through the downward API, HOSTNAME defaults to the pod name
+        let instance_id = env::var("POD_NAME")
+            .or_else(|_| env::var("HOSTNAME"))
+            .unwrap_or_else(|_| format!("workhours-{}", std::process::id()));
+        let mut ttl = env::var("LEADER_LEASE_TTL_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        if ttl < MIN_TTL_SECONDS {
+            warn!("LEADER_LEASE_TTL_SECONDS={} is too short, using {} seconds", ttl, MIN_TTL_SECONDS);
+            ttl = MIN_TTL_SECONDS;
+        }
+        LeaderElector::new(&instance_id, BACKGROUND_JOBS_LEASE, Duration::from_secs(ttl))
+    }
+
+    // Background jobs must check this before doing any work
+    pub fn is_leader(&self) -> bool {
+        self.is_leader.load(Ordering::SeqCst)
+    }
+
+    // Try to acquire or renew the lease once
+    pub fn tick(&self, db: &Database) -> bool {
+        let acquired = match db.try_acquire_lease(&self.lease_name, &self.instance_id, self.ttl) {
+            Ok(acquired) => acquired,
+            Err(e) => {
+                warn!("Failed to renew leader lease {}: {}", self.lease_name, e);
+                false
+            }
+        };
+
+        let was_leader = self.is_leader.swap(acquired, Ordering::SeqCst);
+        if acquired && !was_leader {
+            info!("Instance {} is now the leader for {}", self.instance_id, self.lease_name);
+        } else if !acquired && was_leader {
+            warn!("Instance {} lost the leadership for {}", self.instance_id, self.lease_name);
+        }
+        acquired
+    }
+
+    // Stop leading when the lease could not be renewed at all, another replica may take it over
+    pub fn step_down(&self) {
+        if self.is_leader.swap(false, Ordering::SeqCst) {
+            warn!("Instance {} lost the leadership for {}", self.instance_id, self.lease_name);
+        }
+    }
+
+    pub fn status(&self) -> LeaderStatus {
+        LeaderStatus {
+            instance_id: self.instance_id.clone(),
+            lease_name: self.lease_name.clone(),
+            is_leader: self.is_leader(),
+        }
+    }
+}
+
+// Acquire the lease once, so that background jobs started next know whether they lead,
+// then keep renewing it in the background, well before it expires
+pub async fn spawn(elector: Arc<LeaderElector>, data: web::Data<AppState>) {
+    renew(&elector, &data).await;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(elector.ttl / 3);
+        // The first tick completes immediately, the lease was just acquired
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            renew(&elector, &data).await;
+        }
+    });
+}
+
+async fn renew(elector: &Arc<LeaderElector>, data: &web::Data<AppState>) {
+    let ticking = elector.clone();
+    if let Err(e) = data.db.blocking(move |db| Ok(ticking.tick(db))).await {
+        warn!("Failed to renew leader lease: {}", e);
+        elector.step_down();
+    }
+}
+
+#[get("/admin/leader")]
+pub async fn leader_status(elector: web::Data<LeaderElector>) -> HttpResponse {
+    HttpResponse::Ok().json(elector.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_leader() {
+        let db = Database::new(":memory:").unwrap();
+        let a = LeaderElector::new("pod-a", BACKGROUND_JOBS_LEASE, Duration::from_secs(30));
+        let b = LeaderElector::new("pod-b", BACKGROUND_JOBS_LEASE, Duration::from_secs(30));
+
+        assert!(a.tick(&db));
+        assert!(!b.tick(&db));
+        assert!(a.is_leader());
+        assert!(!b.is_leader());
+    }
+
+    #[test]
+    fn test_step_down_until_renewed() {
+        let db = Database::new(":memory:").unwrap();
+        let a = LeaderElector::new("pod-a", BACKGROUND_JOBS_LEASE, Duration::from_secs(30));
+        assert!(a.tick(&db));
+
+        a.step_down();
+        assert!(!a.is_leader());
+        assert!(!a.status().is_leader);
+        // The lease is still held, the next renewal leads again
+        assert!(a.tick(&db));
+        assert!(a.is_leader());
+    }
+}
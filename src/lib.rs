@@ -1,14 +1,79 @@
+#![recursion_limit = "512"]
+
 // Re-export modules and types for use in tests
 pub mod db;
+pub mod migrations;
+pub mod country;
+pub mod countries;
+pub mod blackouts;
+pub mod breaker;
 pub mod openapi;
 pub mod holidays_api;
-
-use actix_web::{web, HttpResponse, post};
+pub mod holiday_cache;
+pub mod clients;
+pub mod holiday_import;
+pub mod holiday_audit;
+pub mod ics_import;
+pub mod ics_export;
+pub mod custom_holidays;
+pub mod recurring;
+pub mod easter;
+pub mod holiday_check;
+pub mod holiday_list;
+pub mod duplicates;
+pub mod calendar_config;
+pub mod calendar_resolver;
+pub mod calendar_versions;
+pub mod impact;
+pub mod examples;
+pub mod export;
+pub mod fields;
+pub mod freeze;
+pub mod freshness;
+pub mod granularity;
+pub mod trace;
+pub mod warnings;
+pub mod weekdays;
+pub mod events;
+pub mod chunked;
+pub mod consumer;
+pub mod holiday_provider;
+pub mod limits;
+pub mod leader;
+pub mod prefetch;
+pub mod presets;
+pub mod risk;
+pub mod coverage;
+pub mod country_groups;
+pub mod segments;
+pub mod staffing;
+pub mod follow_the_sun;
+pub mod oncall;
+pub mod overtime;
+pub mod capacity;
+pub mod deadline;
+pub mod aggregation;
+pub mod notice;
+pub mod prorata;
+pub mod rest;
+pub mod duration;
+pub mod timesheet;
+pub mod wtd;
+pub mod toil;
+pub mod schedules;
+pub mod saved_queries;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+
+use actix_web::{web, HttpResponse, get, post};
 use serde::{Serialize, Deserialize};
-use chrono::{DateTime, TimeZone, Datelike, NaiveDate};
+use chrono::{DateTime, TimeZone, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Offset, Timelike};
 use chrono_tz::Tz;
-use std::sync::Mutex;
+use std::collections::{btree_map, BTreeMap, HashSet};
 use actix_web::cookie::time::Time;
+use country::{CountryCode, Subdivision};
 
 // Global variables to store the start and end of day times
 
@@ -20,9 +85,9 @@ pub struct Holiday {
     pub description: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WorkHoursRequest {
-    #[serde(rename = "startDate")]
+    #[serde(rename = "startDate", deserialize_with = "deserialize_date_input")]
     pub start_date: String,
     #[serde(flatten)]
     #[serde(default)]
@@ -35,8 +100,92 @@ pub struct WorkHoursRequest {
     pub country: String,
     #[serde(default)]
     pub subdivision: Option<String>,
+    // Named calendar replacing the country, subdivision and hours above, its closure days are days off
+    #[serde(rename = "calendarId", default)]
+    pub calendar_id: Option<String>,
+    // Days off every week, taken from the calendar; Saturday and Sunday otherwise
+    #[serde(skip)]
+    pub weekend: Option<Vec<chrono::Weekday>>,
     #[serde(default)]
     pub timezone: String,
+    // How RFC3339 offsets relate to the timezone: convert the instant (default) or keep the local time
+    #[serde(rename = "timezoneMode", default)]
+    pub timezone_mode: TimezoneMode,
+    // Extra non-working dates (e.g. vacations) treated like holidays for this calculation
+    #[serde(rename = "excludeDates", default)]
+    pub exclude_dates: Vec<String>,
+    // Dates forced to count as working days, even on weekends or holidays
+    #[serde(rename = "includeDates", default)]
+    pub include_dates: Vec<String>,
+    // Split shifts (e.g. 08:00-12:00 and 14:00-18:00), replaces startOfDay/endOfDay when provided
+    #[serde(rename = "workingIntervals", default)]
+    pub working_intervals: Vec<WorkingInterval>,
+    // Calendar whose on-call rotation periods count as working time 24/7
+    #[serde(rename = "onCallCalendar", default)]
+    pub on_call_calendar: Option<String>,
+    // Calendar or employee whose effective-dated schedules replace the schedule above
+    #[serde(rename = "scheduleOwner", default)]
+    pub schedule_owner: Option<String>,
+    // Daily/weekly thresholds splitting the result into regular and overtime hours
+    #[serde(default)]
+    pub overtime: Option<overtime::OvertimeRules>,
+    // How durationSeconds is interpreted: wall-clock time or working time to consume
+    #[serde(rename = "durationType", default)]
+    pub duration_type: DurationType,
+    // Group the hours per day, ISO week or calendar month
+    #[serde(default)]
+    pub aggregate: Option<aggregation::Aggregate>,
+    // Return every counted working interval, not only the totals
+    #[serde(rename = "includeIntervals", default)]
+    pub include_intervals: bool,
+    // iso8601 adds work_duration next to the decimal fields, decimal leaves it out
+    #[serde(rename = "outputFormat", default)]
+    pub output_format: OutputFormat,
+    // Holiday provider for this calculation (openholidays, nager or database), the configured one otherwise
+    #[serde(rename = "holidaySource", default)]
+    pub holiday_source: Option<String>,
+    // ISO 639-1 language of the holiday names (fr, de...), HOLIDAY_LANGUAGE otherwise
+    #[serde(default)]
+    pub language: Option<String>,
+    // Holiday kinds counted as days off, public, regional and company ones otherwise
+    #[serde(rename = "holidayKinds", default)]
+    pub holiday_kinds: Option<Vec<db::HolidayKind>>,
+    // Reduced capacity over the first weeks from a date, e.g. onboarding at 50%
+    #[serde(default)]
+    pub ramp: Option<capacity::Ramp>,
+    // Adds the upstream holiday calls and cache decisions to the response
+    #[serde(default)]
+    pub explain: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum TimezoneMode {
+    #[default]
+    Convert,
+    AssumeLocal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Decimal,
+    #[default]
+    Iso8601,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationType {
+    #[default]
+    Wall,
+    Working,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WorkingInterval {
+    pub start: String,
+    pub end: String,
 }
 
 fn default_start_of_day() -> String {
@@ -47,19 +196,58 @@ fn default_end_of_day() -> String {
     "17:00:00".to_string()
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum EndOrDuration {
     EndDate { 
-        #[serde(alias = "endDate")]
+        #[serde(alias = "endDate", deserialize_with = "deserialize_date_input")]
         end_date: String 
     },
     Duration { 
-        #[serde(alias = "durationSeconds")]
+        #[serde(alias = "durationSeconds", alias = "duration", deserialize_with = "deserialize_duration")]
         duration_seconds: i64 
     },
 }
 
+// durationSeconds as a number of seconds, or duration as an ISO 8601 string (P3D, PT40H)
+fn deserialize_duration<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Seconds(i64),
+        Iso(String),
+    }
+    match Value::deserialize(deserializer)? {
+        Value::Seconds(seconds) => Ok(seconds),
+        Value::Iso(value) => duration::parse_iso8601(&value).map_err(serde::de::Error::custom),
+    }
+}
+
+// Dates may be sent as strings or as integer Unix timestamps, kept as text until parsed
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DateInput {
+    Epoch(i64),
+    Text(String),
+}
+
+impl From<DateInput> for String {
+    fn from(value: DateInput) -> Self {
+        match value {
+            DateInput::Epoch(epoch) => epoch.to_string(),
+            DateInput::Text(text) => text,
+        }
+    }
+}
+
+fn deserialize_date_input<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    DateInput::deserialize(deserializer).map(String::from)
+}
+
+fn deserialize_optional_date_input<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Option::<DateInput>::deserialize(deserializer).map(|value| value.map(String::from))
+}
+
 impl Default for EndOrDuration {
     fn default() -> Self {
         EndOrDuration::EndDate {
@@ -68,27 +256,105 @@ impl Default for EndOrDuration {
     }
 }
 
+// Same defaults as a deserialized request, the schedule included
+impl Default for WorkHoursRequest {
+    fn default() -> Self {
+        WorkHoursRequest {
+            start_date: String::new(),
+            end_or_duration: EndOrDuration::default(),
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: Default::default(),
+            subdivision: Default::default(),
+            calendar_id: Default::default(),
+            weekend: Default::default(),
+            timezone: Default::default(),
+            timezone_mode: Default::default(),
+            exclude_dates: Default::default(),
+            include_dates: Default::default(),
+            working_intervals: Default::default(),
+            on_call_calendar: Default::default(),
+            schedule_owner: Default::default(),
+            overtime: Default::default(),
+            duration_type: Default::default(),
+            aggregate: Default::default(),
+            include_intervals: Default::default(),
+            output_format: Default::default(),
+            holiday_source: Default::default(),
+            language: Default::default(),
+            holiday_kinds: Default::default(),
+            ramp: Default::default(),
+            explain: Default::default(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WorkHoursResponse {
     pub work_hours: f64,
     pub work_minutes: f64,
     pub work_seconds: f64,
+    // Exact total as an ISO 8601 duration, e.g. PT38H30M
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_duration: Option<String>,
     pub start_date: String,
     pub end_date: String,
+    #[serde(default)]
+    pub regular_hours: f64,
+    #[serde(default)]
+    pub overtime_hours: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breakdown: Option<Vec<aggregation::BreakdownEntry>>,
+    // Holidays that removed working time from the range
+    #[serde(rename = "holidaysApplied", default, skip_serializing_if = "Vec::is_empty")]
+    pub holidays_applied: Vec<AppliedHoliday>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intervals: Option<Vec<CountedInterval>>,
+    // Machine-readable notes on how the result was obtained, see GET /warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<warnings::Warning>,
+    // Source and fetch time of holidays older than DATA_FRESHNESS_MAX_AGE_SECONDS
+    #[serde(rename = "dataFreshness", default, skip_serializing_if = "Option::is_none")]
+    pub data_freshness: Option<freshness::DataFreshness>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explain: Option<trace::Explain>,
+}
+
+// A working block counted in the result, as RFC3339 instants
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CountedInterval {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AppliedHoliday {
+    pub date: String,
+    pub description: String,
+    // provider, database, excludeDates or calendar
+    pub source: String,
+    #[serde(default, skip_serializing_if = "db::HolidayPortion::is_full")]
+    pub portion: db::HolidayPortion,
 }
 
 pub struct AppState {
-    pub db: Mutex<db::Database>,
+    pub db: db::Database,
+    pub holidays: Box<dyn holiday_provider::HolidayProvider>,
+    // Shared by the upstream holiday providers, see holidays_api::client_from_env
+    pub http: reqwest::Client,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct WorkHoursQueryParams {
-    #[serde(rename = "startDate")]
+    #[serde(rename = "startDate", deserialize_with = "deserialize_date_input")]
     pub start_date: String,
-    #[serde(rename = "endDate")]
+    #[serde(rename = "endDate", default, deserialize_with = "deserialize_optional_date_input")]
     pub end_date: Option<String>,
     #[serde(rename = "durationSeconds")]
     pub duration_seconds: Option<i64>,
+    // ISO 8601 alternative to durationSeconds, e.g. P3D or PT40H
+    #[serde(default)]
+    pub duration: Option<String>,
     #[serde(rename = "startOfDay", default = "default_start_of_day")]
     pub start_of_day: String,
     #[serde(rename = "endOfDay", default = "default_end_of_day")]
@@ -97,14 +363,49 @@ pub struct WorkHoursQueryParams {
     pub country: String,
     #[serde(default)]
     pub timezone: String,
+    #[serde(rename = "timezoneMode", default)]
+    pub timezone_mode: TimezoneMode,
     #[serde(default)]
     pub subdivision: Option<String>,
+    #[serde(rename = "calendarId", default)]
+    pub calendar_id: Option<String>,
+    #[serde(rename = "excludeDates", default)]
+    pub exclude_dates: Vec<String>,
+    #[serde(rename = "includeDates", default)]
+    pub include_dates: Vec<String>,
+    #[serde(rename = "workingIntervals", default)]
+    pub working_intervals: Vec<WorkingInterval>,
+    #[serde(rename = "onCallCalendar", default)]
+    pub on_call_calendar: Option<String>,
+    #[serde(rename = "scheduleOwner", default)]
+    pub schedule_owner: Option<String>,
+    #[serde(default)]
+    pub overtime: Option<overtime::OvertimeRules>,
+    #[serde(rename = "durationType", default)]
+    pub duration_type: DurationType,
+    #[serde(default)]
+    pub aggregate: Option<aggregation::Aggregate>,
+    #[serde(rename = "includeIntervals", default)]
+    pub include_intervals: bool,
+    #[serde(rename = "outputFormat", default)]
+    pub output_format: OutputFormat,
+    #[serde(rename = "holidaySource", default)]
+    pub holiday_source: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(rename = "holidayKinds", default)]
+    pub holiday_kinds: Option<Vec<db::HolidayKind>>,
+    #[serde(default)]
+    pub ramp: Option<capacity::Ramp>,
+    #[serde(default)]
+    pub explain: bool,
 }
 
 #[post("/")]
 pub async fn get_work_hours(
     data: web::Data<AppState>,
     workhours: web::Json<WorkHoursQueryParams>,
+    fields: web::Query<fields::FieldsParams>,
 ) -> Result<HttpResponse, actix_web::error::Error> {
     log::debug!("Received work hours request: {:?}", workhours);
 
@@ -119,6 +420,10 @@ pub async fn get_work_hours(
             EndOrDuration::Duration { 
                 duration_seconds 
             }
+        } else if let Some(duration) = &workhours.duration {
+            EndOrDuration::Duration {
+                duration_seconds: duration::parse_iso8601(duration).map_err(actix_web::error::ErrorBadRequest)?
+            }
         } else {
             return Ok(HttpResponse::BadRequest().json("Either endDate or durationSeconds must be provided"));
         },
@@ -126,571 +431,2437 @@ pub async fn get_work_hours(
         end_of_day: workhours.end_of_day.clone(),
         country: workhours.country.clone(),
         timezone: workhours.timezone.clone(),
-        subdivision: workhours.subdivision.clone()
+        timezone_mode: workhours.timezone_mode,
+        subdivision: workhours.subdivision.clone(),
+        calendar_id: workhours.calendar_id.clone(),
+        weekend: None,
+        exclude_dates: workhours.exclude_dates.clone(),
+        include_dates: workhours.include_dates.clone(),
+        working_intervals: workhours.working_intervals.clone(),
+        on_call_calendar: workhours.on_call_calendar.clone(),
+        schedule_owner: workhours.schedule_owner.clone(),
+        overtime: workhours.overtime.clone(),
+        duration_type: workhours.duration_type,
+        aggregate: workhours.aggregate,
+        include_intervals: workhours.include_intervals,
+        output_format: workhours.output_format,
+        holiday_source: workhours.holiday_source.clone(),
+        language: workhours.language.clone(),
+        holiday_kinds: workhours.holiday_kinds.clone(),
+        ramp: workhours.ramp.clone(),
+        explain: workhours.explain,
     };
 
-    calculate_work_hours(data, web::Json(request)).await
+    let response = compute_and_publish(&data, &request).await?;
+    fields::respond(&response, fields.fields.as_deref())
 }
 
-pub async fn calculate_work_hours(
-    data: web::Data<AppState>,
-    req: web::Json<WorkHoursRequest>,
-) -> Result<HttpResponse, actix_web::error::Error> {
-    log::debug!("Processing work hours calculation: {:?}", req);
-    // Parse dates and convert to timezone-aware datetimes
-    let start_date = DateTime::parse_from_rfc3339(&req.start_date)
-        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid start date format: {}", e)))?;
+fn parse_time_of_day(value: &str, label: &str) -> Result<Time, actix_web::error::Error> {
     let time_format = actix_web::cookie::time::format_description::parse("[hour]:[minute]:[second]").unwrap();
-    let start_of_day = Time::parse(&req.start_of_day, &time_format)
-        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid start time format: {}", e)))?;
-    let end_of_day = Time::parse(&req.end_of_day, &time_format)
-        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid end time format: {}", e)))?;
-    let timezone: Tz = req.timezone.parse()
-        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone: {}", e)))?;
-
-    // Convert to timezone-aware datetime
-    let start_date = timezone.from_local_datetime(&start_date.naive_local()).unwrap();
+    Time::parse(value, &time_format)
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid {} time format: {}", label, e)))
+}
 
-    let (end_date, _duration_seconds) = match &req.end_or_duration {
-        EndOrDuration::EndDate { end_date } => {
-            let end_date = DateTime::parse_from_rfc3339(end_date)
-                .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid end date format: {}", e)))?;
-            let end_date = timezone.from_local_datetime(&end_date.naive_local()).unwrap();
-            (end_date, (end_date - start_date).num_seconds())
-        }
-        EndOrDuration::Duration { duration_seconds } => {
-            let end_date = start_date + chrono::Duration::seconds(*duration_seconds);
-            (end_date, *duration_seconds)
-        }
-    };
+fn to_naive_time(time: Time) -> NaiveTime {
+    NaiveTime::from_hms_opt(time.hour() as u32, time.minute() as u32, time.second() as u32).unwrap()
+}
 
-    // Validate that start date is strictly before end date
-    if start_date >= end_date {
-        return Err(actix_web::error::ErrorBadRequest("Start date must be strictly before end date"));
+// Time of a schedule, checked against or snapped to the deployment's granularity
+fn parse_schedule_time(value: &str, label: &str) -> Result<NaiveTime, actix_web::error::Error> {
+    let time = to_naive_time(parse_time_of_day(value, label)?);
+    match granularity::from_env() {
+        Some(granularity) => granularity.apply(time, label).map_err(actix_web::error::ErrorBadRequest),
+        None => Ok(time),
     }
+}
 
-    let country = req.country.to_lowercase();
-    let subdivision = req.subdivision.clone().unwrap_or(String::new());
-    let mut work_hours = 0.0;
+// Fetch holidays from the configured provider only, or from the one named by the request
+async fn fetch_provider_holidays(
+    data: &web::Data<AppState>,
+    country: &CountryCode,
+    subdivision: &str,
+    date: NaiveDate,
+    source: Option<&str>,
+) -> Result<Vec<holidays_api::Holiday>, String> {
+    match source {
+        Some(source) => {
+            let provider = holiday_provider::by_name(source, &data.http)
+                .ok_or_else(|| format!("Unknown holiday source: {}", source))?;
+            provider.holidays(&data.db, country, subdivision, date).await
+        }
+        None => data.holidays.holidays(&data.db, country, subdivision, date).await,
+    }
+}
 
+// Fetch holidays from the API, falling back to the database when the API is unavailable.
+// Requests without a country have no holidays.
+async fn load_holidays(
+    data: &web::Data<AppState>,
+    country: Option<&CountryCode>,
+    subdivision: &str,
+    date: NaiveDate,
+) -> Vec<db::Holiday> {
+    match country {
+        Some(country) => load_holidays_with_source(data, country, subdivision, date, None).await.0,
+        None => vec![],
+    }
+}
 
-    let mut current = start_date;
+// Same as load_holidays, also telling whether the provider or the database answered.
+async fn load_holidays_with_source(
+    data: &web::Data<AppState>,
+    country: &CountryCode,
+    subdivision: &str,
+    date: NaiveDate,
+    source: Option<&str>,
+) -> (Vec<db::Holiday>, &'static str) {
+    load_holidays_until(data, country, subdivision, date, date.year() + 1, source).await
+}
 
-    // Fetch holidays from API instead of database
-    let holidays = if cfg!(test) {
-        // In test mode, use the mock implementation
-        match holidays_api::mock::get_holidays_for_country(&country, &subdivision).await {
-            Ok(api_holidays) => {
-                // Convert API holidays to the format expected by the work hours calculation
-                holidays_api::convert_to_db_holiday(api_holidays, &country)
-            },
-            Err(e) => {
-                // Log the error and fall back to database
-                log::error!("Failed to fetch holidays from mock API: {}. Falling back to database.", e);
-                let db = data.db.lock().unwrap();
-                db.get_holidays_by_country(&country).unwrap_or(vec![])
-            }
+// Same as load_holidays_with_source, with recurring custom holidays up to the last year of a calculation.
+// A country group loads the national holidays of each of its countries: a day off in one is off for the group.
+async fn load_holidays_until(
+    data: &web::Data<AppState>,
+    country: &CountryCode,
+    subdivision: &str,
+    date: NaiveDate,
+    last_year: i32,
+    source: Option<&str>,
+) -> (Vec<db::Holiday>, &'static str) {
+    let members = {
+        let group = country.to_string();
+        data.db.blocking(move |db| Ok(country_groups::resolve(db, &group))).await.ok().flatten()
+    };
+    let members = match members {
+        Some(members) => members,
+        None => return load_country_holidays(data, country, subdivision, date, last_year, source).await,
+    };
+    let mut merged: Vec<db::Holiday> = vec![];
+    let mut merged_source = "provider";
+    for member in members.iter().filter_map(|member| CountryCode::parse(member).ok()) {
+        let (holidays, member_source) = load_country_holidays(data, &member, "", date, last_year, source).await;
+        if member_source != "provider" {
+            merged_source = member_source;
         }
-    } else {
-        // In production mode, use the real implementation
-        match holidays_api::get_holidays_for_country(&country, &subdivision, current.date_naive()).await {
-            Ok(api_holidays) => {
-                // Convert API holidays to the format expected by the work hours calculation
-                holidays_api::convert_to_db_holiday(api_holidays, &country)
-            },
-            Err(e) => {
-                // Log the error and fall back to database
-                log::error!("Failed to fetch holidays from API: {}. Falling back to database.", e);
-                let db = data.db.lock().unwrap();
-                db.get_holidays_by_country(&country).unwrap_or(vec![])
+        for holiday in holidays {
+            let description = format!("{} ({})", holiday.description, member);
+            match merged.iter_mut().find(|existing| existing.date == holiday.date) {
+                Some(existing) => {
+                    existing.description = format!("{}, {}", existing.description, description);
+                    existing.portion = existing.portion.combine(holiday.portion);
+                }
+                None => merged.push(db::Holiday { id: None, date: holiday.date, description, country: country.to_string(), portion: holiday.portion, kind: holiday.kind, subdivision: None }),
             }
         }
-    };
+    }
+    (merged, merged_source)
+}
 
-    while current.date_naive() <= end_date.date_naive() {
-        // Skip weekends and holidays
-        if current.weekday() == chrono::Weekday::Sat || current.weekday() == chrono::Weekday::Sun {
-            current = current + chrono::Duration::days(1);
-            continue;
+async fn load_country_holidays(
+    data: &web::Data<AppState>,
+    country: &CountryCode,
+    subdivision: &str,
+    date: NaiveDate,
+    last_year: i32,
+    source: Option<&str>,
+) -> (Vec<db::Holiday>, &'static str) {
+    // Providers return two years at a time, later windows start on January 1st and only keep
+    // their own years, as the stored fallback returns every year at once
+    let mut holidays = vec![];
+    let mut holiday_source = "provider";
+    let mut window = date;
+    loop {
+        let next_year = window.year() + 2;
+        let (dated, window_source) = load_dated_holidays(data, country, subdivision, window, source).await;
+        if window_source != "provider" {
+            holiday_source = window_source;
         }
-        // Check if current date is a holiday
-        let is_holiday = {
-            holidays.iter().any(|h| {
-                println!("raw date from open data: {:?}", h.date);
-                let h_date = NaiveDate::parse_from_str(&h.date[..10], "%Y-%m-%d").unwrap();
-                h_date == current.date_naive()
-            })
-        };
+        let first = window == date;
+        let last = next_year > last_year;
+        holidays.extend(dated.into_iter().filter(|holiday| match holiday_date(holiday) {
+            Some(day) => (first || day.year() >= window.year()) && (last || day.year() < next_year),
+            None => first,
+        }));
+        match NaiveDate::from_ymd_opt(next_year, 1, 1) {
+            Some(next) if !last => window = next,
+            _ => break,
+        }
+    }
+    // Recurring custom holidays, for at least the two years the providers return
+    let recurring = {
+        let country = country.clone();
+        data.db.blocking(move |db| db.get_recurring_holidays(&country)).await.unwrap_or_default()
+    };
+    holidays.extend(recurring::expand(&recurring, date.year()..=last_year.max(date.year() + 1)));
+    (holidays, holiday_source)
+}
 
-        if is_holiday {
-            current = current + chrono::Duration::days(1);
-            continue;
+async fn load_dated_holidays(
+    data: &web::Data<AppState>,
+    country: &CountryCode,
+    subdivision: &str,
+    date: NaiveDate,
+    source: Option<&str>,
+) -> (Vec<db::Holiday>, &'static str) {
+    // A subdivision the providers rejected falls back to the nationwide stored holidays
+    let stored_subdivision = Subdivision::parse_optional(country, subdivision).ok().flatten();
+    // Frozen data is the stored copy, never refreshed from upstream until the freeze ends
+    if let Some(frozen) = data.db.blocking(|db| Ok(freeze::active(db))).await.ok().flatten() {
+        let (holidays, fetched_at) = stored_holidays(data, country, stored_subdivision, date).await;
+        trace::record(trace::UpstreamCall {
+            provider: "database".to_string(),
+            error: Some(format!("Holiday data frozen until {}", frozen.until)),
+            fetched_at,
+            ..Default::default()
+        });
+        return (holidays, "frozen");
+    }
+    match fetch_provider_holidays(data, country, subdivision, date, source).await {
+        Ok(api_holidays) => {
+            // Convert API holidays to the format expected by the work hours calculation
+            (holidays_api::convert_to_db_holiday(api_holidays, country), "provider")
+        },
+        Err(e) => {
+            // Log the error and fall back to database
+            log::error!("Failed to fetch holidays from API: {}. Falling back to database.", e);
+            let (holidays, fetched_at) = stored_holidays(data, country, stored_subdivision, date).await;
+            trace::record(trace::UpstreamCall {
+                provider: "database".to_string(),
+                error: Some(format!("Fallback after provider error: {}", e)),
+                fetched_at,
+                ..Default::default()
+            });
+            (holidays, "database")
         }
+    }
+}
 
-        // Get start and end of day times from global variables
+// Stored holidays of the country, with the oldest fetch among the two years the providers return
+async fn stored_holidays(
+    data: &web::Data<AppState>,
+    country: &CountryCode,
+    subdivision: Option<Subdivision>,
+    date: NaiveDate,
+) -> (Vec<db::Holiday>, Option<String>) {
+    let country = country.clone();
+    data.db.blocking(move |db| Ok((
+        db.get_holidays_by_country(&country, subdivision.as_ref()).unwrap_or_default(),
+        db.get_fetched_at(&country, date.year()..=date.year() + 1).ok().flatten(),
+    ))).await.unwrap_or_default()
+}
 
-        let full_day = (end_of_day - start_of_day).as_seconds_f64() / 3600.0;
+// Working intervals of a day, either the explicit list or the startOfDay/endOfDay window
+fn resolve_intervals(req: &WorkHoursRequest) -> Result<Vec<(NaiveTime, NaiveTime)>, actix_web::error::Error> {
+    resolve_schedule(&req.start_of_day, &req.end_of_day, &req.working_intervals)
+}
 
-        // Create start and end of day datetimes
-        let start_of_day = timezone.from_local_datetime(&current.date_naive().and_hms_opt(start_of_day.hour() as u32, start_of_day.minute() as u32, start_of_day.second() as u32).unwrap()).unwrap();
-        let end_of_day = timezone.from_local_datetime(&current.date_naive().and_hms_opt(end_of_day.hour() as u32, end_of_day.minute() as u32, end_of_day.second() as u32).unwrap()).unwrap();
-        if current.date_naive() == start_date.date_naive() && current.date_naive() == end_date.date_naive() {
-            if start_date.time() > end_of_day.time() || end_date.time() < start_of_day.time()   {
-                current = current + chrono::Duration::days(1);
-                continue;
-            }
-            let effective_start = if start_date.time() < start_of_day.time() {
-                start_of_day
-            } else {
-                start_date
-            };
-            let effective_end = if end_date.time() > end_of_day.time() {
-                end_of_day
-            } else {
-                end_date
-            };
-            work_hours += effective_end.signed_duration_since(effective_start).num_seconds() as f64 / 3600.0;
-        }
-        else if current.date_naive() == start_date.date_naive() {
-            // Start date - special case for standard start time
-            if start_date.time() >= end_of_day.time()   {
-                current = current + chrono::Duration::days(1);
-                continue;
-            }
-            // Partial start day
-            let effective_start = if start_date.time() < start_of_day.time() {
-                start_of_day
-            } else {
-                start_date
-            };
+fn resolve_schedule(
+    start_of_day: &str,
+    end_of_day: &str,
+    working_intervals: &[WorkingInterval],
+) -> Result<Vec<(NaiveTime, NaiveTime)>, actix_web::error::Error> {
+    let mut intervals = if working_intervals.is_empty() {
+        vec![(
+            parse_schedule_time(start_of_day, "start")?,
+            parse_schedule_time(end_of_day, "end")?,
+        )]
+    } else {
+        working_intervals.iter()
+            .map(|interval| Ok((
+                parse_schedule_time(&interval.start, "interval start")?,
+                parse_schedule_time(&interval.end, "interval end")?,
+            )))
+            .collect::<Result<Vec<_>, actix_web::error::Error>>()?
+    };
 
-            work_hours += end_of_day.signed_duration_since(effective_start).num_seconds() as f64 / 3600.0
+    intervals.sort();
+    for (start, end) in &intervals {
+        if start == end {
+            return Err(actix_web::error::ErrorBadRequest(format!("Invalid working interval: {} - {}", start, end)));
         }
-        else if current.date_naive() == end_date.date_naive() {
-            // End date - special case for standard end time
-            if end_date.time() < start_of_day.time()    {
-                current = current + chrono::Duration::days(1);
-                continue;
-            }
-            let effective_end = if end_date.time() > end_of_day.time() {
-                end_of_day
-            } else {
-                end_date
-            };
+    }
 
-            work_hours += effective_end.signed_duration_since(start_of_day).num_seconds() as f64 / 3600.0
-        }
+    // Compare spans in seconds from the anchor day midnight, overnight shifts end the next day
+    let spans: Vec<(u32, u32)> = intervals.iter()
+        .map(|(start, end)| {
+            let start_seconds = start.num_seconds_from_midnight();
+            let mut end_seconds = end.num_seconds_from_midnight();
+            if is_overnight(*start, *end) {
+                end_seconds += 24 * 3600;
+            }
+            (start_seconds, end_seconds)
+        })
+        .collect();
+    let wraps_into_next_day = spans.last().is_some_and(|last| last.1 > spans[0].0 + 24 * 3600);
+    if spans.windows(2).any(|w| w[0].1 > w[1].0) || wraps_into_next_day {
+        return Err(actix_web::error::ErrorBadRequest("Working intervals must not overlap"));
+    }
+    Ok(intervals)
+}
 
-        else {
-            // Full workday - always 8 hours
-            work_hours += full_day;
-        }
+// Longest known wall-clock jump, Samoa skipped a whole day when crossing the date line
+const MAX_GAP_MINUTES: i64 = 24 * 60;
 
-        current = current + chrono::Duration::days(1);
+// Map a wall-clock time to an instant without failing on daylight saving changes:
+// a time repeated when clocks fall back is the first occurrence (the earlier instant),
+// and a time skipped when clocks spring forward is the first valid wall-clock minute
+// after the gap, i.e. the instant of the transition.
+pub fn resolve_local(timezone: &Tz, local: NaiveDateTime) -> DateTime<Tz> {
+    if let Some(instant) = timezone.from_local_datetime(&local).earliest() {
+        return instant;
     }
+    let minute = local.with_second(0).and_then(|l| l.with_nanosecond(0)).unwrap_or(local);
+    (1..=MAX_GAP_MINUTES)
+        .find_map(|step| timezone.from_local_datetime(&(minute + chrono::Duration::minutes(step))).earliest())
+        .unwrap_or_else(|| timezone.from_utc_datetime(&local))
+}
 
-    Ok(HttpResponse::Ok().json(WorkHoursResponse {
-        work_hours,
-        work_minutes: work_hours * 60.0,
-        work_seconds: work_hours * 3600.0,
-        start_date: start_date.to_rfc3339(),
-        end_date: end_date.to_rfc3339(),
-    }))
+// Integer Unix timestamps, in milliseconds when too large to be seconds
+fn parse_epoch(value: &str) -> Option<DateTime<chrono::Utc>> {
+    let epoch: i64 = value.parse().ok()?;
+    if epoch.abs() >= 100_000_000_000 {
+        chrono::Utc.timestamp_millis_opt(epoch).single()
+    } else {
+        chrono::Utc.timestamp_opt(epoch, 0).single()
+    }
 }
 
-// Unit tests for the library
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use actix_web::web;
-    use holidays_api::mock as holidays_api_mock;
+// Accept RFC3339 timestamps, Unix epochs and plain YYYY-MM-DD dates, which snap to the
+// schedule through the given function. RFC3339 offsets are honoured in convert mode, while
+// assumeLocal keeps the wall-clock time and reads it in the request timezone.
+fn parse_request_date(
+    value: &str,
+    label: &str,
+    timezone: Tz,
+    mode: TimezoneMode,
+    snap: impl Fn(NaiveDate) -> NaiveDateTime,
+) -> Result<DateTime<Tz>, actix_web::error::Error> {
+    parse_request_date_with_warnings(value, label, timezone, mode, snap, &mut vec![])
+}
 
-    #[test]
-    fn test_end_or_duration_deserialization() {
-        // Test EndDate variant
-        let json = r#"{"end_date": "2023-10-06T17:00:00Z"}"#;
-        let end_or_duration: EndOrDuration = serde_json::from_str(json).unwrap();
-        match end_or_duration {
-            EndOrDuration::EndDate { end_date } => assert_eq!(end_date, "2023-10-06T17:00:00Z"),
-            _ => panic!("Expected EndDate variant"),
+// Same as parse_request_date, also reporting local times moved by a daylight saving change
+fn parse_request_date_with_warnings(
+    value: &str,
+    label: &str,
+    timezone: Tz,
+    mode: TimezoneMode,
+    snap: impl Fn(NaiveDate) -> NaiveDateTime,
+    warnings: &mut Vec<warnings::Warning>,
+) -> Result<DateTime<Tz>, actix_web::error::Error> {
+    if let Some(instant) = parse_epoch(value) {
+        return Ok(instant.with_timezone(&timezone));
+    }
+    let local = match (NaiveDate::parse_from_str(value, "%Y-%m-%d"), DateTime::parse_from_rfc3339(value)) {
+        (Ok(date), _) => snap(date),
+        (Err(_), Ok(instant)) => match mode {
+            TimezoneMode::Convert => return Ok(instant.with_timezone(&timezone)),
+            TimezoneMode::AssumeLocal => instant.naive_local(),
+        },
+        (Err(_), Err(e)) => {
+            return Err(actix_web::error::ErrorBadRequest(format!("Invalid {} date format: {}", label, e)));
         }
+    };
+    warnings.extend(warnings::local_time_warning(&timezone, local, label));
+    Ok(resolve_local(&timezone, local))
+}
 
-        // Test Duration variant
-        let json = r#"{"duration_seconds": 86400}"#;
-        let end_or_duration: EndOrDuration = serde_json::from_str(json).unwrap();
-        match end_or_duration {
-            EndOrDuration::Duration { duration_seconds } => assert_eq!(duration_seconds, 86400),
-            _ => panic!("Expected Duration variant"),
+type Intervals = Vec<(DateTime<Tz>, DateTime<Tz>)>;
+
+// Split a day's working intervals after the given number of working seconds
+fn split_working_time(intervals: Intervals, seconds: i64) -> (Intervals, Intervals) {
+    let mut remaining = seconds;
+    let mut first = vec![];
+    let mut second = vec![];
+    for (start, end) in intervals {
+        let length = (end - start).num_seconds();
+        if remaining >= length {
+            first.push((start, end));
+        } else if remaining > 0 {
+            let cut = start + chrono::Duration::seconds(remaining);
+            first.push((start, cut));
+            second.push((cut, end));
+        } else {
+            second.push((start, end));
         }
+        remaining = (remaining - length).max(0);
     }
+    (first, second)
+}
 
-    #[test]
-    fn test_holiday_serialization() {
-        let holiday = Holiday {
-            date: "2023-12-25T00:00:00Z".to_string(),
-            description: "Christmas".to_string(),
-        };
+// Working intervals left by a half-day holiday: the morning removes the first half of the
+// day's working time and the afternoon the second half, lunch breaks are not counted
+fn half_day_intervals(intervals: Vec<(DateTime<Tz>, DateTime<Tz>)>, portion: db::HolidayPortion) -> Vec<(DateTime<Tz>, DateTime<Tz>)> {
+    let total: i64 = intervals.iter().map(|(start, end)| (*end - *start).num_seconds()).sum();
+    let (first_half, second_half) = split_working_time(intervals, total / 2);
+    match portion {
+        db::HolidayPortion::Full => vec![],
+        db::HolidayPortion::Morning => second_half,
+        db::HolidayPortion::Afternoon => first_half,
+    }
+}
 
-        let json = serde_json::to_string(&holiday).unwrap();
-        let expected = r#"{"date":"2023-12-25T00:00:00Z","description":"Christmas"}"#;
-        assert_eq!(json, expected);
+// Plain start dates begin with the first working interval of the day
+fn start_of_schedule(date: NaiveDate, intervals: &[(NaiveTime, NaiveTime)]) -> NaiveDateTime {
+    date.and_time(intervals[0].0)
+}
 
-        let deserialized: Holiday = serde_json::from_str(&json).unwrap();
-        assert_eq!(deserialized.date, holiday.date);
-        assert_eq!(deserialized.description, holiday.description);
+// Plain end dates finish with the last working interval, on the next day for overnight shifts
+fn end_of_schedule(date: NaiveDate, intervals: &[(NaiveTime, NaiveTime)]) -> NaiveDateTime {
+    let (start, end) = intervals[intervals.len() - 1];
+    if is_overnight(start, end) {
+        (date + chrono::Duration::days(1)).and_time(end)
+    } else {
+        date.and_time(end)
     }
+}
 
-    // Helper function to create a test database with holidays
-    fn create_test_db_with_holidays(holidays: Vec<(String, String, String)>) -> web::Data<AppState> {
-        let db = db::Database::new(":memory:").unwrap();
+// An interval ending before it starts spans midnight (e.g. 22:00-06:00)
+fn is_overnight(start: NaiveTime, end: NaiveTime) -> bool {
+    end < start
+}
 
-        for (date, description, country) in holidays {
-            let holiday = db::Holiday {
-                id: None,
-                date,
-                description,
-                country,
-            };
-            db.add_holiday(&holiday).unwrap();
+// Merge overlapping or adjacent intervals
+pub fn merge_intervals<T: Ord + Copy>(mut intervals: Vec<(T, T)>) -> Vec<(T, T)> {
+    intervals.sort();
+    let mut merged: Vec<(T, T)> = vec![];
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
         }
+    }
+    merged
+}
 
-        web::Data::new(AppState {
-            db: Mutex::new(db),
+// Parse a list of YYYY-MM-DD dates, RFC3339 timestamps are truncated to their date
+fn parse_dates(values: &[String], label: &str) -> Result<Vec<NaiveDate>, actix_web::error::Error> {
+    values.iter()
+        .map(|value| {
+            value.get(..10)
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Invalid {} date: {}", label, value)))
         })
-    }
+        .collect()
+}
 
-    #[actix_rt::test]
-    async fn test_calculate_work_hours_weekday() {
-        // Test a single workday (Monday)
-        // 8 hours of work (9am to 5pm)
-        let db_data = create_test_db_with_holidays(vec![]);
+fn is_holiday(date: NaiveDate, holidays: &[db::Holiday]) -> bool {
+    holiday_on(date, holidays).is_some()
+}
 
-        let request = WorkHoursRequest {
-            start_date: "2023-10-02T09:00:00Z".to_string(),
-            end_or_duration: EndOrDuration::EndDate { 
-                end_date: "2023-10-02T17:00:00Z".to_string() 
-            },
-            start_of_day: default_start_of_day(),
-            end_of_day: default_end_of_day(),
-            country: "us".to_string(),
-            timezone: "UTC".to_string(),
-            subdivision: None,
-        };
+// Rows whose date cannot be read are skipped rather than failing the request
+fn holiday_on(date: NaiveDate, holidays: &[db::Holiday]) -> Option<&db::Holiday> {
+    holidays.iter().find(|h| holiday_date(h) == Some(date))
+}
 
-        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
-        let body = result.into_body();
-        let bytes = actix_web::body::to_bytes(body).await.unwrap();
-        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+fn holiday_date(holiday: &db::Holiday) -> Option<NaiveDate> {
+    holiday.date.get(..10).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+}
 
-        assert_eq!(response.work_hours, 8.0);
+// Holidays off on a day, bank holidays and observances leave it a working day as in calculations
+fn days_off_on(date: NaiveDate, holidays: &[db::Holiday]) -> impl Iterator<Item = &db::Holiday> {
+    holidays.iter().filter(move |h| db::HolidayKind::DAYS_OFF.contains(&h.kind) && holiday_date(h) == Some(date))
+}
+
+// Part of the day taken off, None when no holiday is off that day
+fn day_off_portion(date: NaiveDate, holidays: &[db::Holiday]) -> Option<db::HolidayPortion> {
+    days_off_on(date, holidays).map(|h| h.portion).reduce(db::HolidayPortion::combine)
+}
+
+// Holiday taking the whole day off, days with a half-day holiday stay working days
+fn day_off_on(date: NaiveDate, holidays: &[db::Holiday]) -> Option<&db::Holiday> {
+    match day_off_portion(date, holidays) {
+        Some(portion) if portion.is_full() => days_off_on(date, holidays).next(),
+        _ => None,
     }
+}
 
-    #[actix_rt::test]
-    async fn test_calculate_work_hours_weekend() {
-        // Test a weekend (Saturday and Sunday)
-        // 0 hours of work
-        let db_data = create_test_db_with_holidays(vec![]);
+// A working day is a weekday that is not a day off
+fn is_working_day(date: NaiveDate, holidays: &[db::Holiday]) -> bool {
+    date.weekday() != chrono::Weekday::Sat
+        && date.weekday() != chrono::Weekday::Sun
+        && day_off_on(date, holidays).is_none()
+}
 
-        let request = WorkHoursRequest {
-            start_date: "2023-10-07T09:00:00Z".to_string(), // Saturday
-            end_or_duration: EndOrDuration::EndDate { 
-                end_date: "2023-10-08T17:00:00Z".to_string() // Sunday
-            },
-            start_of_day: default_start_of_day(),
-            end_of_day: default_end_of_day(),
-            country: "us".to_string(),
-            timezone: "UTC".to_string(),
-            subdivision: None,
-        };
+pub async fn calculate_work_hours(
+    data: web::Data<AppState>,
+    req: web::Json<WorkHoursRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    Ok(HttpResponse::Ok().json(compute_and_publish(&data, &req).await?))
+}
 
-        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
-        let body = result.into_body();
-        let bytes = actix_web::body::to_bytes(body).await.unwrap();
-        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+async fn compute_and_publish(
+    data: &web::Data<AppState>,
+    req: &WorkHoursRequest,
+) -> Result<WorkHoursResponse, actix_web::error::Error> {
+    let response = compute_work_hours(data, req).await?;
+    events::publish(events::Event::CalculationPerformed {
+        start_date: response.start_date.clone(),
+        end_date: response.end_date.clone(),
+        // Already validated by the calculation
+        country: CountryCode::parse_optional(&req.country).ok().flatten().map(String::from).unwrap_or_default(),
+        work_seconds: response.work_seconds,
+    });
+    Ok(response)
+}
 
-        assert_eq!(response.work_hours, 0.0);
+// Working intervals counted for a request, in the request timezone
+#[derive(Debug)]
+pub struct WorkingTime {
+    pub start_date: DateTime<Tz>,
+    pub end_date: DateTime<Tz>,
+    pub intervals: Vec<(DateTime<Tz>, DateTime<Tz>)>,
+    pub holidays_applied: Vec<AppliedHoliday>,
+    // Seconds of the full days counted in closed form, not listed in intervals
+    pub bulk_seconds: i64,
+    pub warnings: Vec<warnings::Warning>,
+}
+
+impl WorkingTime {
+    pub fn work_hours(&self) -> f64 {
+        self.work_seconds() as f64 / 3600.0
     }
 
-    #[actix_rt::test]
-    async fn test_calculate_work_hours_with_holiday() {
-        // Test a workweek with a holiday
+    pub fn work_seconds(&self) -> i64 {
+        self.bulk_seconds + self.intervals.iter()
+            .map(|(start, end)| end.signed_duration_since(*start).num_seconds())
+            .sum::<i64>()
+    }
+}
+
+// Core of the work hours calculation, shared by every endpoint
+pub async fn compute_work_hours(
+    data: &web::Data<AppState>,
+    req: &WorkHoursRequest,
+) -> Result<WorkHoursResponse, actix_web::error::Error> {
+    let max_age = freshness::max_age();
+    if !req.explain && max_age.is_none() {
+        return compute_response(data, req).await;
+    }
+    let (response, upstream_calls) = trace::collect(compute_response(data, req)).await;
+    let mut response = response?;
+    if let Some(max_age) = max_age {
+        response.data_freshness = freshness::watermark(&upstream_calls, max_age, chrono::Utc::now());
+        if let Some(data_freshness) = response.data_freshness.as_ref().filter(|_| freshness::warning_enabled()) {
+            response.warnings.push(freshness::warning(data_freshness));
+        }
+    }
+    if req.explain {
+        response.explain = Some(trace::Explain {
+            holiday_provider: req.holiday_source.clone().unwrap_or_else(|| data.holidays.name().to_string()),
+            upstream_calls,
+        });
+    }
+    Ok(response)
+}
+
+async fn compute_response(
+    data: &web::Data<AppState>,
+    req: &WorkHoursRequest,
+) -> Result<WorkHoursResponse, actix_web::error::Error> {
+    let inferred = infer_timezone(req);
+    let req = inferred.as_ref().map_or(req, |(inferred, _)| inferred);
+    let calendar = calendar_resolver::apply(data, req).await?;
+    let req = calendar.as_ref().unwrap_or(req);
+    let resolved = resolve_working_duration(data, req).await?;
+    let req = resolved.as_ref().unwrap_or(req);
+    // Every interval is only needed for breakdowns, overtime rules and the interval listing
+    let needs_intervals = req.aggregate.is_some() || req.overtime.is_some() || req.include_intervals;
+    let working_time = if needs_intervals {
+        compute_working_time(data, req).await?
+    } else {
+        compute_working_totals(data, req).await?
+    };
+    let work_hours = working_time.work_hours();
+    let (regular_hours, overtime_hours) = match &req.overtime {
+        Some(rules) => overtime::split(&working_time, rules),
+        None => (work_hours, 0.0),
+    };
+
+    Ok(WorkHoursResponse {
+        work_hours,
+        work_minutes: work_hours * 60.0,
+        work_seconds: work_hours * 3600.0,
+        work_duration: match req.output_format {
+            OutputFormat::Iso8601 => Some(duration::format_iso8601(working_time.work_seconds())),
+            OutputFormat::Decimal => None,
+        },
+        start_date: working_time.start_date.to_rfc3339(),
+        end_date: working_time.end_date.to_rfc3339(),
+        regular_hours,
+        overtime_hours,
+        breakdown: req.aggregate.map(|aggregate| aggregation::breakdown(&working_time, aggregate)),
+        intervals: req.include_intervals.then(|| {
+            working_time.intervals.iter()
+                .map(|(start, end)| CountedInterval { start: start.to_rfc3339(), end: end.to_rfc3339() })
+                .collect()
+        }),
+        holidays_applied: working_time.holidays_applied,
+        warnings: inferred.map(|(_, warning)| warning).into_iter().chain(working_time.warnings).collect(),
+        data_freshness: None,
+        explain: None,
+    })
+}
+
+// Requests without a timezone whose RFC3339 dates share a whole-hour offset are computed in
+// that fixed offset, any other request without a timezone is left to fail as invalid
+fn infer_timezone(req: &WorkHoursRequest) -> Option<(WorkHoursRequest, warnings::Warning)> {
+    if !req.timezone.is_empty() {
+        return None;
+    }
+    let offset = DateTime::parse_from_rfc3339(&req.start_date).ok()?.offset().local_minus_utc();
+    if let EndOrDuration::EndDate { end_date } = &req.end_or_duration {
+        if DateTime::parse_from_rfc3339(end_date).ok()?.offset().local_minus_utc() != offset {
+            return None;
+        }
+    }
+    let timezone = fixed_offset_timezone(offset)?;
+    let warning = warnings::Warning::new(
+        warnings::WarningCode::TimezoneInferredFromOffset,
+        format!("No timezone was given, the offset {} of the dates was used as {}", chrono::FixedOffset::east_opt(offset)?, timezone),
+    );
+    let mut inferred = req.clone();
+    inferred.timezone = timezone.name().to_string();
+    Some((inferred, warning))
+}
+
+// Etc/GMT zones count hours west of Greenwich: +02:00 is Etc/GMT-2
+fn fixed_offset_timezone(offset_seconds: i32) -> Option<Tz> {
+    if offset_seconds % 3600 != 0 {
+        return None;
+    }
+    match offset_seconds / 3600 {
+        0 => Some(Tz::UTC),
+        hours => format!("Etc/GMT{:+}", -hours).parse().ok(),
+    }
+}
+
+// Turn a working-time duration into an explicit end date, None for every other request
+async fn resolve_working_duration(
+    data: &web::Data<AppState>,
+    req: &WorkHoursRequest,
+) -> Result<Option<WorkHoursRequest>, actix_web::error::Error> {
+    let duration_seconds = match (&req.end_or_duration, req.duration_type) {
+        (EndOrDuration::Duration { duration_seconds }, DurationType::Working) => *duration_seconds,
+        _ => return Ok(None),
+    };
+    if duration_seconds <= 0 {
+        return Err(actix_web::error::ErrorBadRequest("Start date must be strictly before end date"));
+    }
+
+    let timezone: Tz = req.timezone.parse()
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone: {}", e)))?;
+    let intervals = resolve_intervals(req)?;
+    let start_date = parse_request_date(&req.start_date, "start", timezone, req.timezone_mode, |date| start_of_schedule(date, &intervals))?;
+
+    let end_date = deadline::add_working_seconds(data, req, start_date, duration_seconds).await?;
+    let mut resolved = req.clone();
+    resolved.end_or_duration = EndOrDuration::EndDate { end_date: end_date.to_rfc3339() };
+    Ok(Some(resolved))
+}
+
+// Ranges longer than this are rejected, POST /chunked computes them year by year
+const DEFAULT_MAX_RANGE_YEARS: u32 = 50;
+
+pub fn max_range_years() -> u32 {
+    std::env::var("MAX_RANGE_YEARS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_RANGE_YEARS)
+}
+
+fn check_range(start_date: DateTime<Tz>, end_date: DateTime<Tz>) -> Result<(), actix_web::error::Error> {
+    let years = max_range_years();
+    let limit = start_date.date_naive().checked_add_months(chrono::Months::new(years * 12));
+    if limit.is_some_and(|limit| end_date.date_naive() > limit) {
+        return Err(actix_web::error::ErrorUnprocessableEntity(format!(
+            "Date range {} - {} exceeds the maximum of {} years, use POST /chunked for longer ranges",
+            start_date.to_rfc3339(), end_date.to_rfc3339(), years
+        )));
+    }
+    Ok(())
+}
+
+const DEFAULT_WEEKEND: [chrono::Weekday; 2] = [chrono::Weekday::Sat, chrono::Weekday::Sun];
+
+// Days outside the weekend between two dates, both included
+fn weekdays_between(from: NaiveDate, to: NaiveDate, weekend: &[chrono::Weekday]) -> i64 {
+    if to < from {
+        return 0;
+    }
+    let days = (to - from).num_days() + 1;
+    // Whether each day of the week starting on the first date is worked
+    let week: Vec<bool> = std::iter::successors(Some(from.weekday()), |day| Some(day.succ()))
+        .take(7)
+        .map(|day| !weekend.contains(&day))
+        .collect();
+    let worked = |days: &[bool]| days.iter().filter(|worked| **worked).count() as i64;
+    days / 7 * worked(&week) + worked(&week[..(days % 7) as usize])
+}
+
+// Local dates on which the UTC offset of the timezone changes. Offsets are sampled
+// weekly and each change is located by bisection, zones change at most once a week.
+fn offset_change_dates(from: DateTime<Tz>, to: DateTime<Tz>) -> Vec<NaiveDate> {
+    let offset_at = |instant: DateTime<Tz>| instant.offset().fix();
+    let mut dates = vec![];
+    let mut cursor = from;
+    while cursor < to {
+        let next = (cursor + chrono::Duration::weeks(1)).min(to);
+        if offset_at(cursor) != offset_at(next) {
+            let (mut before, mut after) = (cursor, next);
+            while after - before > chrono::Duration::seconds(1) {
+                let middle = before + (after - before) / 2;
+                if offset_at(middle) == offset_at(before) {
+                    before = middle;
+                } else {
+                    after = middle;
+                }
+            }
+            // The change may move the local date, e.g. clocks going back at midnight
+            dates.push(before.date_naive());
+            dates.push(after.date_naive());
+        }
+        cursor = next;
+    }
+    dates
+}
+
+// Resolve the working intervals between the start and end of a request
+pub async fn compute_working_time(
+    data: &web::Data<AppState>,
+    req: &WorkHoursRequest,
+) -> Result<WorkingTime, actix_web::error::Error> {
+    working_time(data, req, false).await
+}
+
+// Same working time, with the full days inside the range counted in closed form
+// instead of being listed as intervals: only the totals are meaningful
+pub async fn compute_working_totals(
+    data: &web::Data<AppState>,
+    req: &WorkHoursRequest,
+) -> Result<WorkingTime, actix_web::error::Error> {
+    working_time(data, req, true).await
+}
+
+async fn working_time(
+    data: &web::Data<AppState>,
+    req: &WorkHoursRequest,
+    closed_form: bool,
+) -> Result<WorkingTime, actix_web::error::Error> {
+    log::debug!("Processing work hours calculation: {:?}", req);
+    // Parse dates and convert to timezone-aware datetimes
+    let intervals = resolve_intervals(req)?;
+    let timezone: Tz = req.timezone.parse()
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone: {}", e)))?;
+    // Effective-dated schedules replace the request schedule on the days they cover
+    let schedules = match &req.schedule_owner {
+        Some(owner) => schedules::load(data, owner).await?,
+        None => vec![],
+    };
+    let intervals_on = |date: NaiveDate| schedules::intervals_on(&schedules, date, &intervals);
+    let mut warnings = vec![];
+    let start_date = parse_request_date_with_warnings(&req.start_date, "start", timezone, req.timezone_mode, |date| start_of_schedule(date, intervals_on(date)), &mut warnings)?;
+
+    let (end_date, _duration_seconds) = match &req.end_or_duration {
+        EndOrDuration::EndDate { end_date } => {
+            let end_date = parse_request_date_with_warnings(end_date, "end", timezone, req.timezone_mode, |date| end_of_schedule(date, intervals_on(date)), &mut warnings)?;
+            (end_date, (end_date - start_date).num_seconds())
+        }
+        EndOrDuration::Duration { duration_seconds } => {
+            let end_date = start_date + chrono::Duration::seconds(*duration_seconds);
+            (end_date, *duration_seconds)
+        }
+    };
+
+    // Validate that start date is strictly before end date
+    if start_date >= end_date {
+        return Err(actix_web::error::ErrorBadRequest("Start date must be strictly before end date"));
+    }
+    check_range(start_date, end_date)?;
+    if let Some(source) = &req.holiday_source {
+        if holiday_provider::by_name(source, &data.http).is_none() {
+            return Err(actix_web::error::ErrorBadRequest(format!("Unknown holiday source: {}", source)));
+        }
+    }
+
+    // Days computed by the owner's schedule script override the stored schedules
+    #[cfg(feature = "scripting")]
+    let schedules = match &req.schedule_owner {
+        Some(owner) => {
+            let mut schedules = schedules;
+            schedules.extend(scripting::load(data, owner, start_date.date_naive() - chrono::Duration::days(1), end_date.date_naive()).await?);
+            schedules
+        }
+        None => schedules,
+    };
+    #[cfg(feature = "scripting")]
+    let intervals_on = |date: NaiveDate| schedules::intervals_on(&schedules, date, &intervals);
+
+    let country = country::parse_request_optional(&req.country)?;
+    let subdivision = req.subdivision.clone().unwrap_or_default();
+    let mut working_intervals = vec![];
+
+    // Overnight shifts anchored on the previous day may spill into the start date
+    let mut current = start_date.date_naive();
+    let mut all_intervals = intervals.iter().chain(schedules.iter().flat_map(|schedule| schedule.intervals.iter()));
+    let overnight = all_intervals.any(|(start, end)| is_overnight(*start, *end));
+    if overnight {
+        current -= chrono::Duration::days(1);
+    }
+
+    // Fetch holidays from API instead of database
+    if let Some(language) = &req.language {
+        holidays_api::validate_language(language).map_err(actix_web::error::ErrorBadRequest)?;
+    }
+    let (mut holidays, holiday_source) = match &country {
+        Some(country) => holidays_api::with_language(
+            req.language.as_deref(),
+            load_holidays_until(data, country, &subdivision, current, end_date.year(), req.holiday_source.as_deref()),
+        ).await,
+        None => (vec![], "provider"),
+    };
+    let fetched_holidays = holidays.len();
+    let days_off = req.holiday_kinds.as_deref().unwrap_or(&db::HolidayKind::DAYS_OFF);
+    holidays.retain(|holiday| days_off.contains(&holiday.kind));
+    let loaded_holidays = holidays.len();
+    if let Some(country) = &country {
+        if holiday_source == "database" {
+            warnings.push(warnings::Warning::new(
+                warnings::WarningCode::HolidaysUnavailableFallbackUsed,
+                format!("The holiday provider failed for {}, the stored holidays were used", country),
+            ));
+        }
+        if fetched_holidays == 0 {
+            warnings.push(warnings::Warning::new(
+                warnings::WarningCode::NoHolidayData,
+                format!("No holidays are known for {}", country),
+            ));
+        }
+    }
+
+    // Caller-provided exclusion dates are handled exactly like holidays
+    for date in parse_dates(&req.exclude_dates, "exclude")? {
+        holidays.push(db::Holiday {
+            id: None,
+            date: date.format("%Y-%m-%d").to_string(),
+            description: "Excluded date".to_string(),
+            country: country.as_ref().map(ToString::to_string).unwrap_or_default(),
+            portion: db::HolidayPortion::Full,
+            kind: db::HolidayKind::Company,
+            subdivision: None,
+        });
+    }
+    let excluded_holidays = holidays.len();
+    // Closure days of the named calendar, for every year of the range
+    if let Some(calendar_id) = &req.calendar_id {
+        let calendar = calendar_resolver::load(data, calendar_id).await?;
+        holidays.extend(calendar_resolver::closure_holidays(&calendar, current.year()..=end_date.year()));
+    }
+    let include_dates: HashSet<NaiveDate> = parse_dates(&req.include_dates, "include")?.into_iter().collect();
+    let ramp = req.ramp.as_ref()
+        .map(capacity::Ramp::multipliers)
+        .transpose()
+        .map_err(actix_web::error::ErrorBadRequest)?;
+    let mut holidays_applied = vec![];
+
+    // Holidays indexed by day, so that each day is a lookup rather than a scan
+    let mut holiday_index: BTreeMap<NaiveDate, Vec<usize>> = BTreeMap::new();
+    for (index, holiday) in holidays.iter().enumerate() {
+        if let Some(date) = holiday_date(holiday) {
+            holiday_index.entry(date).or_default().push(index);
+        }
+    }
+    // Days only partly off stay working days, with half of their working time removed
+    let mut half_days: BTreeMap<NaiveDate, (db::HolidayPortion, Vec<usize>)> = BTreeMap::new();
+    holiday_index.retain(|date, indexes| {
+        let portion = indexes.iter()
+            .map(|&index| holidays[index].portion)
+            .reduce(db::HolidayPortion::combine)
+            .unwrap_or_default();
+        if portion.is_full() {
+            return true;
+        }
+        half_days.insert(*date, (portion, indexes.clone()));
+        false
+    });
+    let weekend = req.weekend.as_deref().unwrap_or(&DEFAULT_WEEKEND);
+    let is_weekend = |date: NaiveDate| weekend.contains(&date.weekday());
+    let works_on = |date: NaiveDate| {
+        include_dates.contains(&date) || (!is_weekend(date) && !holiday_index.contains_key(&date))
+    };
+    // Holidays that removed a weekday from the requested range
+    let applied_on = |date: NaiveDate, indexes: &[usize]| -> Vec<AppliedHoliday> {
+        if is_weekend(date) || include_dates.contains(&date) || date < start_date.date_naive() {
+            return vec![];
+        }
+        indexes.iter()
+            .map(|&index| AppliedHoliday {
+                date: date.to_string(),
+                description: holidays[index].description.clone(),
+                source: match index {
+                    index if index < loaded_holidays => holiday_source,
+                    index if index < excluded_holidays => "excludeDates",
+                    _ => "calendar",
+                }.to_string(),
+                portion: holidays[index].portion,
+            })
+            .collect()
+    };
+
+    // Full days strictly inside the range are counted in closed form: only the first and
+    // last days (and the day before them for overnight shifts) can be clipped by the range
+    let overnight_days = if overnight { 1 } else { 0 };
+    let bulk_days = (start_date.date_naive() + chrono::Duration::days(1),
+                     end_date.date_naive() - chrono::Duration::days(1 + overnight_days));
+    let eligible = closed_form && schedules.is_empty() && req.on_call_calendar.is_none() && half_days.is_empty() && ramp.is_none();
+    let bulk_days = (eligible && bulk_days.0 <= bulk_days.1).then_some(bulk_days);
+    let mut bulk_seconds = 0;
+
+    while current <= end_date.date_naive() {
+        if let Some((from, to)) = bulk_days.filter(|(from, _)| *from == current) {
+            let nominal: i64 = intervals.iter()
+                .map(|(start, end)| {
+                    let end_day = if is_overnight(*start, *end) { from + chrono::Duration::days(1) } else { from };
+                    (end_day.and_time(*end) - from.and_time(*start)).num_seconds()
+                })
+                .sum();
+            let holidays_off = holiday_index.range(from..=to)
+                .filter(|(date, _)| !is_weekend(**date) && !include_dates.contains(*date))
+                .count() as i64;
+            let weekends_on = include_dates.iter()
+                .filter(|date| (from..=to).contains(*date) && is_weekend(**date))
+                .count() as i64;
+            bulk_seconds += (weekdays_between(from, to, weekend) - holidays_off + weekends_on) * nominal;
+
+            // Days shortened or lengthened by a daylight saving change are corrected one by one
+            let first_instant = resolve_local(&timezone, from.and_hms_opt(0, 0, 0).unwrap());
+            let last_instant = resolve_local(&timezone, (to + chrono::Duration::days(1 + overnight_days)).and_hms_opt(0, 0, 0).unwrap());
+            let mut irregular: Vec<NaiveDate> = offset_change_dates(first_instant, last_instant).into_iter()
+                .flat_map(|date| [date - chrono::Duration::days(overnight_days), date])
+                .filter(|date| (from..=to).contains(date) && works_on(*date))
+                .collect();
+            irregular.sort();
+            irregular.dedup();
+            for day in irregular {
+                let actual: i64 = intervals.iter()
+                    .map(|(start, end)| {
+                        let end_day = if is_overnight(*start, *end) { day + chrono::Duration::days(1) } else { day };
+                        (resolve_local(&timezone, end_day.and_time(*end)) - resolve_local(&timezone, day.and_time(*start))).num_seconds()
+                    })
+                    .sum();
+                bulk_seconds += actual - nominal;
+            }
+
+            for (date, indexes) in holiday_index.range(from..=to) {
+                holidays_applied.extend(applied_on(*date, indexes));
+            }
+            current = to + chrono::Duration::days(1);
+            continue;
+        }
+
+        // Skip weekends and holidays, unless the day is forced as a working day
+        if !works_on(current) {
+            if let Some(indexes) = holiday_index.get(&current) {
+                holidays_applied.extend(applied_on(current, indexes));
+            }
+            current += chrono::Duration::days(1);
+            continue;
+        }
+
+        let mut day_intervals: Vec<(DateTime<Tz>, DateTime<Tz>)> = intervals_on(current).iter()
+            .map(|(interval_start, interval_end)| {
+                let end_day = if is_overnight(*interval_start, *interval_end) {
+                    current + chrono::Duration::days(1)
+                } else {
+                    current
+                };
+                (resolve_local(&timezone, current.and_time(*interval_start)), resolve_local(&timezone, end_day.and_time(*interval_end)))
+            })
+            .collect();
+        if let Some((portion, indexes)) = half_days.get(&current).filter(|_| !include_dates.contains(&current)) {
+            day_intervals = half_day_intervals(day_intervals, *portion);
+            holidays_applied.extend(applied_on(current, indexes));
+        }
+        if let Some(ramp) = &ramp {
+            day_intervals = capacity::scale(day_intervals, ramp.on(current));
+        }
+
+        // Clip the requested range against each working interval of the day
+        for (interval_start, interval_end) in day_intervals {
+            let effective_start = interval_start.max(start_date);
+            let effective_end = interval_end.min(end_date);
+            if effective_start < effective_end {
+                working_intervals.push((effective_start, effective_end));
+            }
+        }
+
+        current += chrono::Duration::days(1);
+    }
+
+    // On-call rotation periods count as working time around the clock
+    if let Some(calendar_id) = &req.on_call_calendar {
+        let periods = {
+            let calendar_id = calendar_id.clone();
+            data.db.blocking(move |db| db.get_on_call_periods(&calendar_id)).await.map_err(actix_web::error::ErrorInternalServerError)?
+        };
+        for period in periods {
+            let (period_start, period_end) = match (
+                DateTime::parse_from_rfc3339(&period.start),
+                DateTime::parse_from_rfc3339(&period.end),
+            ) {
+                (Ok(start), Ok(end)) => (start.with_timezone(&timezone), end.with_timezone(&timezone)),
+                _ => continue,
+            };
+            let effective_start = period_start.max(start_date);
+            let effective_end = period_end.min(end_date);
+            if effective_start < effective_end {
+                working_intervals.push((effective_start, effective_end));
+            }
+        }
+        working_intervals = merge_intervals(working_intervals);
+    }
+
+    Ok(WorkingTime {
+        start_date,
+        end_date,
+        intervals: working_intervals,
+        holidays_applied,
+        bulk_seconds,
+        warnings,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IsWorkingParams {
+    pub at: String,
+    #[serde(rename = "startOfDay", default = "default_start_of_day")]
+    pub start_of_day: String,
+    #[serde(rename = "endOfDay", default = "default_end_of_day")]
+    pub end_of_day: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub timezone: String,
+    #[serde(rename = "timezoneMode", default)]
+    pub timezone_mode: TimezoneMode,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IsWorkingResponse {
+    pub at: String,
+    pub is_working: bool,
+    pub next_working_instant: Option<String>,
+}
+
+// Safety net for the next working instant search (e.g. a country with only holidays)
+const MAX_SEARCH_DAYS: i64 = 3660;
+
+#[get("/is-working")]
+pub async fn is_working(
+    data: web::Data<AppState>,
+    params: web::Query<IsWorkingParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    log::debug!("Received is-working request: {:?}", params);
+
+    let schedule = resolve_schedule(&params.start_of_day, &params.end_of_day, &[])?;
+    let timezone: Tz = params.timezone.parse()
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone: {}", e)))?;
+
+    // Accept RFC3339, Unix epochs and local date-times without offset
+    let local = |naive: NaiveDateTime| resolve_local(&timezone, naive);
+    let at = match (parse_epoch(&params.at), DateTime::parse_from_rfc3339(&params.at)) {
+        (Some(at), _) => at.with_timezone(&timezone),
+        (None, Ok(at)) if params.timezone_mode == TimezoneMode::Convert => at.with_timezone(&timezone),
+        (None, Ok(at)) => local(at.naive_local()),
+        (None, Err(_)) => local(NaiveDateTime::parse_from_str(&params.at, "%Y-%m-%dT%H:%M:%S")
+            .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid date format: {}", e)))?),
+    };
+
+    let country = country::parse_request_optional(&params.country)?;
+    let subdivision = params.subdivision.clone().unwrap_or_default();
+    let holidays = load_holidays(&data, country.as_ref(), &subdivision, at.date_naive()).await;
+
+    // Working hours of a day, a half-day holiday only leaves the other half. Overnight shifts
+    // belong to the day they start on and end the next day.
+    let hours_on = |day: NaiveDate| -> Intervals {
+        if !is_working_day(day, &holidays) {
+            return vec![];
+        }
+        let hours = schedule.iter()
+            .map(|(start, end)| {
+                let end_day = if is_overnight(*start, *end) { day + chrono::Duration::days(1) } else { day };
+                (resolve_local(&timezone, day.and_time(*start)), resolve_local(&timezone, end_day.and_time(*end)))
+            })
+            .collect();
+        match day_off_portion(day, &holidays) {
+            Some(portion) => half_day_intervals(hours, portion),
+            None => hours,
+        }
+    };
+    // A shift started the day before may still be running
+    let previous_day = at.date_naive() - chrono::Duration::days(1);
+    let is_working = [previous_day, at.date_naive()].into_iter()
+        .any(|day| hours_on(day).iter().any(|(start, end)| *start <= at && at < *end));
+
+    let next_working_instant = if is_working {
+        None
+    } else {
+        let mut day = at.date_naive();
+        let mut searched = 0;
+        loop {
+            if let Some((start, _)) = hours_on(day).into_iter().find(|(start, _)| *start > at) {
+                break Some(start.to_rfc3339());
+            }
+            if searched >= MAX_SEARCH_DAYS {
+                return Err(actix_web::error::ErrorUnprocessableEntity("No working day found in the search window"));
+            }
+            day += chrono::Duration::days(1);
+            searched += 1;
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(IsWorkingResponse {
+        at: at.to_rfc3339(),
+        is_working,
+        next_working_instant,
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FirstWorkingDayParams {
+    // YYYY-MM-DD, the day itself is never returned
+    pub after: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FirstWorkingDayResponse {
+    pub after: String,
+    pub date: String,
+    // Holidays between the two dates that pushed the result back
+    pub holidays_skipped: Vec<Holiday>,
+}
+
+// First business day strictly after a date, e.g. the first working day of June is the first one after May 31st
+#[get("/first-working-day")]
+pub async fn first_working_day(
+    data: web::Data<AppState>,
+    params: web::Query<FirstWorkingDayParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let after = NaiveDate::parse_from_str(&params.after, "%Y-%m-%d")
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid after date: {}", e)))?;
+    let country = country::parse_request_optional(&params.country)?;
+    let subdivision = params.subdivision.clone().unwrap_or_default();
+
+    // Holidays are loaded per year, the search may cross into the next one
+    let mut holidays_by_year: BTreeMap<i32, Vec<db::Holiday>> = BTreeMap::new();
+    let mut holidays_skipped = vec![];
+    let mut day = after;
+    for _ in 0..MAX_SEARCH_DAYS {
+        day += chrono::Duration::days(1);
+        if let btree_map::Entry::Vacant(entry) = holidays_by_year.entry(day.year()) {
+            let first_day = NaiveDate::from_ymd_opt(day.year(), 1, 1).unwrap();
+            entry.insert(load_holidays(&data, country.as_ref(), &subdivision, first_day).await);
+        }
+        let holidays = &holidays_by_year[&day.year()];
+        if is_working_day(day, holidays) {
+            return Ok(HttpResponse::Ok().json(FirstWorkingDayResponse {
+                after: after.to_string(),
+                date: day.to_string(),
+                holidays_skipped,
+            }));
+        }
+        if let Some(holiday) = day_off_on(day, holidays) {
+            holidays_skipped.push(Holiday { date: day.to_string(), description: holiday.description.clone() });
+        }
+    }
+    Err(actix_web::error::ErrorUnprocessableEntity("No working day found in the search window"))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NthWorkingDayParams {
+    // YYYY-MM
+    pub month: String,
+    // 1 for the first working day of the month, -1 for the last one
+    pub n: i32,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NthWorkingDayResponse {
+    pub month: String,
+    pub n: i32,
+    pub date: String,
+    // Holidays of the month counted over before reaching the date
+    pub holidays_skipped: Vec<Holiday>,
+}
+
+// Nth business day of a month, counted from its end for negative n, as in finance cut-off schedules
+#[get("/nth-working-day")]
+pub async fn nth_working_day(
+    data: web::Data<AppState>,
+    params: web::Query<NthWorkingDayParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let first_day = NaiveDate::parse_from_str(&format!("{}-01", params.month), "%Y-%m-%d")
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid month: {}", e)))?;
+    if params.n == 0 {
+        return Err(actix_web::error::ErrorBadRequest("n must not be 0"));
+    }
+    let country = country::parse_request_optional(&params.country)?;
+    let subdivision = params.subdivision.clone().unwrap_or_default();
+    let holidays = load_holidays(&data, country.as_ref(), &subdivision, NaiveDate::from_ymd_opt(first_day.year(), 1, 1).unwrap()).await;
+
+    let mut days: Vec<NaiveDate> = first_day.iter_days().take_while(|day| day.month() == first_day.month()).collect();
+    if params.n < 0 {
+        days.reverse();
+    }
+    let mut holidays_skipped = vec![];
+    let mut remaining = params.n.unsigned_abs();
+    for day in days {
+        if is_working_day(day, &holidays) {
+            remaining -= 1;
+            if remaining == 0 {
+                return Ok(HttpResponse::Ok().json(NthWorkingDayResponse {
+                    month: first_day.format("%Y-%m").to_string(),
+                    n: params.n,
+                    date: day.to_string(),
+                    holidays_skipped,
+                }));
+            }
+        } else if let Some(holiday) = day_off_on(day, &holidays) {
+            holidays_skipped.push(Holiday { date: day.to_string(), description: holiday.description.clone() });
+        }
+    }
+    Err(actix_web::error::ErrorUnprocessableEntity(format!("{} has fewer than {} working days", params.month, params.n.unsigned_abs())))
+}
+
+// Unit tests for the library
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::web;
+    use holidays_api::mock as holidays_api_mock;
+
+    #[test]
+    fn test_iso_duration_deserialization() {
+        let json = r#"{"startDate": "2023-10-02T09:00:00Z", "duration": "P5D", "timezone": "UTC"}"#;
+        let request: WorkHoursRequest = serde_json::from_str(json).unwrap();
+        match request.end_or_duration {
+            EndOrDuration::Duration { duration_seconds } => assert_eq!(duration_seconds, 432000),
+            _ => panic!("Expected Duration variant"),
+        }
+
+        let json = r#"{"startDate": "2023-10-02T09:00:00Z", "duration": "P1Y", "timezone": "UTC"}"#;
+        assert!(serde_json::from_str::<WorkHoursRequest>(json).is_err());
+    }
+
+    #[test]
+    fn test_end_or_duration_deserialization() {
+        // Test EndDate variant
+        let json = r#"{"end_date": "2023-10-06T17:00:00Z"}"#;
+        let end_or_duration: EndOrDuration = serde_json::from_str(json).unwrap();
+        match end_or_duration {
+            EndOrDuration::EndDate { end_date } => assert_eq!(end_date, "2023-10-06T17:00:00Z"),
+            _ => panic!("Expected EndDate variant"),
+        }
+
+        // Test Duration variant
+        let json = r#"{"duration_seconds": 86400}"#;
+        let end_or_duration: EndOrDuration = serde_json::from_str(json).unwrap();
+        match end_or_duration {
+            EndOrDuration::Duration { duration_seconds } => assert_eq!(duration_seconds, 86400),
+            _ => panic!("Expected Duration variant"),
+        }
+    }
+
+    #[test]
+    fn test_holiday_serialization() {
+        let holiday = Holiday {
+            date: "2023-12-25T00:00:00Z".to_string(),
+            description: "Christmas".to_string(),
+        };
+
+        let json = serde_json::to_string(&holiday).unwrap();
+        let expected = r#"{"date":"2023-12-25T00:00:00Z","description":"Christmas"}"#;
+        assert_eq!(json, expected);
+
+        let deserialized: Holiday = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.date, holiday.date);
+        assert_eq!(deserialized.description, holiday.description);
+    }
+
+    // Helper function to create a test database with holidays
+    fn create_test_db_with_holidays(holidays: Vec<(String, String, String)>) -> web::Data<AppState> {
+        let db = db::Database::new(":memory:").unwrap();
+
+        for (date, description, country) in holidays {
+            let holiday = db::Holiday {
+                id: None,
+                date,
+                description,
+                country,
+                portion: db::HolidayPortion::Full,
+                kind: db::HolidayKind::Public,
+                subdivision: None,
+            };
+            db.add_holiday(&holiday, "test").unwrap();
+        }
+
+        web::Data::new(AppState {
+            db,
+            holidays: Box::new(holiday_provider::MockProvider),
+            http: reqwest::Client::new(),
+        })
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_weekday() {
+        // Test a single workday (Monday)
+        // 8 hours of work (9am to 5pm)
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate { 
+                end_date: "2023-10-02T17:00:00Z".to_string() 
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let body = result.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 8.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_weekend() {
+        // Test a weekend (Saturday and Sunday)
+        // 0 hours of work
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-07T09:00:00Z".to_string(), // Saturday
+            end_or_duration: EndOrDuration::EndDate { 
+                end_date: "2023-10-08T17:00:00Z".to_string() // Sunday
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let body = result.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 0.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_with_holiday() {
+        // Test a workweek with a holiday
         // Monday to Friday, but Wednesday is a holiday
         // 4 days * 8 hours = 32 hours
 
-        // Set up mock holidays for the API
-        holidays_api_mock::set_mock_holidays("us", vec![
-            holidays_api::Holiday {
-                date: "2023-10-04T00:00:00Z".to_string(),
-                description: "Test Holiday".to_string(),
+        // Set up mock holidays for the API
+        holidays_api_mock::set_mock_holidays("us", vec![
+            holidays_api::Holiday {
+                date: "2023-10-04T00:00:00Z".to_string(),
+                description: "Test Holiday".to_string(),
+                portion: db::HolidayPortion::Full,
+                kind: db::HolidayKind::Public,
+            },
+        ]);
+
+        // Also set up database holidays as fallback
+        let db_data = create_test_db_with_holidays(vec![
+            ("2023-10-04T00:00:00Z".to_string(), "Test Holiday".to_string(), "us".to_string()),
+        ]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T09:00:00Z".to_string(), // Monday
+            end_or_duration: EndOrDuration::EndDate { 
+                end_date: "2023-10-06T17:00:00Z".to_string() // Friday
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let body = result.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 32.0);
+
+        // Clean up mock holidays
+        holidays_api_mock::clear_mock_holidays();
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_partial_day() {
+        // Test a partial workday
+        // Starting at 12pm instead of 9am (3 hours less)
+        // 5 hours of work
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T12:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate { 
+                end_date: "2023-10-02T17:00:00Z".to_string()
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let body = result.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 5.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_different_timezone() {
+        // Test work hours calculation with a different timezone
+        // 9am to 5pm in Europe/Paris
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T09:00:00+02:00".to_string(), // 9am Paris time
+            end_or_duration: EndOrDuration::EndDate { 
+                end_date: "2023-10-02T17:00:00+02:00".to_string() // 5pm Paris time
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "fr".to_string(),
+            timezone: "Europe/Paris".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let body = result.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 8.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_with_duration() {
+        // Test work hours calculation with duration
+        // Starting Monday 9am, duration 5 days
+        // Monday to Friday = 5 workdays * 8 hours = 40 hours
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T09:00:00Z".to_string(), // Monday
+            end_or_duration: EndOrDuration::Duration { 
+                duration_seconds: 432000 // 5 days
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let body = result.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 40.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_invalid_date_range_equal() {
+        // Test case where start_date equals end_date
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate { 
+                end_date: "2023-10-02T09:00:00Z".to_string() // Same as start_date
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await;
+        assert!(result.is_err(), "Expected an error when start_date equals end_date");
+
+        if let Err(e) = result {
+            let error_string = format!("{}", e);
+            assert!(error_string.contains("Start date must be strictly before end date"), 
+                   "Error message should mention that start date must be before end date");
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_invalid_date_range_after() {
+        // Test case where start_date is after end_date
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-03T09:00:00Z".to_string(), // Tuesday
+            end_or_duration: EndOrDuration::EndDate { 
+                end_date: "2023-10-02T09:00:00Z".to_string() // Monday (before start_date)
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await;
+        assert!(result.is_err(), "Expected an error when start_date is after end_date");
+
+        if let Err(e) = result {
+            let error_string = format!("{}", e);
+            assert!(error_string.contains("Start date must be strictly before end date"), 
+                   "Error message should mention that start date must be before end date");
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_with_zero_duration() {
+        // Test case where duration is zero, resulting in start_date equals end_date
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::Duration { 
+                duration_seconds: 0 // Zero duration
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await;
+        assert!(result.is_err(), "Expected an error when duration is zero");
+
+        if let Err(e) = result {
+            let error_string = format!("{}", e);
+            assert!(error_string.contains("Start date must be strictly before end date"), 
+                   "Error message should mention that start date must be before end date");
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_with_negative_duration() {
+        // Test case where duration is negative, resulting in end_date before start_date
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::Duration { 
+                duration_seconds: -86400 // Negative duration (1 day)
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await;
+        assert!(result.is_err(), "Expected an error when duration is negative");
+
+        if let Err(e) = result {
+            let error_string = format!("{}", e);
+            assert!(error_string.contains("Start date must be strictly before end date"), 
+                   "Error message should mention that start date must be before end date");
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_with_custom_day_times() {
+        // Test work hours calculation with custom start and end of day times
+        // 8am to 4pm instead of 9am to 5pm
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T08:00:00Z".to_string(), // Monday at 8am
+            end_or_duration: EndOrDuration::EndDate { 
+                end_date: "2023-10-02T16:00:00Z".to_string() // Monday at 4pm
+            },
+            start_of_day: "08:00:00".to_string(),
+            end_of_day: "16:00:00".to_string(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let body = result.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 8.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_with_partial_custom_day() {
+        // Test work hours calculation with custom start and end of day times
+        // Starting at 10am with custom day from 8am to 4pm
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T10:00:00Z".to_string(), // Monday at 10am
+            end_or_duration: EndOrDuration::EndDate { 
+                end_date: "2023-10-02T16:00:00Z".to_string() // Monday at 4pm
             },
+            start_of_day: "08:00:00".to_string(),
+            end_of_day: "16:00:00".to_string(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            subdivision: None,
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let body = result.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 6.0); // 8am to 4pm = 8 hours
+
+    }
+
+    #[actix_rt::test]
+    async fn test_is_working() {
+        use actix_web::{test, App};
+
+        let db_data = create_test_db_with_holidays(vec![
+            ("2024-05-01T00:00:00Z".to_string(), "Labour Day".to_string(), "fr".to_string()),
         ]);
+        let app = test::init_service(App::new().app_data(db_data).service(is_working)).await;
+
+        // Inside working hours on a regular Thursday
+        let req = test::TestRequest::get()
+            .uri("/is-working?at=2024-05-02T10:00:00&country=fr&timezone=Europe/Paris")
+            .to_request();
+        let response: IsWorkingResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(response.is_working);
+        assert_eq!(response.next_working_instant, None);
+
+        // On a holiday the next working instant is the following morning
+        let req = test::TestRequest::get()
+            .uri("/is-working?at=2024-05-01T10:00:00&country=fr&timezone=Europe/Paris")
+            .to_request();
+        let response: IsWorkingResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(!response.is_working);
+        assert_eq!(response.next_working_instant, Some("2024-05-02T09:00:00+02:00".to_string()));
+    }
 
-        // Also set up database holidays as fallback
+    #[actix_rt::test]
+    async fn test_is_working_overnight_shift() {
+        use actix_web::{test, App};
+        let db_data = create_test_db_with_holidays(vec![]);
+        let app = test::init_service(App::new().app_data(db_data).service(is_working)).await;
+        let at = |at: &str| test::TestRequest::get()
+            .uri(&format!("/is-working?at={}&startOfDay=22:00:00&endOfDay=06:00:00&country=fr&timezone=UTC", at))
+            .to_request();
+
+        // Monday night, the shift still running on Tuesday morning, and Friday's shift on Saturday morning
+        for instant in ["2025-08-04T23:00:00Z", "2025-08-05T03:00:00Z", "2025-08-09T03:00:00Z"] {
+            let response: IsWorkingResponse = test::call_and_read_body_json(&app, at(instant)).await;
+            assert!(response.is_working, "{}", instant);
+        }
+        // No shift starts on Saturday, the next one is Monday night
+        let response: IsWorkingResponse = test::call_and_read_body_json(&app, at("2025-08-10T03:00:00Z")).await;
+        assert!(!response.is_working);
+        assert_eq!(response.next_working_instant, Some("2025-08-11T22:00:00+00:00".to_string()));
+    }
+
+    #[actix_rt::test]
+    async fn test_first_working_day() {
+        use actix_web::{test, App};
         let db_data = create_test_db_with_holidays(vec![
-            ("2023-10-04T00:00:00Z".to_string(), "Test Holiday".to_string(), "us".to_string()),
+            ("2025-05-01".to_string(), "Labour Day".to_string(), "fr".to_string()),
+            ("2026-01-01".to_string(), "New Year's Day".to_string(), "fr".to_string()),
+            // Stored before dates were normalized, skipped by the search
+            ("2025-8-1".to_string(), "Unpadded".to_string(), "fr".to_string()),
+        ]);
+        let app = test::init_service(App::new().app_data(db_data).service(first_working_day)).await;
+        let first_after = |after: &str| test::TestRequest::get()
+            .uri(&format!("/first-working-day?after={}&country=fr", after))
+            .to_request();
+
+        let response: FirstWorkingDayResponse = test::call_and_read_body_json(&app, first_after("2025-04-30")).await;
+        assert_eq!(response.date, "2025-05-02");
+        assert_eq!(response.holidays_skipped[0].description, "Labour Day");
+        // Strictly after: a Friday gives the next Monday
+        let response: FirstWorkingDayResponse = test::call_and_read_body_json(&app, first_after("2025-05-02")).await;
+        assert_eq!(response.date, "2025-05-05");
+        assert!(response.holidays_skipped.is_empty());
+        let response: FirstWorkingDayResponse = test::call_and_read_body_json(&app, first_after("2025-12-31")).await;
+        assert_eq!(response.date, "2026-01-02");
+
+        let response = test::call_service(&app, first_after("2025-13-01")).await;
+        assert_eq!(response.status(), 400);
+    }
+
+    #[actix_rt::test]
+    async fn test_nth_working_day() {
+        use actix_web::{test, App};
+        let db_data = create_test_db_with_holidays(vec![
+            ("2025-05-01".to_string(), "Labour Day".to_string(), "fr".to_string()),
+            ("2025-05-29".to_string(), "Ascension Day".to_string(), "fr".to_string()),
         ]);
+        let app = test::init_service(App::new().app_data(db_data).service(nth_working_day)).await;
+        let nth = |n: i32| test::TestRequest::get()
+            .uri(&format!("/nth-working-day?month=2025-05&n={}&country=fr", n))
+            .to_request();
+
+        let response: NthWorkingDayResponse = test::call_and_read_body_json(&app, nth(1)).await;
+        assert_eq!(response.date, "2025-05-02");
+        assert_eq!(response.holidays_skipped[0].description, "Labour Day");
+        let response: NthWorkingDayResponse = test::call_and_read_body_json(&app, nth(3)).await;
+        assert_eq!(response.date, "2025-05-06");
+        // Counted from the end: the 31st is a Saturday and the 29th a holiday
+        let response: NthWorkingDayResponse = test::call_and_read_body_json(&app, nth(-1)).await;
+        assert_eq!(response.date, "2025-05-30");
+        let response: NthWorkingDayResponse = test::call_and_read_body_json(&app, nth(-2)).await;
+        assert_eq!(response.date, "2025-05-28");
+        assert_eq!(response.holidays_skipped[0].description, "Ascension Day");
+
+        assert_eq!(test::call_service(&app, nth(0)).await.status(), 400);
+        assert_eq!(test::call_service(&app, nth(25)).await.status(), 422);
+    }
+
+    #[actix_rt::test]
+    async fn test_bank_holidays_are_working_days() {
+        use actix_web::{test, App};
+        let db_data = create_test_db_with_holidays(vec![]);
+        db_data.db.add_holiday(&db::Holiday { id: None, date: "2025-08-04".to_string(), description: "Summer Bank Holiday".to_string(), country: "fr".to_string(), portion: db::HolidayPortion::Full, kind: db::HolidayKind::Bank, subdivision: None }, "test").unwrap();
+        let app = test::init_service(App::new().app_data(db_data).service(is_working).service(first_working_day)).await;
+
+        let req = test::TestRequest::get().uri("/is-working?at=2025-08-04T10:00:00Z&country=fr&timezone=UTC").to_request();
+        let response: IsWorkingResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(response.is_working);
+        let req = test::TestRequest::get().uri("/first-working-day?after=2025-08-01&country=fr").to_request();
+        let response: FirstWorkingDayResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(response.date, "2025-08-04");
+        assert!(response.holidays_skipped.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_half_day_holidays_are_working_days() {
+        use actix_web::{test, App};
+        let db_data = create_test_db_with_holidays(vec![]);
+        db_data.db.add_holiday(&db::Holiday { id: None, date: "2025-08-05".to_string(), description: "Half day".to_string(), country: "fr".to_string(), portion: db::HolidayPortion::Morning, kind: db::HolidayKind::Public, subdivision: None }, "test").unwrap();
+        let app = test::init_service(App::new().app_data(db_data).service(is_working).service(first_working_day)).await;
+        let at = |at: &str| test::TestRequest::get().uri(&format!("/is-working?at={}&country=fr&timezone=UTC", at)).to_request();
+
+        // 09:00-17:00 with the morning off leaves 13:00-17:00
+        let response: IsWorkingResponse = test::call_and_read_body_json(&app, at("2025-08-05T15:00:00Z")).await;
+        assert!(response.is_working);
+        let response: IsWorkingResponse = test::call_and_read_body_json(&app, at("2025-08-05T10:00:00Z")).await;
+        assert!(!response.is_working);
+        assert_eq!(response.next_working_instant, Some("2025-08-05T13:00:00+00:00".to_string()));
+        let req = test::TestRequest::get().uri("/first-working-day?after=2025-08-04&country=fr").to_request();
+        let response: FirstWorkingDayResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(response.date, "2025-08-05");
+        assert!(response.holidays_skipped.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_with_exclude_dates() {
+        // Monday to Friday with a vacation on Tuesday and Thursday
+        // 3 days * 8 hours = 24 hours
+        let db_data = create_test_db_with_holidays(vec![]);
 
         let request = WorkHoursRequest {
-            start_date: "2023-10-02T09:00:00Z".to_string(), // Monday
-            end_or_duration: EndOrDuration::EndDate { 
-                end_date: "2023-10-06T17:00:00Z".to_string() // Friday
+            start_date: "2023-10-02T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-06T17:00:00Z".to_string()
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            exclude_dates: vec!["2023-10-03".to_string(), "2023-10-05".to_string()],
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let bytes = actix_web::body::to_bytes(result.into_body()).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 24.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_with_include_dates() {
+        // Saturday and Sunday, with a scheduled Saturday release
+        // 1 day * 8 hours = 8 hours
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-07T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-08T17:00:00Z".to_string()
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            include_dates: vec!["2023-10-07".to_string()],
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let bytes = actix_web::body::to_bytes(result.into_body()).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 8.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_with_split_shift() {
+        // Split shift 08:00-12:00 and 14:00-18:00, starting at 10am and ending at 3pm
+        // 2 hours in the morning + 1 hour in the afternoon = 3 hours
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T10:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-02T15:00:00Z".to_string()
+            },
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            working_intervals: vec![
+                WorkingInterval { start: "08:00:00".to_string(), end: "12:00:00".to_string() },
+                WorkingInterval { start: "14:00:00".to_string(), end: "18:00:00".to_string() },
+            ],
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let bytes = actix_web::body::to_bytes(result.into_body()).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 3.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_overnight_shift() {
+        // Night shift 22:00-06:00 from Friday evening to Monday morning
+        // Friday night shift = 8 hours, the weekend shifts are skipped
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-06T20:00:00Z".to_string(), // Friday
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-09T07:00:00Z".to_string() // Monday
+            },
+            start_of_day: "22:00:00".to_string(),
+            end_of_day: "06:00:00".to_string(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
+        let bytes = actix_web::body::to_bytes(result.into_body()).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 8.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_calculate_work_hours_with_working_duration() {
+        // 10 working hours from Friday 9am end on Monday 11am
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-06T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::Duration {
+                duration_seconds: 36000
             },
             start_of_day: default_start_of_day(),
             end_of_day: default_end_of_day(),
             country: "us".to_string(),
             timezone: "UTC".to_string(),
-            subdivision: None,
+            duration_type: DurationType::Working,
+            ..Default::default()
         };
 
         let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
-        let body = result.into_body();
-        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let bytes = actix_web::body::to_bytes(result.into_body()).await.unwrap();
         let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
 
-        assert_eq!(response.work_hours, 32.0);
-
-        // Clean up mock holidays
-        holidays_api_mock::clear_mock_holidays();
+        assert_eq!(response.work_hours, 10.0);
+        assert_eq!(response.end_date, "2023-10-09T11:00:00+00:00");
     }
 
     #[actix_rt::test]
-    async fn test_calculate_work_hours_partial_day() {
-        // Test a partial workday
-        // Starting at 12pm instead of 9am (3 hours less)
-        // 5 hours of work
+    async fn test_calculate_work_hours_monthly_breakdown() {
+        // From Monday October 30th to Thursday November 2nd
+        // 2 days in October and 2 days in November
         let db_data = create_test_db_with_holidays(vec![]);
 
         let request = WorkHoursRequest {
-            start_date: "2023-10-02T12:00:00Z".to_string(),
-            end_or_duration: EndOrDuration::EndDate { 
-                end_date: "2023-10-02T17:00:00Z".to_string()
+            start_date: "2023-10-30T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-11-02T17:00:00Z".to_string()
             },
             start_of_day: default_start_of_day(),
             end_of_day: default_end_of_day(),
             country: "us".to_string(),
             timezone: "UTC".to_string(),
-            subdivision: None,
+            aggregate: Some(aggregation::Aggregate::Month),
+            ..Default::default()
         };
 
         let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
-        let body = result.into_body();
-        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let bytes = actix_web::body::to_bytes(result.into_body()).await.unwrap();
         let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
 
-        assert_eq!(response.work_hours, 5.0);
+        let breakdown = response.breakdown.unwrap();
+        assert_eq!(breakdown.len(), 2);
+        assert_eq!(breakdown[0].hours, 16.0);
+        assert_eq!(breakdown[1].hours, 16.0);
     }
 
     #[actix_rt::test]
-    async fn test_calculate_work_hours_different_timezone() {
-        // Test work hours calculation with a different timezone
-        // 9am to 5pm in Europe/Paris
-        let db_data = create_test_db_with_holidays(vec![]);
+    async fn test_holidays_applied() {
+        let db_data = create_test_db_with_holidays(vec![
+            ("2023-10-04".to_string(), "Company Day".to_string(), "fr".to_string()),
+            // Falls on a Saturday, no working time is lost
+            ("2023-10-07".to_string(), "Weekend Holiday".to_string(), "fr".to_string()),
+        ]);
 
         let request = WorkHoursRequest {
-            start_date: "2023-10-02T09:00:00+02:00".to_string(), // 9am Paris time
-            end_or_duration: EndOrDuration::EndDate { 
-                end_date: "2023-10-02T17:00:00+02:00".to_string() // 5pm Paris time
+            start_date: "2023-10-02T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-08T17:00:00Z".to_string()
             },
-            start_of_day: default_start_of_day(),
-            end_of_day: default_end_of_day(),
             country: "fr".to_string(),
-            timezone: "Europe/Paris".to_string(),
-            subdivision: None,
+            timezone: "UTC".to_string(),
+            exclude_dates: vec!["2023-10-05".to_string()],
+            ..Default::default()
         };
 
-        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
-        let body = result.into_body();
-        let bytes = actix_web::body::to_bytes(body).await.unwrap();
-        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
-
-        assert_eq!(response.work_hours, 8.0);
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.work_hours, 24.0);
+        assert_eq!(response.holidays_applied, vec![
+            AppliedHoliday {
+                date: "2023-10-04".to_string(),
+                description: "Company Day".to_string(),
+                source: "database".to_string(),
+                portion: db::HolidayPortion::Full,
+            },
+            AppliedHoliday {
+                date: "2023-10-05".to_string(),
+                description: "Excluded date".to_string(),
+                source: "excludeDates".to_string(),
+                portion: db::HolidayPortion::Full,
+            },
+        ]);
     }
 
     #[actix_rt::test]
-    async fn test_calculate_work_hours_with_duration() {
-        // Test work hours calculation with duration
-        // Starting Monday 9am, duration 5 days
-        // Monday to Friday = 5 workdays * 8 hours = 40 hours
+    async fn test_half_day_holidays() {
         let db_data = create_test_db_with_holidays(vec![]);
+        {
+            let db = &db_data.db;
+            for (date, portion) in [("2024-12-24", db::HolidayPortion::Afternoon), ("2024-12-27", db::HolidayPortion::Morning)] {
+                db.add_holiday(&db::Holiday { id: None, date: date.to_string(), description: "Half day".to_string(), country: "fr".to_string(), portion, kind: db::HolidayKind::Public, subdivision: None }, "test").unwrap();
+            }
+        }
+        // Monday 23 to Friday 27, 09:00-12:00 and 13:00-18:00: Christmas and two half days of 4 hours off
+        let request = WorkHoursRequest {
+            start_date: "2024-12-23".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2024-12-27".to_string()
+            },
+            working_intervals: vec![
+                WorkingInterval { start: "09:00:00".to_string(), end: "12:00:00".to_string() },
+                WorkingInterval { start: "13:00:00".to_string(), end: "18:00:00".to_string() },
+            ],
+            country: "fr".to_string(),
+            timezone: "UTC".to_string(),
+            exclude_dates: vec!["2024-12-25".to_string()],
+            include_intervals: true,
+            ..Default::default()
+        };
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.work_hours, 24.0);
+        let intervals = response.intervals.unwrap();
+        assert!(intervals.contains(&CountedInterval { start: "2024-12-24T13:00:00+00:00".to_string(), end: "2024-12-24T14:00:00+00:00".to_string() }));
+        assert!(intervals.contains(&CountedInterval { start: "2024-12-27T14:00:00+00:00".to_string(), end: "2024-12-27T18:00:00+00:00".to_string() }));
+        let portions: Vec<db::HolidayPortion> = response.holidays_applied.iter().map(|h| h.portion).collect();
+        assert_eq!(portions, vec![db::HolidayPortion::Afternoon, db::HolidayPortion::Full, db::HolidayPortion::Morning]);
+    }
 
+    #[actix_rt::test]
+    async fn test_holiday_kinds() {
+        let db_data = create_test_db_with_holidays(vec![]);
+        {
+            let db = &db_data.db;
+            for (date, kind) in [("2023-10-03", db::HolidayKind::Bank), ("2023-10-04", db::HolidayKind::Company)] {
+                db.add_holiday(&db::Holiday { id: None, date: date.to_string(), description: "Day off".to_string(), country: "gb".to_string(), portion: db::HolidayPortion::Full, kind, subdivision: None }, "test").unwrap();
+            }
+        }
         let request = WorkHoursRequest {
-            start_date: "2023-10-02T09:00:00Z".to_string(), // Monday
-            end_or_duration: EndOrDuration::Duration { 
-                duration_seconds: 432000 // 5 days
+            start_date: "2023-10-02".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-06".to_string()
             },
             start_of_day: default_start_of_day(),
             end_of_day: default_end_of_day(),
-            country: "us".to_string(),
+            country: "gb".to_string(),
             timezone: "UTC".to_string(),
-            subdivision: None,
+            ..Default::default()
         };
+        // Bank holidays are working days unless the request counts them
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.work_hours, 32.0);
 
-        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
-        let body = result.into_body();
-        let bytes = actix_web::body::to_bytes(body).await.unwrap();
-        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
-
-        assert_eq!(response.work_hours, 40.0);
+        let request = WorkHoursRequest { holiday_kinds: Some(vec![db::HolidayKind::Bank]), ..request };
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.work_hours, 32.0);
+        assert_eq!(response.holidays_applied[0].date, "2023-10-03");
     }
 
     #[actix_rt::test]
-    async fn test_calculate_work_hours_invalid_date_range_equal() {
-        // Test case where start_date equals end_date
+    async fn test_capacity_ramp() {
         let db_data = create_test_db_with_holidays(vec![]);
-
+        // Half days for the first week, then full days
         let request = WorkHoursRequest {
-            start_date: "2023-10-02T09:00:00Z".to_string(),
-            end_or_duration: EndOrDuration::EndDate { 
-                end_date: "2023-10-02T09:00:00Z".to_string() // Same as start_date
+            start_date: "2025-01-06".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2025-01-17".to_string()
             },
             start_of_day: default_start_of_day(),
             end_of_day: default_end_of_day(),
-            country: "us".to_string(),
+            country: "fr".to_string(),
+            timezone: "Europe/Paris".to_string(),
+            ramp: Some(capacity::Ramp {
+                start: "2025-01-06".to_string(),
+                steps: vec![capacity::RampStep { weeks: 1, days: 0, capacity: 0.5 }],
+            }),
+            ..Default::default()
+        };
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.work_hours, 60.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_country_group_merges_holidays() {
+        let db_data = create_test_db_with_holidays(vec![
+            ("2023-10-03".to_string(), "German Unity Day".to_string(), "de".to_string()),
+            ("2023-10-04".to_string(), "Company Day".to_string(), "fr".to_string()),
+        ]);
+        db_data.db.save_country_group(&db::CountryGroup {
+            name: "eu-team".to_string(),
+            countries: vec!["fr".to_string(), "de".to_string()],
+        }).unwrap();
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-06T17:00:00Z".to_string()
+            },
+            country: "EU-team".to_string(),
             timezone: "UTC".to_string(),
-            subdivision: None,
+            ..Default::default()
         };
 
-        let result = calculate_work_hours(db_data, web::Json(request)).await;
-        assert!(result.is_err(), "Expected an error when start_date equals end_date");
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.work_hours, 24.0);
+        let descriptions: Vec<&str> = response.holidays_applied.iter().map(|h| h.description.as_str()).collect();
+        assert_eq!(descriptions, vec!["German Unity Day (de)", "Company Day (fr)"]);
+    }
 
-        if let Err(e) = result {
-            let error_string = format!("{}", e);
-            assert!(error_string.contains("Start date must be strictly before end date"), 
-                   "Error message should mention that start date must be before end date");
-        }
+    #[actix_rt::test]
+    async fn test_explain_lists_upstream_calls() {
+        let db_data = create_test_db_with_holidays(vec![]);
+        let mut request = WorkHoursRequest {
+            start_date: "2023-10-02T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-06T17:00:00Z".to_string()
+            },
+            country: "fr".to_string(),
+            timezone: "UTC".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(compute_work_hours(&db_data, &request).await.unwrap().explain, None);
+
+        request.explain = true;
+        let explain = compute_work_hours(&db_data, &request).await.unwrap().explain.unwrap();
+        assert_eq!(explain.holiday_provider, "mock");
+        // Nothing registered with the mock, so the database answered
+        assert_eq!(explain.upstream_calls.len(), 1);
+        assert_eq!(explain.upstream_calls[0].provider, "database");
+        assert!(explain.upstream_calls[0].error.as_ref().unwrap().contains("No mock holidays"));
     }
 
     #[actix_rt::test]
-    async fn test_calculate_work_hours_invalid_date_range_after() {
-        // Test case where start_date is after end_date
+    async fn test_include_intervals() {
         let db_data = create_test_db_with_holidays(vec![]);
 
         let request = WorkHoursRequest {
-            start_date: "2023-10-03T09:00:00Z".to_string(), // Tuesday
-            end_or_duration: EndOrDuration::EndDate { 
-                end_date: "2023-10-02T09:00:00Z".to_string() // Monday (before start_date)
+            start_date: "2023-10-06T15:00:00Z".to_string(), // Friday
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-09T12:00:00Z".to_string() // Monday
             },
-            start_of_day: default_start_of_day(),
-            end_of_day: default_end_of_day(),
             country: "us".to_string(),
             timezone: "UTC".to_string(),
-            subdivision: None,
+            include_intervals: true,
+            ..Default::default()
         };
 
-        let result = calculate_work_hours(db_data, web::Json(request)).await;
-        assert!(result.is_err(), "Expected an error when start_date is after end_date");
-
-        if let Err(e) = result {
-            let error_string = format!("{}", e);
-            assert!(error_string.contains("Start date must be strictly before end date"), 
-                   "Error message should mention that start date must be before end date");
-        }
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.intervals, Some(vec![
+            CountedInterval {
+                start: "2023-10-06T15:00:00+00:00".to_string(),
+                end: "2023-10-06T17:00:00+00:00".to_string(),
+            },
+            CountedInterval {
+                start: "2023-10-09T09:00:00+00:00".to_string(),
+                end: "2023-10-09T12:00:00+00:00".to_string(),
+            },
+        ]));
     }
 
     #[actix_rt::test]
-    async fn test_calculate_work_hours_with_zero_duration() {
-        // Test case where duration is zero, resulting in start_date equals end_date
+    async fn test_work_duration_output() {
         let db_data = create_test_db_with_holidays(vec![]);
 
-        let request = WorkHoursRequest {
+        let mut request = WorkHoursRequest {
             start_date: "2023-10-02T09:00:00Z".to_string(),
-            end_or_duration: EndOrDuration::Duration { 
-                duration_seconds: 0 // Zero duration
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-06T15:30:00Z".to_string()
             },
-            start_of_day: default_start_of_day(),
-            end_of_day: default_end_of_day(),
             country: "us".to_string(),
             timezone: "UTC".to_string(),
-            subdivision: None,
+            ..Default::default()
         };
 
-        let result = calculate_work_hours(db_data, web::Json(request)).await;
-        assert!(result.is_err(), "Expected an error when duration is zero");
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.work_hours, 38.5);
+        assert_eq!(response.work_duration.as_deref(), Some("PT38H30M"));
 
-        if let Err(e) = result {
-            let error_string = format!("{}", e);
-            assert!(error_string.contains("Start date must be strictly before end date"), 
-                   "Error message should mention that start date must be before end date");
-        }
+        request.output_format = OutputFormat::Decimal;
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.work_duration, None);
     }
 
     #[actix_rt::test]
-    async fn test_calculate_work_hours_with_negative_duration() {
-        // Test case where duration is negative, resulting in end_date before start_date
+    async fn test_date_only_inputs() {
         let db_data = create_test_db_with_holidays(vec![]);
 
-        let request = WorkHoursRequest {
-            start_date: "2023-10-02T09:00:00Z".to_string(),
-            end_or_duration: EndOrDuration::Duration { 
-                duration_seconds: -86400 // Negative duration (1 day)
+        // Monday to Friday, snapped to the schedule
+        let mut request = WorkHoursRequest {
+            start_date: "2023-10-02".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-06".to_string()
             },
-            start_of_day: default_start_of_day(),
-            end_of_day: default_end_of_day(),
             country: "us".to_string(),
-            timezone: "UTC".to_string(),
-            subdivision: None,
+            timezone: "Europe/Paris".to_string(),
+            ..Default::default()
         };
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.work_hours, 40.0);
+        assert_eq!(response.start_date, "2023-10-02T09:00:00+02:00");
+        assert_eq!(response.end_date, "2023-10-06T17:00:00+02:00");
 
-        let result = calculate_work_hours(db_data, web::Json(request)).await;
-        assert!(result.is_err(), "Expected an error when duration is negative");
+        // Night shifts: the last one ends on Saturday morning
+        request.working_intervals = vec![WorkingInterval { start: "22:00:00".to_string(), end: "06:00:00".to_string() }];
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.work_hours, 40.0);
+        assert_eq!(response.end_date, "2023-10-07T06:00:00+02:00");
+    }
 
-        if let Err(e) = result {
-            let error_string = format!("{}", e);
-            assert!(error_string.contains("Start date must be strictly before end date"), 
-                   "Error message should mention that start date must be before end date");
-        }
+    #[actix_rt::test]
+    async fn test_epoch_inputs() {
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        // 2023-10-02T09:00:00Z in seconds and 2023-10-02T17:00:00Z in milliseconds
+        let json = r#"{"startDate": 1696237200, "endDate": 1696266000000, "timezone": "Europe/Paris", "country": "us"}"#;
+        let request: WorkHoursRequest = serde_json::from_str(json).unwrap();
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+
+        // 11:00 to 19:00 in Paris, clipped to the 09:00-17:00 schedule
+        assert_eq!(response.start_date, "2023-10-02T11:00:00+02:00");
+        assert_eq!(response.work_hours, 6.0);
     }
 
     #[actix_rt::test]
-    async fn test_calculate_work_hours_with_custom_day_times() {
-        // Test work hours calculation with custom start and end of day times
-        // 8am to 4pm instead of 9am to 5pm
+    async fn test_timezone_mode_converts_instants() {
+        let db_data = create_test_db_with_holidays(vec![]);
+
+        // 07:00Z is 09:00 in Paris once summer time started on March 26th
+        let mut request = WorkHoursRequest {
+            start_date: "2023-03-27T07:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-03-27T15:00:00Z".to_string()
+            },
+            country: "fr".to_string(),
+            timezone: "Europe/Paris".to_string(),
+            ..Default::default()
+        };
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.start_date, "2023-03-27T09:00:00+02:00");
+        assert_eq!(response.work_hours, 8.0);
+
+        // The legacy behaviour reads 07:00-15:00 as Paris wall-clock time
+        request.timezone_mode = TimezoneMode::AssumeLocal;
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.start_date, "2023-03-27T07:00:00+02:00");
+        assert_eq!(response.work_hours, 6.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_timezone_mode_across_dst_boundaries() {
         let db_data = create_test_db_with_holidays(vec![]);
 
+        // Friday before the spring change to Monday after it
         let request = WorkHoursRequest {
-            start_date: "2023-10-02T08:00:00Z".to_string(), // Monday at 8am
-            end_or_duration: EndOrDuration::EndDate { 
-                end_date: "2023-10-02T16:00:00Z".to_string() // Monday at 4pm
+            start_date: "2023-03-24T08:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-03-27T15:00:00Z".to_string()
             },
-            start_of_day: "08:00:00".to_string(),
-            end_of_day: "16:00:00".to_string(),
-            country: "us".to_string(),
-            timezone: "UTC".to_string(),
-            subdivision: None,
+            country: "fr".to_string(),
+            timezone: "Europe/Paris".to_string(),
+            ..Default::default()
         };
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.start_date, "2023-03-24T09:00:00+01:00");
+        assert_eq!(response.end_date, "2023-03-27T17:00:00+02:00");
+        assert_eq!(response.work_hours, 16.0);
 
-        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
-        let body = result.into_body();
-        let bytes = actix_web::body::to_bytes(body).await.unwrap();
-        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+        // 00:30Z falls in the repeated hour of the autumn change, the instant is kept as is
+        let request = WorkHoursRequest {
+            start_date: "2023-10-29T00:30:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-30T09:00:00Z".to_string()
+            },
+            country: "fr".to_string(),
+            timezone: "Europe/Paris".to_string(),
+            ..Default::default()
+        };
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.start_date, "2023-10-29T02:30:00+02:00");
+        assert_eq!(response.end_date, "2023-10-30T10:00:00+01:00");
+        assert_eq!(response.work_hours, 1.0);
+    }
 
+    #[actix_rt::test]
+    async fn test_timezone_inferred_from_matching_offsets() {
+        let db_data = create_test_db_with_holidays(vec![]);
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02T09:00:00+02:00".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-02T17:00:00+02:00".to_string()
+            },
+            start_of_day: default_start_of_day(),
+            end_of_day: default_end_of_day(),
+            country: "fr".to_string(),
+            ..Default::default()
+        };
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.start_date, "2023-10-02T09:00:00+02:00");
         assert_eq!(response.work_hours, 8.0);
+        assert_eq!(response.warnings[0].code, "W005");
+        assert!(response.warnings[0].message.contains("Etc/GMT-2"));
+
+        // Different offsets still need an explicit timezone
+        let request = WorkHoursRequest {
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-02T17:00:00+01:00".to_string()
+            },
+            ..request
+        };
+        assert!(compute_work_hours(&db_data, &request).await.is_err());
+        assert_eq!(fixed_offset_timezone(0), Some(Tz::UTC));
+        assert_eq!(fixed_offset_timezone(-5 * 3600), Some(Tz::Etc__GMTPlus5));
+        assert_eq!(fixed_offset_timezone(5 * 3600 + 1800), None);
     }
 
     #[actix_rt::test]
-    async fn test_calculate_work_hours_with_partial_custom_day() {
-        // Test work hours calculation with custom start and end of day times
-        // Starting at 10am with custom day from 8am to 4pm
+    async fn test_day_boundaries_in_dst_gaps_and_overlaps() {
         let db_data = create_test_db_with_holidays(vec![]);
 
+        // 02:30 does not exist on the spring change Sunday, the day starts at 03:00
         let request = WorkHoursRequest {
-            start_date: "2023-10-02T10:00:00Z".to_string(), // Monday at 10am
-            end_or_duration: EndOrDuration::EndDate { 
-                end_date: "2023-10-02T16:00:00Z".to_string() // Monday at 4pm
+            start_date: "2023-03-26".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-03-26".to_string()
             },
-            start_of_day: "08:00:00".to_string(),
-            end_of_day: "16:00:00".to_string(),
-            country: "us".to_string(),
-            timezone: "UTC".to_string(),
-            subdivision: None,
+            start_of_day: "02:30:00".to_string(),
+            end_of_day: "06:00:00".to_string(),
+            country: "fr".to_string(),
+            timezone: "Europe/Paris".to_string(),
+            include_dates: vec!["2023-03-26".to_string()],
+            ..Default::default()
         };
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.start_date, "2023-03-26T03:00:00+02:00");
+        assert_eq!(response.work_hours, 3.0);
+        let codes: Vec<&str> = response.warnings.iter().map(|w| w.code.as_str()).collect();
+        // The mock provider has nothing and the database is empty
+        assert_eq!(codes, vec!["W003", "W001", "W004"]);
+
+        // 02:30 happens twice on the autumn change Sunday, the first occurrence is used
+        let request = WorkHoursRequest {
+            start_date: "2023-10-29".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-29".to_string()
+            },
+            start_of_day: "02:30:00".to_string(),
+            end_of_day: "04:00:00".to_string(),
+            country: "fr".to_string(),
+            timezone: "Europe/Paris".to_string(),
+            include_dates: vec!["2023-10-29".to_string()],
+            ..Default::default()
+        };
+        let response = compute_work_hours(&db_data, &request).await.unwrap();
+        assert_eq!(response.start_date, "2023-10-29T02:30:00+02:00");
+        assert_eq!(response.end_date, "2023-10-29T04:00:00+01:00");
+        assert_eq!(response.work_hours, 2.5);
+        assert_eq!(response.warnings[0].name, "dst_ambiguous_time_resolved_earliest");
+    }
 
-        let result = calculate_work_hours(db_data, web::Json(request)).await.unwrap();
-        let body = result.into_body();
-        let bytes = actix_web::body::to_bytes(body).await.unwrap();
-        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+    #[test]
+    fn test_weekdays_between() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        // Wednesday to the Tuesday two weeks later
+        assert_eq!(weekdays_between(date("2023-10-04"), date("2023-10-17"), &DEFAULT_WEEKEND), 10);
+        assert_eq!(weekdays_between(date("2023-10-07"), date("2023-10-08"), &DEFAULT_WEEKEND), 0);
+        assert_eq!(weekdays_between(date("2023-10-06"), date("2023-10-09"), &DEFAULT_WEEKEND), 2);
+        assert_eq!(weekdays_between(date("2023-10-09"), date("2023-10-06"), &DEFAULT_WEEKEND), 0);
+        // Friday and Saturday off: Thursday 5 to Sunday 8 counts Thursday and Sunday
+        let fri_sat = [chrono::Weekday::Fri, chrono::Weekday::Sat];
+        assert_eq!(weekdays_between(date("2023-10-05"), date("2023-10-08"), &fri_sat), 2);
+        assert_eq!(weekdays_between(date("2023-10-04"), date("2023-10-17"), &fri_sat), 10);
+    }
 
-        assert_eq!(response.work_hours, 6.0); // 8am to 4pm = 8 hours
+    #[actix_rt::test]
+    async fn test_closed_form_totals_match_day_by_day_intervals() {
+        let db_data = create_test_db_with_holidays(vec![
+            ("2023-05-01".to_string(), "Labour Day".to_string(), "fr".to_string()),
+            ("2024-12-25".to_string(), "Christmas Day".to_string(), "fr".to_string()),
+            ("2025-07-14".to_string(), "Bastille Day".to_string(), "fr".to_string()),
+        ]);
+
+        // Several years across daylight saving changes, with a shift starting in the
+        // skipped hour and overnight shifts running through both changes
+        let cases = vec![
+            vec![],
+            vec![WorkingInterval { start: "02:30:00".to_string(), end: "10:00:00".to_string() }],
+            vec![WorkingInterval { start: "22:00:00".to_string(), end: "06:00:00".to_string() }],
+        ];
+        for working_intervals in cases {
+            let request = WorkHoursRequest {
+                start_date: "2023-01-02T10:30:00+01:00".to_string(),
+                end_or_duration: EndOrDuration::EndDate {
+                    end_date: "2026-06-15T15:00:00+02:00".to_string()
+                },
+                country: "fr".to_string(),
+                timezone: "Europe/Paris".to_string(),
+                exclude_dates: vec!["2024-03-05".to_string()],
+                include_dates: vec!["2025-03-29".to_string(), "2023-10-29".to_string()],
+                working_intervals,
+                ..Default::default()
+            };
+            let intervals = compute_working_time(&db_data, &request).await.unwrap();
+            let totals = compute_working_totals(&db_data, &request).await.unwrap();
+
+            assert!(totals.intervals.len() < 10);
+            assert_eq!(totals.work_seconds(), intervals.work_seconds());
+            assert_eq!(totals.holidays_applied.len(), 4);
+            assert_eq!(
+                totals.holidays_applied.iter().map(|h| &h.date).collect::<Vec<_>>(),
+                intervals.holidays_applied.iter().map(|h| &h.date).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    // Holidays of the two years of the date only, like the remote providers
+    struct TwoYearProvider;
+
+    #[async_trait::async_trait]
+    impl holiday_provider::HolidayProvider for TwoYearProvider {
+        fn name(&self) -> &'static str {
+            "two-year"
+        }
+
+        async fn holidays(&self, _db: &db::Database, _country: &CountryCode, _subdivision: &str, date: NaiveDate) -> Result<Vec<holidays_api::Holiday>, String> {
+            Ok((date.year()..=date.year() + 1).map(|year| holidays_api::Holiday {
+                date: format!("{}-03-03", year),
+                description: "Founders Day".to_string(),
+                portion: db::HolidayPortion::Full,
+                kind: db::HolidayKind::Public,
+            }).collect())
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_holidays_are_loaded_for_every_year_of_the_range() {
+        let data = web::Data::new(AppState {
+            db: db::Database::new(":memory:").unwrap(),
+            holidays: Box::new(TwoYearProvider),
+            http: reqwest::Client::new(),
+        });
+        let request = WorkHoursRequest {
+            start_date: "2025-01-01".to_string(),
+            end_or_duration: EndOrDuration::EndDate { end_date: "2028-12-31".to_string() },
+            country: "fr".to_string(),
+            timezone: "UTC".to_string(),
+            ..Default::default()
+        };
+        let totals = compute_working_totals(&data, &request).await.unwrap();
 
+        // March 3rd is a weekday from 2025 to 2028, the last two years come from a second window
+        assert_eq!(
+            totals.holidays_applied.iter().map(|h| h.date.as_str()).collect::<Vec<_>>(),
+            vec!["2025-03-03", "2026-03-03", "2027-03-03", "2028-03-03"]
+        );
     }
 }
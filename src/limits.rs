@@ -0,0 +1,447 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse};
+use serde::{Serialize, Deserialize};
+use log::{info, warn};
+
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+// Replayed responses are kept for a day by default
+const DEFAULT_IDEMPOTENCY_TTL_SECONDS: u64 = 24 * 60 * 60;
+
+// Held by the first request with a key until its response is stored, so that a concurrent
+// retry is rejected instead of running again. Expires in case the replica dies meanwhile.
+const IDEMPOTENCY_PENDING: &str = "pending";
+const IDEMPOTENCY_PENDING_TTL: Duration = Duration::from_secs(5 * 60);
+
+// Counters and stored responses: per pod in memory, cluster-wide in Redis
+pub enum Store {
+    Memory(Mutex<HashMap<String, (SystemTime, String)>>),
+    Redis(redis::aio::ConnectionManager),
+}
+
+impl Store {
+    async fn incr(&self, key: &str, ttl: Duration) -> Result<u64, String> {
+        match self {
+            Store::Memory(entries) => {
+                let mut entries = entries.lock().unwrap();
+                let now = SystemTime::now();
+                // A new counter is started per client and minute, expired ones are dropped then
+                if !entries.contains_key(key) {
+                    entries.retain(|_, (expiration, _)| *expiration > now);
+                }
+                let entry = entries.entry(key.to_string()).or_insert((now + ttl, "0".to_string()));
+                if entry.0 <= now {
+                    *entry = (now + ttl, "0".to_string());
+                }
+                let count = entry.1.parse::<u64>().unwrap_or(0) + 1;
+                entry.1 = count.to_string();
+                Ok(count)
+            }
+            Store::Redis(connection) => {
+                let mut connection = connection.clone();
+                let count: u64 = redis::cmd("INCR").arg(key)
+                    .query_async(&mut connection).await.map_err(|e| e.to_string())?;
+                if count == 1 {
+                    redis::cmd("EXPIRE").arg(key).arg(ttl.as_secs())
+                        .query_async::<_, ()>(&mut connection).await.map_err(|e| e.to_string())?;
+                }
+                Ok(count)
+            }
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, String> {
+        match self {
+            Store::Memory(entries) => {
+                let entries = entries.lock().unwrap();
+                Ok(entries.get(key)
+                    .filter(|(expiration, _)| *expiration > SystemTime::now())
+                    .map(|(_, value)| value.clone()))
+            }
+            Store::Redis(connection) => {
+                let mut connection = connection.clone();
+                redis::cmd("GET").arg(key).query_async(&mut connection).await.map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    // Sets the value unless the key is already present, returns whether it was set
+    async fn set_if_absent(&self, key: &str, value: &str, ttl: Duration) -> Result<bool, String> {
+        match self {
+            Store::Memory(entries) => {
+                let mut entries = entries.lock().unwrap();
+                let now = SystemTime::now();
+                if entries.get(key).is_some_and(|(expiration, _)| *expiration > now) {
+                    return Ok(false);
+                }
+                entries.insert(key.to_string(), (now + ttl, value.to_string()));
+                Ok(true)
+            }
+            Store::Redis(connection) => {
+                let mut connection = connection.clone();
+                let set: Option<String> = redis::cmd("SET").arg(key).arg(value).arg("NX").arg("EX").arg(ttl.as_secs())
+                    .query_async(&mut connection).await.map_err(|e| e.to_string())?;
+                Ok(set.is_some())
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        match self {
+            Store::Memory(entries) => {
+                entries.lock().unwrap().remove(key);
+                Ok(())
+            }
+            Store::Redis(connection) => {
+                let mut connection = connection.clone();
+                redis::cmd("DEL").arg(key).query_async(&mut connection).await.map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<(), String> {
+        match self {
+            Store::Memory(entries) => {
+                let mut entries = entries.lock().unwrap();
+                let now = SystemTime::now();
+                entries.retain(|_, (expiration, _)| *expiration > now);
+                entries.insert(key.to_string(), (now + ttl, value.to_string()));
+                Ok(())
+            }
+            Store::Redis(connection) => {
+                let mut connection = connection.clone();
+                redis::cmd("SET").arg(key).arg(value).arg("EX").arg(ttl.as_secs())
+                    .query_async(&mut connection).await.map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+pub struct Limits {
+    pub store: Store,
+    // Requests per minute and client, unlimited when None
+    pub per_minute: Option<u64>,
+    pub idempotency_ttl: Duration,
+    // X-Api-Key values issued to clients, any other key is ignored
+    pub api_keys: HashSet<String>,
+    // Reverse proxies whose Forwarded and X-Forwarded-For headers are believed
+    pub trusted_proxies: HashSet<IpAddr>,
+}
+
+// Response replayed for a repeated Idempotency-Key
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredResponse {
+    status: u16,
+    content_type: Option<String>,
+    body: String,
+}
+
+impl Limits {
+    pub fn in_memory(per_minute: Option<u64>) -> Self {
+        Limits {
+            store: Store::Memory(Mutex::new(HashMap::new())),
+            per_minute,
+            idempotency_ttl: Duration::from_secs(DEFAULT_IDEMPOTENCY_TTL_SECONDS),
+            api_keys: HashSet::new(),
+            trusted_proxies: HashSet::new(),
+        }
+    }
+
+    // REDIS_URL shares the limits between replicas, RATE_LIMIT_PER_MINUTE enables rate limiting,
+    // API_KEYS lists the comma-separated keys clients are told apart by, TRUSTED_PROXIES the
+    // comma-separated addresses of the proxies that may forward the address of a client
+    pub async fn from_env() -> Self {
+        let per_minute = env::var("RATE_LIMIT_PER_MINUTE").ok().and_then(|v| v.parse().ok());
+        let mut limits = Limits::in_memory(per_minute);
+        limits.api_keys = env::var("API_KEYS").unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(str::to_string)
+            .collect();
+        limits.trusted_proxies = env::var("TRUSTED_PROXIES").unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|proxy| !proxy.is_empty())
+            .filter_map(|proxy| proxy.parse().map_err(|_| warn!("Ignoring invalid trusted proxy: {}", proxy)).ok())
+            .collect();
+        if let Some(ttl) = env::var("IDEMPOTENCY_TTL_SECONDS").ok().and_then(|v| v.parse().ok()) {
+            limits.idempotency_ttl = Duration::from_secs(ttl);
+        }
+        if let Ok(url) = env::var("REDIS_URL") {
+            let connection = match redis::Client::open(url.as_str()) {
+                Ok(client) => redis::aio::ConnectionManager::new(client).await.map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            match connection {
+                Ok(connection) => {
+                    info!("Rate limits and idempotency keys are stored in Redis");
+                    limits.store = Store::Redis(connection);
+                }
+                Err(e) => warn!("Failed to connect to Redis, limits are enforced per instance: {}", e),
+            }
+        }
+        limits
+    }
+}
+
+// Clients are told apart by their API key, or by their address. Only issued keys and the
+// forwarding headers of trusted proxies are believed, otherwise a client could get a fresh
+// rate limit by sending another key or another forwarded address.
+fn client_key(req: &ServiceRequest, limits: &Limits) -> String {
+    if let Some(key) = req.headers().get("X-Api-Key")
+        .and_then(|value| value.to_str().ok())
+        .filter(|key| limits.api_keys.contains(*key))
+    {
+        return format!("key:{}", key);
+    }
+    let peer = req.peer_addr().map(|addr| addr.ip());
+    match peer {
+        Some(peer) if limits.trusted_proxies.contains(&peer) => {
+            format!("ip:{}", req.connection_info().realip_remote_addr().unwrap_or("unknown"))
+        }
+        Some(peer) => format!("ip:{}", peer),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+// Store failures never reject a request: the limits are a protection, not a dependency
+pub async fn enforce(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::error::Error> {
+    let limits = match req.app_data::<web::Data<Limits>>() {
+        Some(limits) => limits.clone(),
+        None => return Ok(next.call(req).await?.map_into_boxed_body()),
+    };
+    let client = client_key(&req, &limits);
+
+    if let Some(per_minute) = limits.per_minute {
+        let window = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs() / 60;
+        match limits.store.incr(&format!("ratelimit:{}:{}", client, window), RATE_LIMIT_WINDOW).await {
+            Ok(count) if count > per_minute => {
+                let response = HttpResponse::TooManyRequests()
+                    .insert_header((header::RETRY_AFTER, RATE_LIMIT_WINDOW.as_secs().to_string()))
+                    .json(format!("Rate limit of {} requests per minute exceeded", per_minute));
+                return Ok(req.into_response(response));
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Rate limit store unavailable: {}", e),
+        }
+    }
+
+    let idempotency_key = req.headers().get("Idempotency-Key")
+        .and_then(|value| value.to_str().ok())
+        .filter(|_| req.method() == Method::POST)
+        .map(|key| format!("idempotency:{}:{}:{}", client, req.path(), key));
+    let idempotency_key = match idempotency_key {
+        Some(key) => key,
+        None => return Ok(next.call(req).await?.map_into_boxed_body()),
+    };
+
+    match limits.store.set_if_absent(&idempotency_key, IDEMPOTENCY_PENDING, IDEMPOTENCY_PENDING_TTL).await {
+        Ok(true) => {}
+        Ok(false) => {
+            let stored = limits.store.get(&idempotency_key).await.unwrap_or_else(|e| {
+                warn!("Idempotency store unavailable: {}", e);
+                None
+            });
+            if let Some(stored) = stored.and_then(|stored| serde_json::from_str::<StoredResponse>(&stored).ok()) {
+                let mut response = HttpResponse::build(StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK));
+                if let Some(content_type) = stored.content_type {
+                    response.content_type(content_type);
+                }
+                response.insert_header(("Idempotent-Replayed", "true"));
+                return Ok(req.into_response(response.body(stored.body)));
+            }
+            let response = HttpResponse::Conflict()
+                .json("A request with this Idempotency-Key is still in progress");
+            return Ok(req.into_response(response));
+        }
+        Err(e) => warn!("Idempotency store unavailable: {}", e),
+    }
+
+    let response = match next.call(req).await {
+        Ok(response) => response,
+        Err(e) => {
+            release(&limits, &idempotency_key).await;
+            return Err(e);
+        }
+    };
+    let (req, response) = response.into_parts();
+    let (response, response_body) = response.into_parts();
+    let bytes = match body::to_bytes(response_body).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            release(&limits, &idempotency_key).await;
+            let e: Box<dyn std::error::Error> = e.into();
+            return Err(actix_web::error::ErrorInternalServerError(e.to_string()));
+        }
+    };
+
+    // Only successful text responses are replayed, failed attempts may be retried
+    match std::str::from_utf8(&bytes).ok().filter(|_| response.status().is_success()) {
+        Some(text) => {
+            let stored = StoredResponse {
+                status: response.status().as_u16(),
+                content_type: response.headers().get(header::CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string),
+                body: text.to_string(),
+            };
+            let stored = serde_json::to_string(&stored).unwrap_or_default();
+            if let Err(e) = limits.store.set(&idempotency_key, &stored, limits.idempotency_ttl).await {
+                warn!("Idempotency store unavailable: {}", e);
+            }
+        }
+        None => release(&limits, &idempotency_key).await,
+    }
+    Ok(ServiceResponse::new(req, response.set_body(bytes).map_into_boxed_body()))
+}
+
+// Lets a failed attempt be retried with the same key
+async fn release(limits: &Limits, idempotency_key: &str) {
+    if let Err(e) = limits.store.delete(idempotency_key).await {
+        warn!("Idempotency store unavailable: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use actix_web::middleware::from_fn;
+    use actix_web::{test, App};
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static SLOW_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    async fn create() -> HttpResponse {
+        HttpResponse::Created().json(CALLS.fetch_add(1, Ordering::SeqCst))
+    }
+
+    async fn create_slowly() -> HttpResponse {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        match SLOW_CALLS.fetch_add(1, Ordering::SeqCst) {
+            0 => HttpResponse::ServiceUnavailable().finish(),
+            calls => HttpResponse::Created().json(calls),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_rate_limit_and_idempotency() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Limits::in_memory(Some(3))))
+                .wrap(from_fn(enforce))
+                .route("/things", web::post().to(create))
+        ).await;
+        let request = |key: &str| test::TestRequest::post()
+            .uri("/things")
+            .insert_header(("X-Api-Key", "tenant-a"))
+            .insert_header(("Idempotency-Key", key.to_string()))
+            .to_request();
+
+        let first: usize = test::call_and_read_body_json(&app, request("order-1")).await;
+        // The retry is answered from the store without running the handler again
+        let response = test::call_service(&app, request("order-1")).await;
+        assert_eq!(response.status(), 201);
+        assert!(response.headers().contains_key("Idempotent-Replayed"));
+        let replayed: usize = test::read_body_json(response).await;
+        assert_eq!(replayed, first);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+        let response = test::call_service(&app, request("order-2")).await;
+        assert_eq!(response.status(), 201);
+        let response = test::call_service(&app, request("order-3")).await;
+        assert_eq!(response.status(), 429);
+    }
+
+    #[actix_rt::test]
+    async fn test_only_issued_api_keys_get_their_own_limit() {
+        let mut limits = Limits::in_memory(Some(2));
+        limits.api_keys.insert("tenant-a".to_string());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(limits))
+                .wrap(from_fn(enforce))
+                .route("/things", web::get().to(|| async { HttpResponse::Ok().finish() }))
+        ).await;
+        let request = |key: &str| test::TestRequest::get().uri("/things").insert_header(("X-Api-Key", key.to_string())).to_request();
+
+        // Made-up keys share the limit of the address
+        for (key, status) in [("made-up-1", 200), ("made-up-2", 200), ("made-up-3", 429), ("tenant-a", 200)] {
+            assert_eq!(test::call_service(&app, request(key)).await.status().as_u16(), status, "{}", key);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_forwarded_addresses_are_only_believed_from_trusted_proxies() {
+        let mut limits = Limits::in_memory(Some(2));
+        limits.trusted_proxies.insert("10.0.0.9".parse().unwrap());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(limits))
+                .wrap(from_fn(enforce))
+                .route("/things", web::get().to(|| async { HttpResponse::Ok().finish() }))
+        ).await;
+        let request = |peer: &str, forwarded: &str| test::TestRequest::get()
+            .uri("/things")
+            .peer_addr(format!("{}:4000", peer).parse().unwrap())
+            .insert_header(("X-Forwarded-For", forwarded.to_string()))
+            .to_request();
+
+        // A client changing its forwarded address keeps the limit of its own address
+        for (forwarded, status) in [("203.0.113.1", 200), ("203.0.113.2", 200), ("203.0.113.3", 429)] {
+            assert_eq!(test::call_service(&app, request("192.0.2.7", forwarded)).await.status().as_u16(), status, "{}", forwarded);
+        }
+        // Behind a trusted proxy each forwarded client has its own limit
+        for forwarded in ["203.0.113.1", "203.0.113.2", "203.0.113.3"] {
+            assert_eq!(test::call_service(&app, request("10.0.0.9", forwarded)).await.status(), 200, "{}", forwarded);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_concurrent_retries_run_once() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Limits::in_memory(None)))
+                .wrap(from_fn(enforce))
+                .route("/things", web::post().to(create_slowly))
+        ).await;
+        let request = || test::TestRequest::post()
+            .uri("/things")
+            .insert_header(("Idempotency-Key", "order-1"))
+            .to_request();
+
+        // The retry sent while the first attempt runs is rejected, the failed attempt frees the key
+        let (first, retry) = futures_util::future::join(test::call_service(&app, request()), test::call_service(&app, request())).await;
+        assert_eq!((first.status().as_u16(), retry.status().as_u16()), (503, 409));
+        let (first, retry) = futures_util::future::join(test::call_service(&app, request()), test::call_service(&app, request())).await;
+        assert_eq!((first.status().as_u16(), retry.status().as_u16()), (201, 409));
+        assert_eq!(SLOW_CALLS.load(Ordering::SeqCst), 2);
+
+        let response = test::call_service(&app, request()).await;
+        assert_eq!(response.status(), 201);
+        assert!(response.headers().contains_key("Idempotent-Replayed"));
+        assert_eq!(SLOW_CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_expired_counters_are_pruned() {
+        let store = Store::Memory(Mutex::new(HashMap::new()));
+        for minute in 0..5 {
+            store.incr(&format!("ratelimit:ip:10.0.0.1:{}", minute), Duration::ZERO).await.unwrap();
+        }
+        let Store::Memory(entries) = &store else { unreachable!() };
+        assert_eq!(entries.lock().unwrap().len(), 1);
+    }
+}
@@ -1,21 +1,74 @@
-use actix_web::{web, App, HttpServer, middleware::Logger, get, Responder, HttpResponse};
+use actix_web::{web, App, HttpServer, middleware::{from_fn, Condition, Logger}, get, Responder, HttpResponse};
 use log::info;
-use std::sync::Mutex;
-use env_logger;
 use dotenv::dotenv;
 use std::env;
 
 // Import from the library
 use workhours::{
     db,
+    breaker,
+    holiday_cache,
+    clients,
+    countries,
+    holiday_import,
+    holiday_audit,
+    ics_import,
+    ics_export,
+    custom_holidays,
+    holiday_check,
+    holiday_list,
+    duplicates,
     get_work_hours,
+    is_working,
+    first_working_day,
+    nth_working_day,
     AppState,
-    openapi
+    openapi,
+    calendar_config,
+    calendar_resolver,
+    calendar_versions,
+    impact,
+    chunked,
+    consumer,
+    holiday_provider,
+    holidays_api,
+    limits,
+    leader,
+    prefetch,
+    presets,
+    examples,
+    risk,
+    coverage,
+    segments,
+    follow_the_sun,
+    oncall,
+    deadline,
+    notice,
+    prorata,
+    rest,
+    timesheet,
+    wtd,
+    toil,
+    schedules,
+    saved_queries,
+    warnings,
+    freeze,
+    country_groups,
+    weekdays,
+    blackouts,
+    staffing
 };
 
+// Still 200 while a breaker is open: the service answers from its fallback holidays
 #[get("/health")]
 async fn health() -> impl Responder {
-    HttpResponse::Ok().body("OK")
+    let breakers = breaker::statuses();
+    let status = if breakers.iter().any(|b| b.state == "open") { "degraded" } else { "ok" };
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": status,
+        "circuit_breakers": breakers,
+        "holiday_cache": holiday_cache::stats().await,
+    }))
 }
 
 #[actix_web::main]
@@ -34,9 +87,40 @@ async fn main() -> std::io::Result<()> {
     let server_url = format!("{}:{}", server_host, server_port);
 
     let database = db::Database::new(&db_location).expect("Failed to initialize database");
+
+    // Reconcile calendars with the declarative configuration file, if any
+    if let Ok(config_path) = env::var("CALENDAR_CONFIG") {
+        let config = calendar_config::load_from_file(&config_path).expect("Failed to load calendar configuration");
+        calendar_config::apply(&database, &config, "config-file").expect("Failed to apply calendar configuration");
+        info!("Calendar configuration: {}", config_path);
+    }
+
+    let http = holidays_api::client_from_env();
     let app_state = web::Data::new(AppState {
-        db: Mutex::new(database),
+        db: database,
+        holidays: holiday_provider::from_env(&http),
+        http,
     });
+
+    // Only the elected replica runs background jobs
+    let elector = web::Data::new(leader::LeaderElector::from_env());
+    leader::spawn(elector.clone().into_inner(), app_state.clone()).await;
+    info!("Instance id: {}", elector.instance_id);
+
+    // Warm the holiday cache and database at startup and on an interval, on the leader
+    if let Some(config) = prefetch::PrefetchConfig::from_env() {
+        prefetch::spawn(config, app_state.clone(), elector.clone().into_inner());
+    }
+
+    // Optional consumer mode for calculation requests sent through a message queue
+    if let Some(config) = consumer::ConsumerConfig::from_env() {
+        consumer::spawn(config, app_state.clone());
+    }
+
+    // Rate limiting and idempotency keys, shared between replicas when REDIS_URL is set
+    let limits = web::Data::new(limits::Limits::from_env().await);
+    let cache_header = env::var("HOLIDAY_CACHE_HEADER").is_ok_and(|value| value == "true");
+
     info!("Database location: {}", db_location);
     info!("Server URL: {}", server_url);
     info!("Starting server...");
@@ -46,9 +130,84 @@ async fn main() -> std::io::Result<()> {
     HttpServer::new(move || {
         App::new()
             .app_data(app_state.clone())
+            .app_data(elector.clone())
+            .app_data(limits.clone())
+            .wrap(from_fn(limits::enforce))
+            .wrap(Condition::new(cache_header, from_fn(holiday_cache::report_cache)))
             .wrap(Logger::default())
             .service(health)
+            .service(holiday_cache::list_cache)
+            .service(holiday_cache::invalidate)
+            .service(holiday_cache::purge)
+            .service(clients::download_client)
             .service(get_work_hours)
+            .service(is_working)
+            .service(first_working_day)
+            .service(nth_working_day)
+            .service(chunked::calculate_chunked)
+            .service(calendar_config::apply_config)
+            .service(calendar_config::create_calendar)
+            .service(custom_holidays::add_holidays)
+            .service(custom_holidays::list_holidays)
+            .service(custom_holidays::get_holiday)
+            .service(custom_holidays::update_holiday)
+            .service(custom_holidays::delete_holiday)
+            .service(holiday_import::import_holidays)
+            .service(ics_import::import_country_ics)
+            .service(ics_import::import_calendar_ics)
+            .service(holiday_check::check_batch)
+            .service(holiday_check::check)
+            .service(holiday_audit::list_audit)
+            .service(holiday_audit::get_snapshot)
+            .service(holiday_list::list_year_holidays)
+            .service(ics_export::export_calendar)
+            .service(countries::list_countries)
+            .service(countries::list_subdivisions)
+            .service(duplicates::list_duplicates)
+            .service(duplicates::merge_duplicates)
+            .service(calendar_resolver::get_resolved_calendar)
+            .service(calendar_versions::list_versions)
+            .service(calendar_versions::rollback)
+            .service(impact::evaluate_impact)
+            .service(leader::leader_status)
+            .service(presets::get_preset)
+            .service(examples::list_examples)
+            .service(risk::get_holiday_risk)
+            .service(coverage::get_coverage)
+            .service(segments::calculate_segments)
+            .service(follow_the_sun::calculate_follow_the_sun)
+            .service(oncall::list_on_call_periods)
+            .service(oncall::add_on_call_periods)
+            .service(oncall::delete_on_call_period)
+            .service(deadline::calculate_deadline)
+            .service(deadline::calculate_promise)
+            .service(notice::calculate_notice_period)
+            .service(prorata::calculate_prorata)
+            .service(rest::validate_rest)
+            .service(timesheet::check_timesheet)
+            .service(wtd::working_time_directive)
+            .service(toil::get_toil_balance)
+            .service(toil::accrue_toil)
+            .service(toil::take_toil)
+            .service(blackouts::list_blackout_periods)
+            .service(blackouts::add_blackout_periods)
+            .service(blackouts::delete_blackout_period)
+            .service(staffing::check_staffing)
+            .configure(schedules::configure)
+            .service(saved_queries::list_queries)
+            .service(saved_queries::save_query)
+            .service(saved_queries::get_query)
+            .service(saved_queries::delete_query)
+            .service(saved_queries::run_query)
+            .service(warnings::list_warnings)
+            .service(freeze::freeze)
+            .service(freeze::get_freeze)
+            .service(freeze::unfreeze)
+            .service(country_groups::list_groups)
+            .service(country_groups::get_group)
+            .service(country_groups::save_group)
+            .service(country_groups::delete_group)
+            .service(weekdays::weekday_histogram)
             .service(openapi::swagger_routes())
     })
     .bind(&server_url)?
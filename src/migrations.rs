@@ -0,0 +1,280 @@
+use log::{info, warn};
+use rusqlite::{Connection, Result, TransactionBehavior, params};
+
+// Step of the schema, applied once in its own transaction and recorded in schema_version
+struct Migration {
+    version: i64,
+    description: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+// New migrations are appended with the next version, applied ones are never edited. The first three
+// stay idempotent: databases created before schema_version existed run them over whatever they hold.
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, description: "initial schema", apply: initial_schema },
+    Migration { version: 2, description: "holiday subdivisions", apply: regional_holidays },
+    Migration { version: 3, description: "holiday timestamps and audit log", apply: holiday_audit },
+    Migration { version: 4, description: "audit log index", apply: audit_log_index },
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+pub fn current_version(conn: &Connection) -> Result<i64> {
+    conn.query_row("SELECT COALESCE(MAX(version), 0) FROM schema_version", [], |row| row.get(0))
+}
+
+// Brings the database to the latest version. A database migrated by a newer release is left as
+// it is, with a warning.
+pub fn migrate(conn: &mut Connection) -> Result<i64> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    let current = current_version(conn)?;
+    if current > latest_version() {
+        warn!("Database schema version {} is newer than this release, which knows up to {}", current, latest_version());
+        return Ok(current);
+    }
+    for migration in MIGRATIONS.iter().filter(|migration| migration.version > current) {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        (migration.apply)(&tx)?;
+        tx.execute(
+            "INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)",
+            params![migration.version, migration.description, chrono::Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+        info!("Migrated the database to version {}: {}", migration.version, migration.description);
+    }
+    current_version(conn)
+}
+
+fn initial_schema(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS holidays (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            description TEXT,
+            country TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS calendars (
+            id TEXT PRIMARY KEY,
+            country TEXT NOT NULL,
+            subdivision TEXT,
+            start_of_day TEXT NOT NULL,
+            end_of_day TEXT NOT NULL,
+            weekend TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS calendar_closures (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            calendar_id TEXT NOT NULL,
+            date TEXT,
+            recurring TEXT,
+            description TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS leases (
+            name TEXT PRIMARY KEY,
+            holder TEXT NOT NULL,
+            expires_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recurring_holidays (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            country TEXT NOT NULL,
+            rule TEXT NOT NULL,
+            description TEXT NOT NULL,
+            kind TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS on_call_periods (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            calendar_id TEXT NOT NULL,
+            start TEXT NOT NULL,
+            end TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS blackout_periods (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            calendar_id TEXT NOT NULL,
+            start TEXT NOT NULL,
+            end TEXT NOT NULL,
+            description TEXT NOT NULL,
+            mode TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS toil_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            employee TEXT NOT NULL,
+            date TEXT NOT NULL,
+            hours REAL NOT NULL,
+            description TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schedules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            owner TEXT NOT NULL,
+            valid_from TEXT NOT NULL,
+            valid_to TEXT,
+            start_of_day TEXT NOT NULL,
+            end_of_day TEXT NOT NULL,
+            working_intervals TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS calendar_versions (
+            calendar_id TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            author TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            changes TEXT NOT NULL,
+            snapshot TEXT,
+            PRIMARY KEY (calendar_id, version)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schedule_scripts (
+            owner TEXT PRIMARY KEY,
+            script TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS saved_queries (
+            name TEXT PRIMARY KEY,
+            request TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS country_groups (
+            name TEXT PRIMARY KEY,
+            countries TEXT NOT NULL
+        )",
+        [],
+    )?;
+    // At most one freeze at a time
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS freezes (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            until TEXT NOT NULL,
+            author TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    ensure_column(conn, "calendars", "parent", "TEXT")?;
+    // Provider that fetched the holiday and its year, NULL for holidays added through the API
+    ensure_column(conn, "holidays", "source", "TEXT")?;
+    ensure_column(conn, "holidays", "year", "INTEGER")?;
+    ensure_column(conn, "holidays", "portion", "TEXT")?;
+    ensure_column(conn, "holidays", "kind", "TEXT NOT NULL DEFAULT 'public'")?;
+    // When a provider returned the holiday (RFC3339), NULL for holidays added through the API
+    ensure_column(conn, "holidays", "fetched_at", "TEXT")?;
+    Ok(())
+}
+
+fn regional_holidays(conn: &Connection) -> Result<()> {
+    // Existing rows keep NULL: they were nationwide for every calculation so far
+    ensure_column(conn, "holidays", "subdivision", "TEXT")?;
+    Ok(())
+}
+
+fn holiday_audit(conn: &Connection) -> Result<()> {
+    // RFC3339 instants, NULL for rows written before they were recorded. Deleted holidays are kept
+    // for the audit log and left out of every read.
+    ensure_column(conn, "holidays", "created_at", "TEXT")?;
+    ensure_column(conn, "holidays", "updated_at", "TEXT")?;
+    ensure_column(conn, "holidays", "deleted_at", "TEXT")?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            holiday_id INTEGER NOT NULL,
+            country TEXT NOT NULL,
+            action TEXT NOT NULL,
+            author TEXT NOT NULL,
+            changed_at TEXT NOT NULL,
+            before TEXT,
+            after TEXT
+        )",
+        [],
+    )?;
+    // Replaced by holidays_unique_live_day, which leaves deleted holidays out
+    conn.execute("DROP INDEX IF EXISTS holidays_unique_day", [])?;
+    Ok(())
+}
+
+fn audit_log_index(conn: &Connection) -> Result<()> {
+    conn.execute("CREATE INDEX audit_log_holiday ON audit_log (country, holiday_id)", [])?;
+    Ok(())
+}
+
+// Add a column to a table created by an earlier version of the schema
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt.query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>>>()?
+        .iter()
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::country::CountryCode;
+
+    #[test]
+    fn test_databases_are_migrated_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("legacy.db");
+        let path = path.to_str().unwrap();
+        // As created by the first releases, before schema_version
+        let conn = Connection::open(path).unwrap();
+        conn.execute("CREATE TABLE holidays (id INTEGER PRIMARY KEY AUTOINCREMENT, date TEXT NOT NULL, description TEXT, country TEXT NOT NULL)", []).unwrap();
+        conn.execute("INSERT INTO holidays (date, description, country) VALUES ('2025-07-14', 'Bastille Day', 'fr')", []).unwrap();
+        drop(conn);
+
+        let db = Database::new(path).unwrap();
+        assert_eq!(db.schema_version().unwrap(), latest_version());
+        let holidays = db.get_holidays_by_country(&CountryCode::parse("fr").unwrap(), None).unwrap();
+        assert_eq!(holidays[0].description, "Bastille Day");
+        drop(db);
+
+        // Reopening applies nothing, a fresh database gets every version
+        drop(Database::new(path).unwrap());
+        let conn = Connection::open(path).unwrap();
+        let applied: i64 = conn.query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(applied, MIGRATIONS.len() as i64);
+        assert_eq!(Database::new(":memory:").unwrap().schema_version().unwrap(), latest_version());
+    }
+}
@@ -0,0 +1,131 @@
+use actix_web::{web, HttpResponse, post};
+use chrono::{Datelike, Months, NaiveDate};
+use serde::{Serialize, Deserialize};
+
+use crate::{country, AppState};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoticePeriodRequest {
+    // Date the notice is given (YYYY-MM-DD)
+    #[serde(rename = "noticeDate")]
+    pub notice_date: String,
+    #[serde(default)]
+    pub months: u32,
+    #[serde(default)]
+    pub weeks: u32,
+    // Many contracts end the notice at the end of a calendar month
+    #[serde(rename = "endOfMonth", default)]
+    pub end_of_month: bool,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoticePeriodResponse {
+    // Calendar end of the notice period
+    pub calendar_end_date: String,
+    // Last working day on or before the calendar end
+    pub end_date: String,
+}
+
+// Give up when no working day is found within this many days
+const MAX_SEARCH_DAYS: i64 = 366;
+
+fn calendar_end(request: &NoticePeriodRequest, notice_date: NaiveDate) -> Option<NaiveDate> {
+    let mut end = notice_date
+        .checked_add_months(Months::new(request.months))?
+        .checked_add_signed(chrono::Duration::weeks(request.weeks as i64))?;
+    if request.end_of_month {
+        let first_of_next_month = NaiveDate::from_ymd_opt(end.year(), end.month(), 1)?
+            .checked_add_months(Months::new(1))?;
+        end = first_of_next_month.pred_opt()?;
+    }
+    Some(end)
+}
+
+#[post("/notice-period")]
+pub async fn calculate_notice_period(
+    data: web::Data<AppState>,
+    req: web::Json<NoticePeriodRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let notice_date = NaiveDate::parse_from_str(&req.notice_date, "%Y-%m-%d")
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid notice date format: {}", e)))?;
+    if req.months == 0 && req.weeks == 0 {
+        return Err(actix_web::error::ErrorBadRequest("Either months or weeks must be provided"));
+    }
+    let calendar_end_date = calendar_end(&req, notice_date)
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("Notice period is out of range"))?;
+
+    let country = country::parse_request_optional(&req.country)?;
+    let subdivision = req.subdivision.clone().unwrap_or_default();
+    let holidays = crate::load_holidays(&data, country.as_ref(), &subdivision, calendar_end_date).await;
+
+    // Walk back to the last working day
+    let mut end_date = calendar_end_date;
+    let mut searched = 0;
+    while !crate::is_working_day(end_date, &holidays) {
+        if searched >= MAX_SEARCH_DAYS {
+            return Err(actix_web::error::ErrorUnprocessableEntity("No working day found before the end of the notice period"));
+        }
+        end_date = end_date.pred_opt().unwrap();
+        searched += 1;
+    }
+
+    Ok(HttpResponse::Ok().json(NoticePeriodResponse {
+        calendar_end_date: calendar_end_date.to_string(),
+        end_date: end_date.to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::db;
+
+    #[actix_rt::test]
+    async fn test_notice_period_ends_on_last_working_day() {
+        let database = db::Database::new(":memory:").unwrap();
+        database.add_holiday(&db::Holiday {
+            id: None,
+            date: "2025-12-31".to_string(),
+            description: "Company closure".to_string(),
+            country: "de".to_string(),
+            portion: db::HolidayPortion::Full,
+            kind: db::HolidayKind::Public,
+            subdivision: None,
+        }, "test").unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data).service(calculate_notice_period)).await;
+
+        // Three months to the end of a month: December 31st is closed, so the 30th
+        let req = test::TestRequest::post()
+            .uri("/notice-period")
+            .set_json(serde_json::json!({
+                "noticeDate": "2025-09-15", "months": 3, "endOfMonth": true, "country": "de"
+            }))
+            .to_request();
+        let response: NoticePeriodResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(response.calendar_end_date, "2025-12-31");
+        assert_eq!(response.end_date, "2025-12-30");
+    }
+
+    #[actix_rt::test]
+    async fn test_calendar_end_with_weeks() {
+        let request = NoticePeriodRequest {
+            notice_date: "2025-01-31".to_string(),
+            months: 1,
+            weeks: 2,
+            end_of_month: false,
+            country: String::new(),
+            subdivision: None,
+        };
+        let notice_date = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+        // January 31st + 1 month is clamped to February 28th
+        assert_eq!(calendar_end(&request, notice_date), NaiveDate::from_ymd_opt(2025, 3, 14));
+    }
+}
@@ -0,0 +1,115 @@
+use actix_web::{web, HttpResponse, get, post, delete};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::db::OnCallPeriod;
+use crate::{freeze, AppState};
+
+// Rotation weeks during which a calendar is on call 24/7
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OnCallPeriodRequest {
+    pub start: String,
+    pub end: String,
+}
+
+fn parse_instant(value: &str) -> Result<DateTime<Utc>, actix_web::error::Error> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|d| d.with_timezone(&Utc))
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid date format: {}", e)))
+}
+
+#[get("/on-call/{calendar}")]
+pub async fn list_on_call_periods(
+    data: web::Data<AppState>,
+    calendar: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let periods = data.db.blocking(move |db| db.get_on_call_periods(&calendar)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(periods))
+}
+
+#[post("/on-call/{calendar}")]
+pub async fn add_on_call_periods(
+    data: web::Data<AppState>,
+    calendar: web::Path<String>,
+    req: web::Json<Vec<OnCallPeriodRequest>>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    // Validate everything before storing anything
+    let mut periods = vec![];
+    for period in req.iter() {
+        let start = parse_instant(&period.start)?;
+        let end = parse_instant(&period.end)?;
+        if start >= end {
+            return Err(actix_web::error::ErrorBadRequest("On-call period start must be strictly before its end"));
+        }
+        periods.push(OnCallPeriod {
+            id: None,
+            calendar_id: calendar.clone(),
+            start: start.to_rfc3339(),
+            end: end.to_rfc3339(),
+        });
+    }
+
+    freeze::ensure_unfrozen(&data.db).await?;
+    let periods = data.db.blocking(move |db| {
+        for period in periods.iter_mut() {
+            period.id = Some(db.add_on_call_period(period)?);
+        }
+        Ok(periods)
+    }).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(periods))
+}
+
+#[delete("/on-call/{calendar}/{id}")]
+pub async fn delete_on_call_period(
+    data: web::Data<AppState>,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let (calendar, id) = path.into_inner();
+    freeze::ensure_unfrozen(&data.db).await?;
+    let deleted = data.db.blocking(move |db| db.delete_on_call_period(&calendar, id)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if deleted == 0 {
+        return Ok(HttpResponse::NotFound().json(format!("On-call period {} not found", id)));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holiday_provider::MockProvider;
+    use crate::{calculate_work_hours, db, EndOrDuration, WorkHoursRequest, WorkHoursResponse};
+
+    #[actix_rt::test]
+    async fn test_on_call_weekend_counts_as_working_time() {
+        let database = db::Database::new(":memory:").unwrap();
+        database.add_on_call_period(&OnCallPeriod {
+            id: None,
+            calendar_id: "sre".to_string(),
+            start: "2023-10-07T00:00:00+00:00".to_string(),
+            end: "2023-10-08T00:00:00+00:00".to_string(),
+        }).unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+
+        // Friday working day plus the Saturday on call
+        let request = WorkHoursRequest {
+            start_date: "2023-10-06T09:00:00Z".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-08T17:00:00Z".to_string()
+            },
+            start_of_day: "09:00:00".to_string(),
+            end_of_day: "17:00:00".to_string(),
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            on_call_calendar: Some("sre".to_string()),
+            ..Default::default()
+        };
+
+        let result = calculate_work_hours(data, web::Json(request)).await.unwrap();
+        let bytes = actix_web::body::to_bytes(result.into_body()).await.unwrap();
+        let response: WorkHoursResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(response.work_hours, 32.0);
+    }
+}
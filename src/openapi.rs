@@ -28,6 +28,9 @@ pub fn swagger_spec() -> String {
                 "post": {
                     "summary": "Calculate work hours between dates",
                     "description": "Calculates the number of work hours between two dates, taking into account weekends, holidays, and timezones.",
+                    "parameters": [
+                        { "name": "fields", "in": "query", "schema": { "type": "string", "example": "workHours,breakdown.date,breakdown.hours" }, "description": "Comma-separated fields to return, dot-separated for nested ones; the whole response when omitted" }
+                    ],
                     "requestBody": {
                         "required": true,
                         "content": {
@@ -63,10 +66,1310 @@ pub fn swagger_spec() -> String {
                                     }
                                 }
                             }
+                        },
+                        "422": { "description": "Range longer than MAX_RANGE_YEARS, use /chunked" }
+                    }
+                }
+            },
+            "/chunked": {
+                "post": {
+                    "summary": "Calculate work hours over a very long range",
+                    "description": "Computes the range year by year and streams one JSON line per year with the running total, for ranges beyond MAX_RANGE_YEARS.",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "$ref": "#/components/schemas/WorkHoursRequest"
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "One line per year: start_date, end_date, work_hours, cumulative_hours",
+                            "content": { "application/x-ndjson": {} }
+                        },
+                        "400": { "description": "Bad request" },
+                        "422": { "description": "Range longer than MAX_CHUNKED_YEARS" }
+                    }
+                }
+            },
+            "/is-working": {
+                "get": {
+                    "summary": "Check whether an instant is inside working hours",
+                    "description": "Returns whether the given instant falls inside working hours and, if not, the next working instant.",
+                    "parameters": [
+                        { "name": "at", "in": "query", "required": true, "schema": { "type": "string", "example": "2024-05-01T10:00:00" } },
+                        { "name": "country", "in": "query", "schema": { "type": "string", "example": "fr" } },
+                        { "name": "subdivision", "in": "query", "schema": { "type": "string" } },
+                        { "name": "timezone", "in": "query", "schema": { "type": "string", "example": "Europe/Paris" } },
+                        { "name": "startOfDay", "in": "query", "schema": { "type": "string", "default": "09:00:00" } },
+                        { "name": "endOfDay", "in": "query", "schema": { "type": "string", "default": "17:00:00" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/first-working-day": {
+                "get": {
+                    "summary": "First working day after a date",
+                    "description": "Returns the first weekday strictly after the given date that is not a holiday of the country, with the holidays skipped on the way. The first working day of a month is the first one after the last day of the previous month.",
+                    "parameters": [
+                        { "name": "after", "in": "query", "required": true, "schema": { "type": "string", "format": "date", "example": "2025-05-01" } },
+                        { "name": "country", "in": "query", "schema": { "type": "string", "example": "fr" } },
+                        { "name": "subdivision", "in": "query", "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" },
+                        "422": { "description": "No working day found in the search window" }
+                    }
+                }
+            },
+            "/nth-working-day": {
+                "get": {
+                    "summary": "Nth working day of a month",
+                    "description": "Returns the nth weekday of the month that is not a holiday of the country, counted from the end of the month for a negative n (-1 is the last working day), with the holidays skipped on the way.",
+                    "parameters": [
+                        { "name": "month", "in": "query", "required": true, "schema": { "type": "string", "example": "2025-06" } },
+                        { "name": "n", "in": "query", "required": true, "schema": { "type": "integer", "example": 3 } },
+                        { "name": "country", "in": "query", "schema": { "type": "string", "example": "de" } },
+                        { "name": "subdivision", "in": "query", "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Invalid month, or n is 0" },
+                        "422": { "description": "The month has fewer working days than requested" }
+                    }
+                }
+            },
+            "/presets/{country}": {
+                "get": {
+                    "summary": "Business-hours presets for a country",
+                    "description": "Returns the commonly used default schedule, weekend days and typical full-time weekly hours for a country.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "fr" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "404": { "description": "No preset for this country" }
+                    }
+                }
+            },
+            "/holiday-risk": {
+                "get": {
+                    "summary": "Holiday data risk for a date range",
+                    "description": "Flags date ranges where computed hours depend on holidays that are not known yet, with a confidence indicator.",
+                    "parameters": [
+                        { "name": "startDate", "in": "query", "required": true, "schema": { "type": "string", "format": "date" } },
+                        { "name": "endDate", "in": "query", "required": true, "schema": { "type": "string", "format": "date" } },
+                        { "name": "country", "in": "query", "schema": { "type": "string", "example": "fr" } },
+                        { "name": "subdivision", "in": "query", "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" },
+                        "422": { "description": "Range longer than MAX_RANGE_YEARS" }
+                    }
+                }
+            },
+            "/segments": {
+                "post": {
+                    "summary": "Calculate work hours across segments",
+                    "description": "Computes business time across an ordered list of segments, each with its own country and timezone, and sums the per-segment hours.",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "properties": {
+                                        "segments": {
+                                            "type": "array",
+                                            "items": { "$ref": "#/components/schemas/WorkHoursRequest" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/follow-the-sun": {
+                "post": {
+                    "summary": "Follow-the-sun coverage",
+                    "description": "Combines several regional calendars and schedules into coverage windows and uncovered gaps over a date range.",
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/deadline": {
+                "post": {
+                    "summary": "Calculate a deadline",
+                    "description": "Consumes workingSeconds of working time from startDate and returns the resulting instant. Requests after the optional cutoff time roll to the next business day, and the result is never earlier than the optional minimumLeadBusinessHours.",
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/promise": {
+                "post": {
+                    "summary": "Calculate a promise date",
+                    "description": "Returns the date businessDays working days after the processing day. Requests after the optional cutoff time are processed on the next business day.",
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/notice-period": {
+                "post": {
+                    "summary": "Calculate the end of a notice period",
+                    "description": "Adds calendar months and weeks to the notice date, optionally snapping to the end of the month, and returns the last working day on or before the result.",
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/prorata": {
+                "post": {
+                    "summary": "Pro-rate a week or month for joiners and leavers",
+                    "description": "Returns the fraction of the period's working hours covered by the employment dates, and the prorated standardHours when given.",
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/rest-validation": {
+                "post": {
+                    "summary": "Check shifts against rest rules",
+                    "description": "Reports rest gaps shorter than rules.minRestHours (default 11) and ISO weeks above rules.maxWeeklyHours (default 48).",
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/timesheet": {
+                "post": {
+                    "summary": "Analyze timesheet punches",
+                    "description": "Splits each punch per local day and flags the hours worked on weekends or public holidays, with the holiday name.",
+                    "parameters": [
+                        {
+                            "name": "format",
+                            "in": "query",
+                            "required": false,
+                            "description": "json, or parquet (one row per punch and day) when built with the parquet feature",
+                            "schema": { "type": "string", "enum": ["json", "parquet"], "default": "json" }
+                        }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Successful response",
+                            "content": {
+                                "application/json": {},
+                                "application/vnd.apache.parquet": {}
+                            }
+                        },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/toil/{employee}": {
+                "get": {
+                    "summary": "Time off in lieu balance",
+                    "description": "Returns the accrued, taken and remaining TOIL hours of an employee with the underlying entries.",
+                    "parameters": [
+                        { "name": "employee", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" }
+                    }
+                }
+            },
+            "/toil/{employee}/accruals": {
+                "post": {
+                    "summary": "Accrue TOIL from a timesheet",
+                    "description": "Takes a timesheet body plus an optional rate (default 1.0) and records the weekend and holiday hours as time off in lieu.",
+                    "parameters": [
+                        { "name": "employee", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "201": { "description": "Entries created" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/toil/{employee}/leave": {
+                "post": {
+                    "summary": "Take TOIL as leave",
+                    "description": "Consumes hours of the TOIL balance on the given date. Fails with 422 when the balance is insufficient, or when the date falls in a rejecting blackout period of the calendar unless override is set.",
+                    "parameters": [
+                        { "name": "employee", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["date", "hours"],
+                                    "properties": {
+                                        "date": { "type": "string", "format": "date" },
+                                        "hours": { "type": "number" },
+                                        "description": { "type": "string" },
+                                        "calendar": { "type": "string", "description": "Calendar whose blackout periods apply" },
+                                        "override": { "type": "boolean", "default": false, "description": "Manager override of a rejecting blackout period" }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "201": { "description": "Entry created, with the blackout periods it falls in" },
+                        "400": { "description": "Bad request" },
+                        "422": { "description": "Insufficient balance or blackout period" }
+                    }
+                }
+            },
+            "/calendars": {
+                "post": {
+                    "summary": "Create a calendar",
+                    "description": "Creates a calendar made of a base country, or a parent calendar, plus company closure days. Calculations use it through calendarId.",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["id"],
+                                    "properties": {
+                                        "id": { "type": "string", "example": "acme-paris" },
+                                        "parent": { "type": "string" },
+                                        "country": { "type": "string", "example": "fr" },
+                                        "subdivision": { "type": "string" },
+                                        "startOfDay": { "type": "string", "example": "08:30:00" },
+                                        "endOfDay": { "type": "string", "example": "17:30:00" },
+                                        "weekend": { "type": "array", "items": { "type": "string" }, "example": ["sat", "sun"] },
+                                        "closures": {
+                                            "type": "array",
+                                            "items": {
+                                                "type": "object",
+                                                "description": "Exactly one of date or recurring",
+                                                "properties": {
+                                                    "date": { "type": "string", "example": "2025-08-14" },
+                                                    "recurring": { "type": "string", "description": "MM-DD", "example": "12-24" },
+                                                    "description": { "type": "string" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "201": { "description": "Created calendar, resolved" },
+                        "400": { "description": "Invalid calendar or unknown parent" },
+                        "409": { "description": "Calendar already exists, or payroll period frozen" }
+                    }
+                }
+            },
+            "/calendars/{id}/resolved": {
+                "get": {
+                    "summary": "Resolve calendar inheritance",
+                    "description": "Returns the calendar with the settings and closures inherited from its parents, root first in chain.",
+                    "parameters": [
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "404": { "description": "Calendar not found" },
+                        "422": { "description": "Broken inheritance chain" }
+                    }
+                }
+            },
+            "/calendars/{id}/versions": {
+                "get": {
+                    "summary": "Calendar history",
+                    "description": "Lists every edit of the calendar with its author, time, changed settings and resulting calendar.",
+                    "parameters": [
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "404": { "description": "Calendar has no history" }
+                    }
+                }
+            },
+            "/calendars/{id}/rollback/{version}": {
+                "post": {
+                    "summary": "Roll a calendar back",
+                    "description": "Restores the calendar as it was after the given version. The rollback is recorded as a new version authored by the X-User header.",
+                    "parameters": [
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "string" } },
+                        { "name": "version", "in": "path", "required": true, "schema": { "type": "integer" } },
+                        { "name": "X-User", "in": "header", "required": false, "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Version recorded by the rollback" },
+                        "404": { "description": "Version not found" }
+                    }
+                }
+            },
+            "/admin/impact": {
+                "post": {
+                    "summary": "Capacity impact of a proposed holiday",
+                    "description": "Evaluates proposed holidays across all stored calendars, without storing them, and reports the working hours each calendar would lose.",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["holidays"],
+                                    "properties": {
+                                        "holidays": {
+                                            "type": "array",
+                                            "items": {
+                                                "type": "object",
+                                                "properties": {
+                                                    "date": { "type": "string", "format": "date" },
+                                                    "description": { "type": "string" }
+                                                }
+                                            }
+                                        },
+                                        "country": { "type": "string", "example": "fr" },
+                                        "subdivision": { "type": "string" }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/queries": {
+                "get": {
+                    "summary": "List saved queries",
+                    "responses": {
+                        "200": { "description": "Successful response" }
+                    }
+                },
+                "post": {
+                    "summary": "Save a query",
+                    "description": "Saves a calculation request under a name, replacing any query of the same name. Strings may contain placeholders such as {{lastMonth.start}}, {{today}} or {{employee.country}}, expanded when the query runs.",
+                    "responses": {
+                        "201": { "description": "Query saved" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/queries/{name}": {
+                "get": {
+                    "summary": "Get a saved query",
+                    "parameters": [
+                        { "name": "name", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "404": { "description": "Query not found" }
+                    }
+                },
+                "delete": {
+                    "summary": "Delete a saved query",
+                    "parameters": [
+                        { "name": "name", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "204": { "description": "Query deleted" },
+                        "404": { "description": "Query not found" }
+                    }
+                }
+            },
+            "/queries/{name}/run": {
+                "post": {
+                    "summary": "Run a saved query",
+                    "description": "Expands the placeholders of the saved request, relative to today in the timezone of the query, with the optional caller variables ({ \"variables\": { \"employee.country\": \"fr\" } }), then computes the work hours.",
+                    "parameters": [
+                        { "name": "name", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Unknown variable or invalid request" },
+                        "404": { "description": "Query not found" }
+                    }
+                }
+            },
+            "/warnings": {
+                "get": {
+                    "summary": "Warning code catalogue",
+                    "description": "Lists the code, name and description of every warning a response may carry. Codes are never reused.",
+                    "responses": {
+                        "200": { "description": "Successful response" }
+                    }
+                }
+            },
+            "/admin/freeze": {
+                "post": {
+                    "summary": "Freeze holiday and calendar data",
+                    "description": "Locks holiday and calendar data until the given instant for payroll processing: calendar changes are rejected with 409 and calculations use the stored holidays without refreshing them from upstream.",
+                    "parameters": [
+                        {
+                            "name": "until",
+                            "in": "query",
+                            "required": true,
+                            "description": "End of the freeze, an RFC3339 date-time or a date to freeze through the end of that day (UTC)",
+                            "schema": { "type": "string", "example": "2025-01-31" }
+                        }
+                    ],
+                    "responses": {
+                        "200": { "description": "Freeze in force" },
+                        "400": { "description": "Invalid or past until" }
+                    }
+                },
+                "get": {
+                    "summary": "Freeze in force",
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "404": { "description": "No freeze in force" }
+                    }
+                },
+                "delete": {
+                    "summary": "Lift the freeze",
+                    "responses": {
+                        "204": { "description": "Freeze lifted" }
+                    }
+                }
+            },
+            "/country-groups": {
+                "get": {
+                    "summary": "List country groups",
+                    "description": "Stored groups and the groups configured with COUNTRY_GROUPS.",
+                    "responses": {
+                        "200": { "description": "Successful response" }
+                    }
+                }
+            },
+            "/country-groups/{name}": {
+                "get": {
+                    "summary": "Get a country group",
+                    "parameters": [
+                        { "name": "name", "in": "path", "required": true, "schema": { "type": "string", "example": "eu-team" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "404": { "description": "Unknown group" }
+                    }
+                },
+                "put": {
+                    "summary": "Create or replace a country group",
+                    "description": "The group name can then be used wherever a country is accepted; its holidays are the national holidays of all its countries.",
+                    "parameters": [
+                        { "name": "name", "in": "path", "required": true, "schema": { "type": "string", "example": "eu-team" } }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["countries"],
+                                    "properties": {
+                                        "countries": { "type": "array", "items": { "type": "string" }, "example": ["fr", "de", "es"] }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Invalid name or country code" }
+                    }
+                },
+                "delete": {
+                    "summary": "Delete a stored country group",
+                    "parameters": [
+                        { "name": "name", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "204": { "description": "Deleted" },
+                        "404": { "description": "Unknown group" }
+                    }
+                }
+            },
+            "/analytics/weekdays": {
+                "post": {
+                    "summary": "Working hours per weekday",
+                    "description": "Splits the hours of a calculation per weekday, with the number of each weekday in the range and how many were public holidays.",
+                    "parameters": [
+                        { "name": "format", "in": "query", "schema": { "type": "string", "enum": ["json", "parquet"], "default": "json" } }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "$ref": "#/components/schemas/WorkHoursRequest"
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/blackouts/{calendar}": {
+                "get": {
+                    "summary": "List blackout periods",
+                    "parameters": [
+                        { "name": "calendar", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Successful response" }
+                    }
+                },
+                "post": {
+                    "summary": "Add blackout periods",
+                    "description": "Periods (inclusive dates) during which leave on the calendar is rejected (mode reject, the default) or only flagged (mode flag).",
+                    "parameters": [
+                        { "name": "calendar", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "required": ["start", "end"],
+                                        "properties": {
+                                            "start": { "type": "string", "format": "date" },
+                                            "end": { "type": "string", "format": "date" },
+                                            "description": { "type": "string" },
+                                            "mode": { "type": "string", "enum": ["reject", "flag"], "default": "reject" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "201": { "description": "Periods created" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/blackouts/{calendar}/{id}": {
+                "delete": {
+                    "summary": "Delete a blackout period",
+                    "parameters": [
+                        { "name": "calendar", "in": "path", "required": true, "schema": { "type": "string" } },
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }
+                    ],
+                    "responses": {
+                        "204": { "description": "Deleted" },
+                        "404": { "description": "Not found" }
+                    }
+                }
+            },
+            "/staffing/check": {
+                "post": {
+                    "summary": "Minimum staffing check",
+                    "description": "Returns the working days where the approved absences of the team leave fewer members available than the minimum headcount. Each member's working days come from their own calendar and schedule.",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["startDate", "endDate", "minimumHeadcount", "members"],
+                                    "properties": {
+                                        "startDate": { "type": "string", "format": "date" },
+                                        "endDate": { "type": "string", "format": "date" },
+                                        "minimumHeadcount": { "type": "integer", "example": 2 },
+                                        "members": {
+                                            "type": "array",
+                                            "items": {
+                                                "type": "object",
+                                                "required": ["id"],
+                                                "properties": {
+                                                    "id": { "type": "string" },
+                                                    "country": { "type": "string", "example": "fr" },
+                                                    "subdivision": { "type": "string" },
+                                                    "timezone": { "type": "string", "default": "UTC" },
+                                                    "startOfDay": { "type": "string", "default": "09:00:00" },
+                                                    "endOfDay": { "type": "string", "default": "17:00:00" },
+                                                    "scheduleOwner": { "type": "string" },
+                                                    "absences": {
+                                                        "type": "array",
+                                                        "items": {
+                                                            "type": "object",
+                                                            "properties": {
+                                                                "start": { "type": "string", "format": "date" },
+                                                                "end": { "type": "string", "format": "date" }
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": { "description": "Successful response" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/admin/cache": {
+                "get": {
+                    "summary": "Cached holiday lookups",
+                    "description": "Entries of this instance's holiday cache with their key, number of holidays and expiry. Keys are provider:country[:subdivision]:year:language.",
+                    "responses": {
+                        "200": {
+                            "description": "Successful response",
+                            "headers": {
+                                "X-Cache-Key-Format": { "schema": { "type": "string", "example": "provider:country[:subdivision]:year:language" } }
+                            }
+                        }
+                    }
+                },
+                "delete": {
+                    "summary": "Purge the holiday cache",
+                    "responses": {
+                        "204": { "description": "Cache purged" }
+                    }
+                }
+            },
+            "/admin/cache/{key}": {
+                "delete": {
+                    "summary": "Invalidate a cached holiday lookup",
+                    "description": "The next calculation fetches the holidays of the key again, e.g. after a holiday was announced during the year.",
+                    "parameters": [
+                        {
+                            "name": "key",
+                            "in": "path",
+                            "required": true,
+                            "description": "provider:country[:subdivision]:year:language, as listed by GET /admin/cache",
+                            "schema": { "type": "string", "example": "openholidays:DE:DE-BY:2025:EN" }
+                        }
+                    ],
+                    "responses": {
+                        "204": { "description": "Entry dropped" },
+                        "400": { "description": "Invalid key" },
+                        "404": { "description": "Key not cached" }
+                    }
+                }
+            },
+            "/admin/clients/{lang}": {
+                "get": {
+                    "summary": "Download a generated API client",
+                    "description": "TypeScript or Python client stub generated from this OpenAPI document, so that it matches the deployed version.",
+                    "parameters": [
+                        { "name": "lang", "in": "path", "required": true, "schema": { "type": "string", "enum": ["typescript", "ts", "python", "py"] } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Client source file, as an attachment" },
+                        "400": { "description": "Unsupported language" }
+                    }
+                }
+            },
+            "/holidays/{country}/check-batch": {
+                "post": {
+                    "summary": "Check a list of dates",
+                    "description": "Tells for each date, in the order given, whether it is a holiday of the country and its name. Up to 10000 dates per call.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "fr" } }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["dates"],
+                                    "properties": {
+                                        "dates": { "type": "array", "items": { "type": "string", "format": "date" }, "example": ["2025-12-25", "2025-12-26"] },
+                                        "subdivision": { "type": "string" },
+                                        "holidayKinds": { "type": "array", "items": { "type": "string", "enum": ["public", "bank", "regional", "observance", "company"] } }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": { "description": "date, holiday, name and kind of each date" },
+                        "400": { "description": "Invalid date or too many dates" }
+                    }
+                }
+            },
+            "/holidays/{country}/import": {
+                "post": {
+                    "summary": "Import holidays",
+                    "description": "Replaces the holidays added through the API for the years covered by the list. Dates imported twice with different descriptions or already stored more than once are conflicts, which refuse the import.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "fr" } },
+                        { "name": "dryRun", "in": "query", "schema": { "type": "boolean", "default": false }, "description": "Report the changes without writing them" }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "required": ["date"],
+                                        "properties": {
+                                            "date": { "type": "string", "example": "2025-12-25" },
+                                            "description": { "type": "string" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": { "description": "Created, updated, deleted and unchanged holidays" },
+                        "400": { "description": "Invalid date" },
+                        "409": { "description": "Conflicts, or a payroll freeze is in force" }
+                    }
+                }
+            },
+            "/holidays/{country}/import/ics": {
+                "post": {
+                    "summary": "Import holidays from iCalendar",
+                    "description": "Imports the VEVENTs of an uploaded .ics file, or of the feed at url, like POST /holidays/{country}/import. Multi-day events give one holiday per day; cancelled events are skipped and recurrence rules are not expanded.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "fr" } },
+                        { "name": "url", "in": "query", "schema": { "type": "string", "example": "webcal://example.com/closures.ics" }, "description": "Feed fetched by the server instead of an uploaded file" },
+                        { "name": "dryRun", "in": "query", "schema": { "type": "boolean", "default": false } }
+                    ],
+                    "requestBody": {
+                        "content": { "text/calendar": { "schema": { "type": "string" } } }
+                    },
+                    "responses": {
+                        "200": { "description": "Created, updated, deleted and unchanged holidays" },
+                        "400": { "description": "Missing or invalid iCalendar data, or an unsupported url" },
+                        "409": { "description": "Conflicts, or a payroll freeze is in force" },
+                        "502": { "description": "The feed could not be fetched" }
+                    }
+                }
+            },
+            "/calendars/{id}/import/ics": {
+                "post": {
+                    "summary": "Import calendar closures from iCalendar",
+                    "description": "Replaces the dated closures of the calendar for the years the events cover; yearly closures and other years are kept.",
+                    "parameters": [
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "string", "example": "acme-paris" } },
+                        { "name": "url", "in": "query", "schema": { "type": "string" } },
+                        { "name": "dryRun", "in": "query", "schema": { "type": "boolean", "default": false } }
+                    ],
+                    "requestBody": {
+                        "content": { "text/calendar": { "schema": { "type": "string" } } }
+                    },
+                    "responses": {
+                        "200": { "description": "Created and deleted closures" },
+                        "400": { "description": "Missing or invalid iCalendar data, or an unsupported url" },
+                        "404": { "description": "Unknown calendar" },
+                        "409": { "description": "Payroll period frozen" },
+                        "502": { "description": "The feed could not be fetched" }
+                    }
+                }
+            },
+            "/admin/coverage": {
+                "get": {
+                    "summary": "Holiday data coverage",
+                    "description": "Reports, for each country and year, whether holidays come from the provider, only from the database, or are missing.",
+                    "parameters": [
+                        { "name": "countries", "in": "query", "required": true, "schema": { "type": "string", "example": "fr,de,us" } },
+                        { "name": "years", "in": "query", "required": true, "description": "A year or an inclusive range of at most 20 years", "schema": { "type": "string", "example": "2024-2026" } },
+                        { "name": "format", "in": "query", "schema": { "type": "string", "enum": ["json", "parquet"], "default": "json" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Coverage entries with the number of missing ones, or a Parquet file of the entries" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/admin/duplicates": {
+                "get": {
+                    "summary": "Duplicated holidays",
+                    "description": "Days with several holidays added through the API for the same country, with their ids and distinct descriptions.",
+                    "responses": {
+                        "200": { "description": "Successful response" }
+                    }
+                }
+            },
+            "/admin/duplicates/merge": {
+                "post": {
+                    "summary": "Merge duplicated holidays",
+                    "description": "Keeps one holiday per duplicated day, with the chosen description or the distinct descriptions joined, and deletes the others.",
+                    "requestBody": {
+                        "required": false,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "required": ["country", "date", "description"],
+                                        "properties": {
+                                            "country": { "type": "string", "example": "fr" },
+                                            "date": { "type": "string", "example": "2025-07-14" },
+                                            "description": { "type": "string", "example": "Bastille Day" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": { "description": "Merged days and number of holidays deleted" },
+                        "409": { "description": "A payroll freeze is in force" }
+                    }
+                }
+            },
+            "/timesheet/wtd": {
+                "post": {
+                    "summary": "Working Time Directive weekly averages",
+                    "description": "Groups the punches of each employee per ISO week and computes the rolling average of the reference period (17 weeks by default) ending with each week. Weeks without punches between the first and last worked week count as zero. Employees whose average goes over limitHours (48 by default) are listed in over_limit.",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["employees", "timezone"],
+                                    "properties": {
+                                        "employees": {
+                                            "type": "array",
+                                            "items": {
+                                                "type": "object",
+                                                "properties": {
+                                                    "id": { "type": "string" },
+                                                    "punches": {
+                                                        "type": "array",
+                                                        "items": {
+                                                            "type": "object",
+                                                            "properties": {
+                                                                "start": { "type": "string", "format": "date-time" },
+                                                                "end": { "type": "string", "format": "date-time" }
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        },
+                                        "timezone": { "type": "string", "example": "Europe/Paris" },
+                                        "referenceWeeks": { "type": "integer", "default": 17 },
+                                        "limitHours": { "type": "number", "default": 48 }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": { "description": "Weekly hours and rolling averages per employee" },
+                        "400": { "description": "Bad request" }
+                    }
+                }
+            },
+            "/holidays/{country}": {
+                "get": {
+                    "summary": "List holidays",
+                    "description": "Holidays stored for the country, added through the API or kept from the providers: the nationwide ones, plus the regional ones of the subdivision and of the subdivisions above it.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "de" } },
+                        { "name": "subdivision", "in": "query", "schema": { "type": "string", "example": "DE-BY" } },
+                        { "name": "from", "in": "query", "description": "First day, inclusive", "schema": { "type": "string", "format": "date" } },
+                        { "name": "to", "in": "query", "description": "Last day, inclusive", "schema": { "type": "string", "format": "date" } },
+                        { "name": "year", "in": "query", "description": "Only the days of this year", "schema": { "type": "integer", "example": 2025 } },
+                        { "name": "limit", "in": "query", "description": "Holidays per page, all of them when omitted", "schema": { "type": "integer", "minimum": 0 } },
+                        { "name": "offset", "in": "query", "schema": { "type": "integer", "minimum": 0, "default": 0 } },
+                        { "name": "order", "in": "query", "description": "By day", "schema": { "type": "string", "enum": ["asc", "desc"], "default": "asc" } }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Stored holidays sorted by day",
+                            "headers": {
+                                "X-Total-Count": { "description": "Holidays on all pages", "schema": { "type": "integer" } }
+                            },
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": {
+                                            "type": "object",
+                                            "properties": {
+                                                "id": { "type": "integer" },
+                                                "date": { "type": "string" },
+                                                "description": { "type": "string" },
+                                                "country": { "type": "string" },
+                                                "portion": { "type": "string", "enum": ["morning", "afternoon"] },
+                                                "kind": { "type": "string", "enum": ["public", "bank", "regional", "observance", "company"] },
+                                                "subdivision": { "type": "string" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "400": { "description": "Invalid country, subdivision, day, year or page" }
+                    }
+                },
+                "post": {
+                    "summary": "Add holidays",
+                    "description": "Adds custom holidays to a country, either on a date or repeated every year with a recurring rule expanded for the years a calculation covers.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "us" } }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "description": "Exactly one of date or recurring",
+                                        "properties": {
+                                            "date": { "type": "string", "example": "2025-12-25" },
+                                            "recurring": { "type": "string", "description": "MM-DD, or a weekday of a month", "example": "last monday of may" },
+                                            "description": { "type": "string" },
+                                            "kind": { "type": "string", "enum": ["public", "bank", "regional", "observance", "company"], "default": "public" },
+                                            "portion": { "type": "string", "enum": ["full", "morning", "afternoon"], "default": "full", "description": "Dated holidays only" },
+                                            "subdivision": { "type": "string", "example": "DE-BY", "description": "Dated holidays only, nationwide when omitted" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "201": { "description": "Stored holidays and recurring definitions, with the ids of the dated holidays that replaced one of the same day in updated" },
+                        "400": { "description": "Invalid date, recurring rule or subdivision" },
+                        "409": { "description": "Payroll period frozen" }
+                    }
+                }
+            },
+            "/holidays/{country}/id/{id}": {
+                "get": {
+                    "summary": "Get a holiday",
+                    "description": "A stored holiday of the country.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "fr" } },
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Stored holiday" },
+                        "404": { "description": "No holiday with this id in the country" }
+                    }
+                },
+                "put": {
+                    "summary": "Update a holiday",
+                    "description": "Replaces the date, description, kind, portion and subdivision of a stored holiday.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "fr" } },
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["date"],
+                                    "properties": {
+                                        "date": { "type": "string", "example": "2025-07-14" },
+                                        "description": { "type": "string" },
+                                        "kind": { "type": "string", "enum": ["public", "bank", "regional", "observance", "company"], "default": "public" },
+                                        "portion": { "type": "string", "enum": ["full", "morning", "afternoon"], "default": "full" },
+                                        "subdivision": { "type": "string", "example": "FR-IDF" }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": { "description": "Updated holiday" },
+                        "400": { "description": "Invalid date or subdivision, or a recurring rule" },
+                        "404": { "description": "No holiday with this id in the country" },
+                        "409": { "description": "Payroll period frozen, or another holiday added for the new day" }
+                    }
+                },
+                "delete": {
+                    "summary": "Delete a holiday",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "fr" } },
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }
+                    ],
+                    "responses": {
+                        "204": { "description": "Holiday deleted" },
+                        "404": { "description": "No holiday with this id in the country" },
+                        "409": { "description": "Payroll period frozen" }
+                    }
+                }
+            },
+            "/holidays/{country}/audit": {
+                "get": {
+                    "summary": "Holiday audit log",
+                    "description": "Creations, updates and deletions of the holidays added to the country through the API, oldest first, with their author (X-User header) and the holiday before and after each change.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "fr" } },
+                        { "name": "holidayId", "in": "query", "schema": { "type": "integer" } }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Audit entries",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": {
+                                            "type": "object",
+                                            "properties": {
+                                                "id": { "type": "integer" },
+                                                "holiday_id": { "type": "integer" },
+                                                "country": { "type": "string" },
+                                                "action": { "type": "string", "enum": ["created", "updated", "deleted"] },
+                                                "author": { "type": "string" },
+                                                "changed_at": { "type": "string", "format": "date-time" },
+                                                "before": { "type": "object", "nullable": true },
+                                                "after": { "type": "object", "nullable": true }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "400": { "description": "Invalid country" }
+                    }
+                }
+            },
+            "/holidays/{country}/snapshot": {
+                "get": {
+                    "summary": "Holidays at an instant",
+                    "description": "Holidays added to the country through the API as they were at the instant, replayed from the audit log.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "fr" } },
+                        { "name": "at", "in": "query", "required": true, "description": "RFC3339 instant, or a day for its end in UTC", "schema": { "type": "string", "example": "2025-03-31" } }
+                    ],
+                    "responses": {
+                        "200": { "description": "Holidays sorted by day" },
+                        "400": { "description": "Invalid country or instant" }
+                    }
+                }
+            },
+            "/holidays/{country}/check": {
+                "get": {
+                    "summary": "Check a date",
+                    "description": "Tells whether the date is a holiday of the country, its name and where it comes from, with the holidays a calculation would use.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "de" } },
+                        { "name": "date", "in": "query", "required": true, "schema": { "type": "string", "format": "date", "example": "2025-12-25" } },
+                        { "name": "subdivision", "in": "query", "schema": { "type": "string", "example": "DE-BY" } }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Holiday check",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "properties": {
+                                            "date": { "type": "string", "format": "date" },
+                                            "holiday": { "type": "boolean" },
+                                            "name": { "type": "string" },
+                                            "kind": { "type": "string", "enum": ["public", "bank", "regional", "observance", "company"] },
+                                            "source": { "type": "string", "enum": ["api", "database", "custom"] }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "400": { "description": "Invalid date or country" }
+                    }
+                }
+            },
+            "/holidays/{country}/{year}": {
+                "get": {
+                    "summary": "Holidays of a year",
+                    "description": "The merged holidays a calculation would load for the year: provider or stored fallback, custom holidays and the closures of a calendar, each with its source.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "fr" } },
+                        { "name": "year", "in": "path", "required": true, "schema": { "type": "integer", "example": 2025 } },
+                        { "name": "subdivision", "in": "query", "schema": { "type": "string", "example": "FR-IDF" } },
+                        { "name": "calendarId", "in": "query", "schema": { "type": "string" }, "description": "Adds the closures of the calendar, which must be of the country" },
+                        { "name": "holidaySource", "in": "query", "schema": { "type": "string" } },
+                        { "name": "language", "in": "query", "schema": { "type": "string", "example": "fr" } }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Holidays of the year",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "properties": {
+                                            "country": { "type": "string" },
+                                            "year": { "type": "integer" },
+                                            "holidays": {
+                                                "type": "array",
+                                                "items": {
+                                                    "type": "object",
+                                                    "properties": {
+                                                        "date": { "type": "string", "format": "date" },
+                                                        "description": { "type": "string" },
+                                                        "kind": { "type": "string", "enum": ["public", "bank", "regional", "observance", "company"] },
+                                                        "portion": { "type": "string" },
+                                                        "source": { "type": "string", "enum": ["provider", "database", "frozen", "custom", "calendar"] }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "400": { "description": "Invalid country, unknown calendar or holiday source" },
+                        "422": { "description": "Broken calendar inheritance chain" }
+                    }
+                }
+            },
+            "/calendar.ics": {
+                "get": {
+                    "summary": "iCalendar feed of holidays",
+                    "description": "The holidays of a year as all-day events to subscribe to from Outlook or Google Calendar, loaded like GET /holidays/{country}/{year}.",
+                    "parameters": [
+                        { "name": "country", "in": "query", "required": true, "schema": { "type": "string", "example": "fr" } },
+                        { "name": "year", "in": "query", "required": true, "schema": { "type": "integer", "example": 2025 } },
+                        { "name": "subdivision", "in": "query", "schema": { "type": "string", "example": "FR-IDF" } },
+                        { "name": "language", "in": "query", "schema": { "type": "string", "example": "fr" } },
+                        { "name": "weekends", "in": "query", "schema": { "type": "boolean", "default": false }, "description": "Adds weekly Saturday and Sunday markers" }
+                    ],
+                    "responses": {
+                        "200": { "description": "iCalendar feed", "content": { "text/calendar": { "schema": { "type": "string" } } } },
+                        "400": { "description": "Invalid country, year or language" }
+                    }
+                }
+            },
+            "/examples": {
+                "get": {
+                    "summary": "Example requests",
+                    "description": "Ready-to-run requests of the main endpoints, dated around today and using EXAMPLES_COUNTRY with the hours and timezone of its preset.",
+                    "responses": {
+                        "200": {
+                            "description": "Example requests",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": {
+                                            "type": "object",
+                                            "properties": {
+                                                "name": { "type": "string" },
+                                                "method": { "type": "string", "enum": ["GET", "POST"] },
+                                                "path": { "type": "string", "example": "/first-working-day?after=2025-06-11&country=fr" },
+                                                "description": { "type": "string" },
+                                                "body": { "type": "object", "description": "JSON body of POST requests" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
                         }
                     }
                 }
             },
+            "/countries": {
+                "get": {
+                    "summary": "List countries",
+                    "description": "Countries known to OpenHolidays, cached for a week by default.",
+                    "parameters": [
+                        { "name": "language", "in": "query", "schema": { "type": "string", "example": "fr" } }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Countries sorted by code",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": {
+                                            "type": "object",
+                                            "properties": {
+                                                "code": { "type": "string", "example": "fr" },
+                                                "name": { "type": "string" },
+                                                "officialLanguages": { "type": "array", "items": { "type": "string" } }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "400": { "description": "Invalid language" },
+                        "502": { "description": "OpenHolidays unavailable" }
+                    }
+                }
+            },
+            "/countries/{country}/subdivisions": {
+                "get": {
+                    "summary": "List subdivisions",
+                    "description": "Subdivisions of a country known to OpenHolidays, nested ones flattened with their parent.",
+                    "parameters": [
+                        { "name": "country", "in": "path", "required": true, "schema": { "type": "string", "example": "de" } },
+                        { "name": "language", "in": "query", "schema": { "type": "string", "example": "de" } }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Subdivisions of the country",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": {
+                                            "type": "object",
+                                            "properties": {
+                                                "code": { "type": "string", "example": "DE-BY" },
+                                                "shortName": { "type": "string" },
+                                                "name": { "type": "string" },
+                                                "category": { "type": "string" },
+                                                "parent": { "type": "string" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "400": { "description": "Invalid country or language" },
+                        "404": { "description": "Country unknown to OpenHolidays" },
+                        "502": { "description": "OpenHolidays unavailable" }
+                    }
+                }
+            },
         },
         "components": {
             "schemas": {
@@ -76,12 +1379,14 @@ pub fn swagger_spec() -> String {
                         "startDate": {
                             "type": "string",
                             "format": "date-time",
-                            "default": current
+                            "default": current,
+                            "description": "RFC3339 timestamp, Unix epoch in seconds or milliseconds, or a YYYY-MM-DD date starting with the first working interval"
                         },
                         "endDate": {
                             "type": "string",
                             "format": "date-time",
-                            "default": onehour
+                            "default": onehour,
+                            "description": "RFC3339 timestamp, Unix epoch in seconds or milliseconds, or a YYYY-MM-DD date ending with the last working interval"
 
                         },
                         "durationSeconds": {
@@ -108,10 +1413,124 @@ pub fn swagger_spec() -> String {
                             "example": "fr",
                             "default": "fr"
                         },
+                        "calendarId": {
+                            "type": "string",
+                            "description": "Calendar created with POST /calendars or the calendar configuration. Replaces country, subdivision, startOfDay and endOfDay, its weekend days replace Saturday and Sunday and its closure days are days off",
+                            "example": "acme-paris"
+                        },
                         "timezone": {
                             "type": "string",
                             "default": "UTC",
                             "example": "Europe/Paris"
+                        },
+                        "timezoneMode": {
+                            "type": "string",
+                            "enum": ["convert", "assumeLocal"],
+                            "default": "convert",
+                            "description": "convert moves RFC3339 instants into the timezone, assumeLocal keeps their wall-clock time and ignores the offset"
+                        },
+                        "excludeDates": {
+                            "type": "array",
+                            "description": "Extra non-working dates (YYYY-MM-DD) treated like holidays, e.g. vacations",
+                            "items": { "type": "string", "format": "date" }
+                        },
+                        "includeDates": {
+                            "type": "array",
+                            "description": "Dates (YYYY-MM-DD) forced to count as working days, even on weekends or holidays",
+                            "items": { "type": "string", "format": "date" }
+                        },
+                        "workingIntervals": {
+                            "type": "array",
+                            "description": "Working intervals of a day (e.g. split shifts), replaces startOfDay/endOfDay when provided",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "start": { "type": "string", "format": "time", "example": "08:00:00" },
+                                    "end": { "type": "string", "format": "time", "example": "12:00:00" }
+                                }
+                            }
+                        },
+                        "onCallCalendar": {
+                            "type": "string",
+                            "description": "Calendar whose on-call rotation periods count as working time 24/7"
+                        },
+                        "overtime": {
+                            "type": "object",
+                            "description": "Thresholds in hours above which worked time counts as overtime",
+                            "properties": {
+                                "dailyThreshold": { "type": "number", "example": 8 },
+                                "weeklyThreshold": { "type": "number", "example": 40 }
+                            }
+                        },
+                        "durationType": {
+                            "type": "string",
+                            "enum": ["wall", "working"],
+                            "default": "wall",
+                            "description": "With working, durationSeconds is working time consumed from startDate (skipping weekends and holidays) instead of wall-clock time"
+                        },
+                        "aggregate": {
+                            "type": "string",
+                            "enum": ["day", "week", "month"],
+                            "description": "Adds a breakdown of the hours per day, ISO week or calendar month"
+                        },
+                        "includeIntervals": {
+                            "type": "boolean",
+                            "default": false,
+                            "description": "Returns every counted working interval as RFC3339 start/end pairs"
+                        },
+                        "outputFormat": {
+                            "type": "string",
+                            "enum": ["iso8601", "decimal"],
+                            "default": "iso8601",
+                            "description": "iso8601 adds workDuration next to the decimal fields, decimal leaves it out"
+                        },
+                        "duration": {
+                            "type": "string",
+                            "example": "P5D",
+                            "description": "ISO 8601 alternative to durationSeconds (weeks, days, hours, minutes and seconds)"
+                        },
+                        "scheduleOwner": {
+                            "type": "string",
+                            "description": "Calendar or employee whose effective-dated schedules replace startOfDay/endOfDay on the days they cover"
+                        },
+                        "explain": {
+                            "type": "boolean",
+                            "default": false,
+                            "description": "Adds an explain object listing the upstream holiday calls (URL, HTTP status, latency) and cache decisions"
+                        },
+                        "holidaySource": {
+                            "type": "string",
+                            "example": "embedded,openholidays",
+                            "description": "Holiday provider used for this calculation instead of the configured one: openholidays, nager, database, embedded, or a comma-separated list tried in order"
+                        },
+                        "language": {
+                            "type": "string",
+                            "example": "fr",
+                            "description": "ISO 639-1 language of the holiday names, HOLIDAY_LANGUAGE (EN by default) otherwise. Nager.Date only has English and local names."
+                        },
+                        "holidayKinds": {
+                            "type": "array",
+                            "items": { "type": "string", "enum": ["public", "bank", "regional", "observance", "company"] },
+                            "example": ["public", "regional", "bank"],
+                            "description": "Holiday kinds counted as days off, public, regional and company holidays by default"
+                        },
+                        "ramp": {
+                            "type": "object",
+                            "description": "Reduced capacity over the first weeks from a date, each step keeps a share of the day's working time",
+                            "properties": {
+                                "start": { "type": "string", "format": "date", "example": "2025-01-06" },
+                                "steps": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "properties": {
+                                            "weeks": { "type": "integer", "example": 4 },
+                                            "days": { "type": "integer" },
+                                            "capacity": { "type": "number", "format": "float", "minimum": 0, "maximum": 1, "example": 0.5 }
+                                        }
+                                    }
+                                }
+                            }
                         }
                     }
                 },
@@ -137,6 +1556,94 @@ pub fn swagger_spec() -> String {
                         "endDate": {
                             "type": "string",
                             "format": "date-time"
+                        },
+                        "regularHours": {
+                            "type": "number",
+                            "format": "float"
+                        },
+                        "overtimeHours": {
+                            "type": "number",
+                            "format": "float"
+                        },
+                        "breakdown": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "period": { "type": "string", "example": "2023-10" },
+                                    "date": { "type": "string", "format": "date" },
+                                    "hours": { "type": "number", "format": "float" }
+                                }
+                            }
+                        },
+                        "holidaysApplied": {
+                            "type": "array",
+                            "description": "Holidays that removed working time from the range",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "date": { "type": "string", "format": "date" },
+                                    "description": { "type": "string" },
+                                    "source": { "type": "string", "enum": ["provider", "database", "frozen", "excludeDates", "calendar"] },
+                                    "portion": { "type": "string", "enum": ["morning", "afternoon"], "description": "Half of the working day taken off, omitted for full days" }
+                                }
+                            }
+                        },
+                        "intervals": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "start": { "type": "string", "format": "date-time" },
+                                    "end": { "type": "string", "format": "date-time" }
+                                }
+                            }
+                        },
+                        "workDuration": {
+                            "type": "string",
+                            "example": "PT38H30M"
+                        },
+                        "explain": {
+                            "type": "object",
+                            "description": "Present with explain: true",
+                            "properties": {
+                                "holiday_provider": { "type": "string" },
+                                "upstream_calls": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "properties": {
+                                            "provider": { "type": "string" },
+                                            "url": { "type": "string" },
+                                            "status": { "type": "integer" },
+                                            "latency_ms": { "type": "integer" },
+                                            "cache": { "type": "string", "enum": ["hit", "stale", "miss", "expired"] },
+                                            "error": { "type": "string" },
+                                            "fetched_at": { "type": "string", "format": "date-time" }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "dataFreshness": {
+                            "type": "object",
+                            "description": "Present when the holidays came from the cache or the database and are older than DATA_FRESHNESS_MAX_AGE_SECONDS",
+                            "properties": {
+                                "source": { "type": "string", "enum": ["cache", "database"] },
+                                "fetchedAt": { "type": "string", "format": "date-time", "nullable": true }
+                            }
+                        },
+                        "warnings": {
+                            "type": "array",
+                            "description": "Stable warning codes, listed by GET /warnings",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "code": { "type": "string", "example": "W001" },
+                                    "name": { "type": "string", "example": "holidays_unavailable_fallback_used" },
+                                    "message": { "type": "string" }
+                                }
+                            }
                         }
                     }
                 }
@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+use chrono::Datelike;
+use serde::{Serialize, Deserialize};
+
+use crate::{aggregation, WorkingTime};
+
+// Thresholds (in hours) above which worked time counts as overtime
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct OvertimeRules {
+    #[serde(rename = "dailyThreshold", default)]
+    pub daily_threshold: Option<f64>,
+    #[serde(rename = "weeklyThreshold", default)]
+    pub weekly_threshold: Option<f64>,
+}
+
+// Split the working time into (regular, overtime) hours.
+// Daily overtime is taken first, the remaining regular hours are then checked
+// against the weekly threshold per ISO week. Intervals crossing midnight, such as
+// overnight shifts or on-call periods, count towards each local day they cover.
+pub fn split(working_time: &WorkingTime, rules: &OvertimeRules) -> (f64, f64) {
+    let mut overtime = 0.0;
+    let mut weekly: BTreeMap<(i32, u32), f64> = BTreeMap::new();
+    for (date, hours) in aggregation::hours_per_day(working_time) {
+        let regular = match rules.daily_threshold {
+            Some(threshold) if hours > threshold => {
+                overtime += hours - threshold;
+                threshold
+            }
+            _ => hours,
+        };
+        let week = date.iso_week();
+        *weekly.entry((week.year(), week.week())).or_insert(0.0) += regular;
+    }
+
+    let mut regular_total = 0.0;
+    for hours in weekly.into_values() {
+        match rules.weekly_threshold {
+            Some(threshold) if hours > threshold => {
+                overtime += hours - threshold;
+                regular_total += threshold;
+            }
+            _ => regular_total += hours,
+        }
+    }
+    (regular_total, overtime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use chrono_tz::Tz;
+
+    fn day(d: u32, from: u32, to: u32) -> (chrono::DateTime<Tz>, chrono::DateTime<Tz>) {
+        let tz: Tz = "UTC".parse().unwrap();
+        (
+            tz.with_ymd_and_hms(2023, 10, d, from, 0, 0).unwrap(),
+            tz.with_ymd_and_hms(2023, 10, d, to, 0, 0).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_daily_and_weekly_overtime() {
+        // Five 10-hour days from Monday to Friday
+        let intervals: Vec<_> = (2..=6).map(|d| day(d, 8, 18)).collect();
+        let working_time = WorkingTime {
+            start_date: intervals[0].0,
+            end_date: intervals[4].1,
+            intervals,
+            holidays_applied: vec![],
+            bulk_seconds: 0,
+            warnings: vec![],
+        };
+
+        let rules = OvertimeRules { daily_threshold: Some(8.0), weekly_threshold: None };
+        assert_eq!(split(&working_time, &rules), (40.0, 10.0));
+
+        let rules = OvertimeRules { daily_threshold: Some(9.0), weekly_threshold: Some(40.0) };
+        assert_eq!(split(&working_time, &rules), (40.0, 10.0));
+
+        let rules = OvertimeRules::default();
+        assert_eq!(split(&working_time, &rules), (50.0, 0.0));
+    }
+
+    #[test]
+    fn test_intervals_crossing_midnight_are_split_per_day() {
+        let tz: Tz = "UTC".parse().unwrap();
+        let at = |d: u32, h: u32| tz.with_ymd_and_hms(2023, 10, d, h, 0, 0).unwrap();
+        let working_time = |intervals: Vec<(chrono::DateTime<Tz>, chrono::DateTime<Tz>)>| WorkingTime {
+            start_date: intervals[0].0,
+            end_date: intervals[intervals.len() - 1].1,
+            intervals,
+            holidays_applied: vec![],
+            bulk_seconds: 0,
+            warnings: vec![],
+        };
+        let rules = OvertimeRules { daily_threshold: Some(8.0), weekly_threshold: Some(40.0) };
+
+        // 22:00-08:00 overnight shift: 2 hours on Monday and 8 on Tuesday, none above 8 a day
+        assert_eq!(split(&working_time(vec![(at(2, 22), at(3, 8))]), &rules), (10.0, 0.0));
+
+        // On-call from Monday 2 to Monday 9 October: seven 24-hour days, 16 hours of daily
+        // overtime each, then 56 regular hours of which 40 fit in the week
+        assert_eq!(split(&working_time(vec![(at(2, 0), at(9, 0))]), &rules), (40.0, 7.0 * 16.0 + 16.0));
+    }
+}
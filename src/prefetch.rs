@@ -0,0 +1,130 @@
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use actix_web::web;
+use chrono::{Datelike, NaiveDate, Utc};
+use serde_json::json;
+use log::{debug, info, warn};
+
+use crate::country::{CountryCode, Subdivision};
+use crate::{events, freeze};
+use crate::leader::LeaderElector;
+use crate::AppState;
+
+// Refreshed well within the 24 hour holiday cache
+const DEFAULT_INTERVAL_SECONDS: u64 = 6 * 60 * 60;
+
+// Country, with the subdivision when the entry is a subdivision code such as DE-BY
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub country: CountryCode,
+    pub subdivision: Option<Subdivision>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PrefetchConfig {
+    pub targets: Vec<Target>,
+    pub interval: Duration,
+}
+
+fn parse_targets(value: &str) -> Vec<Target> {
+    value.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| match entry.split_once('-') {
+            Some((country, _)) => {
+                let country: CountryCode = country.parse().ok()?;
+                let subdivision = Subdivision::parse(&country, entry).ok()?;
+                Some(Target { country, subdivision: Some(subdivision) })
+            }
+            None => Some(Target { country: entry.parse().ok()?, subdivision: None }),
+        })
+        .collect()
+}
+
+impl PrefetchConfig {
+    // Enabled by HOLIDAY_PREFETCH, e.g. fr,de,DE-BY,us
+    pub fn from_env() -> Option<Self> {
+        let targets = parse_targets(&env::var("HOLIDAY_PREFETCH").ok()?);
+        if targets.is_empty() {
+            return None;
+        }
+        let interval = env::var("HOLIDAY_PREFETCH_INTERVAL_SECONDS").ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_INTERVAL_SECONDS);
+        Some(PrefetchConfig { targets, interval: Duration::from_secs(interval) })
+    }
+}
+
+// Fetch the current and next year of every target through the configured provider,
+// which fills its cache and stores fresh remote holidays in the database.
+// Returns the number of successful and failed fetches.
+pub async fn run_once(data: &web::Data<AppState>, targets: &[Target], today: NaiveDate) -> (usize, usize) {
+    let mut fetched = 0;
+    let mut failed = 0;
+    if let Some(frozen) = data.db.blocking(|db| Ok(freeze::active(db))).await.ok().flatten() {
+        info!("Skipping holiday prefetch, data is frozen until {}", frozen.until);
+        return (fetched, failed);
+    }
+    for target in targets {
+        for year in [today.year(), today.year() + 1] {
+            let date = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+            let subdivision = target.subdivision.as_ref().map_or("", Subdivision::as_str);
+            match data.holidays.holidays(&data.db, &target.country, subdivision, date).await {
+                Ok(_) => fetched += 1,
+                Err(e) => {
+                    failed += 1;
+                    warn!("Failed to prefetch holidays for {} {} in {}: {}", target.country, subdivision, year, e);
+                }
+            }
+        }
+    }
+    (fetched, failed)
+}
+
+// Only the leader fetches from the holiday APIs and stores the holidays in the database,
+// the other replicas fill their cache on demand or fall back to the stored holidays
+pub fn spawn(config: PrefetchConfig, data: web::Data<AppState>, elector: Arc<LeaderElector>) {
+    info!("Prefetching holidays for {} countries every {} seconds", config.targets.len(), config.interval.as_secs());
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.interval);
+        loop {
+            interval.tick().await;
+            if !elector.is_leader() {
+                debug!("Skipping holiday prefetch, instance {} is not the leader", elector.instance_id);
+                continue;
+            }
+            let (fetched, failed) = run_once(&data, &config.targets, Utc::now().date_naive()).await;
+            info!("Holiday prefetch done: {} fetched, {} failed", fetched, failed);
+            events::publish(events::Event::JobCompleted {
+                job: "holiday-prefetch".to_string(),
+                details: json!({ "fetched": fetched, "failed": failed }),
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holiday_provider::MockProvider;
+    use crate::holidays_api::{self, Holiday};
+    use crate::db::{self, HolidayKind, HolidayPortion};
+
+    #[actix_rt::test]
+    async fn test_prefetch_targets() {
+        assert_eq!(parse_targets("fr, DE-BY,,us,DE-"), vec![
+            Target { country: "fr".parse().unwrap(), subdivision: None },
+            Target { country: "de".parse().unwrap(), subdivision: Some(Subdivision::parse(&"de".parse().unwrap(), "DE-BY").unwrap()) },
+            Target { country: "us".parse().unwrap(), subdivision: None },
+        ]);
+
+        holidays_api::mock::set_mock_holidays("FR", vec![Holiday { date: "2025-07-14".to_string(), description: "Bastille Day".to_string(), portion: HolidayPortion::Full, kind: HolidayKind::Public }]);
+        let data = web::Data::new(AppState { db: db::Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let today = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+
+        // Two years per target, nothing is registered for the United States
+        assert_eq!(run_once(&data, &parse_targets("fr,us"), today).await, (2, 2));
+        holidays_api::mock::clear_mock_holidays();
+    }
+}
@@ -0,0 +1,82 @@
+use actix_web::{web, HttpResponse, get};
+use serde::Serialize;
+
+// Commonly used business-hours defaults for a country
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Preset {
+    pub country: &'static str,
+    pub timezone: &'static str,
+    #[serde(rename = "startOfDay")]
+    pub start_of_day: &'static str,
+    #[serde(rename = "endOfDay")]
+    pub end_of_day: &'static str,
+    pub weekend: &'static [&'static str],
+    #[serde(rename = "weeklyHours")]
+    pub weekly_hours: f64,
+}
+
+const SAT_SUN: &[&str] = &["sat", "sun"];
+const FRI_SAT: &[&str] = &["fri", "sat"];
+const SUN: &[&str] = &["sun"];
+
+// Curated table of typical full-time schedules, keep sorted by country code
+static PRESETS: &[Preset] = &[
+    Preset { country: "ae", timezone: "Asia/Dubai", start_of_day: "08:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+    Preset { country: "at", timezone: "Europe/Vienna", start_of_day: "08:00:00", end_of_day: "16:30:00", weekend: SAT_SUN, weekly_hours: 38.5 },
+    Preset { country: "au", timezone: "Australia/Sydney", start_of_day: "09:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 38.0 },
+    Preset { country: "be", timezone: "Europe/Brussels", start_of_day: "09:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 38.0 },
+    Preset { country: "br", timezone: "America/Sao_Paulo", start_of_day: "08:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 44.0 },
+    Preset { country: "ca", timezone: "America/Toronto", start_of_day: "09:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+    Preset { country: "ch", timezone: "Europe/Zurich", start_of_day: "08:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 42.0 },
+    Preset { country: "cn", timezone: "Asia/Shanghai", start_of_day: "09:00:00", end_of_day: "18:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+    Preset { country: "de", timezone: "Europe/Berlin", start_of_day: "08:00:00", end_of_day: "16:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+    Preset { country: "eg", timezone: "Africa/Cairo", start_of_day: "09:00:00", end_of_day: "17:00:00", weekend: FRI_SAT, weekly_hours: 40.0 },
+    Preset { country: "es", timezone: "Europe/Madrid", start_of_day: "09:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+    Preset { country: "fr", timezone: "Europe/Paris", start_of_day: "09:00:00", end_of_day: "16:00:00", weekend: SAT_SUN, weekly_hours: 35.0 },
+    Preset { country: "gb", timezone: "Europe/London", start_of_day: "09:00:00", end_of_day: "17:30:00", weekend: SAT_SUN, weekly_hours: 37.5 },
+    Preset { country: "il", timezone: "Asia/Jerusalem", start_of_day: "08:30:00", end_of_day: "17:00:00", weekend: FRI_SAT, weekly_hours: 42.0 },
+    Preset { country: "in", timezone: "Asia/Kolkata", start_of_day: "09:00:00", end_of_day: "18:00:00", weekend: SUN, weekly_hours: 48.0 },
+    Preset { country: "it", timezone: "Europe/Rome", start_of_day: "09:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+    Preset { country: "jp", timezone: "Asia/Tokyo", start_of_day: "09:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+    Preset { country: "mx", timezone: "America/Mexico_City", start_of_day: "09:00:00", end_of_day: "17:00:00", weekend: SUN, weekly_hours: 48.0 },
+    Preset { country: "nl", timezone: "Europe/Amsterdam", start_of_day: "09:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+    Preset { country: "pl", timezone: "Europe/Warsaw", start_of_day: "08:00:00", end_of_day: "16:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+    Preset { country: "pt", timezone: "Europe/Lisbon", start_of_day: "09:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+    Preset { country: "sa", timezone: "Asia/Riyadh", start_of_day: "08:00:00", end_of_day: "16:00:00", weekend: FRI_SAT, weekly_hours: 48.0 },
+    Preset { country: "se", timezone: "Europe/Stockholm", start_of_day: "08:00:00", end_of_day: "16:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+    Preset { country: "us", timezone: "America/New_York", start_of_day: "09:00:00", end_of_day: "17:00:00", weekend: SAT_SUN, weekly_hours: 40.0 },
+];
+
+pub fn preset_for(country: &str) -> Option<&'static Preset> {
+    let country = country.to_lowercase();
+    PRESETS.iter().find(|p| p.country == country)
+}
+
+#[get("/presets/{country}")]
+pub async fn get_preset(country: web::Path<String>) -> HttpResponse {
+    match preset_for(&country) {
+        Some(preset) => HttpResponse::Ok().json(preset),
+        None => HttpResponse::NotFound().json(format!("No preset available for country: {}", country)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_lookup() {
+        let preset = preset_for("FR").unwrap();
+        assert_eq!(preset.weekly_hours, 35.0);
+        assert_eq!(preset.weekend, SAT_SUN);
+        assert!(preset_for("zz").is_none());
+    }
+
+    #[test]
+    fn test_presets_are_consistent() {
+        for preset in PRESETS {
+            assert!(preset.timezone.parse::<chrono_tz::Tz>().is_ok(), "invalid timezone for {}", preset.country);
+            assert!(preset.start_of_day < preset.end_of_day, "invalid schedule for {}", preset.country);
+        }
+    }
+}
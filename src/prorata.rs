@@ -0,0 +1,160 @@
+use actix_web::{web, HttpResponse, post};
+use chrono::{DateTime, Datelike, Months, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+
+use crate::{compute_working_totals, AppState, EndOrDuration, WorkHoursRequest, WorkingInterval};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Week,
+    Month,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProrataRequest {
+    pub period: Period,
+    // Any date inside the payroll period (YYYY-MM-DD)
+    #[serde(rename = "periodDate")]
+    pub period_date: String,
+    // First and last (inclusive) days of employment, open-ended when omitted
+    #[serde(rename = "employmentStart", default)]
+    pub employment_start: Option<String>,
+    #[serde(rename = "employmentEnd", default)]
+    pub employment_end: Option<String>,
+    // Contractual hours of a full period (e.g. 151.67 per month)
+    #[serde(rename = "standardHours", default)]
+    pub standard_hours: Option<f64>,
+    #[serde(rename = "startOfDay", default = "crate::default_start_of_day")]
+    pub start_of_day: String,
+    #[serde(rename = "endOfDay", default = "crate::default_end_of_day")]
+    pub end_of_day: String,
+    #[serde(rename = "workingIntervals", default)]
+    pub working_intervals: Vec<WorkingInterval>,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+    #[serde(default)]
+    pub timezone: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProrataResponse {
+    pub period_start: String,
+    pub period_end: String,
+    pub period_hours: f64,
+    pub employed_hours: f64,
+    pub fraction: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prorated_hours: Option<f64>,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, actix_web::error::Error> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid date format: {}", e)))
+}
+
+fn local_midnight(timezone: Tz, date: NaiveDate) -> DateTime<Tz> {
+    crate::resolve_local(&timezone, date.and_hms_opt(0, 0, 0).unwrap())
+}
+
+// First day of the period and first day of the next one
+fn period_bounds(period: Period, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    match period {
+        Period::Week => {
+            let monday = date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64);
+            (monday, monday + chrono::Duration::days(7))
+        }
+        Period::Month => {
+            let first = NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap();
+            (first, first + Months::new(1))
+        }
+    }
+}
+
+#[post("/prorata")]
+pub async fn calculate_prorata(
+    data: web::Data<AppState>,
+    req: web::Json<ProrataRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let timezone: Tz = req.timezone.parse()
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone: {}", e)))?;
+    let (period_start, period_end) = period_bounds(req.period, parse_date(&req.period_date)?);
+
+    let employed_start = match &req.employment_start {
+        Some(date) => parse_date(date)?.max(period_start),
+        None => period_start,
+    };
+    let employed_end = match &req.employment_end {
+        Some(date) => (parse_date(date)? + chrono::Duration::days(1)).min(period_end),
+        None => period_end,
+    };
+
+    let template = WorkHoursRequest {
+        start_of_day: req.start_of_day.clone(),
+        end_of_day: req.end_of_day.clone(),
+        working_intervals: req.working_intervals.clone(),
+        country: req.country.clone(),
+        subdivision: req.subdivision.clone(),
+        timezone: req.timezone.clone(),
+        ..Default::default()
+    };
+    let hours_between = |from: NaiveDate, to: NaiveDate| {
+        let mut request = template.clone();
+        let data = data.clone();
+        async move {
+            if from >= to {
+                return Ok(0.0);
+            }
+            request.start_date = local_midnight(timezone, from).to_rfc3339();
+            request.end_or_duration = EndOrDuration::EndDate {
+                end_date: local_midnight(timezone, to).to_rfc3339(),
+            };
+            Ok::<f64, actix_web::error::Error>(compute_working_totals(&data, &request).await?.work_hours())
+        }
+    };
+
+    let period_hours = hours_between(period_start, period_end).await?;
+    let employed_hours = hours_between(employed_start, employed_end).await?;
+    let fraction = if period_hours > 0.0 { employed_hours / period_hours } else { 0.0 };
+
+    Ok(HttpResponse::Ok().json(ProrataResponse {
+        period_start: period_start.to_string(),
+        period_end: (period_end - chrono::Duration::days(1)).to_string(),
+        period_hours,
+        employed_hours,
+        fraction,
+        prorated_hours: req.standard_hours.map(|hours| hours * fraction),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::db;
+
+    #[actix_rt::test]
+    async fn test_prorata_for_mid_month_joiner() {
+        let data = web::Data::new(AppState { db: db::Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data).service(calculate_prorata)).await;
+
+        // October 2023 has 22 working days, joining on Monday 16th leaves 12 of them
+        let req = test::TestRequest::post()
+            .uri("/prorata")
+            .set_json(serde_json::json!({
+                "period": "month", "periodDate": "2023-10-01", "employmentStart": "2023-10-16",
+                "standardHours": 176, "country": "us", "timezone": "UTC"
+            }))
+            .to_request();
+        let response: ProrataResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(response.period_hours, 176.0);
+        assert_eq!(response.employed_hours, 96.0);
+        assert_eq!(response.prorated_hours, Some(96.0));
+        assert_eq!(response.period_end, "2023-10-31");
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use chrono::{Datelike, Month, NaiveDate, Weekday};
+
+use crate::db::{Holiday, HolidayPortion, RecurringHoliday};
+
+// Yearly rule of a custom holiday: a fixed day written MM-DD like recurring calendar closures
+// ("12-25"), or a weekday of a month ("last monday of may", "first mon of 9")
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecurringRule {
+    Fixed { month: u32, day: u32 },
+    // nth is 1 to 5, or -1 for the last one
+    Weekday { nth: i32, weekday: Weekday, month: u32 },
+}
+
+const ORDINALS: [(&str, i32); 6] = [("first", 1), ("second", 2), ("third", 3), ("fourth", 4), ("fifth", 5), ("last", -1)];
+
+fn parse_month(value: &str) -> Option<u32> {
+    value.parse::<u32>().ok()
+        .filter(|month| (1..=12).contains(month))
+        .or_else(|| value.parse::<Month>().ok().map(|month| month.number_from_month()))
+}
+
+impl FromStr for RecurringRule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid recurring rule '{}', expected MM-DD or e.g. 'last monday of may'", value);
+        let words: Vec<String> = value.split_whitespace().map(str::to_lowercase).collect();
+        match words.as_slice() {
+            [fixed] => {
+                // 2000 is a leap year, so 02-29 is accepted
+                let date = NaiveDate::parse_from_str(&format!("2000-{}", fixed), "%Y-%m-%d").map_err(|_| invalid())?;
+                Ok(RecurringRule::Fixed { month: date.month(), day: date.day() })
+            }
+            [ordinal, weekday, of, month] if of == "of" => {
+                let nth = ORDINALS.iter().find(|(name, _)| name == ordinal).map(|(_, nth)| *nth).ok_or_else(invalid)?;
+                let weekday = weekday.parse::<Weekday>().map_err(|_| invalid())?;
+                let month = parse_month(month).ok_or_else(invalid)?;
+                Ok(RecurringRule::Weekday { nth, weekday, month })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// Canonical form stored in the database
+impl fmt::Display for RecurringRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecurringRule::Fixed { month, day } => write!(f, "{:02}-{:02}", month, day),
+            RecurringRule::Weekday { nth, weekday, month } => {
+                let ordinal = ORDINALS.iter().find(|(_, n)| n == nth).map_or("", |(name, _)| name);
+                let month = Month::try_from(*month as u8).map_or("", |month| month.name());
+                write!(f, "{} {} of {}", ordinal, weekday.to_string().to_lowercase(), month.to_lowercase())
+            }
+        }
+    }
+}
+
+impl RecurringRule {
+    // Date of the holiday in a year, None when the year has no such day (02-29, fifth monday)
+    pub fn occurrence(&self, year: i32) -> Option<NaiveDate> {
+        match *self {
+            RecurringRule::Fixed { month, day } => NaiveDate::from_ymd_opt(year, month, day),
+            RecurringRule::Weekday { nth: -1, weekday, month } => {
+                let next = if month == 12 { NaiveDate::from_ymd_opt(year + 1, 1, 1) } else { NaiveDate::from_ymd_opt(year, month + 1, 1) }?;
+                let last = next.pred_opt()?;
+                let back = (7 + last.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+                Some(last - chrono::Duration::days(back as i64))
+            }
+            RecurringRule::Weekday { nth, weekday, month } => NaiveDate::from_weekday_of_month_opt(year, month, weekday, nth as u8),
+        }
+    }
+}
+
+// Concrete holidays of the recurring definitions for the given years, rules that no longer
+// parse are skipped
+pub fn expand(definitions: &[RecurringHoliday], years: RangeInclusive<i32>) -> Vec<Holiday> {
+    let mut holidays = vec![];
+    for year in years {
+        for definition in definitions {
+            let date = match definition.rule.parse::<RecurringRule>().ok().and_then(|rule| rule.occurrence(year)) {
+                Some(date) => date,
+                None => continue,
+            };
+            holidays.push(Holiday {
+                id: None,
+                date: date.to_string(),
+                description: definition.description.clone(),
+                country: definition.country.clone(),
+                portion: HolidayPortion::Full,
+                kind: definition.kind,
+                subdivision: None,
+            });
+        }
+    }
+    holidays
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn occurrence(rule: &str, year: i32) -> Option<String> {
+        rule.parse::<RecurringRule>().unwrap().occurrence(year).map(|date| date.to_string())
+    }
+
+    #[test]
+    fn test_rules() {
+        assert_eq!(occurrence("12-25", 2025), Some("2025-12-25".to_string()));
+        assert_eq!(occurrence("02-29", 2025), None);
+        assert_eq!(occurrence("last monday of may", 2025), Some("2025-05-26".to_string()));
+        assert_eq!(occurrence("Last Mon of 5", 2024), Some("2024-05-27".to_string()));
+        assert_eq!(occurrence("fourth thursday of november", 2025), Some("2025-11-27".to_string()));
+        assert_eq!(occurrence("last sunday of december", 2023), Some("2023-12-31".to_string()));
+        assert_eq!(occurrence("fifth friday of february", 2025), None);
+        assert!("every day".parse::<RecurringRule>().is_err());
+        assert!("13-01".parse::<RecurringRule>().is_err());
+        assert_eq!("last mon of 5".parse::<RecurringRule>().unwrap().to_string(), "last mon of may");
+    }
+}
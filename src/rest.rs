@@ -0,0 +1,180 @@
+use actix_web::{HttpResponse, post, web};
+use chrono::DateTime;
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+
+use crate::{aggregation, merge_intervals, WorkingTime};
+
+fn default_min_rest_hours() -> f64 {
+    11.0
+}
+
+fn default_max_weekly_hours() -> f64 {
+    48.0
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Shift {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RestRules {
+    #[serde(rename = "minRestHours", default = "default_min_rest_hours")]
+    pub min_rest_hours: f64,
+    #[serde(rename = "maxWeeklyHours", default = "default_max_weekly_hours")]
+    pub max_weekly_hours: f64,
+}
+
+impl Default for RestRules {
+    fn default() -> Self {
+        RestRules {
+            min_rest_hours: default_min_rest_hours(),
+            max_weekly_hours: default_max_weekly_hours(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RestValidationRequest {
+    pub shifts: Vec<Shift>,
+    #[serde(default)]
+    pub rules: RestRules,
+    // Timezone used to group the shifts per ISO week
+    #[serde(default)]
+    pub timezone: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Violation {
+    // minimum_rest or maximum_weekly_hours
+    pub rule: String,
+    // Rest gap as an RFC3339 start/end pair, or ISO week (2023-W40)
+    pub period: String,
+    pub hours: f64,
+    pub limit: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RestValidationResponse {
+    pub valid: bool,
+    pub violations: Vec<Violation>,
+}
+
+fn parse_shift(shift: &Shift, timezone: Tz) -> Result<(DateTime<Tz>, DateTime<Tz>), actix_web::error::Error> {
+    let start = DateTime::parse_from_rfc3339(&shift.start)
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid shift start format: {}", e)))?;
+    let end = DateTime::parse_from_rfc3339(&shift.end)
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid shift end format: {}", e)))?;
+    if start >= end {
+        return Err(actix_web::error::ErrorBadRequest(format!("Shift must start before it ends: {} - {}", shift.start, shift.end)));
+    }
+    Ok((start.with_timezone(&timezone), end.with_timezone(&timezone)))
+}
+
+pub fn validate(shifts: Vec<(DateTime<Tz>, DateTime<Tz>)>, rules: &RestRules) -> Vec<Violation> {
+    // Overlapping or back-to-back shifts are worked as a single block
+    let shifts = merge_intervals(shifts);
+    let mut violations = vec![];
+
+    for pair in shifts.windows(2) {
+        let rest = pair[1].0.signed_duration_since(pair[0].1).num_seconds() as f64 / 3600.0;
+        if rest < rules.min_rest_hours {
+            violations.push(Violation {
+                rule: "minimum_rest".to_string(),
+                period: format!("{}/{}", pair[0].1.to_rfc3339(), pair[1].0.to_rfc3339()),
+                hours: rest,
+                limit: rules.min_rest_hours,
+            });
+        }
+    }
+
+    let (start_date, end_date) = match (shifts.first(), shifts.last()) {
+        (Some(first), Some(last)) => (first.0, last.1),
+        _ => return violations,
+    };
+    let working_time = WorkingTime {
+        start_date,
+        end_date,
+        intervals: shifts,
+        holidays_applied: vec![],
+        bulk_seconds: 0,
+        warnings: vec![],
+    };
+    for week in aggregation::breakdown(&working_time, aggregation::Aggregate::Week) {
+        if week.hours > rules.max_weekly_hours {
+            violations.push(Violation {
+                rule: "maximum_weekly_hours".to_string(),
+                period: week.period,
+                hours: week.hours,
+                limit: rules.max_weekly_hours,
+            });
+        }
+    }
+    violations
+}
+
+#[post("/rest-validation")]
+pub async fn validate_rest(
+    req: web::Json<RestValidationRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let timezone: Tz = req.timezone.parse()
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone: {}", e)))?;
+    let shifts = req.shifts.iter()
+        .map(|shift| parse_shift(shift, timezone))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let violations = validate(shifts, &req.rules);
+    Ok(HttpResponse::Ok().json(RestValidationResponse {
+        valid: violations.is_empty(),
+        violations,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+
+    fn shift(start: &str, end: &str) -> serde_json::Value {
+        serde_json::json!({ "start": start, "end": end })
+    }
+
+    #[actix_rt::test]
+    async fn test_rest_violations() {
+        let app = test::init_service(App::new().service(validate_rest)).await;
+
+        // A late shift followed by an early one, then long days up to 50 hours in the week
+        let req = test::TestRequest::post()
+            .uri("/rest-validation")
+            .set_json(serde_json::json!({
+                "timezone": "UTC",
+                "shifts": [
+                    shift("2023-10-02T14:00:00Z", "2023-10-02T22:00:00Z"),
+                    shift("2023-10-03T06:00:00Z", "2023-10-03T16:00:00Z"),
+                    shift("2023-10-04T06:00:00Z", "2023-10-04T16:00:00Z"),
+                    shift("2023-10-05T06:00:00Z", "2023-10-05T17:00:00Z"),
+                    shift("2023-10-06T06:00:00Z", "2023-10-06T17:00:00Z"),
+                ]
+            }))
+            .to_request();
+        let response: RestValidationResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert!(!response.valid);
+        assert_eq!(response.violations, vec![
+            Violation {
+                rule: "minimum_rest".to_string(),
+                period: "2023-10-02T22:00:00+00:00/2023-10-03T06:00:00+00:00".to_string(),
+                hours: 8.0,
+                limit: 11.0,
+            },
+            Violation {
+                rule: "maximum_weekly_hours".to_string(),
+                period: "2023-W40".to_string(),
+                hours: 50.0,
+                limit: 48.0,
+            },
+        ]);
+    }
+}
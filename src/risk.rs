@@ -0,0 +1,133 @@
+use actix_web::{web, HttpResponse, get};
+use chrono::{Datelike, NaiveDate};
+use serde::{Serialize, Deserialize};
+
+use crate::{country, AppState};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HolidayRiskParams {
+    #[serde(rename = "startDate")]
+    pub start_date: String,
+    #[serde(rename = "endDate")]
+    pub end_date: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+}
+
+// Part of the requested range whose result may change once holidays are announced
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RiskRange {
+    pub start_date: String,
+    pub end_date: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HolidayRiskResponse {
+    pub confidence: String,
+    pub covered_ratio: f64,
+    pub at_risk: Vec<RiskRange>,
+}
+
+// Accept plain dates as well as RFC3339 timestamps
+fn parse_date(value: &str) -> Result<NaiveDate, actix_web::error::Error> {
+    value.get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Invalid date format: {}", value)))
+}
+
+fn confidence(covered_ratio: f64) -> &'static str {
+    if covered_ratio >= 1.0 {
+        "high"
+    } else if covered_ratio >= 0.5 {
+        "medium"
+    } else {
+        "low"
+    }
+}
+
+#[get("/holiday-risk")]
+pub async fn get_holiday_risk(
+    data: web::Data<AppState>,
+    params: web::Query<HolidayRiskParams>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let start = parse_date(&params.start_date)?;
+    let end = parse_date(&params.end_date)?;
+    if start > end {
+        return Err(actix_web::error::ErrorBadRequest("Start date must be before end date"));
+    }
+    // Every year of the range is looked up, upstream when it is not stored
+    let max_years = crate::max_range_years();
+    if end.year() - start.year() >= max_years as i32 {
+        return Err(actix_web::error::ErrorUnprocessableEntity(format!(
+            "Date range {} - {} exceeds the maximum of {} years", start, end, max_years
+        )));
+    }
+
+    let country = country::parse_request(&params.country)?;
+    let subdivision = params.subdivision.clone().unwrap_or_default();
+
+    // A year without any known holiday most likely has not been published yet
+    let mut at_risk = vec![];
+    let years = end.year() - start.year() + 1;
+    for year in start.year()..=end.year() {
+        let first_day = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+        let holidays = crate::load_holidays(&data, Some(&country), &subdivision, first_day).await;
+        let has_data = holidays.iter().any(|h| h.date.starts_with(&year.to_string()));
+        if !has_data {
+            at_risk.push(RiskRange {
+                start_date: start.max(first_day).to_string(),
+                end_date: end.min(NaiveDate::from_ymd_opt(year, 12, 31).unwrap()).to_string(),
+                reason: format!("No holiday data available for {} in {}", country, year),
+            });
+        }
+    }
+
+    let covered_ratio = (years - at_risk.len() as i32) as f64 / years as f64;
+    Ok(HttpResponse::Ok().json(HolidayRiskResponse {
+        confidence: confidence(covered_ratio).to_string(),
+        covered_ratio,
+        at_risk,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::db;
+
+    #[actix_rt::test]
+    async fn test_future_year_is_flagged() {
+        let database = db::Database::new(":memory:").unwrap();
+        database.add_holiday(&db::Holiday {
+            id: None,
+            date: "2024-12-25T00:00:00Z".to_string(),
+            description: "Christmas".to_string(),
+            country: "fr".to_string(),
+            portion: db::HolidayPortion::Full,
+            kind: db::HolidayKind::Public,
+            subdivision: None,
+        }, "test").unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data).service(get_holiday_risk)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/holiday-risk?startDate=2024-06-01&endDate=2025-06-30&country=fr")
+            .to_request();
+        let response: HolidayRiskResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(response.confidence, "medium");
+        assert_eq!(response.at_risk.len(), 1);
+        assert_eq!(response.at_risk[0].start_date, "2025-01-01");
+        assert_eq!(response.at_risk[0].end_date, "2025-06-30");
+
+        let req = test::TestRequest::get()
+            .uri("/holiday-risk?startDate=2024-01-01&endDate=9999-12-31&country=fr")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 422);
+    }
+}
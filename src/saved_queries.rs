@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use actix_web::{web, HttpResponse, get, post, delete};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+
+use crate::db::SavedQuery;
+use crate::{compute_work_hours, AppState, WorkHoursRequest};
+
+// Caller-provided variables, e.g. { "employee.country": "fr" }
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct RunRequest {
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+fn month_start(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+}
+
+// First and last day of the month containing a date
+fn month_of(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let start = month_start(date.year(), date.month());
+    let next = if date.month() == 12 { month_start(date.year() + 1, 1) } else { month_start(date.year(), date.month() + 1) };
+    (start, next - Duration::days(1))
+}
+
+// Built-in variables relative to today: today, yesterday, tomorrow and the start/end
+// of this and last week (ISO, Monday first), month and year
+fn builtin_variables(today: NaiveDate) -> HashMap<String, String> {
+    let mut variables = HashMap::new();
+    let mut period = |name: &str, (start, end): (NaiveDate, NaiveDate)| {
+        variables.insert(format!("{}.start", name), start.to_string());
+        variables.insert(format!("{}.end", name), end.to_string());
+    };
+    let week_start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    period("thisWeek", (week_start, week_start + Duration::days(6)));
+    period("lastWeek", (week_start - Duration::days(7), week_start - Duration::days(1)));
+    let this_month = month_of(today);
+    period("thisMonth", this_month);
+    period("lastMonth", month_of(this_month.0 - Duration::days(1)));
+    period("thisYear", (month_start(today.year(), 1), NaiveDate::from_ymd_opt(today.year(), 12, 31).unwrap()));
+    period("lastYear", (month_start(today.year() - 1, 1), NaiveDate::from_ymd_opt(today.year() - 1, 12, 31).unwrap()));
+    variables.insert("today".to_string(), today.to_string());
+    variables.insert("yesterday".to_string(), (today - Duration::days(1)).to_string());
+    variables.insert("tomorrow".to_string(), (today + Duration::days(1)).to_string());
+    variables
+}
+
+fn expand_string(value: &str, variables: &HashMap<String, String>) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(open) = rest.find("{{") {
+        let close = rest[open..].find("}}")
+            .ok_or_else(|| format!("Unterminated placeholder in {}", value))?;
+        let name = rest[open + 2..open + close].trim();
+        let replacement = variables.get(name)
+            .ok_or_else(|| format!("Unknown variable {{{{{}}}}}", name))?;
+        expanded.push_str(&rest[..open]);
+        expanded.push_str(replacement);
+        rest = &rest[open + close + 2..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+// Replace the {{variable}} placeholders of every string in the saved request
+pub fn expand(value: &serde_json::Value, variables: &HashMap<String, String>) -> Result<serde_json::Value, String> {
+    Ok(match value {
+        serde_json::Value::String(text) => serde_json::Value::String(expand_string(text, variables)?),
+        serde_json::Value::Array(items) => serde_json::Value::Array(
+            items.iter().map(|item| expand(item, variables)).collect::<Result<_, _>>()?
+        ),
+        serde_json::Value::Object(fields) => serde_json::Value::Object(
+            fields.iter().map(|(key, field)| Ok((key.clone(), expand(field, variables)?))).collect::<Result<_, String>>()?
+        ),
+        other => other.clone(),
+    })
+}
+
+// Saved request with its placeholders expanded; "today" is taken in the timezone of the query
+pub fn instantiate(
+    query: &SavedQuery,
+    today: NaiveDate,
+    caller: &HashMap<String, String>,
+) -> Result<WorkHoursRequest, actix_web::error::Error> {
+    let mut variables = builtin_variables(today);
+    variables.extend(caller.iter().map(|(name, value)| (name.clone(), value.clone())));
+    let request = expand(&query.request, &variables).map_err(actix_web::error::ErrorBadRequest)?;
+    serde_json::from_value(request)
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Saved query {} is not a valid request: {}", query.name, e)))
+}
+
+#[get("/queries")]
+pub async fn list_queries(
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let queries = data.db.blocking(|db| db.get_saved_queries()).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(queries))
+}
+
+#[post("/queries")]
+pub async fn save_query(
+    data: web::Data<AppState>,
+    req: web::Json<SavedQuery>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let query = req.into_inner();
+    if query.name.trim().is_empty() {
+        return Err(actix_web::error::ErrorBadRequest("name must not be empty"));
+    }
+    // Placeholders are only checked when the query runs, with the caller variables
+
+    let stored = query.clone();
+    data.db.blocking(move |db| db.save_query(&stored)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(query))
+}
+
+#[get("/queries/{name}")]
+pub async fn get_query(
+    data: web::Data<AppState>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let query_name = name.to_string();
+    let query = data.db.blocking(move |db| db.get_saved_query(&query_name)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    match query {
+        Some(query) => Ok(HttpResponse::Ok().json(query)),
+        None => Ok(HttpResponse::NotFound().json(format!("Saved query {} not found", name))),
+    }
+}
+
+#[delete("/queries/{name}")]
+pub async fn delete_query(
+    data: web::Data<AppState>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let query_name = name.to_string();
+    let deleted = data.db.blocking(move |db| db.delete_saved_query(&query_name)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if deleted == 0 {
+        return Ok(HttpResponse::NotFound().json(format!("Saved query {} not found", name)));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/queries/{name}/run")]
+pub async fn run_query(
+    data: web::Data<AppState>,
+    name: web::Path<String>,
+    req: Option<web::Json<RunRequest>>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let query_name = name.to_string();
+    let query = data.db.blocking(move |db| db.get_saved_query(&query_name)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let query = match query {
+        Some(query) => query,
+        None => return Ok(HttpResponse::NotFound().json(format!("Saved query {} not found", name))),
+    };
+    let timezone: Tz = query.request.get("timezone").and_then(|tz| tz.as_str())
+        .and_then(|tz| tz.parse().ok())
+        .unwrap_or(chrono_tz::UTC);
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    let variables = req.map(|req| req.into_inner().variables).unwrap_or_default();
+
+    let request = instantiate(&query, today, &variables)?;
+    Ok(HttpResponse::Ok().json(compute_work_hours(&data, &request).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_placeholders_are_expanded_for_the_period() {
+        let query = SavedQuery {
+            name: "monthly-report".to_string(),
+            request: json!({
+                "startDate": "{{lastMonth.start}}",
+                "endDate": "{{ lastMonth.end }}",
+                "country": "{{employee.country}}",
+                "timezone": "UTC",
+                "excludeDates": ["{{today}}"]
+            }),
+        };
+        let today = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let caller = HashMap::from([("employee.country".to_string(), "fr".to_string())]);
+
+        let request = instantiate(&query, today, &caller).unwrap();
+        assert_eq!(request.start_date, "2024-02-01");
+        assert!(matches!(request.end_or_duration, crate::EndOrDuration::EndDate { end_date } if end_date == "2024-02-29"));
+        assert_eq!(request.country, "fr");
+        assert_eq!(request.exclude_dates, vec!["2024-03-15".to_string()]);
+
+        let variables = builtin_variables(NaiveDate::from_ymd_opt(2024, 1, 3).unwrap());
+        assert_eq!(variables["lastMonth.start"], "2023-12-01");
+        assert_eq!(variables["lastWeek.start"], "2023-12-25");
+        assert_eq!(variables["thisWeek.end"], "2024-01-07");
+
+        // The employee variable is missing
+        assert!(instantiate(&query, today, &HashMap::new()).is_err());
+    }
+}
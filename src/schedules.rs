@@ -0,0 +1,159 @@
+use actix_web::{web, HttpResponse, get, post, delete};
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::db::Schedule;
+use crate::{freeze, AppState};
+
+// Stored schedule with its dates and intervals parsed, ready for the engine
+#[derive(Debug, Clone)]
+pub struct EffectiveSchedule {
+    pub valid_from: NaiveDate,
+    pub valid_to: Option<NaiveDate>,
+    pub intervals: Vec<(NaiveTime, NaiveTime)>,
+}
+
+impl EffectiveSchedule {
+    fn covers(&self, date: NaiveDate) -> bool {
+        date >= self.valid_from && self.valid_to.is_none_or(|valid_to| date <= valid_to)
+    }
+}
+
+fn parse_date(value: &str, label: &str) -> Result<NaiveDate, actix_web::error::Error> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid {} date format: {}", label, e)))
+}
+
+fn to_effective(schedule: &Schedule) -> Result<EffectiveSchedule, actix_web::error::Error> {
+    let valid_from = parse_date(&schedule.valid_from, "validFrom")?;
+    let valid_to = schedule.valid_to.as_deref().map(|d| parse_date(d, "validTo")).transpose()?;
+    if valid_to.is_some_and(|valid_to| valid_to < valid_from) {
+        return Err(actix_web::error::ErrorBadRequest("validTo must not be before validFrom"));
+    }
+    Ok(EffectiveSchedule {
+        valid_from,
+        valid_to,
+        intervals: crate::resolve_schedule(&schedule.start_of_day, &schedule.end_of_day, &schedule.working_intervals)?,
+    })
+}
+
+pub async fn load(data: &web::Data<AppState>, owner: &str) -> Result<Vec<EffectiveSchedule>, actix_web::error::Error> {
+    let owner = owner.to_string();
+    let schedules = data.db.blocking(move |db| db.get_schedules(&owner)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    schedules.iter().map(to_effective).collect()
+}
+
+// Intervals of the schedule valid on a day, the most recently started one wins when
+// validity periods overlap (e.g. summer hours inside an open-ended contract schedule)
+pub fn intervals_on<'a>(
+    schedules: &'a [EffectiveSchedule],
+    date: NaiveDate,
+    default: &'a [(NaiveTime, NaiveTime)],
+) -> &'a [(NaiveTime, NaiveTime)] {
+    schedules.iter()
+        .filter(|schedule| schedule.covers(date))
+        .max_by_key(|schedule| schedule.valid_from)
+        .map_or(default, |schedule| schedule.intervals.as_slice())
+}
+
+// Schedule routes, the script routes come first so that /script is not taken for a schedule id
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    #[cfg(feature = "scripting")]
+    cfg.service(crate::scripting::get_script)
+        .service(crate::scripting::put_script)
+        .service(crate::scripting::delete_script);
+    cfg.service(list_schedules)
+        .service(add_schedule)
+        .service(delete_schedule);
+}
+
+#[get("/schedules/{owner}")]
+pub async fn list_schedules(
+    data: web::Data<AppState>,
+    owner: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let schedules = data.db.blocking(move |db| db.get_schedules(&owner)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(schedules))
+}
+
+#[post("/schedules/{owner}")]
+pub async fn add_schedule(
+    data: web::Data<AppState>,
+    owner: web::Path<String>,
+    req: web::Json<Schedule>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let mut schedule = req.into_inner();
+    schedule.owner = owner.into_inner();
+    to_effective(&schedule)?;
+
+    freeze::ensure_unfrozen(&data.db).await?;
+    let schedule = data.db.blocking(move |db| {
+        schedule.id = Some(db.add_schedule(&schedule)?);
+        Ok(schedule)
+    }).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(schedule))
+}
+
+#[delete("/schedules/{owner}/{id}")]
+pub async fn delete_schedule(
+    data: web::Data<AppState>,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let (owner, id) = path.into_inner();
+    freeze::ensure_unfrozen(&data.db).await?;
+    let deleted = data.db.blocking(move |db| db.delete_schedule(&owner, id)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if deleted == 0 {
+        return Ok(HttpResponse::NotFound().json(format!("Schedule {} not found", id)));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holiday_provider::MockProvider;
+    use crate::{compute_work_hours, db, EndOrDuration, WorkHoursRequest};
+
+    #[actix_rt::test]
+    async fn test_schedule_valid_on_each_day_is_used() {
+        let database = db::Database::new(":memory:").unwrap();
+        // Contract change on Wednesday, and a Friday with summer hours on top of it
+        database.add_schedule(&Schedule {
+            id: None,
+            owner: "alice".to_string(),
+            valid_from: "2023-10-04".to_string(),
+            valid_to: None,
+            start_of_day: "08:00:00".to_string(),
+            end_of_day: "18:00:00".to_string(),
+            working_intervals: vec![],
+        }).unwrap();
+        database.add_schedule(&Schedule {
+            id: None,
+            owner: "alice".to_string(),
+            valid_from: "2023-10-06".to_string(),
+            valid_to: Some("2023-10-06".to_string()),
+            start_of_day: "08:00:00".to_string(),
+            end_of_day: "12:00:00".to_string(),
+            working_intervals: vec![],
+        }).unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02".to_string(),
+            end_or_duration: EndOrDuration::EndDate {
+                end_date: "2023-10-06".to_string()
+            },
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            schedule_owner: Some("alice".to_string()),
+            ..Default::default()
+        };
+        let response = compute_work_hours(&data, &request).await.unwrap();
+
+        // 2 x 8 hours, 2 x 10 hours and 4 hours on Friday
+        assert_eq!(response.work_hours, 40.0);
+        assert_eq!(response.end_date, "2023-10-06T12:00:00+00:00");
+    }
+}
@@ -0,0 +1,209 @@
+use std::env;
+use std::time::{Duration, Instant};
+use actix_web::{web, HttpResponse, get, put, delete};
+use chrono::{Datelike, NaiveDate, NaiveTime, Utc};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use serde::{Serialize, Deserialize};
+
+use crate::schedules::EffectiveSchedule;
+use crate::{freeze, AppState, WorkingInterval};
+
+// Budget of a single schedule(day) call, an endless loop stops here
+const MAX_OPERATIONS: u64 = 100_000;
+
+// Wall-clock budget of all the script calls of one calculation (default: 1 second)
+fn script_timeout() -> Duration {
+    Duration::from_millis(env::var("SCRIPT_TIMEOUT_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(1000))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScheduleScript {
+    pub script: String,
+}
+
+// Sandboxed engine: no module imports, no eval, bounded sizes and run time
+fn engine(timeout: Duration) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(10_000);
+    engine.set_max_array_size(1_000);
+    engine.set_max_map_size(1_000);
+    let deadline = Instant::now() + timeout;
+    engine.on_progress(move |_| (Instant::now() > deadline).then_some(Dynamic::UNIT));
+    engine
+}
+
+fn compile(engine: &Engine, script: &str) -> Result<AST, String> {
+    let ast = engine.compile(script).map_err(|e| format!("Invalid schedule script: {}", e))?;
+    if !ast.iter_functions().any(|function| function.name == "schedule" && function.params.len() == 1) {
+        return Err("The schedule script must define fn schedule(day)".to_string());
+    }
+    Ok(ast)
+}
+
+// Intervals returned by schedule(day) for a date: () keeps the regular schedule,
+// [] is a day off, otherwise a list of "HH:MM:SS-HH:MM:SS" intervals
+fn intervals_on(engine: &Engine, ast: &AST, date: NaiveDate) -> Result<Option<Vec<(NaiveTime, NaiveTime)>>, String> {
+    let mut day = Map::new();
+    day.insert("date".into(), date.to_string().into());
+    day.insert("year".into(), (date.year() as i64).into());
+    day.insert("month".into(), (date.month() as i64).into());
+    day.insert("day".into(), (date.day() as i64).into());
+    day.insert("weekday".into(), (date.weekday().number_from_monday() as i64).into());
+    day.insert("isoWeek".into(), (date.iso_week().week() as i64).into());
+
+    let result: Dynamic = engine.call_fn(&mut Scope::new(), ast, "schedule", (day,))
+        .map_err(|e| format!("Schedule script failed on {}: {}", date, e))?;
+    if result.is_unit() {
+        return Ok(None);
+    }
+    let invalid = |found: &str| format!("Schedule script returned {} on {}, expected () or an array of \"HH:MM:SS-HH:MM:SS\"", found, date);
+    let working_intervals = result.into_array().map_err(invalid)?
+        .into_iter()
+        .map(|item| {
+            let item = item.into_string().map_err(invalid)?;
+            let (start, end) = item.split_once('-').ok_or_else(|| invalid(&item))?;
+            Ok(WorkingInterval { start: start.trim().to_string(), end: end.trim().to_string() })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if working_intervals.is_empty() {
+        return Ok(Some(vec![]));
+    }
+    crate::resolve_schedule("", "", &working_intervals)
+        .map(Some)
+        .map_err(|e| format!("Schedule script returned invalid intervals on {}: {}", date, e))
+}
+
+// One-day schedules computed by the owner's script between two dates (inclusive),
+// they take precedence over the stored schedules on the days they cover
+pub async fn load(data: &web::Data<AppState>, owner: &str, from: NaiveDate, to: NaiveDate) -> Result<Vec<EffectiveSchedule>, actix_web::error::Error> {
+    let owner = owner.to_string();
+    let script = data.db.blocking(move |db| db.get_schedule_script(&owner)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let script = match script {
+        Some(script) => script,
+        None => return Ok(vec![]),
+    };
+    // A call per day of the range, run on the blocking thread pool like database work
+    web::block(move || run(&script, from, to)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .map_err(actix_web::error::ErrorUnprocessableEntity)
+}
+
+fn run(script: &str, from: NaiveDate, to: NaiveDate) -> Result<Vec<EffectiveSchedule>, String> {
+    let engine = engine(script_timeout());
+    let ast = compile(&engine, script)?;
+
+    let mut schedules = vec![];
+    for date in from.iter_days().take_while(|date| *date <= to) {
+        if let Some(intervals) = intervals_on(&engine, &ast, date)? {
+            schedules.push(EffectiveSchedule { valid_from: date, valid_to: Some(date), intervals });
+        }
+    }
+    Ok(schedules)
+}
+
+#[get("/schedules/{owner}/script")]
+pub async fn get_script(
+    data: web::Data<AppState>,
+    owner: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let stored_owner = owner.to_string();
+    let script = data.db.blocking(move |db| db.get_schedule_script(&stored_owner)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    match script {
+        Some(script) => Ok(HttpResponse::Ok().json(ScheduleScript { script })),
+        None => Ok(HttpResponse::NotFound().json(format!("No schedule script for {}", owner))),
+    }
+}
+
+#[put("/schedules/{owner}/script")]
+pub async fn put_script(
+    data: web::Data<AppState>,
+    owner: web::Path<String>,
+    req: web::Json<ScheduleScript>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    // Compile and run it once so that a broken script is rejected before any calculation uses it
+    let checked = req.script.clone();
+    let today = Utc::now().date_naive();
+    web::block(move || run(&checked, today, today)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .map_err(actix_web::error::ErrorBadRequest)?;
+
+    freeze::ensure_unfrozen(&data.db).await?;
+    let owner = owner.into_inner();
+    let script = req.into_inner();
+    let stored = script.script.clone();
+    data.db.blocking(move |db| db.set_schedule_script(&owner, &stored)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(script))
+}
+
+#[delete("/schedules/{owner}/script")]
+pub async fn delete_script(
+    data: web::Data<AppState>,
+    owner: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    freeze::ensure_unfrozen(&data.db).await?;
+    let stored_owner = owner.to_string();
+    let deleted = data.db.blocking(move |db| db.delete_schedule_script(&stored_owner)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if deleted == 0 {
+        return Ok(HttpResponse::NotFound().json(format!("No schedule script for {}", owner)));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::{compute_work_hours, db, EndOrDuration, WorkHoursRequest};
+
+    #[actix_rt::test]
+    async fn test_script_computes_per_day_schedules() {
+        let data = web::Data::new(AppState { db: db::Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(
+            App::new().app_data(data.clone()).configure(crate::schedules::configure)
+        ).await;
+
+        // Half days on even ISO weeks' Fridays, no work on the first Monday of the month
+        let script = r#"
+            fn schedule(day) {
+                if day.weekday == 5 && day.isoWeek % 2 == 0 { return ["09:00:00-13:00:00"]; }
+                if day.weekday == 1 && day.day <= 7 { return []; }
+            }
+        "#;
+        let req = test::TestRequest::put().uri("/schedules/alice/script")
+            .set_json(ScheduleScript { script: script.to_string() })
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+        // Monday 2 October is off and Friday 6 October (even week 40) is a half day
+        let request = WorkHoursRequest {
+            start_date: "2023-10-02".to_string(),
+            end_or_duration: EndOrDuration::EndDate { end_date: "2023-10-06".to_string() },
+            country: "us".to_string(),
+            timezone: "UTC".to_string(),
+            schedule_owner: Some("alice".to_string()),
+            ..Default::default()
+        };
+        let response = compute_work_hours(&data, &request).await.unwrap();
+        assert_eq!(response.work_hours, 3.0 * 8.0 + 4.0);
+
+        // Endless scripts are stopped by the engine and rejected
+        let req = test::TestRequest::put().uri("/schedules/bob/script")
+            .set_json(ScheduleScript { script: "fn schedule(day) { loop {} }".to_string() })
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+        let req = test::TestRequest::put().uri("/schedules/bob/script")
+            .set_json(ScheduleScript { script: "let x = 1;".to_string() })
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+}
@@ -0,0 +1,95 @@
+use actix_web::{web, HttpResponse, post};
+use chrono::DateTime;
+use serde::{Serialize, Deserialize};
+
+use crate::{compute_work_hours, AppState, WorkHoursRequest, WorkHoursResponse};
+
+// Ordered list of segments, e.g. a ticket handed over Paris -> New York -> Tokyo
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SegmentsRequest {
+    pub segments: Vec<WorkHoursRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SegmentResult {
+    pub country: String,
+    pub timezone: String,
+    #[serde(flatten)]
+    pub result: WorkHoursResponse,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SegmentsResponse {
+    pub work_hours: f64,
+    pub work_minutes: f64,
+    pub work_seconds: f64,
+    pub segments: Vec<SegmentResult>,
+}
+
+#[post("/segments")]
+pub async fn calculate_segments(
+    data: web::Data<AppState>,
+    req: web::Json<SegmentsRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    if req.segments.is_empty() {
+        return Err(actix_web::error::ErrorBadRequest("At least one segment must be provided"));
+    }
+
+    let mut results: Vec<SegmentResult> = vec![];
+    for (index, segment) in req.segments.iter().enumerate() {
+        let result = compute_work_hours(&data, segment).await
+            .map_err(|e| actix_web::error::ErrorBadRequest(format!("Segment {}: {}", index, e)))?;
+
+        // Segments must follow each other without overlapping
+        if let Some(previous) = results.last() {
+            let previous_end = DateTime::parse_from_rfc3339(&previous.result.end_date).unwrap();
+            let start = DateTime::parse_from_rfc3339(&result.start_date).unwrap();
+            if start < previous_end {
+                return Err(actix_web::error::ErrorBadRequest(format!(
+                    "Segment {} starts before the end of segment {}", index, index - 1
+                )));
+            }
+        }
+
+        results.push(SegmentResult {
+            country: segment.country.to_lowercase(),
+            timezone: segment.timezone.clone(),
+            result,
+        });
+    }
+
+    let work_hours: f64 = results.iter().map(|s| s.result.work_hours).sum();
+    Ok(HttpResponse::Ok().json(SegmentsResponse {
+        work_hours,
+        work_minutes: work_hours * 60.0,
+        work_seconds: work_hours * 3600.0,
+        segments: results,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::db;
+
+    #[actix_rt::test]
+    async fn test_follow_the_sun_segments() {
+        let data = web::Data::new(AppState { db: db::Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data).service(calculate_segments)).await;
+
+        // Paris morning, then New York afternoon on the same Monday
+        let body = serde_json::json!({
+            "segments": [
+                { "startDate": "2023-10-02T09:00:00+02:00", "endDate": "2023-10-02T12:00:00+02:00", "country": "fr", "timezone": "Europe/Paris" },
+                { "startDate": "2023-10-02T09:00:00-04:00", "endDate": "2023-10-02T17:00:00-04:00", "country": "us", "timezone": "America/New_York" }
+            ]
+        });
+        let req = test::TestRequest::post().uri("/segments").set_json(body).to_request();
+        let response: SegmentsResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(response.segments.len(), 2);
+        assert_eq!(response.work_hours, 11.0);
+    }
+}
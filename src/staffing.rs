@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use actix_web::{web, HttpResponse, post};
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+
+use crate::{aggregation, compute_working_time, AppState, EndOrDuration, WorkHoursRequest, WorkingInterval};
+
+// Approved absence, inclusive dates
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Absence {
+    pub start: String,
+    pub end: String,
+}
+
+// Team member with the calendar their working days are computed from
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Member {
+    // Also the employee whose time off in lieu taken as leave counts as an absence
+    pub id: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    #[serde(rename = "startOfDay", default = "crate::default_start_of_day")]
+    pub start_of_day: String,
+    #[serde(rename = "endOfDay", default = "crate::default_end_of_day")]
+    pub end_of_day: String,
+    #[serde(rename = "workingIntervals", default)]
+    pub working_intervals: Vec<WorkingInterval>,
+    #[serde(rename = "scheduleOwner", default)]
+    pub schedule_owner: Option<String>,
+    #[serde(default)]
+    pub absences: Vec<Absence>,
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StaffingRequest {
+    // Inclusive range of days to check (YYYY-MM-DD)
+    #[serde(rename = "startDate")]
+    pub start_date: String,
+    #[serde(rename = "endDate")]
+    pub end_date: String,
+    #[serde(rename = "minimumHeadcount")]
+    pub minimum_headcount: usize,
+    pub members: Vec<Member>,
+}
+
+// Working day of the team with fewer members available than required
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Breach {
+    pub date: String,
+    // Members working that day without the absences
+    pub scheduled: usize,
+    pub available: usize,
+    pub absent: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StaffingResponse {
+    pub start_date: String,
+    pub end_date: String,
+    pub minimum_headcount: usize,
+    pub breaches: Vec<Breach>,
+}
+
+// Keeps a single request within reasonable bounds, one calculation is run per member
+const MAX_MEMBERS: usize = 500;
+
+fn parse_date(value: &str) -> Result<NaiveDate, actix_web::error::Error> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid date format: {}", e)))
+}
+
+// Absent days of the member within the checked range, longer absences are cut to it
+fn absent_dates(member: &Member, range_start: NaiveDate, range_end: NaiveDate) -> Result<HashSet<NaiveDate>, actix_web::error::Error> {
+    let mut dates = HashSet::new();
+    for absence in &member.absences {
+        let (start, end) = (parse_date(&absence.start)?, parse_date(&absence.end)?);
+        if start > end {
+            return Err(actix_web::error::ErrorBadRequest(format!("Absence of {} starts after it ends: {} - {}", member.id, absence.start, absence.end)));
+        }
+        let (start, end) = (start.max(range_start), end.min(range_end));
+        dates.extend(start.iter_days().take_while(|date| *date <= end));
+    }
+    Ok(dates)
+}
+
+// Hours of time off in lieu the employee takes per day
+async fn toil_leave(data: &web::Data<AppState>, employee: &str) -> Result<HashMap<NaiveDate, f64>, actix_web::error::Error> {
+    let employee = employee.to_string();
+    let entries = data.db.blocking(move |db| db.get_toil_entries(&employee)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let mut leave = HashMap::new();
+    for entry in entries.iter().filter(|entry| entry.hours < 0.0) {
+        if let Ok(date) = NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d") {
+            *leave.entry(date).or_insert(0.0) -= entry.hours;
+        }
+    }
+    Ok(leave)
+}
+
+// Working hours of the member per local day, from their calendar and schedule
+async fn working_hours(data: &web::Data<AppState>, member: &Member, start: NaiveDate, end: NaiveDate) -> Result<BTreeMap<NaiveDate, f64>, actix_web::error::Error> {
+    let timezone: Tz = member.timezone.parse()
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone for {}: {}", member.id, e)))?;
+    let midnight = |date: NaiveDate| crate::resolve_local(&timezone, date.and_hms_opt(0, 0, 0).unwrap()).to_rfc3339();
+    let request = WorkHoursRequest {
+        start_date: midnight(start),
+        end_or_duration: EndOrDuration::EndDate { end_date: midnight(end + chrono::Duration::days(1)) },
+        start_of_day: member.start_of_day.clone(),
+        end_of_day: member.end_of_day.clone(),
+        working_intervals: member.working_intervals.clone(),
+        country: member.country.clone(),
+        subdivision: member.subdivision.clone(),
+        timezone: member.timezone.clone(),
+        schedule_owner: member.schedule_owner.clone(),
+        ..Default::default()
+    };
+    let working_time = compute_working_time(data, &request).await?;
+    Ok(aggregation::hours_per_day(&working_time).into_iter()
+        .filter(|(_, hours)| *hours > 0.0)
+        .collect())
+}
+
+pub async fn check(data: &web::Data<AppState>, req: &StaffingRequest) -> Result<StaffingResponse, actix_web::error::Error> {
+    let (start, end) = (parse_date(&req.start_date)?, parse_date(&req.end_date)?);
+    if start > end {
+        return Err(actix_web::error::ErrorBadRequest("startDate must not be after endDate"));
+    }
+    if req.members.is_empty() || req.members.len() > MAX_MEMBERS {
+        return Err(actix_web::error::ErrorBadRequest(format!("Between 1 and {} members must be provided", MAX_MEMBERS)));
+    }
+
+    // Per day: scheduled members and the absent ones among them
+    let mut days: BTreeMap<NaiveDate, (usize, Vec<String>)> = BTreeMap::new();
+    for member in &req.members {
+        let absences = absent_dates(member, start, end)?;
+        let toil = toil_leave(data, &member.id).await?;
+        for (date, hours) in working_hours(data, member, start, end).await? {
+            let day = days.entry(date).or_insert((0, vec![]));
+            day.0 += 1;
+            // Time off in lieu covering the working hours of the day is an absence too
+            if absences.contains(&date) || toil.get(&date).is_some_and(|leave| *leave >= hours) {
+                day.1.push(member.id.clone());
+            }
+        }
+    }
+
+    let breaches = days.into_iter()
+        .filter(|(date, _)| *date >= start && *date <= end)
+        .map(|(date, (scheduled, absent))| Breach { date: date.to_string(), scheduled, available: scheduled - absent.len(), absent })
+        .filter(|breach| breach.available < req.minimum_headcount)
+        .collect();
+
+    Ok(StaffingResponse {
+        start_date: start.to_string(),
+        end_date: end.to_string(),
+        minimum_headcount: req.minimum_headcount,
+        breaches,
+    })
+}
+
+// Days on which approved absences leave the team below its minimum headcount.
+// Days nobody is scheduled to work (weekends, shared holidays) are not checked.
+#[post("/staffing/check")]
+pub async fn check_staffing(
+    data: web::Data<AppState>,
+    req: web::Json<StaffingRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    Ok(HttpResponse::Ok().json(check(&data, &req).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::db;
+
+    #[actix_rt::test]
+    async fn test_absences_breaching_minimum_headcount() {
+        let database = db::Database::new(":memory:").unwrap();
+        database.add_holiday(&db::Holiday {
+            id: None,
+            date: "2023-10-03".to_string(),
+            description: "German Unity Day".to_string(),
+            country: "de".to_string(),
+            portion: db::HolidayPortion::Full,
+            kind: db::HolidayKind::Public,
+            subdivision: None,
+        }, "test").unwrap();
+        // Bob takes two hours of time off in lieu on Wednesday, and the whole of Thursday
+        for (date, hours) in [("2023-10-04", -2.0), ("2023-10-05", -8.0)] {
+            database.add_toil_entry(&db::ToilEntry {
+                id: None,
+                employee: "bob".to_string(),
+                date: date.to_string(),
+                hours,
+                description: "Time off in lieu".to_string(),
+            }).unwrap();
+        }
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data).service(check_staffing)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/staffing/check")
+            .set_json(serde_json::json!({
+                "startDate": "2023-10-02", "endDate": "2023-10-08", "minimumHeadcount": 2,
+                "members": [
+                    { "id": "alice", "country": "fr", "absences": [{ "start": "2023-10-05", "end": "2023-10-06" }] },
+                    { "id": "bob", "country": "fr" },
+                    { "id": "carl", "country": "de", "timezone": "Europe/Berlin", "absences": [{ "start": "2023-10-06", "end": "2023-10-06" }] }
+                ]
+            }))
+            .to_request();
+        let response: StaffingResponse = test::call_and_read_body_json(&app, req).await;
+
+        // Carl is off on German Unity Day anyway, Thursday and Friday breach the minimum
+        assert_eq!(response.breaches, vec![
+            Breach { date: "2023-10-05".to_string(), scheduled: 3, available: 1, absent: vec!["alice".to_string(), "bob".to_string()] },
+            Breach { date: "2023-10-06".to_string(), scheduled: 3, available: 1, absent: vec!["alice".to_string(), "carl".to_string()] },
+        ]);
+    }
+
+    #[actix_rt::test]
+    async fn test_absences_are_cut_to_the_checked_range() {
+        let member: Member = serde_json::from_value(serde_json::json!({
+            "id": "alice",
+            "absences": [{ "start": "0001-01-01", "end": "9999-12-31" }, { "start": "2023-09-01", "end": "2023-09-30" }]
+        })).unwrap();
+        let day = |value: &str| NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap();
+        let dates = absent_dates(&member, day("2023-10-02"), day("2023-10-08")).unwrap();
+        assert_eq!(dates.len(), 7);
+        assert!(dates.contains(&day("2023-10-02")) && dates.contains(&day("2023-10-08")));
+    }
+}
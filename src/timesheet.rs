@@ -0,0 +1,196 @@
+use std::collections::{hash_map, HashMap};
+use actix_web::{web, HttpResponse, post};
+use chrono::{DateTime, Datelike, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+
+use crate::export::{self, ExportParams};
+use crate::{aggregation, country, db, AppState, WorkingTime};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Punch {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimesheetRequest {
+    pub punches: Vec<Punch>,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub subdivision: Option<String>,
+    #[serde(default)]
+    pub timezone: String,
+}
+
+// Hours of a punch worked on one local calendar day
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PunchDay {
+    pub date: String,
+    pub hours: f64,
+    pub weekend: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub holiday: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PunchResult {
+    pub start: String,
+    pub end: String,
+    pub hours: f64,
+    // Hours worked on weekends or public holidays, usually paid at a premium
+    pub premium_hours: f64,
+    pub days: Vec<PunchDay>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimesheetResponse {
+    pub total_hours: f64,
+    pub premium_hours: f64,
+    pub punches: Vec<PunchResult>,
+}
+
+pub fn parse_punch(punch: &Punch, timezone: Tz) -> Result<(DateTime<Tz>, DateTime<Tz>), actix_web::error::Error> {
+    let start = DateTime::parse_from_rfc3339(&punch.start)
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid punch start format: {}", e)))?;
+    let end = DateTime::parse_from_rfc3339(&punch.end)
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid punch end format: {}", e)))?;
+    if start >= end {
+        return Err(actix_web::error::ErrorBadRequest(format!("Punch must start before it ends: {} - {}", punch.start, punch.end)));
+    }
+    Ok((start.with_timezone(&timezone), end.with_timezone(&timezone)))
+}
+
+// Split punches per local day and flag the days worked on weekends or holidays
+pub async fn analyze(
+    data: &web::Data<AppState>,
+    req: &TimesheetRequest,
+) -> Result<TimesheetResponse, actix_web::error::Error> {
+    let timezone: Tz = req.timezone.parse()
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone: {}", e)))?;
+    let country = country::parse_request_optional(&req.country)?;
+    let subdivision = req.subdivision.clone().unwrap_or_default();
+
+    let mut holidays_by_year: HashMap<i32, Vec<db::Holiday>> = HashMap::new();
+    let mut punches = vec![];
+    for punch in &req.punches {
+        let (start, end) = parse_punch(punch, timezone)?;
+        let working_time = WorkingTime {
+            start_date: start,
+            end_date: end,
+            intervals: vec![(start, end)],
+            holidays_applied: vec![],
+            bulk_seconds: 0,
+            warnings: vec![],
+        };
+
+        let mut days = vec![];
+        for (date, hours) in aggregation::hours_per_day(&working_time) {
+            if let hash_map::Entry::Vacant(entry) = holidays_by_year.entry(date.year()) {
+                let first_day = NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap();
+                let holidays = crate::load_holidays(data, country.as_ref(), &subdivision, first_day).await;
+                entry.insert(holidays);
+            }
+            let holiday = crate::holiday_on(date, &holidays_by_year[&date.year()]);
+            days.push(PunchDay {
+                date: date.to_string(),
+                hours,
+                weekend: matches!(date.weekday(), chrono::Weekday::Sat | chrono::Weekday::Sun),
+                holiday: holiday.map(|h| h.description.clone()),
+            });
+        }
+
+        punches.push(PunchResult {
+            start: start.to_rfc3339(),
+            end: end.to_rfc3339(),
+            hours: working_time.work_hours(),
+            premium_hours: days.iter()
+                .filter(|day| day.weekend || day.holiday.is_some())
+                .map(|day| day.hours)
+                .sum(),
+            days,
+        });
+    }
+
+    Ok(TimesheetResponse {
+        total_hours: punches.iter().map(|p| p.hours).sum(),
+        premium_hours: punches.iter().map(|p| p.premium_hours).sum(),
+        punches,
+    })
+}
+
+// One row per punch and local day, for columnar exports
+#[derive(Debug, Serialize)]
+struct TimesheetRow<'a> {
+    punch_start: &'a str,
+    punch_end: &'a str,
+    date: &'a str,
+    hours: f64,
+    weekend: bool,
+    holiday: Option<&'a str>,
+}
+
+#[post("/timesheet")]
+pub async fn check_timesheet(
+    data: web::Data<AppState>,
+    params: web::Query<ExportParams>,
+    req: web::Json<TimesheetRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let response = analyze(&data, &req).await?;
+    let rows: Vec<TimesheetRow> = response.punches.iter()
+        .flat_map(|punch| punch.days.iter().map(move |day| TimesheetRow {
+            punch_start: &punch.start,
+            punch_end: &punch.end,
+            date: &day.date,
+            hours: day.hours,
+            weekend: day.weekend,
+            holiday: day.holiday.as_deref(),
+        }))
+        .collect();
+    export::respond(params.format, &response, &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+
+    #[actix_rt::test]
+    async fn test_holiday_and_weekend_punches_are_flagged() {
+        let database = db::Database::new(":memory:").unwrap();
+        database.add_holiday(&db::Holiday {
+            id: None,
+            date: "2023-12-25".to_string(),
+            description: "Christmas Day".to_string(),
+            country: "gb".to_string(),
+            portion: db::HolidayPortion::Full,
+            kind: db::HolidayKind::Public,
+            subdivision: None,
+        }, "test").unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data).service(check_timesheet)).await;
+
+        // Sunday night shift running into Christmas Day, then a regular Tuesday
+        let req = test::TestRequest::post()
+            .uri("/timesheet")
+            .set_json(serde_json::json!({
+                "country": "gb", "timezone": "UTC",
+                "punches": [
+                    { "start": "2023-12-24T20:00:00Z", "end": "2023-12-25T04:00:00Z" },
+                    { "start": "2023-12-26T09:00:00Z", "end": "2023-12-26T17:00:00Z" }
+                ]
+            }))
+            .to_request();
+        let response: TimesheetResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(response.total_hours, 16.0);
+        assert_eq!(response.premium_hours, 8.0);
+        assert_eq!(response.punches[0].days, vec![
+            PunchDay { date: "2023-12-24".to_string(), hours: 4.0, weekend: true, holiday: None },
+            PunchDay { date: "2023-12-25".to_string(), hours: 4.0, weekend: false, holiday: Some("Christmas Day".to_string()) },
+        ]);
+        assert_eq!(response.punches[1].premium_hours, 0.0);
+    }
+}
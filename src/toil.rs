@@ -0,0 +1,272 @@
+use actix_web::{web, HttpResponse, get, post};
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+
+use crate::blackouts;
+use crate::db::{BlackoutPeriod, ToilEntry};
+use crate::timesheet::{self, TimesheetRequest};
+use crate::AppState;
+
+fn default_rate() -> f64 {
+    1.0
+}
+
+// Timesheet whose weekend and holiday hours accrue time off in lieu
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccrualRequest {
+    #[serde(flatten)]
+    pub timesheet: TimesheetRequest,
+    // Hours of time off earned per premium hour worked
+    #[serde(default = "default_rate")]
+    pub rate: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaveRequest {
+    pub date: String,
+    pub hours: f64,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Calendar of the employee, whose blackout periods apply
+    #[serde(default)]
+    pub calendar: Option<String>,
+    // Set by managers to take leave inside a rejecting blackout period
+    #[serde(rename = "override", default)]
+    pub override_blackout: bool,
+}
+
+// Stored leave, with the blackout periods it falls in when they only flag it or were overridden
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaveResponse {
+    #[serde(flatten)]
+    pub entry: ToilEntry,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blackouts: Vec<BlackoutPeriod>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ToilBalance {
+    pub employee: String,
+    pub accrued_hours: f64,
+    pub taken_hours: f64,
+    pub balance_hours: f64,
+    pub entries: Vec<ToilEntry>,
+}
+
+fn balance(employee: &str, entries: Vec<ToilEntry>) -> ToilBalance {
+    let accrued_hours = entries.iter().filter(|e| e.hours > 0.0).map(|e| e.hours).sum();
+    let taken_hours = entries.iter().filter(|e| e.hours < 0.0).map(|e| -e.hours).sum();
+    ToilBalance {
+        employee: employee.to_string(),
+        accrued_hours,
+        taken_hours,
+        balance_hours: accrued_hours - taken_hours,
+        entries,
+    }
+}
+
+#[get("/toil/{employee}")]
+pub async fn get_toil_balance(
+    data: web::Data<AppState>,
+    employee: web::Path<String>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let employee = employee.into_inner();
+    let stored_employee = employee.clone();
+    let entries = data.db.blocking(move |db| db.get_toil_entries(&stored_employee)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(balance(&employee, entries)))
+}
+
+#[post("/toil/{employee}/accruals")]
+pub async fn accrue_toil(
+    data: web::Data<AppState>,
+    employee: web::Path<String>,
+    req: web::Json<AccrualRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let employee = employee.into_inner();
+    if req.rate <= 0.0 {
+        return Err(actix_web::error::ErrorBadRequest("rate must be strictly positive"));
+    }
+    let analysis = timesheet::analyze(&data, &req.timesheet).await?;
+
+    let days: Vec<_> = analysis.punches.iter().flat_map(|punch| punch.days.iter()).collect();
+    let mut entries: Vec<ToilEntry> = vec![];
+    for day in &days {
+        if (!day.weekend && day.holiday.is_none()) || day.hours <= 0.0 {
+            continue;
+        }
+        let description = match &day.holiday {
+            Some(holiday) => format!("Worked on {}", holiday),
+            None => "Worked on weekend".to_string(),
+        };
+        entries.push(ToilEntry {
+            id: None,
+            employee: employee.clone(),
+            date: day.date.clone(),
+            hours: day.hours * req.rate,
+            description,
+        });
+    }
+
+    // Accruals previously posted for the days of the timesheet are replaced
+    let from = days.iter().map(|day| day.date.clone()).min().unwrap_or_default();
+    let to = days.iter().map(|day| day.date.clone()).max().unwrap_or_default();
+    let stored = entries.clone();
+    let ids = data.db.blocking(move |db| db.replace_toil_accruals(&employee, &from, &to, &stored)).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    for (entry, id) in entries.iter_mut().zip(ids) {
+        entry.id = Some(id);
+    }
+    Ok(HttpResponse::Created().json(entries))
+}
+
+// Consume time off in lieu as leave, the balance may not go negative
+#[post("/toil/{employee}/leave")]
+pub async fn take_toil(
+    data: web::Data<AppState>,
+    employee: web::Path<String>,
+    req: web::Json<LeaveRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let employee = employee.into_inner();
+    let date = NaiveDate::parse_from_str(&req.date, "%Y-%m-%d")
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid date format: {}", e)))?;
+    if req.hours <= 0.0 {
+        return Err(actix_web::error::ErrorBadRequest("hours must be strictly positive"));
+    }
+
+    let (calendar, stored_employee) = (req.calendar.clone(), employee.clone());
+    let (blackouts, entries) = data.db.blocking(move |db| Ok((
+        match &calendar {
+            Some(calendar) => blackouts::covering(db, calendar, date),
+            None => Ok(vec![]),
+        },
+        db.get_toil_entries(&stored_employee)?,
+    ))).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    let blackouts = blackouts.map_err(actix_web::error::ErrorUnprocessableEntity)?;
+    if let Some(period) = blackouts.iter().find(|period| period.mode == "reject") {
+        if !req.override_blackout {
+            return Err(actix_web::error::ErrorUnprocessableEntity(format!(
+                "Leave on {} falls in the blackout period {} to {}{}", date, period.start, period.end,
+                if period.description.is_empty() { String::new() } else { format!(" ({})", period.description) }
+            )));
+        }
+    }
+    let available = balance(&employee, entries).balance_hours;
+    if req.hours > available {
+        return Err(actix_web::error::ErrorUnprocessableEntity(
+            format!("Insufficient time off in lieu: {} hours requested, {} available", req.hours, available)
+        ));
+    }
+
+    let mut entry = ToilEntry {
+        id: None,
+        employee: employee.clone(),
+        date: date.to_string(),
+        hours: -req.hours,
+        description: req.description.clone().unwrap_or("Time off in lieu".to_string()),
+    };
+    let entry = data.db.blocking(move |db| {
+        entry.id = Some(db.add_toil_entry(&entry)?);
+        Ok(entry)
+    }).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(LeaveResponse { entry, blackouts }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::db;
+
+    #[actix_rt::test]
+    async fn test_accrue_and_take_toil() {
+        let data = web::Data::new(AppState { db: db::Database::new(":memory:").unwrap(), holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(
+            App::new().app_data(data).service(get_toil_balance).service(accrue_toil).service(take_toil)
+        ).await;
+
+        // Six hours on a Saturday at time and a half, plus a regular Monday
+        let req = test::TestRequest::post()
+            .uri("/toil/alice/accruals")
+            .set_json(serde_json::json!({
+                "timezone": "UTC", "country": "us", "rate": 1.5,
+                "punches": [
+                    { "start": "2023-10-07T09:00:00Z", "end": "2023-10-07T15:00:00Z" },
+                    { "start": "2023-10-09T09:00:00Z", "end": "2023-10-09T17:00:00Z" }
+                ]
+            }))
+            .to_request();
+        let entries: Vec<ToilEntry> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].hours, 9.0);
+
+        // Posting the timesheet again replaces its accruals
+        let req = test::TestRequest::post()
+            .uri("/toil/alice/accruals")
+            .set_json(serde_json::json!({
+                "timezone": "UTC", "country": "us", "rate": 1.5,
+                "punches": [{ "start": "2023-10-07T09:00:00Z", "end": "2023-10-07T15:00:00Z" }]
+            }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+
+        let req = test::TestRequest::post()
+            .uri("/toil/alice/leave")
+            .set_json(serde_json::json!({ "date": "2023-10-13", "hours": 8 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+
+        // Only one hour is left
+        let req = test::TestRequest::post()
+            .uri("/toil/alice/leave")
+            .set_json(serde_json::json!({ "date": "2023-10-16", "hours": 2 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 422);
+
+        let req = test::TestRequest::get().uri("/toil/alice").to_request();
+        let balance: ToilBalance = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(balance.accrued_hours, 9.0);
+        assert_eq!(balance.taken_hours, 8.0);
+        assert_eq!(balance.balance_hours, 1.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_leave_in_blackout_period() {
+        let database = db::Database::new(":memory:").unwrap();
+        database.add_toil_entry(&ToilEntry {
+            id: None,
+            employee: "bob".to_string(),
+            date: "2023-11-04".to_string(),
+            hours: 24.0,
+            description: "Worked on weekend".to_string(),
+        }).unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(
+            App::new().app_data(data).service(take_toil).service(blackouts::add_blackout_periods)
+        ).await;
+
+        let req = test::TestRequest::post()
+            .uri("/blackouts/retail")
+            .set_json(serde_json::json!([
+                { "start": "2023-12-01", "end": "2023-12-24", "description": "December peak" },
+                { "start": "2023-11-20", "end": "2023-11-30", "mode": "flag" }
+            ]))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+
+        let leave = |body: serde_json::Value| test::TestRequest::post().uri("/toil/bob/leave").set_json(body).to_request();
+        let resp = test::call_service(&app, leave(serde_json::json!({ "date": "2023-12-15", "hours": 8, "calendar": "retail" }))).await;
+        assert_eq!(resp.status(), 422);
+
+        // Flagged, or overridden by a manager
+        let response: LeaveResponse = test::call_and_read_body_json(&app, leave(serde_json::json!({ "date": "2023-11-24", "hours": 8, "calendar": "retail" }))).await;
+        assert_eq!(response.blackouts.len(), 1);
+        assert_eq!(response.blackouts[0].mode, "flag");
+        let response: LeaveResponse = test::call_and_read_body_json(&app, leave(serde_json::json!({ "date": "2023-12-15", "hours": 8, "calendar": "retail", "override": true }))).await;
+        assert_eq!(response.blackouts[0].description, "December peak");
+        assert_eq!(response.entry.hours, -8.0);
+    }
+}
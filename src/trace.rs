@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::future::Future;
+use serde::{Serialize, Deserialize};
+
+// Upstream call or cache decision made while computing an explained request
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct UpstreamCall {
+    // openholidays, nager or database
+    pub provider: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    // hit, stale, miss or expired for cached lookups
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // When the cached or stored holidays used were fetched from the provider (RFC3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetched_at: Option<String>,
+}
+
+// Returned with explain: true, to see why holidays were or were not applied
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Explain {
+    pub holiday_provider: String,
+    pub upstream_calls: Vec<UpstreamCall>,
+}
+
+tokio::task_local! {
+    static CALLS: RefCell<Vec<UpstreamCall>>;
+}
+
+// Recorded only while a request is being explained, a no-op otherwise
+pub fn record(call: UpstreamCall) {
+    let _ = CALLS.try_with(|calls| calls.borrow_mut().push(call));
+}
+
+// Run a future and return the upstream calls it made, they are also recorded by an enclosing collect
+pub async fn collect<F: Future>(future: F) -> (F::Output, Vec<UpstreamCall>) {
+    let (output, calls) = CALLS.scope(RefCell::new(vec![]), async move {
+        let output = future.await;
+        (output, CALLS.with(|calls| calls.take()))
+    }).await;
+    let _ = CALLS.try_with(|outer| outer.borrow_mut().extend(calls.iter().cloned()));
+    (output, calls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_calls_are_only_recorded_inside_collect() {
+        record(UpstreamCall { provider: "ignored".to_string(), ..Default::default() });
+        let (output, calls) = collect(async {
+            record(UpstreamCall { provider: "openholidays".to_string(), cache: Some("hit".to_string()), ..Default::default() });
+            42
+        }).await;
+        assert_eq!(output, 42);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].provider, "openholidays");
+
+        // Nested collects also report to the enclosing one
+        let (_, calls) = collect(async {
+            collect(async { record(UpstreamCall { provider: "nager".to_string(), ..Default::default() }) }).await
+        }).await;
+        assert_eq!(calls.len(), 1);
+    }
+}
@@ -0,0 +1,112 @@
+use actix_web::{HttpResponse, get};
+use chrono::{NaiveDateTime, TimeZone};
+use chrono::offset::LocalResult;
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+
+// Catalogue of the warnings attached to responses. Codes are stable: clients branch on them,
+// so a code is never reused or renumbered, only new ones are added.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WarningCode {
+    HolidaysUnavailableFallbackUsed,
+    DstAmbiguousTimeResolvedEarliest,
+    DstNonexistentTimeShiftedForward,
+    NoHolidayData,
+    TimezoneInferredFromOffset,
+    HolidayDataStale,
+}
+
+pub const CATALOGUE: [WarningCode; 6] = [
+    WarningCode::HolidaysUnavailableFallbackUsed,
+    WarningCode::DstAmbiguousTimeResolvedEarliest,
+    WarningCode::DstNonexistentTimeShiftedForward,
+    WarningCode::NoHolidayData,
+    WarningCode::TimezoneInferredFromOffset,
+    WarningCode::HolidayDataStale,
+];
+
+impl WarningCode {
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarningCode::HolidaysUnavailableFallbackUsed => "W001",
+            WarningCode::DstAmbiguousTimeResolvedEarliest => "W002",
+            WarningCode::DstNonexistentTimeShiftedForward => "W003",
+            WarningCode::NoHolidayData => "W004",
+            WarningCode::TimezoneInferredFromOffset => "W005",
+            WarningCode::HolidayDataStale => "W006",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WarningCode::HolidaysUnavailableFallbackUsed => "holidays_unavailable_fallback_used",
+            WarningCode::DstAmbiguousTimeResolvedEarliest => "dst_ambiguous_time_resolved_earliest",
+            WarningCode::DstNonexistentTimeShiftedForward => "dst_nonexistent_time_shifted_forward",
+            WarningCode::NoHolidayData => "no_holiday_data",
+            WarningCode::TimezoneInferredFromOffset => "timezone_inferred_from_offset",
+            WarningCode::HolidayDataStale => "holiday_data_stale",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            WarningCode::HolidaysUnavailableFallbackUsed => "The holiday provider failed, the holidays stored in the database were used instead",
+            WarningCode::DstAmbiguousTimeResolvedEarliest => "A local time occurring twice at the end of daylight saving time was resolved to its first occurrence",
+            WarningCode::DstNonexistentTimeShiftedForward => "A local time skipped at the start of daylight saving time was moved to the first valid time after it",
+            WarningCode::NoHolidayData => "No holidays are known for the country, only weekends were excluded",
+            WarningCode::TimezoneInferredFromOffset => "No timezone was given, the fixed UTC offset shared by the dates was used",
+            WarningCode::HolidayDataStale => "The holidays came from the cache or the database and are older than DATA_FRESHNESS_MAX_AGE_SECONDS",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Warning {
+    pub code: String,
+    pub name: String,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(code: WarningCode, message: String) -> Self {
+        Warning {
+            code: code.code().to_string(),
+            name: code.name().to_string(),
+            message,
+        }
+    }
+}
+
+// Warning for a local time that does not map to exactly one instant in the timezone
+pub fn local_time_warning(timezone: &Tz, local: NaiveDateTime, label: &str) -> Option<Warning> {
+    match timezone.from_local_datetime(&local) {
+        LocalResult::Single(_) => None,
+        LocalResult::Ambiguous(_, _) => Some(Warning::new(
+            WarningCode::DstAmbiguousTimeResolvedEarliest,
+            format!("The {} time {} occurs twice in {}, the first occurrence was used", label, local, timezone),
+        )),
+        LocalResult::None => Some(Warning::new(
+            WarningCode::DstNonexistentTimeShiftedForward,
+            format!("The {} time {} does not exist in {}, the next valid time was used", label, local, timezone),
+        )),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CatalogueEntry {
+    pub code: String,
+    pub name: String,
+    pub description: String,
+}
+
+#[get("/warnings")]
+pub async fn list_warnings() -> HttpResponse {
+    let entries: Vec<CatalogueEntry> = CATALOGUE.iter()
+        .map(|code| CatalogueEntry {
+            code: code.code().to_string(),
+            name: code.name().to_string(),
+            description: code.description().to_string(),
+        })
+        .collect();
+    HttpResponse::Ok().json(entries)
+}
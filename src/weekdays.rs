@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use actix_web::{web, HttpResponse, post};
+use chrono::{Datelike, NaiveDate};
+use serde::{Serialize, Deserialize};
+
+use crate::export::{self, ExportParams};
+use crate::{aggregation, compute_working_time, AppState, WorkHoursRequest};
+
+// Indexed by Weekday::num_days_from_monday
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WeekdayEntry {
+    // mon to sun, as in calendar weekends
+    pub weekday: String,
+    // Occurrences of the weekday in the range
+    pub days: u32,
+    // Occurrences that were public holidays taking away working time
+    pub holidays: u32,
+    pub hours: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WeekdayHistogram {
+    pub start_date: String,
+    pub end_date: String,
+    pub total_hours: f64,
+    pub weekdays: Vec<WeekdayEntry>,
+}
+
+// Hours contributed by each weekday between the start and end of a calculation request,
+// with the calendar given by its country, timezone and schedule
+pub async fn histogram(data: &web::Data<AppState>, req: &WorkHoursRequest) -> Result<WeekdayHistogram, actix_web::error::Error> {
+    let working_time = compute_working_time(data, req).await?;
+    let mut weekdays: Vec<WeekdayEntry> = WEEKDAYS.iter()
+        .map(|name| WeekdayEntry { weekday: name.to_string(), days: 0, holidays: 0, hours: 0.0 })
+        .collect();
+
+    let first = working_time.start_date.date_naive();
+    let last = working_time.end_date.date_naive();
+    for date in first.iter_days().take_while(|date| *date <= last) {
+        weekdays[date.weekday().num_days_from_monday() as usize].days += 1;
+    }
+    // Dates removed by excludeDates are the caller's, not public holidays
+    let holiday_dates: HashSet<NaiveDate> = working_time.holidays_applied.iter()
+        .filter(|holiday| holiday.source != "excludeDates")
+        .filter_map(|holiday| NaiveDate::parse_from_str(&holiday.date, "%Y-%m-%d").ok())
+        .collect();
+    for date in holiday_dates {
+        weekdays[date.weekday().num_days_from_monday() as usize].holidays += 1;
+    }
+    for (date, hours) in aggregation::hours_per_day(&working_time) {
+        weekdays[date.weekday().num_days_from_monday() as usize].hours += hours;
+    }
+
+    Ok(WeekdayHistogram {
+        start_date: working_time.start_date.to_rfc3339(),
+        end_date: working_time.end_date.to_rfc3339(),
+        total_hours: working_time.work_hours(),
+        weekdays,
+    })
+}
+
+#[post("/analytics/weekdays")]
+pub async fn weekday_histogram(
+    data: web::Data<AppState>,
+    params: web::Query<ExportParams>,
+    req: web::Json<WorkHoursRequest>,
+) -> Result<HttpResponse, actix_web::error::Error> {
+    let response = histogram(&data, &req).await?;
+    export::respond(params.format, &response, &response.weekdays)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::holiday_provider::MockProvider;
+    use crate::db;
+
+    #[actix_rt::test]
+    async fn test_hours_and_holidays_per_weekday() {
+        let database = db::Database::new(":memory:").unwrap();
+        database.add_holiday(&db::Holiday {
+            id: None,
+            date: "2023-10-03".to_string(),
+            description: "Company Day".to_string(),
+            country: "fr".to_string(),
+            portion: db::HolidayPortion::Full,
+            kind: db::HolidayKind::Public,
+            subdivision: None,
+        }, "test").unwrap();
+        let data = web::Data::new(AppState { db: database, holidays: Box::new(MockProvider), http: reqwest::Client::new() });
+        let app = test::init_service(App::new().app_data(data).service(weekday_histogram)).await;
+
+        // Two weeks, the first Tuesday off
+        let req = test::TestRequest::post()
+            .uri("/analytics/weekdays")
+            .set_json(serde_json::json!({
+                "startDate": "2023-10-02T00:00:00Z", "endDate": "2023-10-15T23:59:59Z",
+                "country": "fr", "timezone": "UTC"
+            }))
+            .to_request();
+        let response: WeekdayHistogram = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(response.total_hours, 72.0);
+        assert_eq!(response.weekdays[0], WeekdayEntry { weekday: "mon".to_string(), days: 2, holidays: 0, hours: 16.0 });
+        assert_eq!(response.weekdays[1], WeekdayEntry { weekday: "tue".to_string(), days: 2, holidays: 1, hours: 8.0 });
+        assert_eq!(response.weekdays[6], WeekdayEntry { weekday: "sun".to_string(), days: 2, holidays: 0, hours: 0.0 });
+    }
+}
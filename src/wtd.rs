@@ -0,0 +1,189 @@
+use actix_web::{HttpResponse, post, web};
+use chrono::{DateTime, Datelike, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+
+use crate::timesheet::{parse_punch, Punch};
+use crate::{aggregation, merge_intervals, WorkingTime};
+
+// EU Working Time Directive: at most 48 hours a week on average over a 17-week reference period
+fn default_reference_weeks() -> usize {
+    17
+}
+
+fn default_limit_hours() -> f64 {
+    48.0
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmployeeTimesheet {
+    pub id: String,
+    pub punches: Vec<Punch>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WtdRequest {
+    pub employees: Vec<EmployeeTimesheet>,
+    // Timezone used to group the punches per ISO week
+    #[serde(default)]
+    pub timezone: String,
+    #[serde(rename = "referenceWeeks", default = "default_reference_weeks")]
+    pub reference_weeks: usize,
+    #[serde(rename = "limitHours", default = "default_limit_hours")]
+    pub limit_hours: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WeekAverage {
+    // ISO week, e.g. 2025-W03
+    pub week: String,
+    pub hours: f64,
+    // Average of the reference period ending with this week
+    pub average_hours: f64,
+    // Fewer weeks of timesheet than the reference period before this one
+    pub partial: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmployeeReport {
+    pub id: String,
+    pub max_average_hours: f64,
+    pub over_limit: bool,
+    pub weeks: Vec<WeekAverage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WtdReport {
+    pub reference_weeks: usize,
+    pub limit_hours: f64,
+    // Ids of the employees whose average went over the limit
+    pub over_limit: Vec<String>,
+    pub employees: Vec<EmployeeReport>,
+}
+
+// Hours of every ISO week from the first to the last one worked, weeks without punches
+// count as zero, with the rolling average of the reference period ending with each week
+pub fn weekly_averages(punches: Vec<(DateTime<Tz>, DateTime<Tz>)>, reference_weeks: usize) -> Vec<WeekAverage> {
+    let punches = merge_intervals(punches);
+    let (start_date, end_date) = match (punches.first(), punches.last()) {
+        (Some(first), Some(last)) => (first.0, last.1),
+        _ => return vec![],
+    };
+    let working_time = WorkingTime {
+        start_date,
+        end_date,
+        intervals: punches,
+        holidays_applied: vec![],
+        bulk_seconds: 0,
+        warnings: vec![],
+    };
+    let worked = aggregation::breakdown(&working_time, aggregation::Aggregate::Week);
+    let monday = |entry: &aggregation::BreakdownEntry| NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d").unwrap();
+    let (first, last) = (monday(&worked[0]), monday(&worked[worked.len() - 1]));
+
+    let mut hours = vec![];
+    let mut week = first;
+    while week <= last {
+        let iso = week.iso_week();
+        let label = format!("{}-W{:02}", iso.year(), iso.week());
+        let worked_hours = worked.iter().find(|entry| monday(entry) == week).map_or(0.0, |entry| entry.hours);
+        hours.push((label, worked_hours));
+        week += chrono::Duration::weeks(1);
+    }
+
+    (0..hours.len())
+        .map(|index| {
+            let window = &hours[(index + 1).saturating_sub(reference_weeks)..=index];
+            WeekAverage {
+                week: hours[index].0.clone(),
+                hours: hours[index].1,
+                average_hours: window.iter().map(|(_, hours)| hours).sum::<f64>() / window.len() as f64,
+                partial: window.len() < reference_weeks,
+            }
+        })
+        .collect()
+}
+
+// Rolling weekly averages of each employee against the Working Time Directive limit
+#[post("/timesheet/wtd")]
+pub async fn working_time_directive(req: web::Json<WtdRequest>) -> Result<HttpResponse, actix_web::error::Error> {
+    if req.reference_weeks == 0 {
+        return Err(actix_web::error::ErrorBadRequest("referenceWeeks must be at least 1"));
+    }
+    let timezone: Tz = req.timezone.parse()
+        .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid timezone: {}", e)))?;
+
+    let mut employees = vec![];
+    for employee in &req.employees {
+        let punches = employee.punches.iter()
+            .map(|punch| parse_punch(punch, timezone))
+            .collect::<Result<Vec<_>, _>>()?;
+        let weeks = weekly_averages(punches, req.reference_weeks);
+        let max_average_hours = weeks.iter().map(|week| week.average_hours).fold(0.0, f64::max);
+        employees.push(EmployeeReport {
+            id: employee.id.clone(),
+            max_average_hours,
+            over_limit: max_average_hours > req.limit_hours,
+            weeks,
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(WtdReport {
+        reference_weeks: req.reference_weeks,
+        limit_hours: req.limit_hours,
+        over_limit: employees.iter().filter(|employee| employee.over_limit).map(|employee| employee.id.clone()).collect(),
+        employees,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+
+    // Monday to Friday punches of the given length, for a number of weeks from 2025-01-06
+    fn punches(weeks: i64, hours: i64) -> Vec<serde_json::Value> {
+        let first = NaiveDate::from_ymd_opt(2025, 1, 6).unwrap();
+        (0..weeks * 7)
+            .map(|offset| first + chrono::Duration::days(offset))
+            .filter(|date| date.weekday().num_days_from_monday() < 5)
+            .map(|date| serde_json::json!({
+                "start": format!("{}T08:00:00Z", date),
+                "end": format!("{}T{:02}:00:00Z", date, 8 + hours),
+            }))
+            .collect()
+    }
+
+    #[actix_rt::test]
+    async fn test_rolling_average_against_the_limit() {
+        let app = test::init_service(App::new().service(working_time_directive)).await;
+        let req = test::TestRequest::post().uri("/timesheet/wtd")
+            .set_json(serde_json::json!({
+                "timezone": "UTC",
+                "employees": [
+                    { "id": "alice", "punches": punches(17, 10) },
+                    { "id": "bob", "punches": punches(17, 8) }
+                ]
+            }))
+            .to_request();
+        let report: WtdReport = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(report.over_limit, vec!["alice"]);
+        let alice = &report.employees[0];
+        assert_eq!(alice.weeks.len(), 17);
+        assert_eq!(alice.weeks[16], WeekAverage { week: "2025-W18".to_string(), hours: 50.0, average_hours: 50.0, partial: false });
+        assert!(alice.weeks[0].partial);
+        assert_eq!(report.employees[1].max_average_hours, 40.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_weeks_without_punches_lower_the_average() {
+        let tz: Tz = "UTC".parse().unwrap();
+        let day = |date: &str| {
+            let start = DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", date)).unwrap().with_timezone(&tz);
+            (start, start + chrono::Duration::hours(60))
+        };
+        let weeks = weekly_averages(vec![day("2025-01-06"), day("2025-01-20")], 3);
+        assert_eq!(weeks.iter().map(|week| week.hours).collect::<Vec<_>>(), vec![60.0, 0.0, 60.0]);
+        assert_eq!(weeks[2].average_hours, 40.0);
+    }
+}
@@ -1,5 +1,4 @@
 use actix_web::{test, web, App};
-use actix_http;
 use workhours::{get_work_hours, WorkHoursQueryParams, WorkHoursResponse};
 
 // Helper function to create a test app
@@ -11,9 +10,12 @@ async fn create_test_app() -> impl actix_web::dev::Service<
     // Create an in-memory database
     let db = workhours::db::Database::new(":memory:").unwrap();
 
-    // Create the AppState with the database wrapped in a Mutex
+    // Create the AppState, the database hands out pooled connections
+    let http = reqwest::Client::new();
     let app_state = web::Data::new(workhours::AppState {
-        db: std::sync::Mutex::new(db),
+        db,
+        holidays: workhours::holiday_provider::from_env(&http),
+        http,
     });
 
     test::init_service(
@@ -34,7 +36,8 @@ async fn test_get_work_hours_with_end_date() {
         duration_seconds: None,
         country: "us".to_string(),
         timezone: "UTC".to_string(),
-        subdivision: None
+        subdivision: None,
+        ..Default::default()
     };
 
     // Test work hours calculation with end date
@@ -62,7 +65,8 @@ async fn test_get_work_hours_with_duration() {
         duration_seconds: Some(432000),
         country: "us".to_string(),
         timezone: "UTC".to_string(),
-        subdivision: None
+        subdivision: None,
+        ..Default::default()
     };
     // Test work hours calculation with duration
     // Starting Monday 9am, duration 5 days
@@ -90,7 +94,8 @@ async fn test_get_work_hours_invalid_date() {
         duration_seconds: None,
         country: "us".to_string(),
         timezone: "UTC".to_string(),
-        subdivision: None
+        subdivision: None,
+        ..Default::default()
     };
 
     // Test with invalid date format
@@ -114,7 +119,8 @@ async fn test_get_work_hours_invalid_timezone() {
         duration_seconds: None,
         country: "us".to_string(),
         timezone: "Invalid/Timezone".to_string(),
-        subdivision: None
+        subdivision: None,
+        ..Default::default()
     };
 
     // Test with invalid timezone
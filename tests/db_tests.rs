@@ -1,6 +1,11 @@
-use workhours::db::{Database, Holiday};
+use workhours::country::CountryCode;
+use workhours::db::{Database, Holiday, HolidayKind, HolidayPortion};
 use tempfile::tempdir;
 
+fn code(country: &str) -> CountryCode {
+    CountryCode::parse(country).unwrap()
+}
+
 #[test]
 fn test_database_operations() {
     // Create a temporary directory for the test database
@@ -16,13 +21,16 @@ fn test_database_operations() {
         date: "2023-12-25T00:00:00Z".to_string(),
         description: "Christmas".to_string(),
         country: "us".to_string(),
+        portion: HolidayPortion::Full,
+        kind: HolidayKind::Public,
+        subdivision: None,
     };
     
-    let id = db.add_holiday(&holiday).unwrap();
+    let (id, _) = db.add_holiday(&holiday, "test").unwrap();
     assert!(id > 0);
     
     // Test getting holidays by country
-    let holidays = db.get_holidays_by_country("us").unwrap();
+    let holidays = db.get_holidays_by_country(&code("us"), None).unwrap();
     assert_eq!(holidays.len(), 1);
     assert_eq!(holidays[0].date, "2023-12-25T00:00:00Z");
     assert_eq!(holidays[0].description, "Christmas");
@@ -38,13 +46,16 @@ fn test_database_operations() {
         date: "2023-07-14T00:00:00Z".to_string(),
         description: "Bastille Day".to_string(),
         country: "fr".to_string(),
+        portion: HolidayPortion::Full,
+        kind: HolidayKind::Public,
+        subdivision: None,
     };
     
-    let id2 = db.add_holiday(&holiday2).unwrap();
+    let (id2, _) = db.add_holiday(&holiday2, "test").unwrap();
     assert!(id2 > 0);
     
     // Test getting holidays by country again
-    let fr_holidays = db.get_holidays_by_country("fr").unwrap();
+    let fr_holidays = db.get_holidays_by_country(&code("fr"), None).unwrap();
     assert_eq!(fr_holidays.len(), 1);
     assert_eq!(fr_holidays[0].date, "2023-07-14T00:00:00Z");
     
@@ -53,8 +64,8 @@ fn test_database_operations() {
     assert_eq!(all_holidays.len(), 2);
     
     // Test deleting a holiday
-    db.delete_holiday(id).unwrap();
-    let us_holidays = db.get_holidays_by_country("us").unwrap();
+    db.delete_holiday(id, "test").unwrap();
+    let us_holidays = db.get_holidays_by_country(&code("us"), None).unwrap();
     assert_eq!(us_holidays.len(), 0);
     
     // Test getting all holidays after deletion
@@ -73,8 +84,8 @@ fn test_database_error_handling() {
     let db_path = dir.path().join("test.db").to_str().unwrap().to_string();
     let db = Database::new(&db_path).unwrap();
     
-    // Test getting holidays for a non-existent country
-    let holidays = db.get_holidays_by_country("nonexistent").unwrap();
+    // Test getting holidays for a well-formed but unassigned country code
+    let holidays = db.get_holidays_by_country(&code("zz"), None).unwrap();
     assert_eq!(holidays.len(), 0);
 }
 
@@ -92,15 +103,28 @@ fn test_database_clone() {
         date: "2023-12-25T00:00:00Z".to_string(),
         description: "Christmas".to_string(),
         country: "us".to_string(),
+        portion: HolidayPortion::Full,
+        kind: HolidayKind::Public,
+        subdivision: None,
     };
     
-    db1.add_holiday(&holiday).unwrap();
+    db1.add_holiday(&holiday, "test").unwrap();
     
     // Clone the database
     let db2 = db1.clone();
     
     // Check that the holiday exists in the cloned database
-    let holidays = db2.get_holidays_by_country("us").unwrap();
+    let holidays = db2.get_holidays_by_country(&code("us"), None).unwrap();
     assert_eq!(holidays.len(), 1);
     assert_eq!(holidays[0].date, "2023-12-25T00:00:00Z");
+
+    // Clones share the connections: an in-memory database keeps its data, and later writes are seen
+    let memory = Database::new(":memory:").unwrap();
+    memory.add_holiday(&holiday, "test").unwrap();
+    let clone = memory.clone();
+    let holiday2 = Holiday { date: "2023-07-04T00:00:00Z".to_string(), description: "Independence Day".to_string(), ..holiday };
+    memory.add_holiday(&holiday2, "test").unwrap();
+    assert_eq!(clone.get_holidays_by_country(&code("us"), None).unwrap().len(), 2);
+    drop(memory);
+    assert_eq!(clone.get_all_holidays().unwrap().len(), 2);
 }
\ No newline at end of file